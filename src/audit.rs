@@ -0,0 +1,111 @@
+use crate::config::Config;
+use crate::executor::ExecutionResult;
+use crate::parser::CommandSpec;
+use crate::Result;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Where `security.audit_log` appends executed commands, one JSON object per line
+pub fn audit_path() -> Option<PathBuf> {
+    crate::config::data_dir().map(|dir| dir.join("audit.log"))
+}
+
+/// Append an executed command to the audit log, if enabled. `masked_argv` is the
+/// argv that actually ran (shell-wrapped with `--via-shell`), with sensitive
+/// options and positionals redacted by [`crate::executor::build_masked_argv`].
+pub fn record(
+    config: &Config,
+    masked_argv: &[String],
+    spec: &CommandSpec,
+    result: &ExecutionResult,
+) -> Result<()> {
+    if !config.security.audit_log {
+        return Ok(());
+    }
+    match audit_path() {
+        Some(path) => append(&path, masked_argv, spec, result),
+        None => Ok(()),
+    }
+}
+
+fn append(
+    path: &Path,
+    masked_argv: &[String],
+    spec: &CommandSpec,
+    result: &ExecutionResult,
+) -> Result<()> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let entry = serde_json::json!({
+        "timestamp": timestamp,
+        "command": shell_words::join(masked_argv),
+        "danger_level": spec.danger_level,
+        "exit_code": result.code,
+        "interrupted": result.interrupted,
+    });
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", entry)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::build_masked_argv;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_append_redacts_sensitive_values() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("data").join("audit.log");
+
+        let spec: CommandSpec = serde_json::from_value(serde_json::json!({
+            "command": "curl",
+            "version_hash": "",
+            "description": "",
+            "options": [{
+                "flags": ["--user"],
+                "description": "Server user and password",
+                "argument_type": "string",
+                "sensitive": true
+            }],
+            "positional_args": [],
+            "subcommands": [],
+            "danger_level": "medium",
+            "examples": []
+        }))
+        .unwrap();
+        let values = HashMap::from([("--user".to_string(), "bob:hunter2".to_string())]);
+        let result = ExecutionResult {
+            code: Some(0),
+            stdout: String::new(),
+            stderr: String::new(),
+            interrupted: false,
+            timed_out: false,
+        };
+
+        let masked = build_masked_argv(&spec, &values);
+        append(&path, &masked, &spec, &result).unwrap();
+        append(&path, &masked, &spec, &result).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains("hunter2"));
+        let lines: Vec<_> = contents.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let entry: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(entry["command"], "curl --user '***'");
+        assert_eq!(entry["danger_level"], "medium");
+        assert_eq!(entry["exit_code"], 0);
+        assert!(entry["timestamp"].as_u64().unwrap() > 0);
+    }
+}
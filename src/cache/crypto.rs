@@ -0,0 +1,168 @@
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use sha2::{Digest, Sha256};
+
+const KEYRING_SERVICE: &str = "quocli";
+const KEYRING_USER: &str = "cache-passphrase";
+
+/// Marks a column value as ciphertext so plaintext rows from before encryption still load
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+const NONCE_LEN: usize = 12;
+
+/// AES-256-GCM cipher for the private columns of the cache (values and history args)
+#[derive(Clone)]
+pub struct CacheCipher {
+    cipher: Aes256Gcm,
+}
+
+impl CacheCipher {
+    pub fn from_passphrase(passphrase: &str) -> Self {
+        let digest = Sha256::digest(passphrase.as_bytes());
+        let key = Key::<Aes256Gcm>::from_slice(&digest);
+        Self {
+            cipher: Aes256Gcm::new(key),
+        }
+    }
+
+    /// Encrypt a value into `enc:v1:<hex nonce+ciphertext>`
+    pub fn encrypt(&self, plaintext: &str) -> Result<String, sqlx::Error> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| sqlx::Error::Encode("failed to encrypt cache value".into()))?;
+
+        let mut blob = nonce.to_vec();
+        blob.extend(ciphertext);
+        Ok(format!("{}{}", ENCRYPTED_PREFIX, hex::encode(blob)))
+    }
+
+    /// Decrypt a stored value; plaintext values pass through unchanged.
+    /// Returns None if the value is ciphertext that this key can't open.
+    pub fn decrypt(&self, stored: &str) -> Option<String> {
+        let Some(encoded) = stored.strip_prefix(ENCRYPTED_PREFIX) else {
+            return Some(stored.to_string());
+        };
+
+        let blob = hex::decode(encoded).ok()?;
+        if blob.len() < NONCE_LEN {
+            return None;
+        }
+        let (nonce, ciphertext) = blob.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .ok()?;
+        String::from_utf8(plaintext).ok()
+    }
+}
+
+/// Check whether a stored column value is ciphertext
+pub fn is_encrypted(stored: &str) -> bool {
+    stored.starts_with(ENCRYPTED_PREFIX)
+}
+
+/// Fetch the cache passphrase from the OS keyring, generating one on first use.
+/// This blocks on the platform credential store, so call it off the async runtime.
+pub fn keyring_passphrase() -> Result<String, keyring::Error> {
+    let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER)?;
+    match entry.get_password() {
+        Ok(passphrase) => Ok(passphrase),
+        Err(keyring::Error::NoEntry) => {
+            let mut bytes = [0u8; 32];
+            OsRng.fill_bytes(&mut bytes);
+            let passphrase = hex::encode(bytes);
+            entry.set_password(&passphrase)?;
+            Ok(passphrase)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Where sensitive cached values are kept when `security.keyring_integration`
+/// is on, keyed by `<command>:<flag>`. Calls may block, so make them off the
+/// async runtime.
+pub trait SecretStore: Send + Sync {
+    fn get(&self, key: &str) -> Result<Option<String>, keyring::Error>;
+    fn set(&self, key: &str, secret: &str) -> Result<(), keyring::Error>;
+    fn delete(&self, key: &str) -> Result<(), keyring::Error>;
+}
+
+/// The platform credential store (Keychain, Secret Service, Credential Manager)
+pub struct OsKeyring;
+
+impl OsKeyring {
+    fn entry(key: &str) -> Result<keyring::Entry, keyring::Error> {
+        keyring::Entry::new(KEYRING_SERVICE, &format!("value:{}", key))
+    }
+}
+
+impl SecretStore for OsKeyring {
+    fn get(&self, key: &str) -> Result<Option<String>, keyring::Error> {
+        match Self::entry(key)?.get_password() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn set(&self, key: &str, secret: &str) -> Result<(), keyring::Error> {
+        Self::entry(key)?.set_password(secret)
+    }
+
+    fn delete(&self, key: &str) -> Result<(), keyring::Error> {
+        match Self::entry(key)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        let cipher = CacheCipher::from_passphrase("hunter2");
+        let stored = cipher.encrypt("/home/user/private dir").unwrap();
+
+        assert!(is_encrypted(&stored));
+        assert!(!stored.contains("private"));
+        assert_eq!(
+            cipher.decrypt(&stored).as_deref(),
+            Some("/home/user/private dir")
+        );
+    }
+
+    #[test]
+    fn test_encrypt_uses_fresh_nonce() {
+        let cipher = CacheCipher::from_passphrase("hunter2");
+        assert_ne!(
+            cipher.encrypt("same").unwrap(),
+            cipher.encrypt("same").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_decrypt_plaintext_passes_through() {
+        let cipher = CacheCipher::from_passphrase("hunter2");
+        assert_eq!(
+            cipher.decrypt("plain value").as_deref(),
+            Some("plain value")
+        );
+    }
+
+    #[test]
+    fn test_decrypt_with_wrong_key_fails() {
+        let stored = CacheCipher::from_passphrase("right")
+            .encrypt("secret")
+            .unwrap();
+        let wrong = CacheCipher::from_passphrase("wrong");
+
+        assert!(wrong.decrypt(&stored).is_none());
+        assert!(wrong.decrypt("enc:v1:not-hex").is_none());
+        assert!(wrong.decrypt("enc:v1:abcd").is_none());
+    }
+}
@@ -1,35 +1,32 @@
-use crate::parser::{CommandOption, CommandSpec};
+use super::crypto::{is_encrypted, CacheCipher, OsKeyring, SecretStore};
+use crate::executor::ResourceUsage;
+use crate::parser::{CommandOption, CommandSpec, HelpDocumentation};
 use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-pub struct Cache {
-    pool: SqlitePool,
+/// Stored in `command_values` in place of a sensitive value kept in the keyring
+const KEYRING_MARKER: &str = "keyring:v1";
+
+/// A versioned change to the cache schema, recorded in `schema_migrations` once applied.
+///
+/// Migrations are append-only: released ones must never change, since databases
+/// that already applied them won't run them again. Tables are created with
+/// `IF NOT EXISTS` so databases created before versioning adopt them cleanly.
+#[derive(Debug)]
+pub struct Migration {
+    pub version: i64,
+    pub description: &'static str,
+    statements: &'static [&'static str],
 }
 
-impl Cache {
-    pub async fn new(path: &Path) -> Result<Self, sqlx::Error> {
-        // Ensure parent directory exists
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent).ok();
-        }
-
-        let database_url = format!("sqlite:{}?mode=rwc", path.display());
-
-        let pool = SqlitePoolOptions::new()
-            .max_connections(5)
-            .connect(&database_url)
-            .await?;
-
-        let cache = Cache { pool };
-        cache.run_migrations().await?;
-
-        Ok(cache)
-    }
-
-    async fn run_migrations(&self) -> Result<(), sqlx::Error> {
-        sqlx::query(
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "command specs, cached values and history",
+        statements: &[
             r#"
             CREATE TABLE IF NOT EXISTS command_specs (
                 command_name TEXT PRIMARY KEY,
@@ -41,20 +38,10 @@ impl Cache {
                 use_count INTEGER DEFAULT 0
             )
             "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        sqlx::query(
             r#"
             CREATE INDEX IF NOT EXISTS idx_command_hash
             ON command_specs(command_name, help_hash)
             "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        sqlx::query(
             r#"
             CREATE TABLE IF NOT EXISTS command_values (
                 command_name TEXT NOT NULL,
@@ -64,11 +51,6 @@ impl Cache {
                 PRIMARY KEY (command_name, flag_name)
             )
             "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        sqlx::query(
             r#"
             CREATE TABLE IF NOT EXISTS command_history (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -78,15 +60,331 @@ impl Cache {
                 success INTEGER
             )
             "#,
+        ],
+    },
+    Migration {
+        version: 2,
+        description: "execution measurements",
+        statements: &[r#"
+            CREATE TABLE IF NOT EXISTS execution_measurements (
+                history_id INTEGER PRIMARY KEY REFERENCES command_history(id),
+                wall_ms INTEGER NOT NULL,
+                user_cpu_ms INTEGER,
+                system_cpu_ms INTEGER,
+                max_rss_kb INTEGER
+            )
+            "#],
+    },
+    Migration {
+        version: 3,
+        description: "presets",
+        statements: &[r#"
+            CREATE TABLE IF NOT EXISTS command_presets (
+                command_name TEXT NOT NULL,
+                preset_name TEXT NOT NULL,
+                values_json TEXT NOT NULL,
+                updated_at INTEGER NOT NULL,
+                PRIMARY KEY (command_name, preset_name)
+            )
+            "#],
+    },
+    Migration {
+        version: 4,
+        description: "sensitivity overrides",
+        statements: &[r#"
+            CREATE TABLE IF NOT EXISTS sensitivity_overrides (
+                command_name TEXT NOT NULL,
+                field_id TEXT NOT NULL,
+                sensitive INTEGER NOT NULL,
+                PRIMARY KEY (command_name, field_id)
+            )
+            "#],
+    },
+    Migration {
+        version: 5,
+        description: "command notes",
+        statements: &[r#"
+            CREATE TABLE IF NOT EXISTS command_notes (
+                command_name TEXT PRIMARY KEY,
+                note TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            )
+            "#],
+    },
+    Migration {
+        version: 6,
+        description: "command tags",
+        statements: &[r#"
+            CREATE TABLE IF NOT EXISTS command_tags (
+                command_name TEXT NOT NULL,
+                tag TEXT NOT NULL,
+                PRIMARY KEY (command_name, tag)
+            )
+            "#],
+    },
+    Migration {
+        version: 7,
+        description: "command docs",
+        statements: &[r#"
+            CREATE TABLE IF NOT EXISTS command_docs (
+                command_name TEXT PRIMARY KEY,
+                help_text TEXT NOT NULL,
+                manpage_text TEXT NOT NULL,
+                fetched_at INTEGER NOT NULL
+            )
+            "#],
+    },
+];
+
+pub struct Cache {
+    pool: SqlitePool,
+    cipher: Option<CacheCipher>,
+    spec_dir: Option<PathBuf>,
+    secrets: Option<Arc<dyn SecretStore>>,
+}
+
+/// One row of `--list-cached`: a cached spec and how much it is used
+#[derive(Debug, Clone)]
+pub struct CachedSpecSummary {
+    /// Cache key, with subcommands joined by `:` (e.g. `git:commit`)
+    pub command_name: String,
+    pub danger_level: String,
+    pub use_count: i64,
+    pub last_used: Option<i64>,
+    /// Tags added with `--tag`, sorted
+    pub tags: Vec<String>,
+}
+
+impl CachedSpecSummary {
+    /// Command as typed, e.g. `git commit`
+    pub fn display_name(&self) -> String {
+        self.command_name.replace(':', " ")
+    }
+
+    /// How long ago the spec was last used, e.g. `5m ago` or `3d ago`
+    pub fn last_used_ago(&self, now: i64) -> String {
+        let Some(last_used) = self.last_used else {
+            return "never".to_string();
+        };
+        let secs = (now - last_used).max(0);
+        match secs {
+            0..=59 => "just now".to_string(),
+            60..=3599 => format!("{}m ago", secs / 60),
+            3600..=86_399 => format!("{}h ago", secs / 3600),
+            _ => format!("{}d ago", secs / 86_400),
+        }
+    }
+}
+
+/// A recorded invocation from the command_history table
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    pub args: HashMap<String, String>,
+    pub timestamp: i64,
+    pub success: Option<bool>,
+}
+
+impl Cache {
+    pub async fn new(path: &Path) -> Result<Self, sqlx::Error> {
+        let cache = Self::connect(path).await?;
+        cache.migrate().await?;
+        Ok(cache)
+    }
+
+    /// Open the database without applying pending migrations, e.g. to apply
+    /// and report them explicitly with [`Cache::migrate`]
+    pub async fn connect(path: &Path) -> Result<Self, sqlx::Error> {
+        let path = &crate::config::expand_tilde(path);
+
+        // Ensure parent directory exists
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).ok();
+        }
+
+        let database_url = format!("sqlite:{}?mode=rwc", path.display());
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(5)
+            .connect(&database_url)
+            .await?;
+
+        Ok(Cache { pool, cipher: None, spec_dir: None, secrets: None })
+    }
+
+    /// Open an ephemeral cache that lives only as long as this `Cache`
+    pub async fn new_in_memory() -> Result<Self, sqlx::Error> {
+        // SQLite drops a `:memory:` database when its last connection closes, and each
+        // connection gets its own database, so pin the pool to one long-lived connection
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .min_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await?;
+
+        let cache = Cache { pool, cipher: None, spec_dir: None, secrets: None };
+        cache.migrate().await?;
+
+        Ok(cache)
+    }
+
+    /// Encrypt cached values and history args with a key derived from `passphrase`
+    pub fn with_encryption(mut self, passphrase: &str) -> Self {
+        self.cipher = Some(CacheCipher::from_passphrase(passphrase));
+        self
+    }
+
+    /// Cache sensitive values in the OS keyring instead of skipping them
+    pub fn with_keyring(self) -> Self {
+        self.with_secret_store(Arc::new(OsKeyring))
+    }
+
+    /// Cache sensitive values in `store` instead of skipping them
+    pub fn with_secret_store(mut self, store: Arc<dyn SecretStore>) -> Self {
+        self.secrets = Some(store);
+        self
+    }
+
+    /// Run a secret store operation on the blocking pool; None when no store is configured
+    async fn secret_op<T, F>(&self, op: F) -> Option<Result<T, keyring::Error>>
+    where
+        T: Send + 'static,
+        F: FnOnce(&dyn SecretStore) -> Result<T, keyring::Error> + Send + 'static,
+    {
+        let store = self.secrets.clone()?;
+        let result = tokio::task::spawn_blocking(move || op(store.as_ref()))
+            .await
+            .unwrap_or_else(|e| Err(keyring::Error::PlatformFailure(Box::new(e))));
+        Some(result)
+    }
+
+    /// Remove the keyring copies of a command's values for `flags`
+    async fn forget_secrets(&self, command_name: &str, flags: Vec<String>) {
+        for flag in flags {
+            let key = secret_key(command_name, &flag);
+            if let Some(Err(e)) = self.secret_op(move |store| store.delete(&key)).await {
+                tracing::warn!("Could not remove {} from the keyring: {}", flag, e);
+            }
+        }
+    }
+
+    /// Flags whose cached value for a command lives in the keyring
+    async fn keyring_flags(&self, command_name: &str) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar("SELECT flag_name FROM command_values WHERE command_name = ? AND value = ?")
+            .bind(command_name)
+            .bind(KEYRING_MARKER)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Also keep specs as JSON files in `dir`, one per command.
+    ///
+    /// A spec file takes precedence over the database copy, so specs checked into
+    /// a shared repo win over locally generated ones. Generated specs are written
+    /// to both, keeping them in sync, except that a file edited by hand since
+    /// quocli last wrote it is never overwritten.
+    pub fn with_spec_dir(mut self, dir: &Path) -> Self {
+        self.spec_dir = Some(crate::config::expand_tilde(dir));
+        self
+    }
+
+    /// Path of the spec file for a command (`git:commit` becomes `git__commit.json`)
+    fn spec_file(&self, command_name: &str) -> Option<PathBuf> {
+        let file_name = command_name.replace(':', "__").replace(['/', '\\'], "_");
+        self.spec_dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.json", file_name)))
+    }
+
+    /// Read a command's spec file, if the spec dir has one that parses
+    fn read_spec_file(&self, command_name: &str) -> Option<CommandSpec> {
+        let path = self.spec_file(command_name)?;
+        let json = std::fs::read_to_string(&path).ok()?;
+        match serde_json::from_str(&json) {
+            Ok(spec) => Some(spec),
+            Err(e) => {
+                tracing::warn!("Ignoring unreadable spec file {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Encrypt a column value if encryption is enabled
+    fn seal(&self, value: &str) -> Result<String, sqlx::Error> {
+        match &self.cipher {
+            Some(cipher) => cipher.encrypt(value),
+            None => Ok(value.to_string()),
+        }
+    }
+
+    /// Decrypt a column value, or None if it is ciphertext we can't open
+    fn open(&self, stored: &str) -> Option<String> {
+        match &self.cipher {
+            Some(cipher) => cipher.decrypt(stored),
+            None if is_encrypted(stored) => None,
+            None => Some(stored.to_string()),
+        }
+    }
+
+    /// Apply the migrations this database hasn't had yet, oldest first, each in
+    /// its own transaction; returns the ones applied now
+    pub async fn migrate(&self) -> Result<Vec<&'static Migration>, sqlx::Error> {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS schema_migrations (
+                version INTEGER PRIMARY KEY,
+                description TEXT NOT NULL,
+                applied_at INTEGER NOT NULL
+            )
+            "#,
         )
         .execute(&self.pool)
         .await?;
 
-        Ok(())
+        let current = self.schema_version().await?;
+        let latest = MIGRATIONS.last().map_or(0, |m| m.version);
+        if current > latest {
+            tracing::warn!(
+                "Cache schema version {} is newer than this quocli knows ({}); it may have been upgraded by a newer release",
+                current,
+                latest
+            );
+        }
+
+        let mut applied = Vec::new();
+        for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+            let mut tx = self.pool.begin().await?;
+            for statement in migration.statements {
+                sqlx::query(statement).execute(&mut *tx).await?;
+            }
+            sqlx::query("INSERT INTO schema_migrations (version, description, applied_at) VALUES (?, ?, ?)")
+                .bind(migration.version)
+                .bind(migration.description)
+                .bind(current_timestamp())
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            tracing::debug!("Applied cache migration {}: {}", migration.version, migration.description);
+            applied.push(migration);
+        }
+        Ok(applied)
+    }
+
+    /// Version of the newest migration applied to this database (0 for none)
+    pub async fn schema_version(&self) -> Result<i64, sqlx::Error> {
+        let version: Option<i64> = sqlx::query_scalar("SELECT MAX(version) FROM schema_migrations")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(version.unwrap_or(0))
     }
 
     /// Get a cached command spec
     pub async fn get_spec(&self, command_name: &str) -> Result<Option<CommandSpec>, sqlx::Error> {
+        if let Some(spec) = self.read_spec_file(command_name) {
+            return Ok(Some(spec));
+        }
+
         let row: Option<(String,)> = sqlx::query_as(
             "SELECT spec_json FROM command_specs WHERE command_name = ?",
         )
@@ -114,6 +412,18 @@ impl Cache {
         let spec_json = serde_json::to_string(spec)
             .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
 
+        // The database holds what was last written to the spec file, so read it
+        // before it is replaced to tell whether the file has been edited since
+        let previous_json: Option<(String,)> = match self.spec_dir {
+            Some(_) => {
+                sqlx::query_as("SELECT spec_json FROM command_specs WHERE command_name = ?")
+                    .bind(command_name)
+                    .fetch_optional(&self.pool)
+                    .await?
+            }
+            None => None,
+        };
+
         sqlx::query(
             r#"
             INSERT INTO command_specs (command_name, help_hash, spec_json, danger_level, created_at, last_used, use_count)
@@ -122,6 +432,7 @@ impl Cache {
                 help_hash = excluded.help_hash,
                 spec_json = excluded.spec_json,
                 danger_level = excluded.danger_level,
+                created_at = excluded.created_at,
                 last_used = excluded.last_used,
                 use_count = use_count + 1
             "#,
@@ -135,9 +446,92 @@ impl Cache {
         .execute(&self.pool)
         .await?;
 
+        if let Some(path) = self.spec_file(command_name) {
+            if spec_file_was_edited(&path, previous_json.as_ref().map(|(json,)| json.as_str())) {
+                tracing::warn!(
+                    "Not overwriting {}, which was edited after quocli wrote it; the regenerated spec is only in the cache database",
+                    path.display()
+                );
+                return Ok(());
+            }
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let pretty = serde_json::to_string_pretty(spec)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+            std::fs::write(&path, pretty + "\n")?;
+        }
+
         Ok(())
     }
 
+    /// Seconds since the cached spec was generated, if the database has it
+    pub async fn get_spec_age(&self, command_name: &str) -> Result<Option<i64>, sqlx::Error> {
+        let row: Option<(i64,)> =
+            sqlx::query_as("SELECT created_at FROM command_specs WHERE command_name = ?")
+                .bind(command_name)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(row.map(|(created_at,)| current_timestamp() - created_at))
+    }
+
+    /// Whether the cached spec is older than `ttl_days` (0 disables expiry)
+    pub async fn is_spec_expired(&self, command_name: &str, ttl_days: u32) -> Result<bool, sqlx::Error> {
+        if ttl_days == 0 {
+            return Ok(false);
+        }
+        let age = self.get_spec_age(command_name).await?;
+        Ok(age.is_some_and(|age| age > i64::from(ttl_days) * 86_400))
+    }
+
+    /// Summaries of every cached spec, most used first; with `tag`, only the
+    /// specs of commands tagged with it
+    pub async fn list_specs(&self, tag: Option<&str>) -> Result<Vec<CachedSpecSummary>, sqlx::Error> {
+        let rows: Vec<(String, String, Option<i64>, Option<i64>)> = sqlx::query_as(
+            r#"
+            SELECT command_name, danger_level, use_count, last_used
+            FROM command_specs
+            WHERE ?1 IS NULL
+                OR command_name IN (SELECT command_name FROM command_tags WHERE tag = ?1)
+            ORDER BY use_count DESC, command_name ASC
+            "#,
+        )
+        .bind(tag)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+        let tag_rows: Vec<(String, String)> =
+            sqlx::query_as("SELECT command_name, tag FROM command_tags ORDER BY tag")
+                .fetch_all(&self.pool)
+                .await?;
+        for (command_name, tag) in tag_rows {
+            tags.entry(command_name).or_default().push(tag);
+        }
+
+        Ok(rows
+            .into_iter()
+            .map(|(command_name, danger_level, use_count, last_used)| CachedSpecSummary {
+                tags: tags.remove(&command_name).unwrap_or_default(),
+                command_name,
+                danger_level,
+                use_count: use_count.unwrap_or(0),
+                last_used,
+            })
+            .collect())
+    }
+
+    /// Tag a command for `--list-cached --tag`; false if it already had the tag
+    pub async fn add_tag(&self, command_name: &str, tag: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("INSERT OR IGNORE INTO command_tags (command_name, tag) VALUES (?, ?)")
+            .bind(command_name)
+            .bind(tag)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
     /// Update usage statistics
     pub async fn update_usage(&self, command_name: &str) -> Result<(), sqlx::Error> {
         let now = current_timestamp();
@@ -165,10 +559,35 @@ impl Cache {
         .fetch_all(&self.pool)
         .await?;
 
-        Ok(rows.into_iter().collect())
+        let mut values = HashMap::new();
+        for (flag, stored) in rows {
+            if stored == KEYRING_MARKER {
+                let key = secret_key(command_name, &flag);
+                match self.secret_op(move |store| store.get(&key)).await {
+                    Some(Ok(Some(secret))) => {
+                        values.insert(flag, secret);
+                    }
+                    Some(Ok(None)) => tracing::warn!("The keyring has no cached value for {}", flag),
+                    Some(Err(e)) => tracing::warn!("Could not read {} from the keyring: {}", flag, e),
+                    // Keyring integration is off: the value stays out of reach, as if never cached
+                    None => {}
+                }
+                continue;
+            }
+
+            match self.open(&stored) {
+                Some(value) => {
+                    values.insert(flag, value);
+                }
+                None => tracing::warn!("Skipping cached value for {} that could not be decrypted", flag),
+            }
+        }
+
+        Ok(values)
     }
 
-    /// Save non-sensitive values for a command
+    /// Save a command's values. Sensitive ones are kept in the keyring when it is
+    /// enabled (see [`Cache::with_keyring`]) and skipped otherwise.
     pub async fn save_values(
         &self,
         command_name: &str,
@@ -185,16 +604,27 @@ impl Cache {
             .collect();
 
         for (flag, value) in values {
-            // Skip sensitive values
-            if sensitive_flags.contains(flag) {
-                continue;
-            }
-
             // Skip empty values
             if value.is_empty() {
                 continue;
             }
 
+            // Sensitive values never reach the table: only a marker that the keyring has them
+            let stored = if sensitive_flags.contains(flag) {
+                let key = secret_key(command_name, flag);
+                let secret = value.clone();
+                match self.secret_op(move |store| store.set(&key, &secret)).await {
+                    Some(Ok(())) => KEYRING_MARKER.to_string(),
+                    Some(Err(e)) => {
+                        tracing::warn!("Keyring unavailable, not caching {}: {}", flag, e);
+                        continue;
+                    }
+                    None => continue,
+                }
+            } else {
+                self.seal(value)?
+            };
+
             sqlx::query(
                 r#"
                 INSERT INTO command_values (command_name, flag_name, value, last_used)
@@ -206,7 +636,7 @@ impl Cache {
             )
             .bind(command_name)
             .bind(flag)
-            .bind(value)
+            .bind(stored)
             .bind(now)
             .execute(&self.pool)
             .await?;
@@ -215,29 +645,348 @@ impl Cache {
         Ok(())
     }
 
-    /// Clear cached values for a command
-    pub async fn clear_values(&self, command_name: &str) -> Result<(), sqlx::Error> {
-        sqlx::query("DELETE FROM command_values WHERE command_name = ?")
-            .bind(command_name)
-            .execute(&self.pool)
-            .await?;
+    /// Clear cached values for a command
+    pub async fn clear_values(&self, command_name: &str) -> Result<(), sqlx::Error> {
+        let flags = self.keyring_flags(command_name).await?;
+        self.forget_secrets(command_name, flags).await;
+
+        sqlx::query("DELETE FROM command_values WHERE command_name = ?")
+            .bind(command_name)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Fields whose cached value is held in the keyring (sensitive by definition)
+    pub async fn keyring_field_ids(&self, command_name: &str) -> Result<Vec<String>, sqlx::Error> {
+        let rows: Vec<(String,)> =
+            sqlx::query_as("SELECT flag_name FROM command_values WHERE command_name = ? AND value = ?")
+                .bind(command_name)
+                .bind(KEYRING_MARKER)
+                .fetch_all(&self.pool)
+                .await?;
+        Ok(rows.into_iter().map(|(flag,)| flag).collect())
+    }
+
+    /// Clear cached values only for the given fields (e.g. the sensitive ones),
+    /// keeping the rest of the command's values. Returns how many were removed.
+    pub async fn clear_field_values(
+        &self,
+        command_name: &str,
+        field_ids: &[String],
+    ) -> Result<u64, sqlx::Error> {
+        let flags = self
+            .keyring_flags(command_name)
+            .await?
+            .into_iter()
+            .filter(|flag| field_ids.contains(flag))
+            .collect();
+        self.forget_secrets(command_name, flags).await;
+
+        let mut removed = 0;
+        for field_id in field_ids {
+            removed += sqlx::query("DELETE FROM command_values WHERE command_name = ? AND flag_name = ?")
+                .bind(command_name)
+                .bind(field_id)
+                .execute(&self.pool)
+                .await?
+                .rows_affected();
+        }
+
+        Ok(removed)
+    }
+
+    /// Save a named set of values for a command, replacing any preset of the
+    /// same name. Sensitive fields are left out, as with `save_values`.
+    pub async fn save_preset(
+        &self,
+        command_name: &str,
+        preset_name: &str,
+        values: &HashMap<String, String>,
+        spec: &CommandSpec,
+    ) -> Result<(), sqlx::Error> {
+        let sensitive = spec.sensitive_field_ids();
+        let values: HashMap<&String, &String> = values
+            .iter()
+            .filter(|(field, value)| !value.is_empty() && !sensitive.contains(field))
+            .collect();
+        let values_json =
+            serde_json::to_string(&values).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO command_presets (command_name, preset_name, values_json, updated_at)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(command_name, preset_name) DO UPDATE SET
+                values_json = excluded.values_json,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(command_name)
+        .bind(preset_name)
+        .bind(self.seal(&values_json)?)
+        .bind(current_timestamp())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Names of a command's presets, alphabetically
+    pub async fn list_presets(&self, command_name: &str) -> Result<Vec<String>, sqlx::Error> {
+        let rows: Vec<(String,)> = sqlx::query_as(
+            "SELECT preset_name FROM command_presets WHERE command_name = ? ORDER BY preset_name",
+        )
+        .bind(command_name)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|(name,)| name).collect())
+    }
+
+    /// Values stored in a preset, or None if there is no such preset
+    pub async fn load_preset(
+        &self,
+        command_name: &str,
+        preset_name: &str,
+    ) -> Result<Option<HashMap<String, String>>, sqlx::Error> {
+        let row: Option<(String,)> = sqlx::query_as(
+            "SELECT values_json FROM command_presets WHERE command_name = ? AND preset_name = ?",
+        )
+        .bind(command_name)
+        .bind(preset_name)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let Some((stored,)) = row else {
+            return Ok(None);
+        };
+        let Some(values_json) = self.open(&stored) else {
+            tracing::warn!("Skipping preset {} that could not be decrypted", preset_name);
+            return Ok(None);
+        };
+        serde_json::from_str(&values_json)
+            .map(Some)
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))
+    }
+
+    /// The help text and manpage a command's spec was last generated from
+    pub async fn get_docs(&self, command_name: &str) -> Result<Option<HelpDocumentation>, sqlx::Error> {
+        let row: Option<(String, String)> = sqlx::query_as(
+            "SELECT help_text, manpage_text FROM command_docs WHERE command_name = ?",
+        )
+        .bind(command_name)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|(help_text, manpage_text)| HelpDocumentation {
+            help_text,
+            manpage_text,
+        }))
+    }
+
+    /// Remember the documentation fetched for a command, replacing any earlier copy
+    pub async fn save_docs(&self, command_name: &str, docs: &HelpDocumentation) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO command_docs (command_name, help_text, manpage_text, fetched_at)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(command_name) DO UPDATE SET
+                help_text = excluded.help_text,
+                manpage_text = excluded.manpage_text,
+                fetched_at = excluded.fetched_at
+            "#,
+        )
+        .bind(command_name)
+        .bind(&docs.help_text)
+        .bind(&docs.manpage_text)
+        .bind(current_timestamp())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// The user's note about a command (keyed like specs, e.g. `git:commit`).
+    /// Notes are kept apart from the spec, so they survive regeneration.
+    pub async fn get_note(&self, command_name: &str) -> Result<Option<String>, sqlx::Error> {
+        let row: Option<(String,)> =
+            sqlx::query_as("SELECT note FROM command_notes WHERE command_name = ?")
+                .bind(command_name)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(row.and_then(|(stored,)| {
+            let note = self.open(&stored);
+            if note.is_none() {
+                tracing::warn!("Skipping the note for {} that could not be decrypted", command_name);
+            }
+            note
+        }))
+    }
+
+    /// Replace a command's note; a blank note deletes it
+    pub async fn save_note(&self, command_name: &str, note: &str) -> Result<(), sqlx::Error> {
+        if note.trim().is_empty() {
+            sqlx::query("DELETE FROM command_notes WHERE command_name = ?")
+                .bind(command_name)
+                .execute(&self.pool)
+                .await?;
+            return Ok(());
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO command_notes (command_name, note, updated_at)
+            VALUES (?, ?, ?)
+            ON CONFLICT(command_name) DO UPDATE SET
+                note = excluded.note,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(command_name)
+        .bind(self.seal(note.trim_end())?)
+        .bind(current_timestamp())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Add a line to the end of a command's note, returning the whole note
+    pub async fn add_note(&self, command_name: &str, text: &str) -> Result<String, sqlx::Error> {
+        let note = match self.get_note(command_name).await? {
+            Some(existing) => format!("{}\n{}", existing, text.trim()),
+            None => text.trim().to_string(),
+        };
+        self.save_note(command_name, &note).await?;
+        Ok(note)
+    }
+
+    /// Get the user's sensitivity overrides for a command (field id -> sensitive)
+    pub async fn get_sensitivity_overrides(
+        &self,
+        command_name: &str,
+    ) -> Result<HashMap<String, bool>, sqlx::Error> {
+        let rows: Vec<(String, bool)> = sqlx::query_as(
+            "SELECT field_id, sensitive FROM sensitivity_overrides WHERE command_name = ?",
+        )
+        .bind(command_name)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    /// Persist sensitivity overrides for a command, dropping any cached
+    /// value for fields that are now sensitive
+    pub async fn save_sensitivity_overrides(
+        &self,
+        command_name: &str,
+        overrides: &HashMap<String, bool>,
+    ) -> Result<(), sqlx::Error> {
+        for (field_id, sensitive) in overrides {
+            sqlx::query(
+                r#"
+                INSERT INTO sensitivity_overrides (command_name, field_id, sensitive)
+                VALUES (?, ?, ?)
+                ON CONFLICT(command_name, field_id) DO UPDATE SET
+                    sensitive = excluded.sensitive
+                "#,
+            )
+            .bind(command_name)
+            .bind(field_id)
+            .bind(sensitive)
+            .execute(&self.pool)
+            .await?;
+
+            if *sensitive {
+                sqlx::query("DELETE FROM command_values WHERE command_name = ? AND flag_name = ?")
+                    .bind(command_name)
+                    .bind(field_id)
+                    .execute(&self.pool)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Log command execution to history, with sensitive values redacted.
+    /// Returns the history entry's id.
+    pub async fn log_execution(
+        &self,
+        command_name: &str,
+        args: &HashMap<String, String>,
+        spec: &CommandSpec,
+        success: bool,
+    ) -> Result<i64, sqlx::Error> {
+        self.insert_history(command_name, &redact_sensitive(args, spec), Some(success))
+            .await
+    }
+
+    /// Log an execution cut short by Ctrl+C: a failure, marked with
+    /// `INTERRUPTED_KEY` so it can be told apart from the command failing itself
+    pub async fn log_interrupted(
+        &self,
+        command_name: &str,
+        args: &HashMap<String, String>,
+        spec: &CommandSpec,
+    ) -> Result<i64, sqlx::Error> {
+        let mut args = redact_sensitive(args, spec);
+        args.insert(INTERRUPTED_KEY.to_string(), "true".to_string());
+        self.insert_history(command_name, &args, Some(false)).await
+    }
+
+    /// Log a detached launch to history. Its outcome is unknown, so the entry is
+    /// marked with the child's pid under `BACKGROUND_PID_KEY` instead of a status.
+    pub async fn log_background(
+        &self,
+        command_name: &str,
+        args: &HashMap<String, String>,
+        spec: &CommandSpec,
+        pid: u32,
+    ) -> Result<(), sqlx::Error> {
+        let mut args = redact_sensitive(args, spec);
+        args.insert(BACKGROUND_PID_KEY.to_string(), pid.to_string());
+        self.insert_history(command_name, &args, None).await?;
+        Ok(())
+    }
+
+    /// Attach `--measure` resource usage to a logged execution
+    pub async fn log_measurement(&self, history_id: i64, usage: &ResourceUsage) -> Result<(), sqlx::Error> {
+        let millis = |d: std::time::Duration| d.as_millis() as i64;
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO execution_measurements
+                (history_id, wall_ms, user_cpu_ms, system_cpu_ms, max_rss_kb)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(history_id)
+        .bind(millis(usage.wall))
+        .bind(usage.user_cpu.map(millis))
+        .bind(usage.system_cpu.map(millis))
+        .bind(usage.max_rss_kb.map(|kb| kb as i64))
+        .execute(&self.pool)
+        .await?;
 
         Ok(())
     }
 
-    /// Log command execution to history
-    #[allow(dead_code)]
-    pub async fn log_execution(
+    async fn insert_history(
         &self,
         command_name: &str,
         args: &HashMap<String, String>,
-        success: bool,
-    ) -> Result<(), sqlx::Error> {
+        success: Option<bool>,
+    ) -> Result<i64, sqlx::Error> {
         let now = current_timestamp();
         let args_json = serde_json::to_string(args)
             .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+        let args_json = self.seal(&args_json)?;
 
-        sqlx::query(
+        let id = sqlx::query(
             r#"
             INSERT INTO command_history (command_name, args_json, timestamp, success)
             VALUES (?, ?, ?, ?)
@@ -248,9 +997,135 @@ impl Cache {
         .bind(now)
         .bind(success)
         .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+
+        Ok(id)
+    }
+
+    /// Get recorded invocations of a command, oldest first
+    pub async fn get_history(
+        &self,
+        command_name: &str,
+        since: Option<i64>,
+        successful_only: bool,
+    ) -> Result<Vec<HistoryEntry>, sqlx::Error> {
+        let rows: Vec<(String, i64, Option<bool>)> = sqlx::query_as(
+            r#"
+            SELECT args_json, timestamp, success FROM command_history
+            WHERE command_name = ? AND timestamp >= ? AND (? = 0 OR success = 1)
+            ORDER BY timestamp, id
+            "#,
+        )
+        .bind(command_name)
+        .bind(since.unwrap_or(0))
+        .bind(successful_only)
+        .fetch_all(&self.pool)
         .await?;
 
-        Ok(())
+        let mut entries = Vec::new();
+        for (stored, timestamp, success) in rows {
+            let Some(args_json) = self.open(&stored) else {
+                tracing::warn!("Skipping history entry that could not be decrypted");
+                continue;
+            };
+            let args = serde_json::from_str(&args_json)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+            entries.push(HistoryEntry {
+                args,
+                timestamp,
+                success,
+            });
+        }
+
+        Ok(entries)
+    }
+}
+
+/// A cached value that saving would replace
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueOverwrite {
+    pub flag: String,
+    pub old: String,
+    pub new: String,
+}
+
+/// Cached values that `save_values` would change, sorted by flag.
+/// Mirrors its filtering: sensitive and empty values are never saved.
+pub fn value_overwrites(
+    cached: &HashMap<String, String>,
+    values: &HashMap<String, String>,
+    options: &[CommandOption],
+) -> Vec<ValueOverwrite> {
+    let sensitive_flags: HashSet<&String> = options
+        .iter()
+        .filter(|o| o.sensitive)
+        .flat_map(|o| o.flags.iter())
+        .collect();
+
+    let mut changes: Vec<ValueOverwrite> = values
+        .iter()
+        .filter(|(flag, value)| !value.is_empty() && !sensitive_flags.contains(flag))
+        .filter_map(|(flag, value)| {
+            let old = cached.get(flag)?;
+            (old != value).then(|| ValueOverwrite {
+                flag: flag.clone(),
+                old: old.clone(),
+                new: value.clone(),
+            })
+        })
+        .collect();
+    changes.sort_by(|a, b| a.flag.cmp(&b.flag));
+    changes
+}
+
+/// History arg key marking an entry as a background launch; the value is the pid
+pub const BACKGROUND_PID_KEY: &str = "_background_pid";
+
+/// History arg key marking an entry as interrupted with Ctrl+C
+pub const INTERRUPTED_KEY: &str = "_interrupted";
+
+/// Secret store key for a command's cached value of `flag`
+fn secret_key(command_name: &str, flag: &str) -> String {
+    format!("{}:{}", command_name, flag)
+}
+
+/// Replace values of sensitive options and positionals with `***`
+fn redact_sensitive(args: &HashMap<String, String>, spec: &CommandSpec) -> HashMap<String, String> {
+    let sensitive_keys: HashSet<String> = spec
+        .options
+        .iter()
+        .filter(|o| o.sensitive)
+        .flat_map(|o| o.flags.iter().cloned())
+        .chain(
+            spec.positional_args
+                .iter()
+                .filter(|a| a.sensitive)
+                .map(|a| format!("_pos_{}", a.name)),
+        )
+        .collect();
+
+    args.iter()
+        .map(|(key, value)| {
+            if sensitive_keys.contains(key) && !value.is_empty() {
+                (key.clone(), "***".to_string())
+            } else {
+                (key.clone(), value.clone())
+            }
+        })
+        .collect()
+}
+
+/// Whether a spec file differs from `last_written`, the spec quocli last saved
+/// for it. A file written by someone else (no database copy) counts as edited.
+fn spec_file_was_edited(path: &Path, last_written: Option<&str>) -> bool {
+    let Ok(contents) = std::fs::read_to_string(path) else {
+        return false;
+    };
+    let parse = |json: &str| serde_json::from_str::<serde_json::Value>(json).ok();
+    match (parse(&contents), last_written.and_then(parse)) {
+        (Some(file), Some(written)) => file != written,
+        _ => true,
     }
 }
 
@@ -267,12 +1142,9 @@ mod tests {
     use crate::parser::{ArgumentType, DangerLevel, OptionLevel, PositionalArg};
     use tempfile::TempDir;
 
-    // Helper to create a test cache in a temporary directory
-    async fn create_test_cache() -> (Cache, TempDir) {
-        let temp_dir = TempDir::new().unwrap();
-        let db_path = temp_dir.path().join("test_cache.db");
-        let cache = Cache::new(&db_path).await.unwrap();
-        (cache, temp_dir)
+    // Helper to create an ephemeral test cache
+    async fn create_test_cache() -> Cache {
+        Cache::new_in_memory().await.unwrap()
     }
 
     // Helper to create a minimal CommandSpec for testing
@@ -287,6 +1159,8 @@ mod tests {
             danger_level: DangerLevel::Low,
             examples: vec!["example1".to_string()],
             positionals_first: false,
+            tool_version: None,
+            basic_only: false,
         }
     }
 
@@ -310,6 +1184,11 @@ mod tests {
                     default: None,
                     enum_values: vec![],
                     level: OptionLevel::Basic,
+                    deprecated: false,
+                    example: None,
+                    min: None,
+                    max: None,
+                    pattern: None,
                 },
                 CommandOption {
                     flags: vec!["--password".to_string()],
@@ -324,6 +1203,11 @@ mod tests {
                     default: None,
                     enum_values: vec![],
                     level: OptionLevel::Basic,
+                    deprecated: false,
+                    example: None,
+                    min: None,
+                    max: None,
+                    pattern: None,
                 },
                 CommandOption {
                     flags: vec!["--output".to_string(), "-o".to_string()],
@@ -338,6 +1222,11 @@ mod tests {
                     default: None,
                     enum_values: vec![],
                     level: OptionLevel::Basic,
+                    deprecated: false,
+                    example: None,
+                    min: None,
+                    max: None,
+                    pattern: None,
                 },
             ],
             positional_args: vec![PositionalArg {
@@ -352,6 +1241,8 @@ mod tests {
             danger_level: DangerLevel::Medium,
             examples: vec![],
             positionals_first: false,
+            tool_version: None,
+            basic_only: false,
         }
     }
 
@@ -374,9 +1265,46 @@ mod tests {
         assert!(db_path.exists());
     }
 
+    #[tokio::test]
+    async fn test_migrations_apply_once_and_adopt_legacy_databases() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("cache.db");
+
+        let cache = Cache::connect(&db_path).await.unwrap();
+        let applied = cache.migrate().await.unwrap();
+        assert_eq!(applied.len(), MIGRATIONS.len());
+        assert_eq!(cache.schema_version().await.unwrap(), MIGRATIONS.last().unwrap().version);
+        assert!(cache.migrate().await.unwrap().is_empty());
+        drop(cache);
+
+        // A database from before versioning already has the tables but no record of them
+        let cache = Cache::connect(&db_path).await.unwrap();
+        cache.save_spec("ls", &create_test_spec("ls")).await.unwrap();
+        sqlx::query("DROP TABLE schema_migrations").execute(&cache.pool).await.unwrap();
+        assert_eq!(cache.migrate().await.unwrap().len(), MIGRATIONS.len());
+        assert!(cache.get_spec("ls").await.unwrap().is_some());
+    }
+
+    #[test]
+    fn test_migration_versions_increase() {
+        assert!(MIGRATIONS.windows(2).all(|pair| pair[0].version < pair[1].version));
+        assert_eq!(MIGRATIONS[0].version, 1);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_caches_are_isolated() {
+        let first = Cache::new_in_memory().await.unwrap();
+        let second = Cache::new_in_memory().await.unwrap();
+
+        first.save_spec("ls", &create_test_spec("ls")).await.unwrap();
+
+        assert!(first.get_spec("ls").await.unwrap().is_some());
+        assert!(second.get_spec("ls").await.unwrap().is_none());
+    }
+
     #[tokio::test]
     async fn test_save_and_get_spec() {
-        let (cache, _temp) = create_test_cache().await;
+        let cache = create_test_cache().await;
         let spec = create_test_spec("ls");
 
         // Save spec
@@ -392,9 +1320,142 @@ mod tests {
         assert_eq!(retrieved.description, "Test command description");
     }
 
+    #[tokio::test]
+    async fn test_spec_dir_is_kept_in_sync_and_preferred() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = create_test_cache().await.with_spec_dir(temp_dir.path());
+
+        let spec = create_test_spec("git");
+        cache.save_spec("git:commit", &spec).await.unwrap();
+        let path = temp_dir.path().join("git__commit.json");
+        assert!(path.exists());
+
+        // An edited (e.g. team-curated) file wins over the database copy
+        let mut curated = spec.clone();
+        curated.description = "Curated description".to_string();
+        std::fs::write(&path, serde_json::to_string(&curated).unwrap()).unwrap();
+        let retrieved = cache.get_spec("git:commit").await.unwrap().unwrap();
+        assert_eq!(retrieved.description, "Curated description");
+
+        // A broken file falls back to the database
+        std::fs::write(&path, "not json").unwrap();
+        let retrieved = cache.get_spec("git:commit").await.unwrap().unwrap();
+        assert_eq!(retrieved.description, "Test command description");
+    }
+
+    #[tokio::test]
+    async fn test_regeneration_keeps_edited_spec_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = create_test_cache().await.with_spec_dir(temp_dir.path());
+        let path = temp_dir.path().join("git.json");
+
+        // Unedited files follow regeneration
+        let mut spec = create_test_spec("git");
+        cache.save_spec("git", &spec).await.unwrap();
+        spec.description = "Regenerated".to_string();
+        cache.save_spec("git", &spec).await.unwrap();
+        assert!(std::fs::read_to_string(&path).unwrap().contains("Regenerated"));
+
+        // A curated file is left alone
+        let mut curated = spec.clone();
+        curated.description = "Curated description".to_string();
+        std::fs::write(&path, serde_json::to_string(&curated).unwrap()).unwrap();
+        spec.description = "Regenerated again".to_string();
+        cache.save_spec("git", &spec).await.unwrap();
+        assert_eq!(cache.get_spec("git").await.unwrap().unwrap().description, "Curated description");
+
+        // So is a shared file this cache never wrote
+        let shared = temp_dir.path().join("tar.json");
+        std::fs::write(&shared, serde_json::to_string(&create_test_spec("tar")).unwrap()).unwrap();
+        cache.save_spec("tar", &spec).await.unwrap();
+        assert_eq!(cache.get_spec("tar").await.unwrap().unwrap().command, "tar");
+    }
+
+    #[tokio::test]
+    async fn test_backdated_spec_is_expired() {
+        let cache = create_test_cache().await;
+        cache.save_spec("ls", &create_test_spec("ls")).await.unwrap();
+        assert!(!cache.is_spec_expired("ls", 30).await.unwrap());
+
+        // Backdate the spec to 31 days ago
+        sqlx::query("UPDATE command_specs SET created_at = created_at - ? WHERE command_name = ?")
+            .bind(31 * 86_400)
+            .bind("ls")
+            .execute(&cache.pool)
+            .await
+            .unwrap();
+        assert!(cache.get_spec_age("ls").await.unwrap().unwrap() >= 31 * 86_400);
+        assert!(cache.is_spec_expired("ls", 30).await.unwrap());
+        assert!(!cache.is_spec_expired("ls", 60).await.unwrap());
+        assert!(!cache.is_spec_expired("ls", 0).await.unwrap());
+        assert!(!cache.is_spec_expired("missing", 30).await.unwrap());
+
+        // Regenerating resets the age
+        cache.save_spec("ls", &create_test_spec("ls")).await.unwrap();
+        assert!(!cache.is_spec_expired("ls", 30).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_list_specs_most_used_first() {
+        let cache = create_test_cache().await;
+        cache.save_spec("ls", &create_test_spec("ls")).await.unwrap();
+        cache.save_spec("git:commit", &create_test_spec("git")).await.unwrap();
+        cache.update_usage("git:commit").await.unwrap();
+        cache.update_usage("git:commit").await.unwrap();
+
+        let specs = cache.list_specs(None).await.unwrap();
+        assert_eq!(specs.len(), 2);
+        assert_eq!(specs[0].display_name(), "git commit");
+        assert_eq!(specs[0].use_count, 3);
+        assert_eq!(specs[0].danger_level, "low");
+        assert_eq!(specs[1].command_name, "ls");
+        assert_eq!(specs[1].use_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_list_specs_filters_by_tag() {
+        let cache = create_test_cache().await;
+        for name in ["kubectl", "helm", "ls"] {
+            cache.save_spec(name, &create_test_spec(name)).await.unwrap();
+        }
+        cache.update_usage("helm").await.unwrap();
+        assert!(cache.add_tag("kubectl", "k8s").await.unwrap());
+        assert!(cache.add_tag("kubectl", "devops").await.unwrap());
+        assert!(cache.add_tag("helm", "k8s").await.unwrap());
+        assert!(!cache.add_tag("helm", "k8s").await.unwrap());
+
+        // Still most used first
+        let specs = cache.list_specs(Some("k8s")).await.unwrap();
+        let names: Vec<&str> = specs.iter().map(|s| s.command_name.as_str()).collect();
+        assert_eq!(names, vec!["helm", "kubectl"]);
+        assert_eq!(specs[1].tags, vec!["devops", "k8s"]);
+
+        assert!(cache.list_specs(Some("none")).await.unwrap().is_empty());
+        let all = cache.list_specs(None).await.unwrap();
+        assert_eq!(all.len(), 3);
+        assert!(all.iter().find(|s| s.command_name == "ls").unwrap().tags.is_empty());
+    }
+
+    #[test]
+    fn test_cached_spec_last_used_ago() {
+        let mut summary = CachedSpecSummary {
+            command_name: "ls".to_string(),
+            danger_level: "low".to_string(),
+            use_count: 1,
+            last_used: None,
+            tags: vec![],
+        };
+        assert_eq!(summary.last_used_ago(1000), "never");
+        summary.last_used = Some(1000);
+        assert_eq!(summary.last_used_ago(1030), "just now");
+        assert_eq!(summary.last_used_ago(1000 + 5 * 60), "5m ago");
+        assert_eq!(summary.last_used_ago(1000 + 2 * 3600), "2h ago");
+        assert_eq!(summary.last_used_ago(1000 + 3 * 86_400), "3d ago");
+    }
+
     #[tokio::test]
     async fn test_get_spec_not_found() {
-        let (cache, _temp) = create_test_cache().await;
+        let cache = create_test_cache().await;
 
         let result = cache.get_spec("nonexistent").await.unwrap();
         assert!(result.is_none());
@@ -402,7 +1463,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_save_spec_updates_existing() {
-        let (cache, _temp) = create_test_cache().await;
+        let cache = create_test_cache().await;
 
         // Save initial spec
         let spec1 = create_test_spec("git");
@@ -422,7 +1483,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_save_spec_preserves_options() {
-        let (cache, _temp) = create_test_cache().await;
+        let cache = create_test_cache().await;
         let spec = create_spec_with_options();
 
         cache.save_spec("test", &spec).await.unwrap();
@@ -438,7 +1499,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_update_usage() {
-        let (cache, _temp) = create_test_cache().await;
+        let cache = create_test_cache().await;
         let spec = create_test_spec("curl");
 
         cache.save_spec("curl", &spec).await.unwrap();
@@ -455,7 +1516,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_save_and_get_values() {
-        let (cache, _temp) = create_test_cache().await;
+        let cache = create_test_cache().await;
         let spec = create_spec_with_options();
 
         let mut values = HashMap::new();
@@ -471,7 +1532,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_save_values_filters_sensitive() {
-        let (cache, _temp) = create_test_cache().await;
+        let cache = create_test_cache().await;
         let spec = create_spec_with_options();
 
         let mut values = HashMap::new();
@@ -483,13 +1544,164 @@ mod tests {
 
         let retrieved = cache.get_values("test").await.unwrap();
         assert_eq!(retrieved.get("--verbose"), Some(&"true".to_string()));
-        assert!(retrieved.get("--password").is_none()); // Should be filtered
+        assert!(!retrieved.contains_key("--password")); // Should be filtered
+        assert_eq!(retrieved.get("--output"), Some(&"/tmp/out.txt".to_string()));
+    }
+
+    /// In-memory secret store; `fail` makes every call error, like a locked keyring
+    #[derive(Default)]
+    struct MemoryStore {
+        secrets: std::sync::Mutex<HashMap<String, String>>,
+        fail: bool,
+    }
+
+    impl MemoryStore {
+        fn check(&self) -> Result<(), keyring::Error> {
+            if self.fail {
+                Err(keyring::Error::NoStorageAccess("locked".into()))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    impl SecretStore for MemoryStore {
+        fn get(&self, key: &str) -> Result<Option<String>, keyring::Error> {
+            self.check()?;
+            Ok(self.secrets.lock().unwrap().get(key).cloned())
+        }
+
+        fn set(&self, key: &str, secret: &str) -> Result<(), keyring::Error> {
+            self.check()?;
+            self.secrets.lock().unwrap().insert(key.to_string(), secret.to_string());
+            Ok(())
+        }
+
+        fn delete(&self, key: &str) -> Result<(), keyring::Error> {
+            self.check()?;
+            self.secrets.lock().unwrap().remove(key);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_save_values_keeps_sensitive_in_secret_store() {
+        let store = Arc::new(MemoryStore::default());
+        let cache = create_test_cache().await.with_secret_store(store.clone());
+        let spec = create_spec_with_options();
+
+        let mut values = HashMap::new();
+        values.insert("--password".to_string(), "secret123".to_string());
+        values.insert("--output".to_string(), "/tmp/out.txt".to_string());
+        cache.save_values("test", &values, &spec.options).await.unwrap();
+
+        assert_eq!(
+            store.secrets.lock().unwrap().get("test:--password").map(String::as_str),
+            Some("secret123")
+        );
+        let stored: String = sqlx::query_scalar(
+            "SELECT value FROM command_values WHERE command_name = 'test' AND flag_name = '--password'",
+        )
+        .fetch_one(&cache.pool)
+        .await
+        .unwrap();
+        assert_eq!(stored, KEYRING_MARKER);
+        assert_eq!(cache.keyring_field_ids("test").await.unwrap(), vec!["--password"]);
+
+        let retrieved = cache.get_values("test").await.unwrap();
+        assert_eq!(retrieved.get("--password"), Some(&"secret123".to_string()));
+        assert_eq!(retrieved.get("--output"), Some(&"/tmp/out.txt".to_string()));
+
+        // Clearing the sensitive fields also forgets the keyring copy
+        cache.clear_field_values("test", &["--password".to_string()]).await.unwrap();
+        assert!(store.secrets.lock().unwrap().is_empty());
+        assert!(!cache.get_values("test").await.unwrap().contains_key("--password"));
+    }
+
+    #[tokio::test]
+    async fn test_save_values_skips_sensitive_when_keyring_fails() {
+        let store = Arc::new(MemoryStore { fail: true, ..Default::default() });
+        let cache = create_test_cache().await.with_secret_store(store);
+        let spec = create_spec_with_options();
+
+        let mut values = HashMap::new();
+        values.insert("--password".to_string(), "secret123".to_string());
+        values.insert("--output".to_string(), "/tmp/out.txt".to_string());
+        cache.save_values("test", &values, &spec.options).await.unwrap();
+
+        let retrieved = cache.get_values("test").await.unwrap();
+        assert!(!retrieved.contains_key("--password"));
         assert_eq!(retrieved.get("--output"), Some(&"/tmp/out.txt".to_string()));
     }
 
+    #[tokio::test]
+    async fn test_presets_round_trip_without_sensitive_values() {
+        let cache = create_test_cache().await;
+        let spec = create_spec_with_options();
+
+        let mut staging = HashMap::new();
+        staging.insert("--output".to_string(), "/tmp/staging".to_string());
+        staging.insert("--password".to_string(), "secret123".to_string());
+        cache.save_preset("test", "staging", &staging, &spec).await.unwrap();
+
+        let mut dev = HashMap::new();
+        dev.insert("--verbose".to_string(), "true".to_string());
+        cache.save_preset("test", "dev", &dev, &spec).await.unwrap();
+
+        assert_eq!(cache.list_presets("test").await.unwrap(), vec!["dev", "staging"]);
+        assert!(cache.list_presets("other").await.unwrap().is_empty());
+
+        let loaded = cache.load_preset("test", "staging").await.unwrap().unwrap();
+        assert_eq!(loaded.get("--output"), Some(&"/tmp/staging".to_string()));
+        assert!(!loaded.contains_key("--password"));
+        assert!(cache.load_preset("test", "prod").await.unwrap().is_none());
+
+        // Saving under an existing name replaces the preset
+        cache.save_preset("test", "dev", &HashMap::new(), &spec).await.unwrap();
+        assert!(cache.load_preset("test", "dev").await.unwrap().unwrap().is_empty());
+        assert_eq!(cache.list_presets("test").await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_notes_survive_spec_regeneration() {
+        let cache = create_test_cache().await;
+        assert!(cache.get_note("aws:s3").await.unwrap().is_none());
+
+        cache.add_note("aws:s3", "set --region for prod").await.unwrap();
+        let note = cache.add_note("aws:s3", "  --dryrun first ").await.unwrap();
+        assert_eq!(note, "set --region for prod\n--dryrun first");
+
+        cache.save_spec("aws:s3", &create_spec_with_options()).await.unwrap();
+        assert_eq!(cache.get_note("aws:s3").await.unwrap().as_deref(), Some(note.as_str()));
+        assert!(cache.get_note("aws").await.unwrap().is_none());
+
+        cache.save_note("aws:s3", "  \n").await.unwrap();
+        assert!(cache.get_note("aws:s3").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_docs_round_trip() {
+        let cache = create_test_cache().await;
+        assert!(cache.get_docs("tar").await.unwrap().is_none());
+
+        let docs = HelpDocumentation {
+            help_text: "Usage: tar [OPTION...]".to_string(),
+            manpage_text: String::new(),
+        };
+        cache.save_docs("tar", &docs).await.unwrap();
+        assert_eq!(cache.get_docs("tar").await.unwrap(), Some(docs));
+
+        let docs = HelpDocumentation {
+            help_text: "Usage: tar [OPTION...]".to_string(),
+            manpage_text: "TAR(1)".to_string(),
+        };
+        cache.save_docs("tar", &docs).await.unwrap();
+        assert_eq!(cache.get_docs("tar").await.unwrap(), Some(docs));
+    }
+
     #[tokio::test]
     async fn test_save_values_skips_empty() {
-        let (cache, _temp) = create_test_cache().await;
+        let cache = create_test_cache().await;
         let spec = create_spec_with_options();
 
         let mut values = HashMap::new();
@@ -500,12 +1712,12 @@ mod tests {
 
         let retrieved = cache.get_values("test").await.unwrap();
         assert_eq!(retrieved.get("--verbose"), Some(&"true".to_string()));
-        assert!(retrieved.get("--output").is_none()); // Should be skipped
+        assert!(!retrieved.contains_key("--output")); // Should be skipped
     }
 
     #[tokio::test]
     async fn test_save_values_updates_existing() {
-        let (cache, _temp) = create_test_cache().await;
+        let cache = create_test_cache().await;
         let spec = create_spec_with_options();
 
         // Save initial values
@@ -526,15 +1738,44 @@ mod tests {
 
     #[tokio::test]
     async fn test_get_values_empty_command() {
-        let (cache, _temp) = create_test_cache().await;
+        let cache = create_test_cache().await;
 
         let retrieved = cache.get_values("nonexistent").await.unwrap();
         assert!(retrieved.is_empty());
     }
 
+    #[test]
+    fn test_value_overwrites() {
+        let spec = create_spec_with_options();
+        let cached = HashMap::from([
+            ("--output".to_string(), "old.txt".to_string()),
+            ("--verbose".to_string(), "true".to_string()),
+            ("--password".to_string(), "stale".to_string()),
+        ]);
+        let values = HashMap::from([
+            ("--output".to_string(), "new.txt".to_string()),
+            ("--verbose".to_string(), "true".to_string()),
+            ("--password".to_string(), "changed".to_string()),
+            ("_pos_file".to_string(), "fresh.txt".to_string()),
+        ]);
+
+        assert_eq!(
+            value_overwrites(&cached, &values, &spec.options),
+            vec![ValueOverwrite {
+                flag: "--output".to_string(),
+                old: "old.txt".to_string(),
+                new: "new.txt".to_string(),
+            }]
+        );
+
+        // Empty values are skipped by save_values, so they never overwrite
+        let cleared = HashMap::from([("--output".to_string(), String::new())]);
+        assert!(value_overwrites(&cached, &cleared, &spec.options).is_empty());
+    }
+
     #[tokio::test]
     async fn test_clear_values() {
-        let (cache, _temp) = create_test_cache().await;
+        let cache = create_test_cache().await;
         let spec = create_spec_with_options();
 
         // Save some values
@@ -551,9 +1792,62 @@ mod tests {
         assert!(retrieved.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_clear_field_values_keeps_other_fields() {
+        let cache = create_test_cache().await;
+        let spec = create_spec_with_options();
+
+        let mut values = HashMap::new();
+        values.insert("--verbose".to_string(), "true".to_string());
+        values.insert("--output".to_string(), "/tmp/out.txt".to_string());
+        cache.save_values("test", &values, &spec.options).await.unwrap();
+
+        let removed = cache
+            .clear_field_values("test", &["--output".to_string(), "--missing".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(removed, 1);
+
+        let retrieved = cache.get_values("test").await.unwrap();
+        assert_eq!(retrieved.len(), 1);
+        assert_eq!(retrieved.get("--verbose"), Some(&"true".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_sensitivity_overrides_roundtrip() {
+        let cache = create_test_cache().await;
+        let spec = create_spec_with_options();
+
+        let mut values = HashMap::new();
+        values.insert("--output".to_string(), "/tmp/out.txt".to_string());
+        values.insert("--verbose".to_string(), "true".to_string());
+        cache.save_values("test", &values, &spec.options).await.unwrap();
+
+        let mut overrides = HashMap::new();
+        overrides.insert("--output".to_string(), true);
+        overrides.insert("--password".to_string(), false);
+        cache.save_sensitivity_overrides("test", &overrides).await.unwrap();
+
+        assert_eq!(cache.get_sensitivity_overrides("test").await.unwrap(), overrides);
+        assert!(cache.get_sensitivity_overrides("other").await.unwrap().is_empty());
+
+        // The newly sensitive value is no longer cached
+        let retrieved = cache.get_values("test").await.unwrap();
+        assert!(!retrieved.contains_key("--output"));
+        assert_eq!(retrieved.get("--verbose"), Some(&"true".to_string()));
+
+        // Toggling back updates the existing row
+        overrides.insert("--output".to_string(), false);
+        cache.save_sensitivity_overrides("test", &overrides).await.unwrap();
+        assert_eq!(
+            cache.get_sensitivity_overrides("test").await.unwrap().get("--output"),
+            Some(&false)
+        );
+    }
+
     #[tokio::test]
     async fn test_clear_values_nonexistent() {
-        let (cache, _temp) = create_test_cache().await;
+        let cache = create_test_cache().await;
 
         // Should not error on nonexistent command
         let result = cache.clear_values("nonexistent").await;
@@ -562,33 +1856,141 @@ mod tests {
 
     #[tokio::test]
     async fn test_log_execution() {
-        let (cache, _temp) = create_test_cache().await;
+        let cache = create_test_cache().await;
 
         let mut args = HashMap::new();
         args.insert("--verbose".to_string(), "true".to_string());
         args.insert("file".to_string(), "input.txt".to_string());
 
+        let spec = create_spec_with_options();
+
         // Log successful execution
-        cache.log_execution("test", &args, true).await.unwrap();
+        cache.log_execution("test", &args, &spec, true).await.unwrap();
 
         // Log failed execution
-        cache.log_execution("test", &args, false).await.unwrap();
+        cache.log_execution("test", &args, &spec, false).await.unwrap();
 
         // We can't directly verify the history without raw SQL, but no error means success
     }
 
+    #[tokio::test]
+    async fn test_log_measurement() {
+        let cache = create_test_cache().await;
+        let spec = create_spec_with_options();
+        let id = cache.log_execution("test", &HashMap::new(), &spec, true).await.unwrap();
+
+        let usage = ResourceUsage {
+            wall: std::time::Duration::from_millis(1500),
+            user_cpu: Some(std::time::Duration::from_millis(700)),
+            system_cpu: None,
+            max_rss_kb: Some(2048),
+        };
+        cache.log_measurement(id, &usage).await.unwrap();
+
+        let row: (i64, Option<i64>, Option<i64>, Option<i64>) = sqlx::query_as(
+            "SELECT wall_ms, user_cpu_ms, system_cpu_ms, max_rss_kb FROM execution_measurements WHERE history_id = ?",
+        )
+        .bind(id)
+        .fetch_one(&cache.pool)
+        .await
+        .unwrap();
+        assert_eq!(row, (1500, Some(700), None, Some(2048)));
+    }
+
     #[tokio::test]
     async fn test_log_execution_empty_args() {
-        let (cache, _temp) = create_test_cache().await;
+        let cache = create_test_cache().await;
 
         let args = HashMap::new();
-        let result = cache.log_execution("test", &args, true).await;
+        let result = cache
+            .log_execution("test", &args, &create_test_spec("test"), true)
+            .await;
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_log_execution_redacts_sensitive() {
+        let cache = create_test_cache().await;
+        let mut spec = create_spec_with_options();
+        spec.positional_args[0].sensitive = true;
+
+        let mut args = HashMap::new();
+        args.insert("--password".to_string(), "hunter2".to_string());
+        args.insert("--output".to_string(), "out.txt".to_string());
+        args.insert("_pos_file".to_string(), "secret.key".to_string());
+        cache.log_execution("test", &args, &spec, true).await.unwrap();
+
+        let (stored,): (String,) = sqlx::query_as("SELECT args_json FROM command_history")
+            .fetch_one(&cache.pool)
+            .await
+            .unwrap();
+        let logged: HashMap<String, String> = serde_json::from_str(&stored).unwrap();
+        assert_eq!(logged.get("--password"), Some(&"***".to_string()));
+        assert_eq!(logged.get("_pos_file"), Some(&"***".to_string()));
+        assert_eq!(logged.get("--output"), Some(&"out.txt".to_string()));
+        assert!(!stored.contains("hunter2"));
+    }
+
+    #[tokio::test]
+    async fn test_log_background_marks_entry() {
+        let cache = create_test_cache().await;
+        let spec = create_spec_with_options();
+
+        let mut args = HashMap::new();
+        args.insert("--password".to_string(), "hunter2".to_string());
+        cache.log_background("test", &args, &spec, 4242).await.unwrap();
+
+        let entries = cache.get_history("test", None, false).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].success, None);
+        assert_eq!(entries[0].args.get(BACKGROUND_PID_KEY), Some(&"4242".to_string()));
+        assert_eq!(entries[0].args.get("--password"), Some(&"***".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_log_interrupted_marks_entry() {
+        let cache = create_test_cache().await;
+        let spec = create_spec_with_options();
+
+        let mut args = HashMap::new();
+        args.insert("--password".to_string(), "hunter2".to_string());
+        cache.log_interrupted("test", &args, &spec).await.unwrap();
+
+        let entries = cache.get_history("test", None, false).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].success, Some(false));
+        assert_eq!(entries[0].args.get(INTERRUPTED_KEY), Some(&"true".to_string()));
+        assert_eq!(entries[0].args.get("--password"), Some(&"***".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_get_history_filters() {
+        let cache = create_test_cache().await.with_encryption("passphrase");
+        let spec = create_spec_with_options();
+
+        let mut args = HashMap::new();
+        args.insert("--output".to_string(), "a.txt".to_string());
+        cache.log_execution("test", &args, &spec, true).await.unwrap();
+        args.insert("--output".to_string(), "b.txt".to_string());
+        cache.log_execution("test", &args, &spec, false).await.unwrap();
+        cache.log_execution("other", &args, &spec, true).await.unwrap();
+
+        let all = cache.get_history("test", None, false).await.unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].args.get("--output"), Some(&"a.txt".to_string()));
+        assert_eq!(all[1].success, Some(false));
+
+        let ok = cache.get_history("test", None, true).await.unwrap();
+        assert_eq!(ok.len(), 1);
+        assert_eq!(ok[0].success, Some(true));
+
+        let future = current_timestamp() + 3600;
+        assert!(cache.get_history("test", Some(future), false).await.unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn test_multiple_commands() {
-        let (cache, _temp) = create_test_cache().await;
+        let cache = create_test_cache().await;
 
         // Save specs for multiple commands
         let spec1 = create_test_spec("ls");
@@ -607,7 +2009,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_values_isolation_between_commands() {
-        let (cache, _temp) = create_test_cache().await;
+        let cache = create_test_cache().await;
         let spec = create_spec_with_options();
 
         // Save values for command1
@@ -630,7 +2032,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_spec_with_all_danger_levels() {
-        let (cache, _temp) = create_test_cache().await;
+        let cache = create_test_cache().await;
 
         for (name, level) in [
             ("low", DangerLevel::Low),
@@ -649,7 +2051,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_spec_with_positionals_first() {
-        let (cache, _temp) = create_test_cache().await;
+        let cache = create_test_cache().await;
 
         let mut spec = create_test_spec("find");
         spec.positionals_first = true;
@@ -662,7 +2064,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_spec_with_subcommands() {
-        let (cache, _temp) = create_test_cache().await;
+        let cache = create_test_cache().await;
 
         let mut spec = create_test_spec("git");
         spec.subcommands = vec!["commit".to_string(), "push".to_string(), "pull".to_string()];
@@ -675,7 +2077,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_spec_with_examples() {
-        let (cache, _temp) = create_test_cache().await;
+        let cache = create_test_cache().await;
 
         let mut spec = create_test_spec("curl");
         spec.examples = vec![
@@ -691,7 +2093,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_values_with_special_characters() {
-        let (cache, _temp) = create_test_cache().await;
+        let cache = create_test_cache().await;
         let spec = create_spec_with_options();
 
         let mut values = HashMap::new();
@@ -706,9 +2108,82 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_encrypted_values_roundtrip() {
+        let cache = create_test_cache().await.with_encryption("passphrase");
+        let spec = create_spec_with_options();
+
+        let mut values = HashMap::new();
+        values.insert("--output".to_string(), "/private/out.txt".to_string());
+        cache.save_values("test", &values, &spec.options).await.unwrap();
+
+        let (stored,): (String,) =
+            sqlx::query_as("SELECT value FROM command_values WHERE flag_name = '--output'")
+                .fetch_one(&cache.pool)
+                .await
+                .unwrap();
+        assert!(is_encrypted(&stored));
+        assert!(!stored.contains("private"));
+
+        let retrieved = cache.get_values("test").await.unwrap();
+        assert_eq!(retrieved.get("--output"), Some(&"/private/out.txt".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_values_unreadable_without_key() {
+        let mut cache = create_test_cache().await.with_encryption("right");
+        let spec = create_spec_with_options();
+
+        let mut values = HashMap::new();
+        values.insert("--output".to_string(), "/private/out.txt".to_string());
+        cache.save_values("test", &values, &spec.options).await.unwrap();
+
+        // Ciphertext is never handed back as a value
+        cache.cipher = None;
+        assert!(cache.get_values("test").await.unwrap().is_empty());
+
+        cache.cipher = Some(CacheCipher::from_passphrase("wrong"));
+        assert!(cache.get_values("test").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_encryption_reads_existing_plaintext() {
+        let cache = create_test_cache().await;
+        let spec = create_spec_with_options();
+
+        let mut values = HashMap::new();
+        values.insert("--output".to_string(), "plain.txt".to_string());
+        cache.save_values("test", &values, &spec.options).await.unwrap();
+
+        let cache = cache.with_encryption("passphrase");
+        let retrieved = cache.get_values("test").await.unwrap();
+        assert_eq!(retrieved.get("--output"), Some(&"plain.txt".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_log_execution() {
+        let cache = create_test_cache().await.with_encryption("passphrase");
+
+        let mut args = HashMap::new();
+        args.insert("--host".to_string(), "internal.example".to_string());
+        cache
+            .log_execution("ssh", &args, &create_test_spec("ssh"), true)
+            .await
+            .unwrap();
+
+        let (stored,): (String,) = sqlx::query_as("SELECT args_json FROM command_history")
+            .fetch_one(&cache.pool)
+            .await
+            .unwrap();
+        assert!(is_encrypted(&stored));
+        assert!(!stored.contains("internal.example"));
+    }
+
     #[tokio::test]
     async fn test_concurrent_operations() {
-        let (cache, _temp) = create_test_cache().await;
+        // File-backed so the saves actually spread across pooled connections
+        let temp_dir = TempDir::new().unwrap();
+        let cache = Cache::new(&temp_dir.path().join("test_cache.db")).await.unwrap();
 
         // Perform multiple concurrent saves
         let futures: Vec<_> = (0..10)
@@ -1,3 +1,8 @@
+mod crypto;
 mod db;
 
-pub use db::Cache;
+pub use crypto::{keyring_passphrase, CacheCipher, OsKeyring, SecretStore};
+pub use db::{
+    value_overwrites, Cache, CachedSpecSummary, HistoryEntry, Migration, ValueOverwrite,
+    BACKGROUND_PID_KEY, INTERRUPTED_KEY,
+};
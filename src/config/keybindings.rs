@@ -0,0 +1,349 @@
+use crate::QuocliError;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A `[keybindings]` entry: one key string, or several
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum KeySpec {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeySpec {
+    fn keys(&self) -> &[String] {
+        match self {
+            KeySpec::One(key) => std::slice::from_ref(key),
+            KeySpec::Many(keys) => keys,
+        }
+    }
+}
+
+/// Form actions that can be bound to keys in `[keybindings]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    Help,
+    AcceptEnvHint,
+    Jump,
+    ToggleSensitive,
+    TypeFilter,
+    ToggleSection,
+    Assistant,
+    AddValue,
+    RemoveValue,
+    Execute,
+    Background,
+    Preview,
+    ExportInvocation,
+    Clear,
+    ClearSensitive,
+    Copy,
+    LoadPreset,
+    SavePreset,
+    Note,
+    ScrollUp,
+    ScrollDown,
+    Search,
+    SearchDescriptions,
+    NextTab,
+    PrevTab,
+    TabBasic,
+    TabAdvanced,
+    TabFrequent,
+    TabExamples,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Top,
+    Bottom,
+    Edit,
+    NextField,
+    PrevField,
+}
+
+impl Action {
+    /// Every action with its config name and default keys
+    const ALL: &'static [(Action, &'static str, &'static [&'static str])] = &[
+        (Action::Quit, "quit", &["q", "esc"]),
+        (Action::Help, "help", &["?"]),
+        (Action::AcceptEnvHint, "accept_env_hint", &["$"]),
+        (Action::Jump, "jump", &["g"]),
+        (Action::ToggleSensitive, "toggle_sensitive", &["s"]),
+        (Action::TypeFilter, "type_filter", &["t"]),
+        (Action::ToggleSection, "toggle_section", &["a"]),
+        (Action::Assistant, "assistant", &["ctrl+a"]),
+        (Action::AddValue, "add_value", &["+"]),
+        (Action::RemoveValue, "remove_value", &["-"]),
+        (Action::Execute, "execute", &["ctrl+e"]),
+        (Action::Background, "background", &["ctrl+b"]),
+        (Action::Preview, "preview", &["ctrl+p"]),
+        (Action::ExportInvocation, "export_invocation", &["ctrl+o"]),
+        (Action::Clear, "clear", &["ctrl+x"]),
+        (Action::ClearSensitive, "clear_sensitive", &["X"]),
+        (Action::Copy, "copy", &["ctrl+y"]),
+        (Action::LoadPreset, "load_preset", &["p"]),
+        (Action::SavePreset, "save_preset", &["P"]),
+        (Action::Note, "note", &["n"]),
+        (Action::ScrollUp, "scroll_up", &["ctrl+up"]),
+        (Action::ScrollDown, "scroll_down", &["ctrl+down"]),
+        (Action::Search, "search", &["/"]),
+        (Action::SearchDescriptions, "search_descriptions", &["ctrl+/"]),
+        (Action::NextTab, "next_tab", &["`"]),
+        (Action::PrevTab, "prev_tab", &[]),
+        (Action::TabBasic, "tab_basic", &["1"]),
+        (Action::TabAdvanced, "tab_advanced", &["2"]),
+        (Action::TabFrequent, "tab_frequent", &["3"]),
+        (Action::TabExamples, "tab_examples", &["4"]),
+        (Action::Up, "up", &["up", "k"]),
+        (Action::Down, "down", &["down", "j"]),
+        (Action::PageUp, "page_up", &["pageup"]),
+        (Action::PageDown, "page_down", &["pagedown"]),
+        (Action::Top, "top", &["home"]),
+        (Action::Bottom, "bottom", &["end"]),
+        (Action::Edit, "edit", &["enter"]),
+        (Action::NextField, "next_field", &["tab"]),
+        (Action::PrevField, "prev_field", &["shift+tab"]),
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|(_, action_name, _)| *action_name == name)
+            .map(|(action, _, _)| *action)
+    }
+}
+
+/// A key with its modifiers, normalized so it compares equal to the event crossterm reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    /// Shift is implied by the character itself (`X`, `?`) or by BackTab,
+    /// and terminals disagree on whether they report it, so it is ignored there
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let modifiers = match code {
+            KeyCode::Char(_) | KeyCode::BackTab => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        Self { code, modifiers }
+    }
+}
+
+/// Parse a key string like `x`, `X`, `ctrl+e`, `alt+enter` or `shift+tab`
+pub fn parse_key(spec: &str) -> Result<(KeyCode, KeyModifiers), QuocliError> {
+    let invalid = |reason: &str| QuocliError::Config(format!("Invalid key \"{}\" in [keybindings]: {}", spec, reason));
+
+    // Split off modifiers, taking care that "+" and "ctrl++" name the plus key
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = spec;
+    while let Some((modifier, key)) = rest.split_once('+').filter(|(_, key)| !key.is_empty()) {
+        modifiers |= match modifier.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => KeyModifiers::CONTROL,
+            "alt" => KeyModifiers::ALT,
+            "shift" => KeyModifiers::SHIFT,
+            _ => return Err(invalid(&format!("unknown modifier \"{}\"", modifier))),
+        };
+        rest = key;
+    }
+
+    let mut chars = rest.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        (None, _) => return Err(invalid("no key given")),
+        _ => match rest.to_ascii_lowercase().as_str() {
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "space" => KeyCode::Char(' '),
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                Some(n @ 1..=12) => KeyCode::F(n),
+                _ => return Err(invalid("unknown key name")),
+            },
+        },
+    };
+    Ok((code, modifiers))
+}
+
+/// How a parsed key is shown in help text, e.g. `Ctrl+E`, `↓` or `X`
+fn display_key(code: KeyCode, modifiers: KeyModifiers) -> String {
+    let mut label = String::new();
+    if modifiers.contains(KeyModifiers::CONTROL) {
+        label.push_str("Ctrl+");
+    }
+    if modifiers.contains(KeyModifiers::ALT) {
+        label.push_str("Alt+");
+    }
+    let shifted = modifiers.contains(KeyModifiers::SHIFT);
+    if shifted && !matches!(code, KeyCode::Char(_) | KeyCode::BackTab) {
+        label.push_str("Shift+");
+    }
+    match code {
+        KeyCode::Char(' ') => label.push_str("Space"),
+        KeyCode::Char(c) if label.is_empty() => label.push(c),
+        KeyCode::Char(c) => label.push(c.to_ascii_uppercase()),
+        KeyCode::BackTab => label.push_str("Shift+Tab"),
+        KeyCode::Up => label.push('↑'),
+        KeyCode::Down => label.push('↓'),
+        KeyCode::Left => label.push('←'),
+        KeyCode::Right => label.push('→'),
+        KeyCode::PageUp => label.push_str("PgUp"),
+        KeyCode::PageDown => label.push_str("PgDn"),
+        KeyCode::Delete => label.push_str("Del"),
+        KeyCode::Insert => label.push_str("Ins"),
+        KeyCode::F(n) => label.push_str(&format!("F{}", n)),
+        other => label.push_str(&format!("{:?}", other)),
+    }
+    label
+}
+
+/// Key to action lookup for the form, from the defaults plus `[keybindings]`
+#[derive(Debug, Clone)]
+pub struct KeyBindings {
+    keys: HashMap<Key, Action>,
+    labels: HashMap<Action, Vec<String>>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self::from_config(&HashMap::new()).expect("default keybindings are valid")
+    }
+}
+
+impl KeyBindings {
+    /// Apply `[keybindings]` over the defaults. Binding an action replaces its
+    /// default keys, and a key bound by the user takes precedence over a default use
+    /// of the same key. Unknown actions and malformed keys are config errors.
+    pub fn from_config(config: &HashMap<String, KeySpec>) -> Result<Self, QuocliError> {
+        let mut custom = Vec::new();
+        for (name, spec) in config {
+            let action = Action::from_name(name).ok_or_else(|| {
+                QuocliError::Config(format!("Unknown action \"{}\" in [keybindings]", name))
+            })?;
+            custom.push((action, spec.keys().to_vec()));
+        }
+
+        let mut bindings = Self {
+            keys: HashMap::new(),
+            labels: HashMap::new(),
+        };
+        for (action, name, defaults) in Action::ALL {
+            if !config.contains_key(*name) {
+                bindings.bind(*action, defaults.iter().map(|key| key.to_string()).collect())?;
+            }
+        }
+        for (action, keys) in custom {
+            bindings.bind(action, keys)?;
+        }
+        Ok(bindings)
+    }
+
+    fn bind(&mut self, action: Action, keys: Vec<String>) -> Result<(), QuocliError> {
+        let mut labels = Vec::new();
+        for key in &keys {
+            let (code, modifiers) = parse_key(key)?;
+            self.keys.insert(Key::new(code, modifiers), action);
+            labels.push(display_key(code, modifiers));
+        }
+        self.labels.insert(action, labels);
+        Ok(())
+    }
+
+    /// The action bound to a key press, if any
+    pub fn action(&self, event: &KeyEvent) -> Option<Action> {
+        self.keys.get(&Key::new(event.code, event.modifiers)).copied()
+    }
+
+    /// The keys bound to an action, for help text (e.g. `Ctrl+E`, or `↑/k`)
+    pub fn label(&self, action: Action) -> String {
+        self.labels.get(&action).map(|keys| keys.join("/")).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(parse_key("x").unwrap(), (KeyCode::Char('x'), KeyModifiers::NONE));
+        assert_eq!(parse_key("ctrl+e").unwrap(), (KeyCode::Char('e'), KeyModifiers::CONTROL));
+        assert_eq!(parse_key("Ctrl+Alt+Enter").unwrap(), (KeyCode::Enter, KeyModifiers::CONTROL | KeyModifiers::ALT));
+        assert_eq!(parse_key("+").unwrap(), (KeyCode::Char('+'), KeyModifiers::NONE));
+        assert_eq!(parse_key("ctrl++").unwrap(), (KeyCode::Char('+'), KeyModifiers::CONTROL));
+        assert_eq!(parse_key("shift+tab").unwrap().0, KeyCode::BackTab);
+        assert_eq!(parse_key("f5").unwrap().0, KeyCode::F(5));
+
+        assert!(parse_key("").is_err());
+        assert!(parse_key("hyper+x").is_err());
+        assert!(parse_key("ctrl+banana").is_err());
+    }
+
+    #[test]
+    fn test_defaults() {
+        let bindings = KeyBindings::default();
+        assert_eq!(bindings.action(&press(KeyCode::Char('e'), KeyModifiers::CONTROL)), Some(Action::Execute));
+        assert_eq!(bindings.action(&press(KeyCode::Char('a'), KeyModifiers::NONE)), Some(Action::ToggleSection));
+        assert_eq!(bindings.action(&press(KeyCode::Char('a'), KeyModifiers::CONTROL)), Some(Action::Assistant));
+        // Terminals report Shift with uppercase letters
+        assert_eq!(bindings.action(&press(KeyCode::Char('X'), KeyModifiers::SHIFT)), Some(Action::ClearSensitive));
+        assert_eq!(bindings.action(&press(KeyCode::BackTab, KeyModifiers::SHIFT)), Some(Action::PrevField));
+        assert_eq!(bindings.action(&press(KeyCode::Char('h'), KeyModifiers::NONE)), None);
+        assert_eq!(bindings.label(Action::Down), "↓/j");
+        assert_eq!(bindings.label(Action::Execute), "Ctrl+E");
+        assert_eq!(bindings.label(Action::Quit), "q/Esc");
+    }
+
+    #[test]
+    fn test_custom_bindings_replace_defaults() {
+        let config = HashMap::from([
+            ("execute".to_string(), KeySpec::One("ctrl+r".to_string())),
+            ("prev_tab".to_string(), KeySpec::One("h".to_string())),
+            ("next_tab".to_string(), KeySpec::Many(vec!["l".to_string(), "`".to_string()])),
+            // Takes "a" away from toggle_section
+            ("add_value".to_string(), KeySpec::One("a".to_string())),
+        ]);
+        let bindings = KeyBindings::from_config(&config).unwrap();
+
+        assert_eq!(bindings.action(&press(KeyCode::Char('r'), KeyModifiers::CONTROL)), Some(Action::Execute));
+        assert_eq!(bindings.action(&press(KeyCode::Char('e'), KeyModifiers::CONTROL)), None);
+        assert_eq!(bindings.action(&press(KeyCode::Char('h'), KeyModifiers::NONE)), Some(Action::PrevTab));
+        assert_eq!(bindings.action(&press(KeyCode::Char('l'), KeyModifiers::NONE)), Some(Action::NextTab));
+        assert_eq!(bindings.action(&press(KeyCode::Char('a'), KeyModifiers::NONE)), Some(Action::AddValue));
+        assert_eq!(bindings.label(Action::NextTab), "l/`");
+    }
+
+    #[test]
+    fn test_invalid_bindings_are_config_errors() {
+        let unknown = HashMap::from([("explode".to_string(), KeySpec::One("x".to_string()))]);
+        let err = KeyBindings::from_config(&unknown).unwrap_err();
+        assert!(matches!(err, QuocliError::Config(_)));
+        assert!(err.to_string().contains("Unknown action \"explode\""), "{}", err);
+
+        let malformed = HashMap::from([("execute".to_string(), KeySpec::One("ctrl+".to_string()))]);
+        let err = KeyBindings::from_config(&malformed).unwrap_err();
+        assert!(err.to_string().contains("Invalid key \"ctrl+\""), "{}", err);
+    }
+}
@@ -1,26 +1,126 @@
-use super::Config;
+use super::{Config, KeyBindings};
 use anyhow::Result;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-/// Load configuration from file or return defaults
-pub fn load_config() -> Result<Config> {
-    let config_path = get_config_path();
+/// Environment variable naming a config file to use instead of the default
+pub const CONFIG_ENV: &str = "QUOCLI_CONFIG";
 
-    if config_path.exists() {
+/// Environment variable naming a cache database to use instead of `cache.path`
+pub const CACHE_ENV: &str = "QUOCLI_CACHE";
+
+/// Load configuration from file or return defaults, with the named
+/// `[profiles.<name>]` table merged over the base settings
+pub fn load_config(profile: Option<&str>) -> Result<Config> {
+    let config_path = config_path();
+    // A config named explicitly is never replaced by defaults, so a typo can't go unnoticed
+    if env_path(CONFIG_ENV).is_some() && !config_path.exists() {
+        anyhow::bail!("{} names {}, which does not exist", CONFIG_ENV, config_path.display());
+    }
+
+    let mut config = if config_path.exists() {
         let contents = std::fs::read_to_string(&config_path)?;
-        let config: Config = toml::from_str(&contents)?;
-        Ok(config)
+        let mut config = parse_config(&contents, profile)
+            .map_err(|e| e.context(format!("in {}", config_path.display())))?;
+        expand_config_paths(&mut config);
+        config
+    } else if let Some(name) = profile {
+        anyhow::bail!("No profile named '{}': {} does not exist", name, config_path.display());
     } else {
-        // Create default config directory if it doesn't exist
-        if let Some(parent) = config_path.parent() {
-            std::fs::create_dir_all(parent)?;
+        // Write a starter config at the default location
+        write_template(&config_path);
+        Config::default()
+    };
+
+    if let Some(cache_path) = env_path(CACHE_ENV) {
+        config.cache.path = cache_path;
+    }
+    Ok(config)
+}
+
+/// Write the commented starter config, warning (but carrying on with defaults) on failure
+fn write_template(config_path: &Path) {
+    let written = config_path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(config_path, Config::default_toml_template()));
+    match written {
+        Ok(()) => tracing::info!("Wrote a starter config to {}", config_path.display()),
+        Err(e) => tracing::warn!("Could not write a starter config to {}: {}", config_path.display(), e),
+    }
+}
+
+/// Parse config TOML, applying the named profile if given
+fn parse_config(contents: &str, profile: Option<&str>) -> Result<Config> {
+    let mut table: toml::Table = contents.parse()?;
+    let profiles = table.remove("profiles");
+
+    if let Some(name) = profile {
+        let overlay = profiles
+            .as_ref()
+            .and_then(|profiles| profiles.get(name))
+            .and_then(|overlay| overlay.as_table())
+            .ok_or_else(|| anyhow::anyhow!("No profile named '{}' in the config", name))?;
+        merge_profile(&mut table, overlay);
+    }
+
+    let config: Config = toml::Value::Table(table).try_into()?;
+    // Catch typos in [keybindings] now rather than silently ignoring a key in the form
+    KeyBindings::from_config(&config.keybindings)?;
+    if !matches!(config.ui.confirm_execute.as_str(), "off" | "twice" | "enter") {
+        anyhow::bail!(
+            "ui.confirm_execute must be \"off\", \"twice\" or \"enter\", not \"{}\"",
+            config.ui.confirm_execute
+        );
+    }
+    Ok(config)
+}
+
+/// Overlay a profile onto the base config: tables merge key by key, and any
+/// other value present in the overlay replaces the base one
+fn merge_profile(base: &mut toml::Table, overlay: &toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(key), value) {
+            (Some(toml::Value::Table(base_table)), toml::Value::Table(overlay_table)) => {
+                merge_profile(base_table, overlay_table);
+            }
+            _ => {
+                base.insert(key.clone(), value.clone());
+            }
         }
-        Ok(Config::default())
     }
 }
 
-/// Get the path to the config file
-fn get_config_path() -> PathBuf {
+/// A non-empty path from an environment variable
+fn env_path(name: &str) -> Option<PathBuf> {
+    std::env::var_os(name)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Expand a leading `~` in a configured path
+pub fn expand_tilde(path: &Path) -> PathBuf {
+    PathBuf::from(shellexpand::tilde(&path.to_string_lossy()).to_string())
+}
+
+/// Expand tildes in every path-valued config field
+fn expand_config_paths(config: &mut Config) {
+    config.cache.path = expand_tilde(&config.cache.path);
+    if let Some(dir) = &config.cache.spec_dir {
+        config.cache.spec_dir = Some(expand_tilde(dir));
+    }
+    if let Some(path) = &config.llm.api_key_file {
+        config.llm.api_key_file = Some(expand_tilde(path));
+    }
+    if let Some(path) = &config.logging.file {
+        config.logging.file = Some(expand_tilde(path));
+    }
+}
+
+/// Get the path to the config file: `QUOCLI_CONFIG` if set, else the platform config dir
+pub fn config_path() -> PathBuf {
+    if let Some(path) = env_path(CONFIG_ENV) {
+        return path;
+    }
     directories::ProjectDirs::from("", "", "quocli")
         .map(|dirs| dirs.config_dir().join("config.toml"))
         .unwrap_or_else(|| PathBuf::from("~/.config/quocli/config.toml"))
@@ -29,6 +129,7 @@ fn get_config_path() -> PathBuf {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_env::EnvGuard;
     use tempfile::TempDir;
 
     #[test]
@@ -43,8 +144,15 @@ mod tests {
         let config = super::super::LlmConfig::default();
         assert_eq!(config.provider, "anthropic");
         assert_eq!(config.api_key_env, "ANTHROPIC_API_KEY");
+        assert!(config.api_key_file.is_none());
         assert_eq!(config.model, "claude-sonnet-4-5-20250929");
         assert_eq!(config.fallback_model, "claude-haiku-4-5-20250514");
+        assert_eq!(config.max_options, 150);
+        assert!(config.base_url.is_none());
+        assert!(config.max_concurrent_requests.is_none());
+        assert!(config.provider_command.is_none());
+        assert_eq!(config.chat_timeout_secs, 30);
+        assert!(config.auto_single_pass);
     }
 
     #[test]
@@ -52,16 +160,42 @@ mod tests {
         let config = super::super::CacheConfig::default();
         assert!(config.auto_refresh);
         assert_eq!(config.ttl_days, 30);
+        assert!(!config.confirm_value_overwrite);
+        assert!(config.spec_dir.is_none());
         // Path should end with cache.db
         assert!(config.path.to_string_lossy().ends_with("cache.db"));
     }
 
+    #[test]
+    fn test_fallback_cache_path() {
+        let xdg = super::super::fallback_cache_path(Some("/tmp/xdg-data".into()));
+        assert_eq!(xdg, PathBuf::from("/tmp/xdg-data/quocli/cache.db"));
+
+        // A relative XDG_DATA_HOME is invalid per the spec and must be ignored
+        for path in [
+            super::super::fallback_cache_path(Some("relative/dir".into())),
+            super::super::fallback_cache_path(None),
+        ] {
+            assert!(path.is_absolute(), "{:?} should be absolute", path);
+            assert!(!path.to_string_lossy().contains('~'));
+            assert!(path.ends_with("quocli/cache.db"));
+        }
+    }
+
     #[test]
     fn test_default_ui_config() {
         let config = super::super::UiConfig::default();
         assert_eq!(config.theme, "dark");
+        assert!(config.colors.is_empty());
         assert!(config.show_examples);
         assert!(config.preview_command);
+        assert_eq!(config.field_order, "documented");
+        assert!(config.description_wrap_width.is_none());
+        assert_eq!(config.label_style, "friendly");
+        assert!(!config.review_before_exec);
+        assert!(!config.render_markdown);
+        assert_eq!(config.confirm_execute, "off");
+        assert!(config.strict_numeric_input);
     }
 
     #[test]
@@ -70,6 +204,8 @@ mod tests {
         assert_eq!(config.shell_type, "auto");
         assert_eq!(config.history_file, "auto");
         assert!(config.export_envvars);
+        assert!(config.history_marker);
+        assert!(!config.zsh_interactive_comments);
     }
 
     #[test]
@@ -78,6 +214,36 @@ mod tests {
         assert!(!config.keyring_integration);
         assert!(config.confirm_dangerous);
         assert!(config.audit_log);
+        assert!(!config.confirm_before_form);
+        assert!(!config.encrypt_cache);
+    }
+
+    #[test]
+    fn test_default_parser_config() {
+        let config = super::super::ParserConfig::default();
+        assert_eq!(config.ignore_flags, vec!["-h", "--help", "--version"]);
+    }
+
+    #[test]
+    fn test_default_help_config() {
+        let config = super::super::HelpConfig::default();
+        assert!(!config.pty_fallback);
+        assert_eq!(config.timeout_secs, 10);
+        assert!(!config.basic_only);
+    }
+
+    #[test]
+    fn test_default_execution_config() {
+        let config = super::super::ExecutionConfig::default();
+        assert_eq!(config.timeout_secs, None);
+    }
+
+    #[test]
+    fn test_default_logging_config() {
+        let config = super::super::LoggingConfig::default();
+        assert_eq!(config.file, None);
+        assert_eq!(config.level, "debug");
+        assert_eq!(config.max_size, 10 * 1024 * 1024);
     }
 
     #[test]
@@ -132,6 +298,130 @@ audit_log = false
         assert!(!config.security.confirm_dangerous);
     }
 
+    #[test]
+    fn test_load_config_honors_env_overrides() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("project.toml");
+        std::fs::write(&config_path, "[llm]\nmodel = \"project-model\"\n").unwrap();
+        let cache_path = temp_dir.path().join("project-cache.db");
+
+        let mut env = EnvGuard::lock();
+        env.set(CONFIG_ENV, &config_path).set(CACHE_ENV, &cache_path);
+        let config = load_config(None).unwrap();
+        assert_eq!(super::config_path(), config_path);
+        assert_eq!(config.llm.model, "project-model");
+        assert_eq!(config.cache.path, cache_path);
+    }
+
+    #[test]
+    fn test_load_config_rejects_missing_env_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("typo.toml");
+
+        let mut env = EnvGuard::lock();
+        env.set(CONFIG_ENV, &config_path);
+        let err = load_config(None).unwrap_err();
+        assert!(err.to_string().contains("does not exist"), "{}", err);
+        // No starter config is written in its place
+        assert!(!config_path.exists());
+    }
+
+    #[test]
+    fn test_profile_overrides_only_its_own_fields() {
+        let config_content = r#"
+[llm]
+provider = "anthropic"
+model = "base-model"
+
+[ui]
+theme = "light"
+
+[profiles.work.llm]
+provider = "ollama"
+base_url = "http://gpu-box:11434/api/chat"
+
+[profiles.work.cache]
+ttl_days = 7
+"#;
+
+        let base = parse_config(config_content, None).unwrap();
+        assert_eq!(base.llm.provider, "anthropic");
+        assert_eq!(base.cache.ttl_days, 30);
+
+        let work = parse_config(config_content, Some("work")).unwrap();
+        assert_eq!(work.llm.provider, "ollama");
+        assert_eq!(work.llm.base_url.as_deref(), Some("http://gpu-box:11434/api/chat"));
+        assert_eq!(work.cache.ttl_days, 7);
+        // Untouched by the profile: the base value, then the default
+        assert_eq!(work.llm.model, "base-model");
+        assert_eq!(work.ui.theme, "light");
+        assert!(work.cache.auto_refresh);
+
+        let err = parse_config(config_content, Some("home")).unwrap_err();
+        assert!(err.to_string().contains("No profile named 'home'"), "{}", err);
+    }
+
+    #[test]
+    fn test_merge_profile_replaces_non_table_values() {
+        let mut base: toml::Table = "ignore = [\"-h\"]\n[a]\nx = 1\ny = 2\n".parse().unwrap();
+        let overlay: toml::Table = "ignore = [\"-v\"]\n[a]\ny = 3\nz = 4\n".parse().unwrap();
+        merge_profile(&mut base, &overlay);
+
+        let expected: toml::Table = "ignore = [\"-v\"]\n[a]\nx = 1\ny = 3\nz = 4\n".parse().unwrap();
+        assert_eq!(base, expected);
+    }
+
+    #[test]
+    fn test_template_parses_to_defaults() {
+        let template = Config::default_toml_template();
+        let config = parse_config(template, None).unwrap();
+        assert_eq!(
+            toml::to_string(&config).unwrap(),
+            toml::to_string(&Config::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_template_documents_every_setting() {
+        let template = Config::default_toml_template();
+        let defaults = toml::Value::try_from(Config::default()).unwrap();
+        for (section, table) in defaults.as_table().unwrap() {
+            // commands and workflows are maps with no default entries
+            if table.as_table().unwrap().is_empty() {
+                continue;
+            }
+            assert!(template.contains(&format!("[{}]", section)), "missing [{}]", section);
+            for key in table.as_table().unwrap().keys() {
+                assert!(
+                    template.contains(&format!("# {} = ", key)),
+                    "missing {}.{}",
+                    section,
+                    key
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_keybindings_are_validated_on_load() {
+        let config = parse_config("[keybindings]\nexecute = \"ctrl+r\"\nnext_tab = [\"l\", \"`\"]\n", None).unwrap();
+        assert_eq!(config.keybindings.len(), 2);
+
+        let err = parse_config("[keybindings]\nexecut = \"ctrl+r\"\n", None).unwrap_err();
+        assert!(err.to_string().contains("Unknown action \"execut\""), "{}", err);
+        let err = parse_config("[keybindings]\nexecute = \"cmd+r\"\n", None).unwrap_err();
+        assert!(err.to_string().contains("unknown modifier"), "{}", err);
+    }
+
+    #[test]
+    fn test_confirm_execute_is_validated_on_load() {
+        let config = parse_config("[ui]\nconfirm_execute = \"twice\"\n", None).unwrap();
+        assert_eq!(config.ui.confirm_execute, "twice");
+
+        let err = parse_config("[ui]\nconfirm_execute = \"yes\"\n", None).unwrap_err();
+        assert!(err.to_string().contains("ui.confirm_execute"), "{}", err);
+    }
+
     #[test]
     fn test_partial_config_uses_defaults() {
         let config_content = r#"
@@ -180,6 +470,87 @@ ttl_days = 90
         assert_eq!(config.ui.theme, deserialized.ui.theme);
     }
 
+    #[test]
+    fn test_command_config_lookup() {
+        let config_content = r#"
+[commands.ffmpeg]
+help_command = "ffmpeg -h full"
+model = "claude-opus-4"
+
+[commands."git commit"]
+help_command = "git help commit | cat"
+
+[commands.dd]
+confirm_before_form = true
+"#;
+
+        let config: Config = toml::from_str(config_content).unwrap();
+
+        let ffmpeg = config.command_config("ffmpeg", &[]).unwrap();
+        assert_eq!(ffmpeg.help_command.as_deref(), Some("ffmpeg -h full"));
+        assert_eq!(ffmpeg.model.as_deref(), Some("claude-opus-4"));
+
+        let commit = config
+            .command_config("git", &["commit".to_string()])
+            .unwrap();
+        assert_eq!(commit.help_command.as_deref(), Some("git help commit | cat"));
+        assert_eq!(commit.confirm_before_form, None);
+        assert!(commit.model.is_none());
+
+        let dd = config.command_config("dd", &[]).unwrap();
+        assert_eq!(dd.confirm_before_form, Some(true));
+        assert!(dd.help_command.is_none());
+
+        assert!(config.command_config("git", &[]).is_none());
+        assert!(Config::default().commands.is_empty());
+    }
+
+    #[test]
+    fn test_expand_config_paths() {
+        let config_content = r#"
+[llm]
+api_key_file = "~/secrets/anthropic"
+
+[cache]
+path = "~/caches/q.db"
+spec_dir = "~/team/specs"
+"#;
+
+        let mut config: Config = toml::from_str(config_content).unwrap();
+        expand_config_paths(&mut config);
+
+        let home = shellexpand::tilde("~").to_string();
+        assert_eq!(config.cache.path, Path::new(&home).join("caches/q.db"));
+        assert_eq!(config.cache.spec_dir, Some(Path::new(&home).join("team/specs")));
+        assert_eq!(
+            config.llm.api_key_file,
+            Some(Path::new(&home).join("secrets/anthropic"))
+        );
+        assert_eq!(expand_tilde(Path::new("/abs/path")), PathBuf::from("/abs/path"));
+    }
+
+    #[test]
+    fn test_workflow_config() {
+        let config_content = r#"
+[workflows.release]
+description = "Stage, commit and push"
+steps = ["git add", "git  commit", "", "git push"]
+"#;
+
+        let config: Config = toml::from_str(config_content).unwrap();
+        let release = &config.workflows["release"];
+        assert_eq!(release.description.as_deref(), Some("Stage, commit and push"));
+        assert_eq!(
+            release.step_parts(),
+            vec![
+                vec!["git".to_string(), "add".to_string()],
+                vec!["git".to_string(), "commit".to_string()],
+                vec!["git".to_string(), "push".to_string()],
+            ]
+        );
+        assert!(Config::default().workflows.is_empty());
+    }
+
     #[test]
     fn test_invalid_config_returns_error() {
         // Use truly invalid TOML syntax
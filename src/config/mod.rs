@@ -1,11 +1,14 @@
+mod keybindings;
 mod loader;
 
-pub use loader::load_config;
+pub use keybindings::{parse_key, Action, KeyBindings, KeySpec};
+pub use loader::{config_path, expand_tilde, load_config, CACHE_ENV, CONFIG_ENV};
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Config {
     #[serde(default)]
     pub llm: LlmConfig,
@@ -17,17 +20,40 @@ pub struct Config {
     pub shell: ShellConfig,
     #[serde(default)]
     pub security: SecurityConfig,
+    #[serde(default)]
+    pub help: HelpConfig,
+    #[serde(default)]
+    pub parser: ParserConfig,
+    #[serde(default)]
+    pub execution: ExecutionConfig,
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    /// Form keys by action name (e.g. `execute = "ctrl+r"`), replacing that action's defaults
+    #[serde(default)]
+    pub keybindings: HashMap<String, KeySpec>,
+    /// Per-command overrides, keyed by the command as typed (e.g. "ffmpeg" or "git commit")
+    #[serde(default)]
+    pub commands: HashMap<String, CommandConfig>,
+    /// Named multi-command workflows, run with `--workflow <name>`
+    #[serde(default)]
+    pub workflows: HashMap<String, WorkflowConfig>,
 }
 
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            llm: LlmConfig::default(),
-            cache: CacheConfig::default(),
-            ui: UiConfig::default(),
-            shell: ShellConfig::default(),
-            security: SecurityConfig::default(),
-        }
+impl Config {
+    /// Starter `config.toml` written on first run: every setting, commented
+    /// out with its default, so the file changes nothing until edited
+    pub fn default_toml_template() -> &'static str {
+        include_str!("template.toml")
+    }
+
+    /// Get the per-command overrides for a command and its subcommands, if any
+    pub fn command_config(&self, command: &str, subcommands: &[String]) -> Option<&CommandConfig> {
+        let key = if subcommands.is_empty() {
+            command.to_string()
+        } else {
+            format!("{} {}", command, subcommands.join(" "))
+        };
+        self.commands.get(&key)
     }
 }
 
@@ -37,10 +63,32 @@ pub struct LlmConfig {
     pub provider: String,
     #[serde(default = "default_api_key_env")]
     pub api_key_env: String,
+    /// File containing the API key (e.g. a Docker secret); takes precedence over `api_key_env`
+    #[serde(default)]
+    pub api_key_file: Option<PathBuf>,
+    /// Model name; for Ollama this is the local model tag (`llama3.1` unless set)
     #[serde(default = "default_model")]
     pub model: String,
     #[serde(default = "default_fallback_model")]
     pub fallback_model: String,
+    /// Ask before generating a spec with more options than this (0 disables the guard)
+    #[serde(default = "default_max_options")]
+    pub max_options: usize,
+    /// Endpoint for self-hosted providers (Ollama defaults to `http://localhost:11434/api/chat`)
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// Concurrent per-option requests during generation (defaults to 10, or 2 for Ollama)
+    #[serde(default)]
+    pub max_concurrent_requests: Option<usize>,
+    /// Program run via `sh -c` by `provider = "command"`: help text on stdin, spec JSON on stdout
+    #[serde(default)]
+    pub provider_command: Option<String>,
+    /// Seconds before an assist (chat) request is abandoned
+    #[serde(default = "default_chat_timeout_secs")]
+    pub chat_timeout_secs: u64,
+    /// Generate commands with few flags in one call; false always fans out (unless --single-pass)
+    #[serde(default = "default_auto_single_pass")]
+    pub auto_single_pass: bool,
 }
 
 fn default_provider() -> String {
@@ -59,13 +107,39 @@ fn default_fallback_model() -> String {
     "claude-haiku-4-5-20250514".to_string()
 }
 
+fn default_max_options() -> usize {
+    150
+}
+
+fn default_chat_timeout_secs() -> u64 {
+    30
+}
+
+fn default_auto_single_pass() -> bool {
+    true
+}
+
+impl LlmConfig {
+    /// Whether `model` is still the default (a Claude model), i.e. none was chosen
+    pub fn has_default_model(&self) -> bool {
+        self.model == default_model()
+    }
+}
+
 impl Default for LlmConfig {
     fn default() -> Self {
         Self {
             provider: default_provider(),
             api_key_env: default_api_key_env(),
+            api_key_file: None,
             model: default_model(),
             fallback_model: default_fallback_model(),
+            max_options: default_max_options(),
+            base_url: None,
+            max_concurrent_requests: None,
+            provider_command: None,
+            chat_timeout_secs: default_chat_timeout_secs(),
+            auto_single_pass: default_auto_single_pass(),
         }
     }
 }
@@ -78,12 +152,39 @@ pub struct CacheConfig {
     pub auto_refresh: bool,
     #[serde(default = "default_ttl_days")]
     pub ttl_days: u32,
+    /// Ask before a run replaces values already in the cache
+    #[serde(default)]
+    pub confirm_value_overwrite: bool,
+    /// Directory of shareable per-command spec files, checked before the database
+    #[serde(default)]
+    pub spec_dir: Option<PathBuf>,
+}
+
+/// The platform data directory (e.g. `~/.local/share/quocli`), home to the
+/// cache, audit log, debug responses and background logs
+pub fn data_dir() -> Option<PathBuf> {
+    directories::ProjectDirs::from("", "", "quocli").map(|dirs| dirs.data_dir().to_path_buf())
 }
 
 fn default_cache_path() -> PathBuf {
-    directories::ProjectDirs::from("", "", "quocli")
-        .map(|dirs| dirs.data_dir().join("cache.db"))
-        .unwrap_or_else(|| PathBuf::from("~/.local/share/quocli/cache.db"))
+    data_dir()
+        .map(|dir| dir.join("cache.db"))
+        .unwrap_or_else(|| fallback_cache_path(std::env::var_os("XDG_DATA_HOME")))
+}
+
+/// Cache path used when the platform data dir can't be determined.
+/// Honors an absolute `XDG_DATA_HOME`, then `~/.local/share`, then the temp dir.
+pub(crate) fn fallback_cache_path(xdg_data_home: Option<std::ffi::OsString>) -> PathBuf {
+    let data_home = xdg_data_home
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| {
+            let expanded = shellexpand::tilde("~/.local/share").to_string();
+            Some(PathBuf::from(expanded)).filter(|dir| dir.is_absolute())
+        })
+        .unwrap_or_else(std::env::temp_dir);
+
+    data_home.join("quocli").join("cache.db")
 }
 
 fn default_auto_refresh() -> bool {
@@ -100,18 +201,45 @@ impl Default for CacheConfig {
             path: default_cache_path(),
             auto_refresh: default_auto_refresh(),
             ttl_days: default_ttl_days(),
+            confirm_value_overwrite: false,
+            spec_dir: None,
         }
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UiConfig {
+    /// dark, light, auto (from the terminal background) or custom (from `colors`)
     #[serde(default = "default_theme")]
     pub theme: String,
+    /// Element (normal, selected, required, ...) to color name or hex, for theme = "custom"
+    #[serde(default)]
+    pub colors: HashMap<String, String>,
     #[serde(default = "default_show_examples")]
     pub show_examples: bool,
     #[serde(default = "default_preview_command")]
     pub preview_command: bool,
+    /// Field layout: documented, alphabetical, required_first or type_grouped
+    #[serde(default = "default_field_order")]
+    pub field_order: String,
+    /// Max column width for descriptions and other popup text (defaults to the popup width)
+    #[serde(default)]
+    pub description_wrap_width: Option<usize>,
+    /// Field labels: friendly, primary or all_flags
+    #[serde(default = "default_label_style")]
+    pub label_style: String,
+    /// Show a summary of the values set and ask before every run
+    #[serde(default)]
+    pub review_before_exec: bool,
+    /// Style **bold**, `code` and bullet lists in the description popup
+    #[serde(default)]
+    pub render_markdown: bool,
+    /// Keystroke confirming Ctrl+E (and Ctrl+B): off (run at once), twice (the key again) or enter
+    #[serde(default = "default_confirm_execute")]
+    pub confirm_execute: String,
+    /// Ignore keystrokes that can't be part of a number in int and float fields
+    #[serde(default = "default_strict_numeric_input")]
+    pub strict_numeric_input: bool,
 }
 
 fn default_theme() -> String {
@@ -126,12 +254,36 @@ fn default_preview_command() -> bool {
     true
 }
 
+fn default_field_order() -> String {
+    "documented".to_string()
+}
+
+fn default_label_style() -> String {
+    "friendly".to_string()
+}
+
+fn default_confirm_execute() -> String {
+    "off".to_string()
+}
+
+fn default_strict_numeric_input() -> bool {
+    true
+}
+
 impl Default for UiConfig {
     fn default() -> Self {
         Self {
             theme: default_theme(),
+            colors: HashMap::new(),
             show_examples: default_show_examples(),
             preview_command: default_preview_command(),
+            field_order: default_field_order(),
+            description_wrap_width: None,
+            label_style: default_label_style(),
+            review_before_exec: false,
+            render_markdown: false,
+            confirm_execute: default_confirm_execute(),
+            strict_numeric_input: default_strict_numeric_input(),
         }
     }
 }
@@ -144,6 +296,13 @@ pub struct ShellConfig {
     pub history_file: String,
     #[serde(default = "default_export_envvars")]
     pub export_envvars: bool,
+    /// Tag history entries with a trailing `# via quocli` comment
+    #[serde(default = "default_history_marker")]
+    pub history_marker: bool,
+    /// Zsh only reads `#` as a comment with `setopt interactivecomments`, so the
+    /// marker is left off zsh entries unless this says that option is set
+    #[serde(default)]
+    pub zsh_interactive_comments: bool,
 }
 
 fn default_shell_type() -> String {
@@ -158,24 +317,37 @@ fn default_export_envvars() -> bool {
     true
 }
 
+fn default_history_marker() -> bool {
+    true
+}
+
 impl Default for ShellConfig {
     fn default() -> Self {
         Self {
             shell_type: default_shell_type(),
             history_file: default_history_file(),
             export_envvars: default_export_envvars(),
+            history_marker: default_history_marker(),
+            zsh_interactive_comments: false,
         }
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SecurityConfig {
+    /// Cache sensitive values in the OS keyring instead of never caching them
     #[serde(default)]
     pub keyring_integration: bool,
     #[serde(default = "default_confirm_dangerous")]
     pub confirm_dangerous: bool,
     #[serde(default = "default_audit_log")]
     pub audit_log: bool,
+    /// Ask before even opening the form for critical commands
+    #[serde(default)]
+    pub confirm_before_form: bool,
+    /// Encrypt cached values and history with a key kept in the OS keyring
+    #[serde(default)]
+    pub encrypt_cache: bool,
 }
 
 fn default_confirm_dangerous() -> bool {
@@ -192,6 +364,136 @@ impl Default for SecurityConfig {
             keyring_integration: false,
             confirm_dangerous: default_confirm_dangerous(),
             audit_log: default_audit_log(),
+            confirm_before_form: false,
+            encrypt_cache: false,
         }
     }
 }
+
+/// How help text is fetched from wrapped commands
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HelpConfig {
+    /// Retry thin or missing help under a pseudo-terminal, for tools that
+    /// only print full help on a TTY
+    #[serde(default)]
+    pub pty_fallback: bool,
+    /// Seconds to wait for the manpage before generating without it
+    #[serde(default = "default_help_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Generate specs from plain `--help` only, skipping the manpage and
+    /// extended help (like `--basic-only`)
+    #[serde(default)]
+    pub basic_only: bool,
+}
+
+fn default_help_timeout_secs() -> u64 {
+    10
+}
+
+impl Default for HelpConfig {
+    fn default() -> Self {
+        Self {
+            pty_fallback: false,
+            timeout_secs: default_help_timeout_secs(),
+            basic_only: false,
+        }
+    }
+}
+
+/// How options are extracted from help text
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParserConfig {
+    /// Flags left out of every generated spec (an option is dropped if any of its flags match)
+    #[serde(default = "default_ignore_flags")]
+    pub ignore_flags: Vec<String>,
+}
+
+fn default_ignore_flags() -> Vec<String> {
+    vec!["-h".to_string(), "--help".to_string(), "--version".to_string()]
+}
+
+impl Default for ParserConfig {
+    fn default() -> Self {
+        Self {
+            ignore_flags: default_ignore_flags(),
+        }
+    }
+}
+
+/// How wrapped commands are run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExecutionConfig {
+    /// Kill the command if it runs longer than this many seconds (like `--timeout`)
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+/// Optional log file, kept alongside the stderr output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggingConfig {
+    /// Write logs to this file as well as stderr (disabled when unset)
+    #[serde(default)]
+    pub file: Option<PathBuf>,
+    /// Most verbose level written to the file: error, warn, info, debug or trace
+    #[serde(default = "default_log_level")]
+    pub level: String,
+    /// Rotate the file once it grows past this many bytes
+    #[serde(default = "default_log_max_size")]
+    pub max_size: u64,
+}
+
+fn default_log_level() -> String {
+    "debug".to_string()
+}
+
+fn default_log_max_size() -> u64 {
+    10 * 1024 * 1024
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            file: None,
+            level: default_log_level(),
+            max_size: default_log_max_size(),
+        }
+    }
+}
+
+/// A sequence of commands whose forms are filled and run in turn
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkflowConfig {
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Commands as typed, e.g. "git add" or "git commit"
+    #[serde(default)]
+    pub steps: Vec<String>,
+}
+
+impl WorkflowConfig {
+    /// Split each step into its command and subcommands
+    pub fn step_parts(&self) -> Vec<Vec<String>> {
+        self.steps
+            .iter()
+            .map(|step| step.split_whitespace().map(str::to_string).collect::<Vec<_>>())
+            .filter(|parts| !parts.is_empty())
+            .collect()
+    }
+}
+
+/// Overrides that apply to a single wrapped command
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CommandConfig {
+    /// Shell snippet run via `sh -c` to obtain help text instead of `--help`
+    #[serde(default)]
+    pub help_command: Option<String>,
+    /// Force (or suppress) the warning gate shown before the form opens
+    #[serde(default)]
+    pub confirm_before_form: Option<bool>,
+    /// Model used when generating this command's spec, instead of `llm.model`
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Flags ignored for this command, on top of `parser.ignore_flags`
+    #[serde(default)]
+    pub ignore_flags: Vec<String>,
+}
@@ -1,3 +1,8 @@
 mod runner;
 
-pub use runner::{build_command, execute, ExecutionResult};
+pub use runner::{
+    build_argv, build_command, build_masked_argv, execute, execute_captured, execute_measured,
+    format_trace, parse_command, sample_values, shell_argv, spawn_background, split_values,
+    timed_out_error, user_shell, BackgroundLaunch, ExecutionResult, ParsedCommand, ResourceUsage,
+    VALUE_SEPARATOR,
+};
@@ -2,17 +2,102 @@ use crate::parser::{ArgumentType, CommandSpec};
 use crate::shell::resolve_and_convert;
 use crate::QuocliError;
 use std::collections::HashMap;
-use std::process::Stdio;
-use tokio::process::Command;
-
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::{Child, Command};
+use tokio::task::JoinHandle;
+
+#[derive(Debug)]
 pub struct ExecutionResult {
     pub code: Option<i32>,
+    /// Captured output; empty unless run with [`execute_captured`]
     pub stdout: String,
     pub stderr: String,
+    /// Ctrl+C (SIGINT) was pressed while the command ran
+    pub interrupted: bool,
+    /// Killed after outliving its timeout (only [`execute_captured`] returns such runs)
+    pub timed_out: bool,
 }
 
-/// Build the command line string from spec and values
-pub fn build_command(spec: &CommandSpec, values: &HashMap<String, String>) -> String {
+impl ExecutionResult {
+    /// The exit code for quocli to pass on: the command's own, else 130 (the
+    /// shell convention for SIGINT) when interrupted, else 0
+    pub fn exit_code(&self) -> i32 {
+        self.code.unwrap_or(if self.interrupted { 130 } else { 0 })
+    }
+
+    /// Exited with 0 without being interrupted
+    pub fn succeeded(&self) -> bool {
+        self.code == Some(0) && !self.interrupted
+    }
+
+    /// The `--json` report: `{"command", "exit_code", "stdout", "stderr", "timed_out"}`
+    pub fn to_json(&self, command: &str) -> serde_json::Value {
+        serde_json::json!({
+            "command": command,
+            "exit_code": self.code,
+            "stdout": self.stdout,
+            "stderr": self.stderr,
+            "timed_out": self.timed_out,
+        })
+    }
+}
+
+/// Resources a command used, as reported by `--measure`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResourceUsage {
+    pub wall: Duration,
+    /// CPU time in user and kernel mode (Unix only)
+    pub user_cpu: Option<Duration>,
+    pub system_cpu: Option<Duration>,
+    /// Peak resident set size in KiB (Unix only)
+    pub max_rss_kb: Option<u64>,
+}
+
+impl ResourceUsage {
+    /// One-line summary, e.g. `wall 1.24 s · user 0.80 s · sys 0.10 s · peak RSS 45.2 MiB`
+    pub fn summary(&self) -> String {
+        let secs = |d: Duration| format!("{:.2} s", d.as_secs_f64());
+        let mut parts = vec![format!("wall {}", secs(self.wall))];
+        if let Some(user) = self.user_cpu {
+            parts.push(format!("user {}", secs(user)));
+        }
+        if let Some(system) = self.system_cpu {
+            parts.push(format!("sys {}", secs(system)));
+        }
+        if let Some(kb) = self.max_rss_kb {
+            parts.push(format!("peak RSS {:.1} MiB", kb as f64 / 1024.0));
+        }
+        parts.join(" · ")
+    }
+}
+
+/// Separates the values of a repeatable option within its single form value
+/// (`A=1\nB=2` for `-e A=1 -e B=2`)
+pub const VALUE_SEPARATOR: char = '\n';
+
+/// The non-empty values held in a form value, split for repeatable options
+pub fn split_values(value: &str, repeatable: bool) -> Vec<&str> {
+    if repeatable {
+        value.split(VALUE_SEPARATOR).filter(|v| !v.trim().is_empty()).collect()
+    } else {
+        vec![value]
+    }
+}
+
+/// A command launched detached from quocli
+pub struct BackgroundLaunch {
+    pub pid: u32,
+    pub log_path: PathBuf,
+}
+
+/// Build the exact argument vector (program first) from spec and values.
+///
+/// Values are passed through verbatim, with no quoting: they go straight to the
+/// child process, never through a shell.
+pub fn build_argv(spec: &CommandSpec, values: &HashMap<String, String>) -> Vec<String> {
     let mut parts = vec![spec.command.clone()];
     let mut flag_parts: Vec<String> = Vec::new();
     let mut positional_parts: Vec<String> = Vec::new();
@@ -36,33 +121,22 @@ pub fn build_command(spec: &CommandSpec, values: &HashMap<String, String>) -> St
                 continue;
             }
 
-            // Resolve environment variables and convert to appropriate type
-            let resolved = resolve_and_convert(value, &opt.argument_type);
-
-            match opt.argument_type {
-                ArgumentType::Bool => {
-                    if resolved == "true" {
-                        flag_parts.push(primary.to_string());
-                    }
-                }
-                ArgumentType::Path => {
+            if opt.argument_type == ArgumentType::Bool {
+                if resolve_and_convert(value, &opt.argument_type) == "true" {
                     flag_parts.push(primary.to_string());
-                    // Expand tilde for path arguments
-                    let expanded = shellexpand::tilde(&resolved).to_string();
-                    if expanded.contains(' ') {
-                        flag_parts.push(format!("\"{}\"", expanded));
-                    } else {
-                        flag_parts.push(expanded);
-                    }
                 }
-                _ => {
-                    flag_parts.push(primary.to_string());
-                    // Quote values with spaces
-                    if resolved.contains(' ') {
-                        flag_parts.push(format!("\"{}\"", resolved));
-                    } else {
-                        flag_parts.push(resolved);
-                    }
+                continue;
+            }
+            // A repeatable option is emitted once per value
+            for value in split_values(value, opt.repeatable) {
+                // Resolve environment variables and convert to appropriate type
+                let resolved = resolve_and_convert(value, &opt.argument_type);
+                flag_parts.push(primary.to_string());
+                if opt.argument_type == ArgumentType::Path {
+                    // Expand tilde for path arguments
+                    flag_parts.push(shellexpand::tilde(&resolved).to_string());
+                } else {
+                    flag_parts.push(resolved);
                 }
             }
         }
@@ -85,11 +159,7 @@ pub fn build_command(spec: &CommandSpec, values: &HashMap<String, String>) -> St
             resolved
         };
 
-        if final_value.contains(' ') {
-            positional_parts.push(format!("\"{}\"", final_value));
-        } else {
-            positional_parts.push(final_value);
-        }
+        positional_parts.push(final_value);
     }
 
     // Combine based on positionals_first setting
@@ -101,39 +171,594 @@ pub fn build_command(spec: &CommandSpec, values: &HashMap<String, String>) -> St
         parts.extend(positional_parts);
     }
 
-    parts.join(" ")
+    parts
+}
+
+/// [`build_argv`] with the values of sensitive options and positionals replaced by `***`
+pub fn build_masked_argv(spec: &CommandSpec, values: &HashMap<String, String>) -> Vec<String> {
+    let mut masked = values.clone();
+    for opt in spec.options.iter().filter(|opt| opt.sensitive) {
+        // A boolean's value only decides whether the flag is present
+        if opt.argument_type == ArgumentType::Bool {
+            continue;
+        }
+        if let Some(value) = masked.get_mut(opt.primary_flag()) {
+            if !value.is_empty() {
+                // Each value of a repeatable option is masked on its own
+                let count = split_values(value, opt.repeatable).len();
+                *value = vec!["***"; count].join(&VALUE_SEPARATOR.to_string());
+            }
+        }
+    }
+    for arg in spec.positional_args.iter().filter(|arg| arg.sensitive) {
+        if let Some(value) = masked.get_mut(&format!("_pos_{}", arg.name)) {
+            if !value.is_empty() {
+                *value = "***".to_string();
+            }
+        }
+    }
+    build_argv(spec, &masked)
+}
+
+/// Lay out an argv for `--trace-command`: the program, then each argument on its
+/// own line with its index, Debug-quoted so whitespace and empty arguments show
+pub fn format_trace(argv: &[String]) -> String {
+    let mut out = String::from("Executing argv:\n");
+    for (i, arg) in argv.iter().enumerate() {
+        let label = if i == 0 { "program".to_string() } else { format!("[{}]", i) };
+        out.push_str(&format!("  {:>7} {:?}\n", label, arg));
+    }
+    out
+}
+
+/// Render the command line for display (preview, history, logs), shell-quoted so
+/// it can be pasted back into a shell. Execution uses [`build_argv`] instead.
+pub fn build_command(spec: &CommandSpec, values: &HashMap<String, String>) -> String {
+    shell_words::join(build_argv(spec, values))
+}
+
+/// Form values parsed back out of a command line, e.g. one of a spec's examples
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParsedCommand {
+    /// Field id (primary flag, or `_pos_<name>`) to value
+    pub values: HashMap<String, String>,
+    /// Flags the spec doesn't know and words left over once positionals are filled
+    pub unrecognized: Vec<String>,
+}
+
+/// Parse a command line into form values, roughly the inverse of [`build_command`].
+/// Flags are matched against every spelling in the spec (`--flag value`,
+/// `--flag=value`); bare words fill positionals in order. The program name is
+/// skipped, as are any leading words (such as a subcommand) beyond what the
+/// positionals can take. `None` if the line can't be split (e.g. an unclosed quote).
+pub fn parse_command(spec: &CommandSpec, command_line: &str) -> Option<ParsedCommand> {
+    let tokens = shell_words::split(command_line).ok()?;
+    let mut tokens = tokens.into_iter().peekable();
+    let program = spec.command.rsplit('/').next().unwrap_or(&spec.command);
+    if tokens.peek().is_some_and(|t| t.rsplit('/').next() == Some(program)) {
+        tokens.next();
+    }
+
+    let mut parsed = ParsedCommand::default();
+    // Bare words, and whether a flag had been seen before each one
+    let mut words: Vec<(String, bool)> = Vec::new();
+    let mut seen_flag = false;
+    let mut only_words = false;
+    while let Some(token) = tokens.next() {
+        if only_words || !token.starts_with('-') || token == "-" {
+            words.push((token, seen_flag));
+            continue;
+        }
+        if token == "--" {
+            only_words = true;
+            continue;
+        }
+        seen_flag = true;
+
+        let (flag, inline) = match token.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+            _ => (token.clone(), None),
+        };
+        let Some(opt) = spec.options.iter().find(|o| o.flags.contains(&flag)) else {
+            parsed.unrecognized.push(token);
+            continue;
+        };
+        let value = match (&opt.argument_type, inline) {
+            (ArgumentType::Bool, _) => "true".to_string(),
+            (_, Some(value)) => value,
+            (_, None) => match tokens.next() {
+                Some(value) => value,
+                None => {
+                    parsed.unrecognized.push(token);
+                    continue;
+                }
+            },
+        };
+        match parsed.values.get_mut(opt.primary_flag()) {
+            Some(existing) if opt.repeatable => {
+                existing.push(VALUE_SEPARATOR);
+                existing.push_str(&value);
+            }
+            _ => {
+                parsed.values.insert(opt.primary_flag().to_string(), value);
+            }
+        }
+    }
+
+    // Leading words the positionals can't take are taken to be part of the command
+    let excess = words.len().saturating_sub(spec.positional_args.len());
+    let leading = words.iter().take_while(|(_, after_flag)| !after_flag).count();
+    let mut words = words.into_iter().skip(excess.min(leading)).map(|(word, _)| word);
+    for arg in &spec.positional_args {
+        match words.next() {
+            Some(word) => {
+                parsed.values.insert(format!("_pos_{}", arg.name), word);
+            }
+            None => break,
+        }
+    }
+    parsed.unrecognized.extend(words);
+
+    Some(parsed)
 }
 
-/// Execute a command and return the result
-pub async fn execute(command_line: &str) -> Result<ExecutionResult, QuocliError> {
-    tracing::info!("Executing: {}", command_line);
+/// Wrap an argument vector to run through `shell` as an interactive shell
+/// (`shell -i -c '<command>'`), so aliases and functions from its rc files resolve
+pub fn shell_argv(shell: &str, argv: &[String]) -> Vec<String> {
+    vec![
+        shell.to_string(),
+        "-i".to_string(),
+        "-c".to_string(),
+        shell_words::join(argv),
+    ]
+}
+
+/// The user's shell, from `$SHELL`
+pub fn user_shell() -> Result<String, QuocliError> {
+    std::env::var("SHELL")
+        .ok()
+        .filter(|shell| !shell.is_empty())
+        .ok_or_else(|| QuocliError::Execution("--via-shell needs $SHELL to be set".to_string()))
+}
 
-    // Parse the command line
-    let parts: Vec<String> = shell_words::split(command_line)
-        .map_err(|e| QuocliError::Execution(format!("Failed to parse command: {}", e)))?;
+/// Values for a sample invocation: each option's and positional's default,
+/// with a `<placeholder>` for required arguments that have no default
+pub fn sample_values(spec: &CommandSpec) -> HashMap<String, String> {
+    let mut values = HashMap::new();
 
-    if parts.is_empty() {
-        return Err(QuocliError::Execution("Empty command".to_string()));
+    for opt in &spec.options {
+        let value = match &opt.default {
+            Some(default) if !default.is_empty() => default.clone(),
+            _ if opt.required && opt.argument_type != ArgumentType::Bool => format!(
+                "<{}>",
+                opt.argument_name
+                    .as_deref()
+                    .unwrap_or_else(|| opt.primary_flag().trim_start_matches('-'))
+                    .to_lowercase()
+            ),
+            _ => continue,
+        };
+        values.insert(opt.primary_flag().to_string(), value);
     }
 
-    let program = &parts[0];
-    let args = &parts[1..];
+    for arg in &spec.positional_args {
+        let value = match &arg.default {
+            Some(default) if !default.is_empty() => default.clone(),
+            _ if arg.required => format!("<{}>", arg.name),
+            _ => continue,
+        };
+        values.insert(format!("_pos_{}", arg.name), value);
+    }
 
-    let output = Command::new(program)
+    values
+}
+
+/// Execute an argument vector (program first) and return the result,
+/// killing the command (and anything it started) if it outlives `timeout`
+pub async fn execute(argv: &[String], timeout: Option<Duration>) -> Result<ExecutionResult, QuocliError> {
+    tracing::info!("Executing: {}", shell_words::join(argv));
+
+    let (program, args) = argv
+        .split_first()
+        .ok_or_else(|| QuocliError::Execution("Empty command".to_string()))?;
+
+    let mut command = Command::new(program);
+    command
         .args(args)
         .stdin(Stdio::inherit())
         .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
+        .stderr(Stdio::inherit());
+    if timeout.is_some() {
+        isolate_process_group(command.as_std_mut());
+    }
+    let mut child = command
         .spawn()
-        .map_err(|e| QuocliError::Execution(format!("Failed to spawn command: {}", e)))?
-        .wait()
-        .await
-        .map_err(|e| QuocliError::Execution(format!("Failed to wait for command: {}", e)))?;
+        .map_err(|e| QuocliError::Execution(format!("Failed to spawn command: {}", e)))?;
+    let _terminal = timeout.map(|_| TerminalHandback);
+    let (status, interrupted) = wait_with_timeout(&mut child, timeout)
+        .await?
+        .ok_or_else(|| timed_out_error(timeout))?;
 
     Ok(ExecutionResult {
-        code: output.code(),
+        code: status.code(),
         stdout: String::new(), // Output goes directly to terminal
         stderr: String::new(),
+        interrupted,
+        timed_out: false,
+    })
+}
+
+/// Execute an argument vector like [`execute`], but capture stdout and stderr
+/// into the result instead of passing them through to the terminal. A run that
+/// times out is still returned (flagged `timed_out`) with the output it produced.
+pub async fn execute_captured(
+    argv: &[String],
+    timeout: Option<Duration>,
+) -> Result<ExecutionResult, QuocliError> {
+    tracing::info!("Executing (captured): {}", shell_words::join(argv));
+
+    let (program, args) = argv
+        .split_first()
+        .ok_or_else(|| QuocliError::Execution("Empty command".to_string()))?;
+
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if timeout.is_some() {
+        isolate_process_group(command.as_std_mut());
+    }
+    let mut child = command
+        .spawn()
+        .map_err(|e| QuocliError::Execution(format!("Failed to spawn command: {}", e)))?;
+    let _terminal = timeout.map(|_| TerminalHandback);
+
+    // Drain both pipes while waiting, so a chatty command can't fill one and block
+    let stdout = read_pipe(child.stdout.take());
+    let stderr = read_pipe(child.stderr.take());
+    let waited = wait_with_timeout(&mut child, timeout).await?;
+
+    Ok(ExecutionResult {
+        code: waited.and_then(|(status, _)| status.code()),
+        stdout: stdout.await.unwrap_or_default(),
+        stderr: stderr.await.unwrap_or_default(),
+        interrupted: waited.is_some_and(|(_, interrupted)| interrupted),
+        timed_out: waited.is_none(),
+    })
+}
+
+/// The error for a command killed after running longer than `timeout`
+pub fn timed_out_error(timeout: Option<Duration>) -> QuocliError {
+    QuocliError::Execution(format!(
+        "Command timed out after {}s",
+        timeout.unwrap_or_default().as_secs()
+    ))
+}
+
+/// Read a child's output pipe to the end on a background task
+fn read_pipe<R: AsyncRead + Send + Unpin + 'static>(pipe: Option<R>) -> JoinHandle<String> {
+    tokio::spawn(async move {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf).await;
+        }
+        String::from_utf8_lossy(&buf).to_string()
+    })
+}
+
+/// Wait for `child`, returning its status and whether Ctrl+C ended it, or None
+/// if it outlived `timeout` and was killed (and reaped).
+/// Ctrl+C doesn't end quocli while the command runs: the terminal already sends
+/// SIGINT to the command, which is left to handle it (a REPL may just cancel a
+/// line) and the run is flagged as interrupted only if the command died of it.
+async fn wait_with_timeout(
+    child: &mut Child,
+    timeout: Option<Duration>,
+) -> Result<Option<(ExitStatus, bool)>, QuocliError> {
+    let _ignore_interrupt = IgnoreInterrupt::new();
+    let wait_error = |e: std::io::Error| QuocliError::Execution(format!("Failed to wait for command: {}", e));
+    let deadline = async {
+        match timeout {
+            Some(timeout) => tokio::time::sleep(timeout).await,
+            None => std::future::pending().await,
+        }
+    };
+
+    tokio::select! {
+        status = child.wait() => {
+            let status = status.map_err(wait_error)?;
+            Ok(Some((status, was_interrupted(&status))))
+        }
+        _ = deadline => {
+            // Commands with a timeout lead their own process group (see isolate_process_group)
+            if let Some(pid) = child.id() {
+                kill_process_group(pid);
+            }
+            child
+                .kill()
+                .await
+                .map_err(|e| QuocliError::Execution(format!("Failed to kill timed out command: {}", e)))?;
+            Ok(None)
+        }
+    }
+}
+
+/// Whether a command ended because of Ctrl+C: killed by SIGINT, or exiting with
+/// 130 as shells and many interpreters do after handling it
+fn was_interrupted(status: &ExitStatus) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if status.signal() == Some(libc::SIGINT) {
+            return true;
+        }
+    }
+    status.code() == Some(130)
+}
+
+/// Run a command with a timeout in its own process group, so the timeout kills
+/// everything it started (a script's children too), not just the direct child.
+/// The group becomes the terminal's foreground group so keyboard input and Ctrl+C
+/// still reach it; [`TerminalHandback`] returns the terminal to quocli afterwards.
+#[cfg(unix)]
+fn isolate_process_group(command: &mut std::process::Command) {
+    use std::os::unix::process::CommandExt;
+    // SAFETY: the hook only makes async-signal-safe calls (setpgid, getpid and
+    // those in take_terminal) between fork and exec
+    unsafe {
+        command.pre_exec(|| {
+            libc::setpgid(0, 0);
+            take_terminal(libc::getpid());
+            Ok(())
+        });
+    }
+}
+
+#[cfg(not(unix))]
+fn isolate_process_group(_command: &mut std::process::Command) {}
+
+/// Make `pgid` the terminal's foreground process group if stdin is a terminal.
+/// SIGTTOU is ignored meanwhile, as a background group may not otherwise do this.
+///
+/// # Safety
+/// Changes signal dispositions, so call it only between fork and exec or while
+/// no other thread relies on SIGTTOU.
+#[cfg(unix)]
+unsafe fn take_terminal(pgid: libc::pid_t) {
+    if libc::isatty(libc::STDIN_FILENO) == 1 {
+        let previous = libc::signal(libc::SIGTTOU, libc::SIG_IGN);
+        libc::tcsetpgrp(libc::STDIN_FILENO, pgid);
+        libc::signal(libc::SIGTTOU, previous);
+    }
+}
+
+/// Hands the terminal back to quocli's own process group when dropped, after a
+/// command run by [`isolate_process_group`] has finished
+struct TerminalHandback;
+
+impl Drop for TerminalHandback {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        // SAFETY: the command has exited, so nothing else is changing SIGTTOU
+        unsafe {
+            take_terminal(libc::getpgrp());
+        }
+    }
+}
+
+/// SIGKILL a whole process group led by `pid`
+#[cfg(unix)]
+fn kill_process_group(pid: u32) {
+    // SAFETY: kill only sends a signal; the leader hasn't been reaped, so the group is still ours
+    unsafe {
+        libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(_pid: u32) {}
+
+/// Ignores SIGINT in quocli itself while a foreground command runs, restoring the
+/// previous disposition when dropped so Ctrl+C works as before afterwards.
+/// Create it after spawning, or the command would inherit the ignored signal.
+struct IgnoreInterrupt {
+    #[cfg(unix)]
+    previous: libc::sighandler_t,
+}
+
+impl IgnoreInterrupt {
+    #[cfg(unix)]
+    fn new() -> Self {
+        // SAFETY: swapping in SIG_IGN installs no handler code, and Drop puts back
+        // exactly the disposition that was there
+        let previous = unsafe { libc::signal(libc::SIGINT, libc::SIG_IGN) };
+        IgnoreInterrupt { previous }
+    }
+
+    #[cfg(not(unix))]
+    fn new() -> Self {
+        IgnoreInterrupt {}
+    }
+}
+
+impl Drop for IgnoreInterrupt {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        // SAFETY: restores the disposition saved in new()
+        unsafe {
+            libc::signal(libc::SIGINT, self.previous);
+        }
+    }
+}
+
+/// Execute an argument vector like [`execute`], also measuring wall time and,
+/// on Unix, the child's CPU time and peak memory (via `wait4`)
+pub async fn execute_measured(
+    argv: &[String],
+    timeout: Option<Duration>,
+) -> Result<(ExecutionResult, ResourceUsage), QuocliError> {
+    tracing::info!("Executing (measured): {}", shell_words::join(argv));
+    let argv = argv.to_vec();
+    tokio::task::spawn_blocking(move || run_measured(&argv, timeout))
+        .await
+        .map_err(|e| QuocliError::Execution(format!("Measured execution failed: {}", e)))?
+}
+
+fn run_measured(argv: &[String], timeout: Option<Duration>) -> Result<(ExecutionResult, ResourceUsage), QuocliError> {
+    let (program, args) = argv
+        .split_first()
+        .ok_or_else(|| QuocliError::Execution("Empty command".to_string()))?;
+
+    let started = Instant::now();
+    let mut command = std::process::Command::new(program);
+    command
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit());
+    if timeout.is_some() {
+        isolate_process_group(&mut command);
+    }
+    let child = command
+        .spawn()
+        .map_err(|e| QuocliError::Execution(format!("Failed to spawn command: {}", e)))?;
+    let _terminal = timeout.map(|_| TerminalHandback);
+    let _ignore_interrupt = IgnoreInterrupt::new();
+
+    #[cfg(unix)]
+    {
+        // wait4 blocks, so a watchdog thread enforces the timeout; dropping
+        // `finished` tells it the command ended first
+        let pid = child.id();
+        let (finished, watch) = std::sync::mpsc::channel::<()>();
+        let watchdog = timeout.map(|timeout| {
+            std::thread::spawn(move || {
+                let expired = watch.recv_timeout(timeout) == Err(std::sync::mpsc::RecvTimeoutError::Timeout);
+                if expired {
+                    kill_process_group(pid);
+                }
+                expired
+            })
+        });
+
+        let mut status: libc::c_int = 0;
+        // SAFETY: rusage is plain old data, and wait4 only writes into the two out-params
+        let mut rusage: libc::rusage = unsafe { std::mem::zeroed() };
+        let waited = unsafe { libc::wait4(pid as libc::pid_t, &mut status, 0, &mut rusage) };
+        let wall = started.elapsed();
+        drop(finished);
+        if waited < 0 {
+            return Err(QuocliError::Execution(format!(
+                "Failed to wait for command: {}",
+                std::io::Error::last_os_error()
+            )));
+        }
+        if watchdog.is_some_and(|watchdog| watchdog.join().unwrap_or(false)) {
+            return Err(timed_out_error(timeout));
+        }
+
+        let code = libc::WIFEXITED(status).then(|| libc::WEXITSTATUS(status));
+        let interrupted = (libc::WIFSIGNALED(status) && libc::WTERMSIG(status) == libc::SIGINT) || code == Some(130);
+        let timeval = |tv: libc::timeval| {
+            Duration::from_secs(tv.tv_sec as u64) + Duration::from_micros(tv.tv_usec as u64)
+        };
+        // Linux reports ru_maxrss in KiB, macOS in bytes
+        let max_rss = rusage.ru_maxrss as u64;
+        let max_rss_kb = if cfg!(target_os = "macos") { max_rss / 1024 } else { max_rss };
+
+        Ok((
+            ExecutionResult {
+                code,
+                stdout: String::new(),
+                stderr: String::new(),
+                interrupted,
+                timed_out: false,
+            },
+            ResourceUsage {
+                wall,
+                user_cpu: Some(timeval(rusage.ru_utime)),
+                system_cpu: Some(timeval(rusage.ru_stime)),
+                max_rss_kb: Some(max_rss_kb),
+            },
+        ))
+    }
+
+    #[cfg(not(unix))]
+    {
+        let mut child = child;
+        let wait_error = |e: std::io::Error| QuocliError::Execution(format!("Failed to wait for command: {}", e));
+        // Poll so the timeout can be enforced without a second handle on the child
+        let status = loop {
+            if let Some(status) = child.try_wait().map_err(wait_error)? {
+                break status;
+            }
+            if timeout.is_some_and(|timeout| started.elapsed() >= timeout) {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(timed_out_error(timeout));
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        };
+        Ok((
+            ExecutionResult {
+                code: status.code(),
+                stdout: String::new(),
+                stderr: String::new(),
+                interrupted: was_interrupted(&status),
+                timed_out: false,
+            },
+            ResourceUsage {
+                wall: started.elapsed(),
+                ..Default::default()
+            },
+        ))
+    }
+}
+
+/// Spawn a command detached: stdin is closed, stdout/stderr go to a log file
+/// in `log_dir`, and the child is not awaited
+pub fn spawn_background(argv: &[String], log_dir: &Path) -> Result<BackgroundLaunch, QuocliError> {
+    tracing::info!("Launching in background: {}", shell_words::join(argv));
+
+    let (program, args) = argv
+        .split_first()
+        .ok_or_else(|| QuocliError::Execution("Empty command".to_string()))?;
+
+    std::fs::create_dir_all(log_dir)?;
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let program_name = Path::new(program)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| program.clone());
+    let log_path = log_dir.join(format!("{}-{}.log", program_name, timestamp));
+    let log_file = std::fs::File::create(&log_path)?;
+
+    let mut command = std::process::Command::new(program);
+    command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(log_file.try_clone()?)
+        .stderr(log_file);
+
+    // Own process group, so Ctrl+C in the launching terminal doesn't reach it
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+
+    let child = command
+        .spawn()
+        .map_err(|e| QuocliError::Execution(format!("Failed to spawn command: {}", e)))?;
+
+    Ok(BackgroundLaunch {
+        pid: child.id(),
+        log_path,
     })
 }
 
@@ -154,6 +779,8 @@ mod tests {
             danger_level: DangerLevel::Low,
             examples: vec![],
             positionals_first: false,
+            tool_version: None,
+            basic_only: false,
         }
     }
 
@@ -172,6 +799,11 @@ mod tests {
             default: None,
             enum_values: vec![],
             level: OptionLevel::Basic,
+            deprecated: false,
+            example: None,
+            min: None,
+            max: None,
+            pattern: None,
         }
     }
 
@@ -230,7 +862,7 @@ mod tests {
         values.insert("--pattern".to_string(), "hello world".to_string());
 
         let result = build_command(&spec, &values);
-        assert_eq!(result, "grep --pattern \"hello world\"");
+        assert_eq!(result, "grep --pattern 'hello world'");
     }
 
     #[test]
@@ -254,7 +886,7 @@ mod tests {
         values.insert("--output".to_string(), "/path/with spaces/file.txt".to_string());
 
         let result = build_command(&spec, &values);
-        assert_eq!(result, "cat --output \"/path/with spaces/file.txt\"");
+        assert_eq!(result, "cat --output '/path/with spaces/file.txt'");
     }
 
     #[test]
@@ -373,7 +1005,7 @@ mod tests {
         values.insert("_pos_file".to_string(), "my file.txt".to_string());
 
         let result = build_command(&spec, &values);
-        assert_eq!(result, "cat \"my file.txt\"");
+        assert_eq!(result, "cat 'my file.txt'");
     }
 
     #[test]
@@ -417,7 +1049,7 @@ mod tests {
         values.insert("_pos_path".to_string(), "/home".to_string());
 
         let result = build_command(&spec, &values);
-        assert_eq!(result, "find /home --name *.txt");
+        assert_eq!(result, "find /home --name '*.txt'");
     }
 
     #[test]
@@ -491,7 +1123,7 @@ mod tests {
         let result = build_command(&spec, &values);
         assert!(result.starts_with("curl"));
         assert!(result.contains("--request POST"));
-        assert!(result.contains("--header \"Content-Type: application/json\""));
+        assert!(result.contains("--header 'Content-Type: application/json'"));
         assert!(result.contains("https://api.example.com"));
     }
 
@@ -533,47 +1165,365 @@ mod tests {
         assert_eq!(result, "test first second third");
     }
 
+    fn argv(parts: &[&str]) -> Vec<String> {
+        parts.iter().map(|s| s.to_string()).collect()
+    }
+
     #[tokio::test]
     async fn test_execute_simple_command() {
-        let result = execute("echo hello").await.unwrap();
+        let result = execute(&argv(&["echo", "hello"]), None).await.unwrap();
         assert_eq!(result.code, Some(0));
     }
 
     #[tokio::test]
     async fn test_execute_command_with_args() {
-        let result = execute("echo hello world").await.unwrap();
+        let result = execute(&argv(&["echo", "hello", "world"]), None).await.unwrap();
         assert_eq!(result.code, Some(0));
     }
 
     #[tokio::test]
     async fn test_execute_command_with_quoted_args() {
-        let result = execute("echo \"hello world\"").await.unwrap();
+        let result = execute(&argv(&["echo", "hello world"]), None).await.unwrap();
         assert_eq!(result.code, Some(0));
     }
 
     #[tokio::test]
     async fn test_execute_empty_command_fails() {
-        let result = execute("").await;
+        let result = execute(&[], None).await;
         assert!(result.is_err());
     }
 
     #[tokio::test]
     async fn test_execute_nonexistent_command_fails() {
-        let result = execute("nonexistent_command_12345").await;
+        let result = execute(&argv(&["nonexistent_command_12345"]), None).await;
         assert!(result.is_err());
     }
 
     #[tokio::test]
     async fn test_execute_command_exit_code() {
         // true command always exits with 0
-        let result = execute("true").await.unwrap();
+        let result = execute(&argv(&["true"]), None).await.unwrap();
         assert_eq!(result.code, Some(0));
 
         // false command always exits with 1
-        let result = execute("false").await.unwrap();
+        let result = execute(&argv(&["false"]), None).await.unwrap();
         assert_eq!(result.code, Some(1));
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_was_interrupted_only_for_sigint_deaths() {
+        use std::os::unix::process::ExitStatusExt;
+        // Raw wait statuses: the low byte is the terminating signal, the next the exit code
+        assert!(was_interrupted(&ExitStatus::from_raw(libc::SIGINT)));
+        assert!(was_interrupted(&ExitStatus::from_raw(130 << 8)));
+        assert!(!was_interrupted(&ExitStatus::from_raw(0)));
+        assert!(!was_interrupted(&ExitStatus::from_raw(1 << 8)));
+        assert!(!was_interrupted(&ExitStatus::from_raw(libc::SIGTERM)));
+    }
+
+    #[tokio::test]
+    async fn test_execute_captured_collects_output() {
+        let result = execute_captured(&argv(&["sh", "-c", "echo out; echo err >&2; exit 3"]), None)
+            .await
+            .unwrap();
+        assert_eq!(result.code, Some(3));
+        assert_eq!(result.stdout, "out\n");
+        assert_eq!(result.stderr, "err\n");
+
+        let json = result.to_json("sh -c ...");
+        assert_eq!(json["command"], "sh -c ...");
+        assert_eq!(json["exit_code"], 3);
+        assert_eq!(json["stdout"], "out\n");
+
+        assert!(execute_captured(&[], None).await.is_err());
+    }
+
+    #[test]
+    fn test_build_masked_argv_hides_sensitive_values() {
+        let mut spec = create_test_spec("curl");
+        let mut user = create_option(vec!["--user"], ArgumentType::String);
+        user.sensitive = true;
+        spec.options.push(user);
+        spec.options.push(create_option(vec!["--url"], ArgumentType::String));
+        spec.positional_args.push(PositionalArg {
+            name: "token".to_string(),
+            description: "API token".to_string(),
+            required: false,
+            sensitive: true,
+            argument_type: ArgumentType::String,
+            default: None,
+        });
+
+        let mut values = HashMap::new();
+        values.insert("--user".to_string(), "me:hunter2".to_string());
+        values.insert("--url".to_string(), "https://example.com".to_string());
+        values.insert("_pos_token".to_string(), "abc123".to_string());
+
+        assert_eq!(
+            build_masked_argv(&spec, &values),
+            argv(&["curl", "--user", "***", "--url", "https://example.com", "***"])
+        );
+        assert_eq!(build_argv(&spec, &values)[2], "me:hunter2");
+    }
+
+    #[test]
+    fn test_build_command_repeats_repeatable_flags() {
+        let mut spec = create_test_spec("docker");
+        let mut env = create_option(vec!["-e", "--env"], ArgumentType::String);
+        env.repeatable = true;
+        spec.options.push(env);
+        // Not repeatable: a newline is just part of the value
+        spec.options.push(create_option(vec!["--label"], ArgumentType::String));
+
+        let mut values = HashMap::new();
+        values.insert("--env".to_string(), "A=1\nB=2\n".to_string());
+        values.insert("--label".to_string(), "x\ny".to_string());
+
+        assert_eq!(
+            build_argv(&spec, &values),
+            argv(&["docker", "--env", "A=1", "--env", "B=2", "--label", "x\ny"])
+        );
+        assert_eq!(build_command(&spec, &values), "docker --env 'A=1' --env 'B=2' --label 'x\ny'");
+
+        spec.options[0].sensitive = true;
+        assert_eq!(
+            build_masked_argv(&spec, &values),
+            argv(&["docker", "--env", "***", "--env", "***", "--label", "x\ny"])
+        );
+
+        // Three values, masked one by one next to a visible repeatable option
+        let mut header = create_option(vec!["-H", "--header"], ArgumentType::String);
+        header.repeatable = true;
+        spec.options.push(header);
+        values.insert("--env".to_string(), "A=1\nB=2\nC=3".to_string());
+        values.insert("--header".to_string(), "A: 1\nB: 2".to_string());
+        values.remove("--label");
+        assert_eq!(
+            build_masked_argv(&spec, &values),
+            argv(&["docker", "--env", "***", "--env", "***", "--env", "***", "--header", "A: 1", "--header", "B: 2"])
+        );
+        assert_eq!(
+            shell_words::join(build_masked_argv(&spec, &values)),
+            "docker --env '***' --env '***' --env '***' --header 'A: 1' --header 'B: 2'"
+        );
+
+        let parsed = parse_command(&spec, "docker -e A=1 --env B=2").unwrap();
+        assert_eq!(parsed.values.get("--env").unwrap(), "A=1\nB=2");
+    }
+
+    #[test]
+    fn test_parse_command_fills_flags_and_positionals() {
+        let mut spec = create_test_spec("git");
+        spec.options.push(create_option(vec!["-m", "--message"], ArgumentType::String));
+        spec.options.push(create_option(vec!["-a", "--all"], ArgumentType::Bool));
+        spec.options.push(create_option(vec!["--author"], ArgumentType::String));
+        spec.positional_args.push(PositionalArg {
+            name: "pathspec".to_string(),
+            description: "Files to commit".to_string(),
+            required: false,
+            sensitive: false,
+            argument_type: ArgumentType::Path,
+            default: None,
+        });
+
+        let parsed = parse_command(
+            &spec,
+            "git commit -a --message 'fix: typo' --author=me --amend src/main.rs",
+        )
+        .unwrap();
+        assert_eq!(parsed.values.get("--message").unwrap(), "fix: typo");
+        assert_eq!(parsed.values.get("--all").unwrap(), "true");
+        assert_eq!(parsed.values.get("--author").unwrap(), "me");
+        assert_eq!(parsed.values.get("_pos_pathspec").unwrap(), "src/main.rs");
+        assert_eq!(parsed.unrecognized, vec!["--amend"]);
+
+        // Round-trips what build_command renders
+        let values = HashMap::from([
+            ("--message".to_string(), "two words".to_string()),
+            ("_pos_pathspec".to_string(), "a b.txt".to_string()),
+        ]);
+        let parsed = parse_command(&spec, &build_command(&spec, &values)).unwrap();
+        assert_eq!(parsed.values, values);
+        assert!(parsed.unrecognized.is_empty());
+
+        assert!(parse_command(&spec, "git commit -m 'unclosed").is_none());
+    }
+
+    #[test]
+    fn test_format_trace_lists_each_argument() {
+        let trace = format_trace(&argv(&["grep", "-e", "two words", ""]));
+        assert_eq!(
+            trace,
+            "Executing argv:\n  program \"grep\"\n      [1] \"-e\"\n      [2] \"two words\"\n      [3] \"\"\n"
+        );
+    }
+
+    #[test]
+    fn test_shell_argv_quotes_the_command() {
+        let wrapped = shell_argv("/bin/zsh", &argv(&["ll", "my dir"]));
+        assert_eq!(wrapped, argv(&["/bin/zsh", "-i", "-c", "ll 'my dir'"]));
+    }
+
+    #[tokio::test]
+    async fn test_shell_argv_runs_through_the_shell() {
+        let wrapped = shell_argv("sh", &argv(&["sh", "-c", "exit 4"]));
+        let result = execute(&wrapped, None).await.unwrap();
+        assert_eq!(result.code, Some(4));
+    }
+
+    #[tokio::test]
+    async fn test_execute_kills_command_after_timeout() {
+        let started = Instant::now();
+        let err = execute(&argv(&["sleep", "5"]), Some(Duration::from_secs(1)))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("timed out"), "{}", err);
+        assert!(started.elapsed() < Duration::from_secs(4));
+
+        let result = execute_captured(&argv(&["sh", "-c", "echo partial; sleep 5"]), Some(Duration::from_secs(1)))
+            .await
+            .unwrap();
+        assert!(result.timed_out);
+        assert_eq!(result.code, None);
+        assert_eq!(result.stdout, "partial\n");
+        assert_eq!(result.to_json("x")["timed_out"], true);
+
+        let result = execute(&argv(&["true"]), Some(Duration::from_secs(5))).await.unwrap();
+        assert_eq!(result.code, Some(0));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_timeout_kills_the_whole_process_group() {
+        // The backgrounded sleep holds the captured pipes open; killing only the
+        // shell would leave it running and the output never finishing
+        let started = Instant::now();
+        let result = execute_captured(&argv(&["sh", "-c", "sleep 5 & wait"]), Some(Duration::from_secs(1)))
+            .await
+            .unwrap();
+        assert!(result.timed_out);
+        assert!(started.elapsed() < Duration::from_secs(4));
+    }
+
+    #[tokio::test]
+    async fn test_execute_measured_reports_usage() {
+        let (result, usage) = execute_measured(&argv(&["sh", "-c", "sleep 0.1; exit 3"]), None)
+            .await
+            .unwrap();
+        assert_eq!(result.code, Some(3));
+        assert!(usage.wall >= Duration::from_millis(100));
+        #[cfg(unix)]
+        assert!(usage.max_rss_kb.unwrap() > 0);
+
+        assert!(execute_measured(&[], None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_execute_measured_applies_timeout() {
+        let started = Instant::now();
+        let err = execute_measured(&argv(&["sh", "-c", "sleep 5 & wait"]), Some(Duration::from_secs(1)))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("timed out"), "{}", err);
+        assert!(started.elapsed() < Duration::from_secs(4));
+    }
+
+    #[test]
+    fn test_resource_usage_summary() {
+        let usage = ResourceUsage {
+            wall: Duration::from_millis(1240),
+            user_cpu: Some(Duration::from_millis(800)),
+            system_cpu: Some(Duration::from_millis(100)),
+            max_rss_kb: Some(46285),
+        };
+        assert_eq!(usage.summary(), "wall 1.24 s · user 0.80 s · sys 0.10 s · peak RSS 45.2 MiB");
+
+        let wall_only = ResourceUsage { wall: Duration::from_millis(50), ..Default::default() };
+        assert_eq!(wall_only.summary(), "wall 0.05 s");
+    }
+
+    #[test]
+    fn test_build_argv_passes_values_verbatim() {
+        let mut spec = create_test_spec("find");
+        spec.options.push(create_option(vec!["--name"], ArgumentType::String));
+        spec.options.push(create_option(vec!["--message"], ArgumentType::String));
+        spec.positional_args.push(PositionalArg {
+            name: "dir".to_string(),
+            description: "Directory".to_string(),
+            required: true,
+            sensitive: false,
+            argument_type: ArgumentType::Path,
+            default: None,
+        });
+
+        let mut values = HashMap::new();
+        values.insert("--name".to_string(), "*.txt".to_string());
+        values.insert("--message".to_string(), r#"it's "quoted""#.to_string());
+        values.insert("_pos_dir".to_string(), "/my dir".to_string());
+
+        let argv = build_argv(&spec, &values);
+        assert_eq!(argv[0], "find");
+        assert_eq!(argv.last().unwrap(), "/my dir");
+        let name = argv.iter().position(|a| a == "--name").unwrap();
+        assert_eq!(argv[name + 1], "*.txt");
+        let message = argv.iter().position(|a| a == "--message").unwrap();
+        assert_eq!(argv[message + 1], r#"it's "quoted""#);
+
+        // The display string round-trips back to the same argv
+        assert_eq!(shell_words::split(&build_command(&spec, &values)).unwrap(), argv);
+    }
+
+    #[test]
+    fn test_spawn_background_receives_args_verbatim() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let script = r#"for a in "$@"; do printf '[%s]\n' "$a"; done"#;
+        let launch = spawn_background(
+            &argv(&["sh", "-c", script, "sh", "*.txt", r#"it's "quoted""#, "a b"]),
+            temp_dir.path(),
+        )
+        .unwrap();
+
+        let expected = "[*.txt]\n[it's \"quoted\"]\n[a b]\n";
+        let mut contents = String::new();
+        for _ in 0..50 {
+            contents = std::fs::read_to_string(&launch.log_path).unwrap();
+            if contents == expected {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        assert_eq!(contents, expected);
+    }
+
+    #[test]
+    fn test_sample_values_use_defaults_and_placeholders() {
+        let mut spec = create_test_spec("grep");
+        let mut context = create_option(vec!["--context", "-C"], ArgumentType::Int);
+        context.default = Some("2".to_string());
+        let mut regexp = create_option(vec!["--regexp", "-e"], ArgumentType::String);
+        regexp.required = true;
+        regexp.argument_name = Some("PATTERNS".to_string());
+        spec.options.push(context);
+        spec.options.push(regexp);
+        spec.options.push(create_option(vec!["--count"], ArgumentType::Bool));
+        spec.positional_args.push(PositionalArg {
+            name: "file".to_string(),
+            description: "Input file".to_string(),
+            required: true,
+            sensitive: false,
+            argument_type: ArgumentType::String,
+            default: None,
+        });
+
+        let values = sample_values(&spec);
+        assert_eq!(values.len(), 3);
+        assert_eq!(
+            build_command(&spec, &values),
+            "grep --context 2 --regexp '<patterns>' '<file>'"
+        );
+    }
+
     #[test]
     fn test_build_command_env_var_in_value() {
         let mut spec = create_test_spec("echo");
@@ -597,4 +1547,36 @@ mod tests {
 
         std::env::remove_var("TEST_BUILD_VAR");
     }
+
+    #[test]
+    fn test_spawn_background_writes_log() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let launch = spawn_background(&argv(&["echo", "detached"]), temp_dir.path()).unwrap();
+
+        assert!(launch.pid > 0);
+        assert!(launch.log_path.starts_with(temp_dir.path()));
+        assert!(launch
+            .log_path
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("echo-"));
+
+        // The child isn't awaited, so poll briefly for its output
+        let mut contents = String::new();
+        for _ in 0..50 {
+            contents = std::fs::read_to_string(&launch.log_path).unwrap();
+            if !contents.is_empty() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        assert_eq!(contents, "detached\n");
+    }
+
+    #[test]
+    fn test_spawn_background_nonexistent_command_fails() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        assert!(spawn_background(&argv(&["nonexistent_command_12345"]), temp_dir.path()).is_err());
+    }
 }
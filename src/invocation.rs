@@ -0,0 +1,133 @@
+use crate::parser::CommandSpec;
+use crate::{QuocliError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// A shareable snapshot of a filled-in form: the command, the hash of the spec
+/// it was filled against, and its values. Sensitive values are left out, as with presets.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InvocationDescriptor {
+    /// Command as typed, e.g. `git commit`
+    pub command: String,
+    /// `version_hash` of the spec the values belong to
+    pub version_hash: String,
+    /// Field id (primary flag, or `_pos_<name>`) to value; sorted for stable, diffable files
+    pub values: BTreeMap<String, String>,
+}
+
+impl InvocationDescriptor {
+    pub fn new(command: &str, spec: &CommandSpec, values: &HashMap<String, String>) -> Self {
+        let sensitive = spec.sensitive_field_ids();
+        Self {
+            command: command.to_string(),
+            version_hash: spec.version_hash.clone(),
+            values: values
+                .iter()
+                .filter(|(id, value)| !value.is_empty() && !sensitive.contains(id))
+                .map(|(id, value)| (id.clone(), value.clone()))
+                .collect(),
+        }
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        let descriptor: Self = serde_json::from_str(&contents)?;
+        if descriptor.command.trim().is_empty() {
+            return Err(QuocliError::Parser(format!(
+                "Invocation file {} names no command",
+                path.display()
+            )));
+        }
+        Ok(descriptor)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+
+    /// The command split into program and subcommands
+    pub fn command_parts(&self) -> Vec<String> {
+        self.command.split_whitespace().map(str::to_string).collect()
+    }
+
+    /// The values to load into the form, provided `spec` is the one they were
+    /// exported from; a different spec could give the same flags other meanings
+    pub fn values_for(&self, spec: &CommandSpec) -> Result<HashMap<String, String>> {
+        if self.version_hash != spec.version_hash {
+            return Err(QuocliError::Parser(format!(
+                "The invocation was exported from a different spec of {} ({} here, {} in the file); \
+                 regenerate the spec from the same help text to import it",
+                self.command,
+                short_hash(&spec.version_hash),
+                short_hash(&self.version_hash)
+            )));
+        }
+        Ok(self.values.clone().into_iter().collect())
+    }
+}
+
+/// Default file name for an exported invocation, e.g. `git-commit.invocation.json`
+pub fn default_file_name(command: &str) -> String {
+    format!("{}.invocation.json", command.split_whitespace().collect::<Vec<_>>().join("-"))
+}
+
+fn short_hash(hash: &str) -> &str {
+    &hash[..hash.len().min(12)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(version_hash: &str) -> CommandSpec {
+        serde_json::from_value(serde_json::json!({
+            "command": "curl",
+            "version_hash": version_hash,
+            "description": "",
+            "options": [
+                {"flags": ["--user"], "description": "", "argument_type": "string", "sensitive": true},
+                {"flags": ["-L", "--location"], "description": "", "argument_type": "bool"}
+            ],
+            "positional_args": [{"name": "url", "description": "", "argument_type": "string"}],
+            "subcommands": [],
+            "danger_level": "low",
+            "examples": []
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_round_trip_drops_sensitive_values() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join(default_file_name("curl"));
+        let values = HashMap::from([
+            ("--user".to_string(), "bob:hunter2".to_string()),
+            ("--location".to_string(), "true".to_string()),
+            ("_pos_url".to_string(), "https://example.com".to_string()),
+        ]);
+
+        InvocationDescriptor::new("curl", &spec("abc"), &values).write(&path).unwrap();
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("hunter2"));
+
+        let descriptor = InvocationDescriptor::read(&path).unwrap();
+        assert_eq!(descriptor.command_parts(), vec!["curl"]);
+        let loaded = descriptor.values_for(&spec("abc")).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded["--location"], "true");
+        assert_eq!(loaded["_pos_url"], "https://example.com");
+    }
+
+    #[test]
+    fn test_values_for_rejects_other_spec() {
+        let descriptor = InvocationDescriptor::new("curl", &spec("abc"), &HashMap::new());
+        let err = descriptor.values_for(&spec("def")).unwrap_err();
+        assert!(err.to_string().contains("different spec"), "{}", err);
+    }
+
+    #[test]
+    fn test_default_file_name() {
+        assert_eq!(default_file_name("git commit"), "git-commit.invocation.json");
+    }
+}
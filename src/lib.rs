@@ -1,11 +1,16 @@
+pub mod audit;
 pub mod cache;
 pub mod config;
 pub mod executor;
+pub mod invocation;
 pub mod llm;
 pub mod parser;
 pub mod shell;
 pub mod tui;
 
+#[cfg(test)]
+mod test_env;
+
 pub use config::Config;
 pub use parser::CommandSpec;
 
@@ -0,0 +1,161 @@
+use crate::llm::LlmClient;
+use crate::QuocliError;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+/// An assist request running on a background task
+///
+/// The TUI event loop polls [`PendingChat::try_result`] between key events, so
+/// it stays responsive while the request is in flight. Cancelling (or dropping)
+/// aborts the task, which drops the in-flight HTTP request with it.
+pub struct PendingChat {
+    rx: oneshot::Receiver<Result<String, QuocliError>>,
+    handle: JoinHandle<()>,
+}
+
+impl PendingChat {
+    /// Start `client.chat(context, message)`, failing it after `timeout`
+    ///
+    /// Must be called from within a tokio runtime.
+    pub fn spawn(
+        client: Arc<dyn LlmClient>,
+        context: String,
+        message: String,
+        timeout: Duration,
+    ) -> Self {
+        let (tx, rx) = oneshot::channel();
+        let handle = tokio::spawn(async move {
+            let result = match tokio::time::timeout(timeout, client.chat(&context, &message)).await {
+                Ok(result) => result,
+                Err(_) => Err(QuocliError::Llm(format!(
+                    "Assist request timed out after {}s",
+                    timeout.as_secs()
+                ))),
+            };
+            let _ = tx.send(result);
+        });
+        Self { rx, handle }
+    }
+
+    /// The reply, once it has arrived; `None` while still waiting
+    pub fn try_result(&mut self) -> Option<Result<String, QuocliError>> {
+        match self.rx.try_recv() {
+            Ok(result) => Some(result),
+            Err(oneshot::error::TryRecvError::Empty) => None,
+            Err(oneshot::error::TryRecvError::Closed) => Some(Err(QuocliError::Llm(
+                "Assist request ended without a reply".to_string(),
+            ))),
+        }
+    }
+
+    /// Abort the request
+    pub fn cancel(self) {
+        self.handle.abort();
+    }
+}
+
+impl Drop for PendingChat {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// The command an assist reply suggests: its last line starting with `$ `
+pub fn suggested_command(reply: &str) -> Option<String> {
+    reply
+        .lines()
+        .rev()
+        .map(|line| line.trim().trim_matches('`'))
+        .find_map(|line| line.strip_prefix("$ "))
+        .map(|command| command.trim().to_string())
+        .filter(|command| !command.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::client::async_trait;
+    use crate::parser::{CommandSpec, HelpDocumentation};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// Replies after `delay`, recording whether it got to finish
+    struct SlowClient {
+        delay: Duration,
+        finished: Arc<AtomicBool>,
+    }
+
+    #[async_trait]
+    impl LlmClient for SlowClient {
+        async fn generate_spec(
+            &self,
+            _command: &str,
+            _subcommands: &[String],
+            _docs: &HelpDocumentation,
+            _help_hash: &str,
+        ) -> Result<CommandSpec, QuocliError> {
+            Err(QuocliError::Llm("SlowClient only chats".to_string()))
+        }
+
+        async fn chat(&self, _context: &str, message: &str) -> Result<String, QuocliError> {
+            tokio::time::sleep(self.delay).await;
+            self.finished.store(true, Ordering::SeqCst);
+            Ok(format!("re: {}", message))
+        }
+    }
+
+    fn slow_client(delay: Duration) -> (Arc<dyn LlmClient>, Arc<AtomicBool>) {
+        let finished = Arc::new(AtomicBool::new(false));
+        let client = SlowClient {
+            delay,
+            finished: finished.clone(),
+        };
+        (Arc::new(client), finished)
+    }
+
+    #[test]
+    fn test_suggested_command_takes_last_dollar_line() {
+        let reply = "Use -L to follow redirects and -O to save the file.\n\n`$ curl -L -O https://example.com/a.tgz`\n";
+        assert_eq!(
+            suggested_command(reply).as_deref(),
+            Some("curl -L -O https://example.com/a.tgz")
+        );
+        assert_eq!(suggested_command("No option does that."), None);
+        assert_eq!(suggested_command("$ "), None);
+    }
+
+    #[tokio::test]
+    async fn test_pending_chat_delivers_reply() {
+        let (client, _) = slow_client(Duration::from_millis(10));
+        let mut pending =
+            PendingChat::spawn(client, "ctx".to_string(), "hi".to_string(), Duration::from_secs(5));
+        assert!(pending.try_result().is_none());
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(pending.try_result().unwrap().unwrap(), "re: hi");
+    }
+
+    #[tokio::test]
+    async fn test_pending_chat_times_out() {
+        let (client, finished) = slow_client(Duration::from_secs(30));
+        let mut pending =
+            PendingChat::spawn(client, "ctx".to_string(), "hi".to_string(), Duration::from_millis(20));
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let err = pending.try_result().unwrap().unwrap_err();
+        assert!(err.to_string().contains("timed out"), "{}", err);
+        assert!(!finished.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_pending_chat_cancel_drops_request() {
+        let (client, finished) = slow_client(Duration::from_millis(50));
+        let pending =
+            PendingChat::spawn(client, "ctx".to_string(), "hi".to_string(), Duration::from_secs(5));
+        pending.cancel();
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert!(!finished.load(Ordering::SeqCst));
+    }
+}
@@ -1,8 +1,262 @@
-use crate::config::Config;
-use crate::parser::{CommandSpec, HelpDocumentation};
+use crate::config::{Config, LlmConfig};
+use crate::parser::{ArgumentType, CommandOption, CommandSpec, DangerLevel, HelpDocumentation, PositionalArg};
 use crate::QuocliError;
+use serde::Deserialize;
+use std::time::Duration;
 
 use super::providers::anthropic::AnthropicClient;
+use super::providers::command::CommandProvider;
+use super::providers::ollama::{self, OllamaClient};
+
+/// How spec generation reports progress on stderr
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ProgressMode {
+    /// Live progress bars while options are processed
+    #[default]
+    Interactive,
+    /// No live output, only a one-line summary when done (clean CI logs)
+    Quiet,
+}
+
+impl ProgressMode {
+    /// Quiet when requested or when stderr isn't a terminal
+    pub fn detect(quiet: bool) -> Self {
+        use std::io::IsTerminal;
+        if quiet || !std::io::stderr().is_terminal() {
+            ProgressMode::Quiet
+        } else {
+            ProgressMode::Interactive
+        }
+    }
+}
+
+/// Per-run knobs for spec generation
+#[derive(Debug, Clone, Default)]
+pub struct GenerationOptions {
+    /// Model to use instead of `llm.model`
+    pub model: Option<String>,
+    pub progress: ProgressMode,
+    /// Generate the whole spec in one call instead of per-option fan-out
+    pub single_pass: bool,
+    /// Print a per-phase timing report after generation
+    pub timings: bool,
+    /// Skip the confirmation for commands with more than `llm.max_options` options
+    pub assume_yes: bool,
+    /// Flags to leave out of the spec (`parser.ignore_flags` plus per-command ones)
+    pub ignore_flags: Vec<String>,
+    /// Generate from plain `--help` only, skipping the manpage and extended help
+    pub basic_only: bool,
+}
+
+/// Wall-clock durations of named phases (spec generation, or a whole run with `--profile`)
+#[derive(Debug, Clone, Default)]
+pub struct GenerationTimings {
+    phases: Vec<(String, Duration, Option<usize>)>,
+}
+
+impl GenerationTimings {
+    pub fn record(&mut self, phase: &str, duration: Duration) {
+        self.phases.push((phase.to_string(), duration, None));
+    }
+
+    /// Record a phase that processed `items` things, so the report can show a per-item average
+    pub fn record_items(&mut self, phase: &str, duration: Duration, items: usize) {
+        self.phases.push((phase.to_string(), duration, Some(items)));
+    }
+
+    pub fn extend(&mut self, other: GenerationTimings) {
+        self.phases.extend(other.phases);
+    }
+
+    /// Total time recorded under `phase`
+    pub fn duration(&self, phase: &str) -> Duration {
+        self.phases
+            .iter()
+            .filter(|(name, _, _)| name == phase)
+            .map(|(_, duration, _)| *duration)
+            .sum()
+    }
+
+    /// Render a small aligned report under `title`, ending with the total wall time
+    pub fn report(&self, title: &str, total: Duration) -> String {
+        let width = self
+            .phases
+            .iter()
+            .map(|(name, _, _)| name.len())
+            .chain(std::iter::once("total".len()))
+            .max()
+            .unwrap_or(0);
+
+        let mut out = format!("{}:\n", title);
+        for (name, duration, items) in &self.phases {
+            out.push_str(&format!("  {:<width$}  {:>8}", name, format_duration(*duration)));
+            if let Some(count) = items {
+                if *count > 0 {
+                    let average = *duration / *count as u32;
+                    out.push_str(&format!("  ({} items, avg {})", count, format_duration(average)));
+                }
+            }
+            out.push('\n');
+        }
+        out.push_str(&format!("  {:<width$}  {:>8}\n", "total", format_duration(total)));
+        out
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    if duration.as_millis() < 1000 {
+        format!("{} ms", duration.as_millis())
+    } else {
+        format!("{:.1} s", duration.as_secs_f64())
+    }
+}
+
+/// A spec as generated, before post-processing: the single-pass response shape,
+/// and what the per-option fan-out assembles from its calls
+#[derive(Deserialize)]
+pub(crate) struct GeneratedSpec {
+    pub description: String,
+    pub danger_level: DangerLevel,
+    #[serde(default)]
+    pub positionals_first: bool,
+    #[serde(default)]
+    pub options: Vec<CommandOption>,
+    #[serde(default)]
+    pub positional_args: Vec<PositionalArg>,
+    #[serde(default)]
+    pub examples: Vec<String>,
+    /// Options and positional args whose detail requests failed and were left out
+    #[serde(skip)]
+    pub failed: usize,
+}
+
+impl GeneratedSpec {
+    /// Finish a generated spec the same way whichever path produced it: ignored
+    /// flags dropped, descriptions enriched from the docs and enum defaults normalized
+    pub(crate) fn into_spec(
+        self,
+        command: &str,
+        help_hash: &str,
+        docs: &HelpDocumentation,
+        ignore_flags: &[String],
+    ) -> CommandSpec {
+        let mut options = drop_ignored_options(self.options, ignore_flags);
+        enrich_descriptions(&mut options, docs);
+        normalize_enum_defaults(&mut options);
+
+        CommandSpec {
+            command: command.to_string(),
+            version_hash: help_hash.to_string(),
+            description: self.description,
+            options,
+            positional_args: self.positional_args,
+            subcommands: vec![],
+            danger_level: self.danger_level,
+            examples: self.examples,
+            positionals_first: self.positionals_first,
+            tool_version: None,
+            basic_only: false,
+        }
+    }
+}
+
+/// The positional call's reply: argument names from the usage syntax
+#[derive(Deserialize, Default)]
+pub(crate) struct PositionalNames {
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub positionals_first: bool,
+}
+
+/// The metadata call's reply
+#[derive(Deserialize)]
+pub(crate) struct CommandMetadata {
+    pub description: String,
+    pub danger_level: DangerLevel,
+}
+
+impl CommandMetadata {
+    /// Stand-in metadata when the call fails or its reply doesn't parse
+    pub(crate) fn fallback(full_command: &str) -> Self {
+        CommandMetadata {
+            description: format!("Command: {}", full_command),
+            danger_level: DangerLevel::Low,
+        }
+    }
+}
+
+/// Whether an option (or extracted flag group) uses any of the ignored flags
+pub fn is_ignored_flag(flags: &[String], ignore_flags: &[String]) -> bool {
+    flags.iter().any(|flag| ignore_flags.contains(flag))
+}
+
+/// Drop generated options that use an ignored flag (the model may list them anyway)
+pub(crate) fn drop_ignored_options(options: Vec<CommandOption>, ignore_flags: &[String]) -> Vec<CommandOption> {
+    options
+        .into_iter()
+        .filter(|option| !is_ignored_flag(&option.flags, ignore_flags))
+        .collect()
+}
+
+/// Use the documentation's own description for options whose generated one is
+/// empty or clearly cut short; any other generated text is kept as written
+pub(crate) fn enrich_descriptions(options: &mut [CommandOption], docs: &HelpDocumentation) {
+    for option in options {
+        if let Some(documented) = docs.option_description(&option.flags) {
+            if is_truncated_description(&option.description, &documented) {
+                option.description = documented;
+            }
+        }
+    }
+}
+
+/// Whether a generated description is empty or cut short: it trails off in an
+/// ellipsis, or is just the start of the documented text
+fn is_truncated_description(generated: &str, documented: &str) -> bool {
+    let normalize = |text: &str| text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    let generated = normalize(generated);
+    let documented = normalize(documented);
+    generated.is_empty()
+        || generated.ends_with("...")
+        || generated.ends_with('…')
+        || (documented.len() > generated.len() && documented.starts_with(&generated))
+}
+
+/// Make each enum option's default one of its `enum_values`: a case-insensitive
+/// match takes the listed spelling, and anything else is added to the list,
+/// since a documented default is usually a real value the model left out
+pub(crate) fn normalize_enum_defaults(options: &mut [CommandOption]) {
+    for option in options.iter_mut().filter(|o| o.argument_type == ArgumentType::Enum) {
+        let Some(default) = option.default.as_deref().map(str::trim) else {
+            continue;
+        };
+        if default.is_empty() {
+            option.default = None;
+        } else if let Some(listed) = option.enum_values.iter().find(|v| v.eq_ignore_ascii_case(default)) {
+            option.default = Some(listed.clone());
+        } else {
+            tracing::debug!("Adding default {:?} to the values of {}", default, option.primary_flag());
+            let default = default.to_string();
+            option.enum_values.push(default.clone());
+            option.default = Some(default);
+        }
+    }
+}
+
+/// One-line summary of a generated spec, e.g. for CI logs. `skipped` counts
+/// options dropped by `parser.ignore_flags`, `failed` the items whose detail
+/// requests failed
+pub fn generation_summary(spec: &CommandSpec, full_command: &str, skipped: usize, failed: usize) -> String {
+    format!(
+        "Generated spec for {}: {} options ({} skipped, {} failed), {} positional, danger={}",
+        full_command,
+        spec.options.len(),
+        skipped,
+        failed,
+        spec.positional_args.len(),
+        spec.danger_level
+    )
+}
 
 /// Trait for LLM clients
 #[async_trait::async_trait]
@@ -20,23 +274,56 @@ pub trait LlmClient: Send + Sync {
         context: &str,
         message: &str,
     ) -> Result<String, QuocliError>;
+
+    /// Phase timings recorded during the last `generate_spec` call
+    fn timings(&self) -> GenerationTimings {
+        GenerationTimings::default()
+    }
 }
 
-/// Create an LLM client based on configuration
-pub fn create_client(config: &Config) -> Result<Box<dyn LlmClient>, QuocliError> {
+/// Create an LLM client based on configuration and per-run generation options
+pub fn create_client(
+    config: &Config,
+    options: &GenerationOptions,
+) -> Result<Box<dyn LlmClient>, QuocliError> {
+    let model = options.model.clone().unwrap_or_else(|| config.llm.model.clone());
     match config.llm.provider.as_str() {
         "anthropic" => {
-            let api_key = std::env::var(&config.llm.api_key_env).map_err(|_| {
-                QuocliError::Config(format!(
-                    "API key not found in environment variable: {}",
-                    config.llm.api_key_env
-                ))
-            })?;
+            let api_key = resolve_api_key(&config.llm)?;
 
-            Ok(Box::new(AnthropicClient::new(
-                api_key,
-                config.llm.model.clone(),
-            )))
+            let mut client = AnthropicClient::new(api_key, model, config.llm.fallback_model.clone())
+                .with_progress(options.progress)
+                .with_single_pass(options.single_pass, config.llm.auto_single_pass)
+                .with_option_guard(config.llm.max_options, options.assume_yes)
+                .with_ignore_flags(options.ignore_flags.clone());
+            if let Some(concurrency) = config.llm.max_concurrent_requests {
+                client = client.with_concurrency(concurrency);
+            }
+            Ok(Box::new(client))
+        }
+        "ollama" => {
+            // Local models cost nothing per call, so there is no option guard
+            let mut client = OllamaClient::new(ollama_model(&config.llm, options.model.as_deref()))
+                .with_progress(options.progress)
+                .with_single_pass(options.single_pass, config.llm.auto_single_pass)
+                .with_ignore_flags(options.ignore_flags.clone());
+            if let Some(base_url) = &config.llm.base_url {
+                client = client.with_base_url(base_url);
+            }
+            if let Some(concurrency) = config.llm.max_concurrent_requests {
+                client = client.with_concurrency(concurrency);
+            }
+            Ok(Box::new(client))
+        }
+        "command" => {
+            let program = config.llm.provider_command.clone().ok_or_else(|| {
+                QuocliError::Config("provider = \"command\" requires llm.provider_command".to_string())
+            })?;
+            Ok(Box::new(
+                CommandProvider::new(program)
+                    .with_progress(options.progress)
+                    .with_ignore_flags(options.ignore_flags.clone()),
+            ))
         }
         provider => Err(QuocliError::Config(format!(
             "Unsupported LLM provider: {}",
@@ -45,5 +332,266 @@ pub fn create_client(config: &Config) -> Result<Box<dyn LlmClient>, QuocliError>
     }
 }
 
+/// The Ollama model to use: the per-run or configured one, except that the
+/// default `llm.model` (a Claude model no Ollama server has) means Ollama's default
+fn ollama_model(config: &LlmConfig, model_override: Option<&str>) -> String {
+    match model_override {
+        Some(model) => model.to_string(),
+        None if config.has_default_model() => ollama::DEFAULT_MODEL.to_string(),
+        None => config.model.clone(),
+    }
+}
+
+/// Read the API key from `api_key_file` if set, otherwise from `api_key_env`
+fn resolve_api_key(config: &LlmConfig) -> Result<String, QuocliError> {
+    if let Some(path) = &config.api_key_file {
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            QuocliError::Config(format!(
+                "Failed to read API key file {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let key = contents.trim();
+        if key.is_empty() {
+            return Err(QuocliError::Config(format!(
+                "API key file is empty: {}",
+                path.display()
+            )));
+        }
+        return Ok(key.to_string());
+    }
+
+    std::env::var(&config.api_key_env).map_err(|_| {
+        QuocliError::Config(format!(
+            "API key not found in environment variable: {}",
+            config.api_key_env
+        ))
+    })
+}
+
 // Re-export async_trait for providers
 pub use async_trait::async_trait;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_env::EnvGuard;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_generation_summary() {
+        let spec = CommandSpec {
+            command: "curl".to_string(),
+            version_hash: "hash".to_string(),
+            description: "Transfer a URL".to_string(),
+            options: vec![],
+            positional_args: vec![],
+            subcommands: vec![],
+            danger_level: crate::parser::DangerLevel::Medium,
+            examples: vec![],
+            positionals_first: false,
+            tool_version: None,
+            basic_only: false,
+        };
+        assert_eq!(
+            generation_summary(&spec, "curl", 0, 0),
+            "Generated spec for curl: 0 options (0 skipped, 0 failed), 0 positional, danger=medium"
+        );
+        assert_eq!(
+            generation_summary(&spec, "curl", 2, 1),
+            "Generated spec for curl: 0 options (2 skipped, 1 failed), 0 positional, danger=medium"
+        );
+    }
+
+    #[test]
+    fn test_generation_timings_report() {
+        let mut timings = GenerationTimings::default();
+        timings.record("help fetch", Duration::from_millis(120));
+        timings.record_items("option fan-out", Duration::from_millis(2500), 10);
+        timings.record_items("positional details", Duration::from_millis(0), 0);
+
+        assert_eq!(timings.duration("help fetch"), Duration::from_millis(120));
+        assert_eq!(timings.duration("missing"), Duration::ZERO);
+
+        let report = timings.report("Generation timings for curl", Duration::from_millis(3000));
+        assert!(report.starts_with("Generation timings for curl:\n"));
+        assert!(report.contains("  help fetch            120 ms\n"));
+        assert!(report.contains("  option fan-out         2.5 s  (10 items, avg 250 ms)\n"));
+        assert!(report.contains("  positional details      0 ms\n"));
+        assert!(report.ends_with("  total                  3.0 s\n"));
+    }
+
+    #[test]
+    fn test_progress_mode_quiet_flag() {
+        assert_eq!(ProgressMode::detect(true), ProgressMode::Quiet);
+    }
+
+    #[test]
+    fn test_resolve_api_key_from_file_trims_whitespace() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("api_key");
+        std::fs::write(&path, "  sk-test-123\n").unwrap();
+
+        let config = LlmConfig {
+            api_key_file: Some(path),
+            api_key_env: "QUOCLI_TEST_UNSET_API_KEY".to_string(),
+            ..LlmConfig::default()
+        };
+        assert_eq!(resolve_api_key(&config).unwrap(), "sk-test-123");
+    }
+
+    #[test]
+    fn test_resolve_api_key_file_takes_precedence() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("api_key");
+        std::fs::write(&path, "from-file").unwrap();
+        let mut env = EnvGuard::lock();
+        env.set("QUOCLI_TEST_PRECEDENCE_API_KEY", "from-env");
+
+        let config = LlmConfig {
+            api_key_file: Some(path),
+            api_key_env: "QUOCLI_TEST_PRECEDENCE_API_KEY".to_string(),
+            ..LlmConfig::default()
+        };
+        assert_eq!(resolve_api_key(&config).unwrap(), "from-file");
+    }
+
+    #[test]
+    fn test_resolve_api_key_file_errors() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let missing = LlmConfig {
+            api_key_file: Some(temp_dir.path().join("missing")),
+            ..LlmConfig::default()
+        };
+        let err = resolve_api_key(&missing).unwrap_err();
+        assert!(matches!(err, QuocliError::Config(ref msg) if msg.contains("Failed to read API key file")));
+
+        let empty_path = temp_dir.path().join("empty");
+        std::fs::write(&empty_path, "\n").unwrap();
+        let empty = LlmConfig {
+            api_key_file: Some(empty_path),
+            ..LlmConfig::default()
+        };
+        let err = resolve_api_key(&empty).unwrap_err();
+        assert!(matches!(err, QuocliError::Config(ref msg) if msg.contains("empty")));
+    }
+
+    #[test]
+    fn test_enrich_descriptions_replaces_only_truncated_text() {
+        let docs = HelpDocumentation {
+            help_text: "  -r, --recursive   copy directories\n  -f, --force   never prompt\n  -n   no clobber\n  -R   copy directories recursively\n".to_string(),
+            manpage_text: "       -r, --recursive\n              copy directories recursively, following\n              symbolic links given on the command line\n".to_string(),
+        };
+        let mut options: Vec<CommandOption> = serde_json::from_str(
+            r#"[{"flags":["-r","--recursive"],"description":"Copy directories","argument_type":"bool"},
+                {"flags":["-v"],"description":"Explain what is being done in detail","argument_type":"bool"},
+                {"flags":["-f","--force"],"description":"","argument_type":"bool"},
+                {"flags":["-n"],"description":"Do not overwrite...","argument_type":"bool"},
+                {"flags":["-R"],"description":"Same as -r","argument_type":"bool"}]"#,
+        )
+        .unwrap();
+
+        enrich_descriptions(&mut options, &docs);
+        assert_eq!(
+            options[0].description,
+            "copy directories recursively, following symbolic links given on the command line"
+        );
+        assert_eq!(options[1].description, "Explain what is being done in detail");
+        assert_eq!(options[2].description, "never prompt");
+        assert_eq!(options[3].description, "no clobber");
+        // Shorter than the documentation, but complete in its own right
+        assert_eq!(options[4].description, "Same as -r");
+    }
+
+    #[test]
+    fn test_normalize_enum_defaults() {
+        let mut options: Vec<CommandOption> = serde_json::from_str(
+            r#"[{"flags":["--color"],"description":"","argument_type":"enum","enum_values":["auto","always","never"],"default":"auto"},
+                {"flags":["--format"],"description":"","argument_type":"enum","enum_values":["json","yaml"],"default":"JSON"},
+                {"flags":["--level"],"description":"","argument_type":"enum","enum_values":["low","high"],"default":"medium"},
+                {"flags":["--mode"],"description":"","argument_type":"enum","enum_values":["a"],"default":" "},
+                {"flags":["--name"],"description":"","argument_type":"string","default":"x"}]"#,
+        )
+        .unwrap();
+
+        normalize_enum_defaults(&mut options);
+        assert_eq!(options[0].default.as_deref(), Some("auto"));
+        assert_eq!(options[0].enum_values.len(), 3);
+        assert_eq!(options[1].default.as_deref(), Some("json"));
+        assert_eq!(options[1].enum_values, vec!["json", "yaml"]);
+        assert_eq!(options[2].default.as_deref(), Some("medium"));
+        assert_eq!(options[2].enum_values, vec!["low", "high", "medium"]);
+        assert_eq!(options[3].default, None);
+        assert_eq!(options[4].default.as_deref(), Some("x"));
+    }
+
+    #[test]
+    fn test_generated_spec_post_processing() {
+        let generated: GeneratedSpec = serde_json::from_str(
+            r#"{"description": "d", "danger_level": "low",
+                "options": [{"flags":["-h","--help"],"description":"help","argument_type":"bool"},
+                            {"flags":["--color"],"description":"","argument_type":"enum","enum_values":["auto"],"default":"AUTO"}],
+                "examples": ["tool --color auto"]}"#,
+        )
+        .unwrap();
+        let docs = HelpDocumentation {
+            help_text: String::new(),
+            manpage_text: String::new(),
+        };
+
+        let spec = generated.into_spec("tool", "hash", &docs, &["--help".to_string()]);
+        assert_eq!(spec.command, "tool");
+        assert_eq!(spec.version_hash, "hash");
+        assert_eq!(spec.options.len(), 1);
+        assert_eq!(spec.options[0].default.as_deref(), Some("auto"));
+        assert_eq!(spec.examples, vec!["tool --color auto"]);
+    }
+
+    #[test]
+    fn test_is_ignored_flag() {
+        let ignore = vec!["--help".to_string(), "--version".to_string()];
+        assert!(is_ignored_flag(&["-h".to_string(), "--help".to_string()], &ignore));
+        assert!(!is_ignored_flag(&["-v".to_string(), "--verbose".to_string()], &ignore));
+        assert!(!is_ignored_flag(&["--help".to_string()], &[]));
+    }
+
+    #[test]
+    fn test_create_ollama_client_needs_no_api_key() {
+        let mut config = Config::default();
+        config.llm.provider = "ollama".to_string();
+        config.llm.api_key_env = "QUOCLI_TEST_OLLAMA_NO_KEY".to_string();
+        assert!(create_client(&config, &GenerationOptions::default()).is_ok());
+
+        config.llm.provider = "nonexistent".to_string();
+        assert!(create_client(&config, &GenerationOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_ollama_model_replaces_the_default_claude_model() {
+        let mut config = LlmConfig::default();
+        assert_eq!(ollama_model(&config, None), ollama::DEFAULT_MODEL);
+        assert_eq!(ollama_model(&config, Some("qwen2.5")), "qwen2.5");
+
+        config.model = "mistral".to_string();
+        assert_eq!(ollama_model(&config, None), "mistral");
+    }
+
+    #[test]
+    fn test_resolve_api_key_from_env() {
+        let mut env = EnvGuard::lock();
+        env.set("QUOCLI_TEST_ENV_API_KEY", "env-key");
+        let config = LlmConfig {
+            api_key_env: "QUOCLI_TEST_ENV_API_KEY".to_string(),
+            ..LlmConfig::default()
+        };
+        assert_eq!(resolve_api_key(&config).unwrap(), "env-key");
+
+        let missing = LlmConfig {
+            api_key_env: "QUOCLI_TEST_MISSING_API_KEY".to_string(),
+            ..LlmConfig::default()
+        };
+        assert!(resolve_api_key(&missing).is_err());
+    }
+}
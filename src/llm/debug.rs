@@ -0,0 +1,74 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Where responses that failed to parse are saved for inspection
+pub fn debug_dir() -> Option<PathBuf> {
+    crate::config::data_dir().map(|dir| dir.join("debug"))
+}
+
+/// A saved failed response
+#[derive(Debug, Clone)]
+pub struct DebugFile {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+/// The files in `dir`, newest first; a missing directory has none
+pub fn list_debug_files(dir: &Path) -> io::Result<Vec<DebugFile>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut files = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_file() {
+            files.push(DebugFile {
+                path: entry.path(),
+                size: metadata.len(),
+                modified: metadata.modified().ok(),
+            });
+        }
+    }
+    files.sort_by(|a, b| b.modified.cmp(&a.modified).then_with(|| a.path.cmp(&b.path)));
+    Ok(files)
+}
+
+/// Delete the files in `dir`, returning how many were removed
+pub fn clear_debug_files(dir: &Path) -> io::Result<usize> {
+    let files = list_debug_files(dir)?;
+    for file in &files {
+        std::fs::remove_file(&file.path)?;
+    }
+    Ok(files.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_and_clear_debug_files() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path().join("debug");
+        assert!(list_debug_files(&dir).unwrap().is_empty());
+
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        std::fs::write(dir.join("failed_verbose.json"), "{not json").unwrap();
+        std::fs::write(dir.join("failed_output.json"), "").unwrap();
+
+        let files = list_debug_files(&dir).unwrap();
+        assert_eq!(files.len(), 2);
+        let verbose = files.iter().find(|f| f.path.ends_with("failed_verbose.json")).unwrap();
+        assert_eq!(verbose.size, 9);
+
+        assert_eq!(clear_debug_files(&dir).unwrap(), 2);
+        assert!(list_debug_files(&dir).unwrap().is_empty());
+        assert!(dir.join("nested").is_dir());
+    }
+}
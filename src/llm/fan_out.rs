@@ -0,0 +1,105 @@
+use super::progress::BatchProgress;
+use crate::QuocliError;
+use futures::future::BoxFuture;
+use futures::stream::{self, StreamExt};
+use std::time::Duration;
+
+/// What a fan-out gathered, and how many of its requests failed
+pub(crate) struct FanOut<T> {
+    pub items: Vec<T>,
+    pub failed: usize,
+}
+
+/// Make one detail request per item, at most `concurrency` at a time, keeping the
+/// items' order and advancing `progress` as each finishes.
+///
+/// A failed request is logged (under `label(item)`) and its item left out, so one
+/// bad reply doesn't cost the whole spec; only when every request fails is the
+/// last error returned. With `warm_up`, the first request runs alone and the rest
+/// wait that long after it, so they can read a prompt cache it wrote.
+pub(crate) async fn fan_out<'a, I, T>(
+    items: Vec<I>,
+    concurrency: usize,
+    warm_up: Option<Duration>,
+    progress: &mut BatchProgress,
+    label: impl Fn(&I) -> String,
+    request: impl Fn(I) -> BoxFuture<'a, Result<T, QuocliError>>,
+) -> Result<FanOut<T>, QuocliError> {
+    let mut gathered = FanOut {
+        items: Vec::with_capacity(items.len()),
+        failed: 0,
+    };
+    let mut last_error = None;
+    let mut record = |label: String, result: Result<T, QuocliError>| {
+        match result {
+            Ok(item) => gathered.items.push(item),
+            Err(e) => {
+                tracing::warn!("Leaving {} out of the spec: {}", label, e);
+                gathered.failed += 1;
+                last_error = Some(e);
+            }
+        }
+        progress.advance(1);
+    };
+
+    // Results come back in order, so they line up with the labels
+    let mut labels = items.iter().map(label).collect::<Vec<_>>().into_iter();
+    let mut requests = items.into_iter().map(request);
+    if let Some(delay) = warm_up {
+        if let Some(first) = requests.next() {
+            record(labels.next().unwrap_or_default(), first.await);
+            tokio::time::sleep(delay).await;
+        }
+    }
+    let mut results = stream::iter(requests).buffered(concurrency.max(1));
+    while let Some(result) = results.next().await {
+        record(labels.next().unwrap_or_default(), result);
+    }
+
+    match last_error {
+        Some(e) if gathered.items.is_empty() => Err(e),
+        _ => Ok(gathered),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::ProgressMode;
+
+    #[tokio::test]
+    async fn test_fan_out_leaves_failed_items_out() {
+        let mut progress = BatchProgress::start(ProgressMode::Quiet, "Processing options", 3);
+        let gathered = fan_out(vec![1, 2, 3], 2, None, &mut progress, |n| n.to_string(), |n| {
+            Box::pin(async move {
+                if n == 2 {
+                    Err(QuocliError::Llm("bad reply".to_string()))
+                } else {
+                    Ok(n * 10)
+                }
+            }) as BoxFuture<'_, _>
+        })
+        .await
+        .unwrap();
+        assert_eq!(gathered.items, vec![10, 30]);
+        assert_eq!(gathered.failed, 1);
+    }
+
+    #[tokio::test]
+    async fn test_fan_out_fails_when_every_request_fails() {
+        let mut progress = BatchProgress::start(ProgressMode::Quiet, "Processing options", 2);
+        let result = fan_out(vec![1, 2], 2, Some(Duration::ZERO), &mut progress, |n| n.to_string(), |_| {
+            Box::pin(async { Err::<i32, _>(QuocliError::Llm("server down".to_string())) }) as BoxFuture<'_, _>
+        })
+        .await;
+        assert!(result.is_err());
+
+        let mut progress = BatchProgress::start(ProgressMode::Quiet, "Processing options", 0);
+        let gathered = fan_out(Vec::<i32>::new(), 2, None, &mut progress, |n| n.to_string(), |n| {
+            Box::pin(async move { Ok(n) }) as BoxFuture<'_, _>
+        })
+        .await
+        .unwrap();
+        assert!(gathered.items.is_empty());
+    }
+}
@@ -1,5 +1,15 @@
+mod chat;
 mod client;
+mod debug;
+mod fan_out;
+mod progress;
 mod prompt;
 pub mod providers;
 
-pub use client::{create_client, LlmClient};
+pub use chat::{suggested_command, PendingChat};
+pub use prompt::chat_context;
+pub use debug::{clear_debug_files, debug_dir, list_debug_files, DebugFile};
+pub use client::{
+    create_client, generation_summary, is_ignored_flag, GenerationOptions, GenerationTimings, LlmClient,
+    ProgressMode,
+};
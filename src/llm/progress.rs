@@ -0,0 +1,107 @@
+use super::client::ProgressMode;
+use indicatif::{ProgressBar, ProgressStyle};
+
+/// How many log lines a batch writes at most when there is no progress bar
+const LOG_STEPS: usize = 10;
+
+/// Progress of one batch of per-item requests (options or positional args).
+///
+/// Interactive runs get a progress bar on stderr; otherwise (`--quiet`, or
+/// stderr redirected) the count goes to the log every tenth of the way, so
+/// long generations still show signs of life without cursor tricks.
+pub(crate) struct BatchProgress {
+    label: &'static str,
+    total: usize,
+    current: usize,
+    bar: Option<ProgressBar>,
+}
+
+impl BatchProgress {
+    /// Start counting `total` items, drawing the bar at 0/total straight away
+    pub(crate) fn start(mode: ProgressMode, label: &'static str, total: usize) -> Self {
+        let bar = (mode == ProgressMode::Interactive).then(|| {
+            let bar = ProgressBar::new(total as u64);
+            if let Ok(style) = ProgressStyle::with_template("{msg} [{bar:30}] {pos}/{len}") {
+                bar.set_style(style.progress_chars("=> "));
+            }
+            bar.set_message(label);
+            bar.tick();
+            bar
+        });
+        BatchProgress {
+            label,
+            total,
+            current: 0,
+            bar,
+        }
+    }
+
+    /// Record `count` more finished items
+    pub(crate) fn advance(&mut self, count: usize) {
+        let before = self.current;
+        self.current = (self.current + count).min(self.total);
+        match &self.bar {
+            Some(bar) => bar.set_position(self.current as u64),
+            None if crosses_log_step(before, self.current, self.total) => {
+                tracing::info!("{}: {}/{}", self.label, self.current, self.total);
+            }
+            None => {}
+        }
+    }
+
+    /// Leave the bar showing the full count (the batch succeeded)
+    pub(crate) fn finish(self) {
+        if let Some(bar) = &self.bar {
+            bar.set_position(self.total as u64);
+            bar.finish();
+        }
+    }
+}
+
+impl Drop for BatchProgress {
+    /// A batch abandoned on an error clears its bar so the error prints cleanly
+    fn drop(&mut self) {
+        if let Some(bar) = &self.bar {
+            if !bar.is_finished() {
+                bar.finish_and_clear();
+            }
+        }
+    }
+}
+
+/// Whether going from `before` to `after` of `total` passes one of the
+/// `LOG_STEPS` evenly spaced checkpoints (the last item always counts)
+fn crosses_log_step(before: usize, after: usize, total: usize) -> bool {
+    if total == 0 || after == before {
+        return false;
+    }
+    after == total || before * LOG_STEPS / total != after * LOG_STEPS / total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_steps_for_large_batch() {
+        let logged: Vec<usize> = (1..=100).filter(|&n| crosses_log_step(n - 1, n, 100)).collect();
+        assert_eq!(logged, vec![10, 20, 30, 40, 50, 60, 70, 80, 90, 100]);
+    }
+
+    #[test]
+    fn test_log_steps_for_small_batch() {
+        let logged: Vec<usize> = (1..=3).filter(|&n| crosses_log_step(n - 1, n, 3)).collect();
+        assert_eq!(logged, vec![1, 2, 3]);
+        assert!(!crosses_log_step(0, 0, 0));
+    }
+
+    #[test]
+    fn test_quiet_batch_counts_without_bar() {
+        let mut progress = BatchProgress::start(ProgressMode::Quiet, "Processing options", 2);
+        assert!(progress.bar.is_none());
+        progress.advance(1);
+        progress.advance(5);
+        assert_eq!(progress.current, 2);
+        progress.finish();
+    }
+}
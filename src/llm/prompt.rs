@@ -1,3 +1,8 @@
+use crate::executor::build_masked_argv;
+use crate::parser::CommandSpec;
+use std::collections::HashMap;
+use std::fmt::Write;
+
 /// Get detailed info for a single option
 pub fn option_detail_system_prompt() -> String {
     r#"You are a CLI command parser. Extract detailed information about command-line options.
@@ -5,6 +10,93 @@ pub fn option_detail_system_prompt() -> String {
 Respond with valid JSON only, no markdown formatting."#.to_string()
 }
 
+/// System prompt for generating a whole spec in one call
+pub fn spec_generation_system_prompt() -> String {
+    r#"You are a CLI command parser. Convert command documentation into a structured JSON spec describing every option and positional argument.
+
+Respond with valid JSON only, no markdown formatting."#.to_string()
+}
+
+/// User prompt for single-pass spec generation (all options in one response)
+pub fn spec_generation_user_prompt(context: &str) -> String {
+    format!(r#"{context}
+
+Describe this command as a JSON object with this structure:
+{{
+  "description": "brief description of the command",
+  "danger_level": "low",
+  "positionals_first": false,
+  "options": [
+    {{
+      "flags": ["-v", "--verbose"],
+      "description": "Detailed description of what this option does",
+      "argument_type": "bool",
+      "argument_name": null,
+      "required": false,
+      "sensitive": false,
+      "repeatable": false,
+      "conflicts_with": [],
+      "requires": [],
+      "default": null,
+      "enum_values": [],
+      "level": "basic",
+      "deprecated": false,
+      "example": null,
+      "min": null,
+      "max": null,
+      "pattern": null
+    }}
+  ],
+  "positional_args": [
+    {{
+      "name": "file",
+      "description": "What this argument represents",
+      "argument_type": "path",
+      "required": true,
+      "sensitive": false,
+      "default": null
+    }}
+  ],
+  "examples": ["ls -la /tmp"]
+}}
+
+Guidelines:
+- Include every option from the documentation, with all of its flag spellings in "flags"
+- description: when an option is documented in both the help text and the MANPAGE section, use whichever description is more detailed (usually the manpage); never shorten a detailed description to match a terse one
+- danger_level: low/medium/high/critical based on potential for data loss
+- positionals_first: true if positional args come BEFORE flags in typical usage
+- argument_type: "bool", "string", "int", "float", "path", or "enum"
+- sensitive: true if this typically contains secrets/tokens/passwords
+- enum_values: if argument_type is "enum", list allowed values
+- level: "basic" for common/frequently-used options, "advanced" for specialized/rarely-used options
+- deprecated: true if the documentation marks this option as deprecated, obsolete, or kept only for compatibility
+- example: a representative value for options that take one, in the expected format (e.g. "POST" for --request, "5s" for a duration); null for bool options
+- min/max: for int and float options, the smallest/largest accepted value when the documentation states a range (e.g. 1 and 65535 for a port); otherwise null
+- pattern: for string options with a documented format, a regex the whole value must match (e.g. "[0-9]+[smh]" for a duration); otherwise null
+- Do not list option placeholders like "OPTION" as positional arguments
+- examples: 3-5 realistic invocations of the full command using only the options and arguments above, from the documentation where it has them
+
+Respond with only JSON, no other text."#)
+}
+
+/// User prompt for a command's description and danger level
+pub fn command_metadata_query(full_command: &str, help_text: &str) -> String {
+    format!(
+        r#"Analyze this command and return JSON with description and danger_level.
+
+COMMAND: {full_command}
+
+HELP TEXT (first 500 chars):
+{}
+
+Return: {{"description": "brief description", "danger_level": "low"}}
+danger_level: low/medium/high/critical based on potential for data loss.
+
+JSON only, no other text."#,
+        help_text.chars().take(500).collect::<String>()
+    )
+}
+
 /// Build the cached context containing help text and manpage
 pub fn build_cached_context(command: &str, help_text: &str, manpage_text: Option<&str>) -> String {
     let manpage_section = if let Some(manpage) = manpage_text {
@@ -38,11 +130,16 @@ Return a JSON object with this structure:
   "requires": [],
   "default": null,
   "enum_values": [],
-  "level": "basic"
+  "level": "basic",
+  "deprecated": false,
+  "example": null,
+  "min": null,
+  "max": null,
+  "pattern": null
 }}
 
 Guidelines:
-- description: Full description from the documentation above
+- description: Full description from the documentation above; when the option is documented in both the help text and the MANPAGE section, use the more detailed one (usually the manpage) and never shorten it to match a terse one
 - argument_type: "bool", "string", "int", "float", "path", or "enum"
 - sensitive: true if this typically contains secrets/tokens/passwords
 - conflicts_with: list of flags that cannot be used with this one
@@ -50,12 +147,32 @@ Guidelines:
 - enum_values: if argument_type is "enum", list allowed values
 - default: default value if specified
 - level: "basic" for common/frequently-used options, "advanced" for specialized/rarely-used options
+- deprecated: true if the documentation marks this option as deprecated, obsolete, or kept only for compatibility
+- example: a representative value in the expected format (e.g. "POST" for --request, "5s" for a duration); null for bool options
+- min/max: for int and float options, the smallest/largest accepted value when the documentation states a range (e.g. 1 and 65535 for a port); otherwise null
+- pattern: for string options with a documented format, a regex the whole value must match (e.g. "[0-9]+[smh]" for a duration); otherwise null
 
 Respond with only JSON, no other text."#,
         flags_str
     )
 }
 
+/// User prompt for example invocations (used with cached context)
+pub fn examples_query(command: &str) -> String {
+    format!(r#"Write 3-5 realistic example invocations of `{command}` based on the documentation above.
+
+Return a JSON object with this structure:
+{{"examples": ["{command} --verbose input.txt"]}}
+
+Guidelines:
+- Each example is a complete command line starting with `{command}`
+- Use only options and arguments from the documentation, preferring examples it gives itself
+- Cover different common tasks rather than variations of one
+- Quote values containing spaces as a shell would
+
+Respond with only JSON, no other text."#)
+}
+
 /// User prompt for single positional argument extraction (used with cached context)
 pub fn single_positional_arg_query(arg_name: &str) -> String {
     format!(r#"Extract detailed information for this positional argument: {arg_name}
@@ -105,3 +222,79 @@ If there are no positional arguments: {{"args": [], "positionals_first": false}}
 
 JSON object only, no other text."#)
 }
+
+/// System prompt for the form's assistant panel: a summary of the spec and the
+/// command as currently filled in, with sensitive values masked
+pub fn chat_context(spec: &CommandSpec, values: &HashMap<String, String>) -> String {
+    let mut summary = String::new();
+    for opt in &spec.options {
+        let _ = write!(summary, "- {} ({})", opt.flags.join(", "), opt.argument_type);
+        if !opt.enum_values.is_empty() {
+            let _ = write!(summary, " [{}]", opt.enum_values.join("|"));
+        }
+        if opt.repeatable {
+            summary.push_str(" repeatable");
+        }
+        let description: String = opt.description.chars().take(200).collect();
+        let _ = writeln!(summary, ": {}", description.replace('\n', " "));
+    }
+    for arg in &spec.positional_args {
+        let _ = writeln!(
+            summary,
+            "- <{}> positional{}: {}",
+            arg.name,
+            if arg.required { ", required" } else { "" },
+            arg.description.replace('\n', " ")
+        );
+    }
+    let current = shell_words::join(build_masked_argv(spec, values));
+
+    format!(r#"You help fill in a form for the command `{command}`: {description}
+
+OPTIONS AND ARGUMENTS:
+{summary}
+CURRENT COMMAND (*** marks hidden sensitive values):
+$ {current}
+
+The user describes what they want to do. Reply in a few short sentences explaining which options to use, then end with the complete suggested command on its own line starting with "$ ".
+- Use only the options and arguments listed above
+- Keep the current values unless the user asks to change them
+- Leave *** values as they are and never invent secrets
+- If nothing fits, say so and leave out the "$ " line"#,
+        command = spec.command,
+        description = spec.description,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chat_context_masks_sensitive_values() {
+        let spec: CommandSpec = serde_json::from_value(serde_json::json!({
+            "command": "curl",
+            "version_hash": "",
+            "description": "transfer a URL",
+            "options": [
+                {"flags": ["-u", "--user"], "description": "Server user and password", "argument_type": "string", "sensitive": true},
+                {"flags": ["-L", "--location"], "description": "Follow redirects", "argument_type": "bool"}
+            ],
+            "positional_args": [{"name": "url", "description": "URL to fetch", "argument_type": "string", "required": true}],
+            "subcommands": [],
+            "danger_level": "low",
+            "examples": []
+        }))
+        .unwrap();
+        let values = HashMap::from([
+            ("--user".to_string(), "bob:hunter2".to_string()),
+            ("--location".to_string(), "true".to_string()),
+        ]);
+
+        let context = chat_context(&spec, &values);
+        assert!(!context.contains("hunter2"));
+        assert!(context.contains("$ curl --user '***' --location"), "{}", context);
+        assert!(context.contains("- -L, --location (bool): Follow redirects"));
+        assert!(context.contains("- <url> positional, required: URL to fetch"));
+    }
+}
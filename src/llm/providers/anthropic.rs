@@ -1,35 +1,302 @@
-use crate::llm::client::{async_trait, LlmClient};
+use crate::llm::client::{
+    async_trait, generation_summary, is_ignored_flag, CommandMetadata, GeneratedSpec, GenerationTimings,
+    LlmClient, PositionalNames, ProgressMode,
+};
+use crate::llm::fan_out::fan_out;
+use crate::llm::progress::BatchProgress;
 use crate::llm::prompt;
-use crate::parser::{ArgumentType, CommandOption, CommandSpec, DangerLevel, HelpDocumentation, PositionalArg};
+use crate::parser::{ArgumentType, CommandOption, CommandSpec, HelpDocumentation, PositionalArg};
 use crate::QuocliError;
-use futures::stream::{FuturesUnordered, StreamExt};
-use futures::future::BoxFuture;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
-use std::io::{self, Write};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 /// Maximum concurrent API requests to avoid rate limiting
-const MAX_CONCURRENT_REQUESTS: usize = 10;
+pub(crate) const MAX_CONCURRENT_REQUESTS: usize = 10;
+
+/// Anthropic Messages API endpoint
+const API_URL: &str = "https://api.anthropic.com/v1/messages";
+
+/// Backoff between retries of a failed request, in milliseconds
+const RETRY_DELAYS_MS: [u64; 4] = [2000, 4000, 8000, 16000];
+
+/// Longest `Retry-After` we will wait for before retrying
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Commands with at most this many extracted flags are generated in a single call
+pub(crate) const SINGLE_PASS_MAX_FLAGS: usize = 12;
+
+/// Rough per-token prices (USD per million tokens) used for cost estimates
+const HAIKU_INPUT_PRICE: f64 = 1.0;
+const HAIKU_OUTPUT_PRICE: f64 = 5.0;
+const SONNET_INPUT_PRICE: f64 = 3.0;
+const SONNET_OUTPUT_PRICE: f64 = 15.0;
+
+/// Rough token counts for one per-option detail call
+const OPTION_QUERY_TOKENS: f64 = 150.0;
+const OPTION_OUTPUT_TOKENS: f64 = 400.0;
+
+/// Estimate the cost in USD of a per-option fan-out over a help context of `context_chars`.
+///
+/// Assumes ~4 characters per token, one cache write of the context and a cache
+/// read (10% of the input price) for every option call after it.
+pub fn estimate_fan_out_cost(context_chars: usize, option_count: usize) -> f64 {
+    let context_tokens = context_chars as f64 / 4.0;
+    let options = option_count as f64;
+
+    // Positional and metadata calls on the main model, without caching
+    let setup = 2.0 * (context_tokens * SONNET_INPUT_PRICE + 256.0 * SONNET_OUTPUT_PRICE);
+
+    let cache_write = context_tokens * HAIKU_INPUT_PRICE * 1.25;
+    let cache_reads = (options - 1.0).max(0.0) * context_tokens * HAIKU_INPUT_PRICE * 0.1;
+    let queries = options * (OPTION_QUERY_TOKENS * HAIKU_INPUT_PRICE + OPTION_OUTPUT_TOKENS * HAIKU_OUTPUT_PRICE);
+
+    (setup + cache_write + cache_reads + queries) / 1_000_000.0
+}
+
+/// Parse an examples reply: `{"examples": [...]}`, or a bare array, keeping non-empty lines
+fn parse_examples(json: &str) -> Result<Vec<String>, serde_json::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum ExamplesResponse {
+        Object { examples: Vec<String> },
+        List(Vec<String>),
+    }
+
+    let (ExamplesResponse::Object { examples } | ExamplesResponse::List(examples)) = serde_json::from_str(json)?;
+    Ok(examples
+        .into_iter()
+        .map(|example| example.trim().to_string())
+        .filter(|example| !example.is_empty())
+        .collect())
+}
 
 pub struct AnthropicClient {
     api_key: String,
     model: String,
+    /// Model to retry with once when a request against the primary model fails
+    fallback_model: String,
+    api_url: String,
     client: reqwest::Client,
+    progress: ProgressMode,
+    single_pass: bool,
+    /// Generate commands with few flags in one call even without `single_pass`
+    auto_single_pass: bool,
+    ignore_flags: Vec<String>,
+    max_options: usize,
+    assume_yes: bool,
+    concurrency: usize,
+    timings: Mutex<GenerationTimings>,
 }
 
 impl AnthropicClient {
-    pub fn new(api_key: String, model: String) -> Self {
+    pub fn new(api_key: String, model: String, fallback_model: String) -> Self {
         Self {
             api_key,
             model,
+            fallback_model,
+            api_url: API_URL.to_string(),
             client: reqwest::Client::new(),
+            progress: ProgressMode::default(),
+            single_pass: false,
+            auto_single_pass: true,
+            ignore_flags: Vec::new(),
+            max_options: 0,
+            assume_yes: false,
+            concurrency: MAX_CONCURRENT_REQUESTS,
+            timings: Mutex::new(GenerationTimings::default()),
+        }
+    }
+
+    /// Record how long a generation phase took
+    fn record_timing(&self, phase: &str, started: Instant, items: Option<usize>) {
+        if let Ok(mut timings) = self.timings.lock() {
+            match items {
+                Some(count) => timings.record_items(phase, started.elapsed(), count),
+                None => timings.record(phase, started.elapsed()),
+            }
+        }
+    }
+
+    /// Leave options using any of these flags out of generated specs
+    pub fn with_ignore_flags(mut self, ignore_flags: Vec<String>) -> Self {
+        self.ignore_flags = ignore_flags;
+        self
+    }
+
+    /// Generate specs with one call instead of per-option fan-out: always with
+    /// `single_pass`, or with `auto` for commands with few flags
+    pub fn with_single_pass(mut self, single_pass: bool, auto: bool) -> Self {
+        self.single_pass = single_pass;
+        self.auto_single_pass = auto;
+        self
+    }
+
+    /// Limit how many per-option requests run at once
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Ask before fanning out over more than `max_options` options (0 disables the guard)
+    pub fn with_option_guard(mut self, max_options: usize, assume_yes: bool) -> Self {
+        self.max_options = max_options;
+        self.assume_yes = assume_yes;
+        self
+    }
+
+    /// Confirm a fan-out over an unusually large number of options, since each one is a paid call
+    fn confirm_option_count(&self, full_command: &str, option_count: usize, context_chars: usize) -> Result<(), QuocliError> {
+        if self.max_options == 0 || option_count <= self.max_options || self.assume_yes {
+            return Ok(());
+        }
+
+        let cancelled = || QuocliError::Llm(format!(
+            "Spec generation for {} cancelled: {} options exceeds llm.max_options ({}); pass --yes to proceed",
+            full_command, option_count, self.max_options
+        ));
+
+        eprintln!(
+            "{} has {} options; generating its spec makes about {} API calls (roughly ${:.2}).",
+            full_command,
+            option_count,
+            option_count + 2,
+            estimate_fan_out_cost(context_chars, option_count)
+        );
+        if !io::stdin().is_terminal() {
+            return Err(cancelled());
+        }
+
+        eprint!("Continue? [y/N] ");
+        io::stderr().flush().ok();
+        let mut answer = String::new();
+        io::stdin().lock().read_line(&mut answer)?;
+        if matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+            Ok(())
+        } else {
+            Err(cancelled())
+        }
+    }
+
+    /// Generate the whole spec in one call (cheaper and faster for small commands)
+    async fn generate_single_pass(&self, cached_context: &str) -> Result<GeneratedSpec, QuocliError> {
+        let system = prompt::spec_generation_system_prompt();
+        let user = prompt::spec_generation_user_prompt(cached_context);
+        let json = self.call_api(&system, &user, 16384, None).await?;
+
+        serde_json::from_str(&json)
+            .map_err(|e| QuocliError::Llm(format!("Failed to parse single-pass spec: {}", e)))
+    }
+
+    /// Ask for example invocations. Examples are a nicety, so a failed call or
+    /// an unparseable reply just leaves them empty.
+    async fn generate_examples(&self, system: &str, cached_context: &str, full_command: &str) -> Vec<String> {
+        let query = prompt::examples_query(full_command);
+        match self
+            .call_api_cached(system, cached_context, &query, 1024, Some("claude-haiku-4-5-20251001"))
+            .await
+        {
+            Ok(json) => parse_examples(&json).unwrap_or_else(|e| {
+                tracing::warn!("Failed to parse examples for {}: {}", full_command, e);
+                vec![]
+            }),
+            Err(e) => {
+                tracing::warn!("Failed to generate examples for {}: {}", full_command, e);
+                vec![]
+            }
+        }
+    }
+
+    /// Ask for one option's details. A reply that doesn't parse is saved to the
+    /// debug directory (see `--list-debug`) before the error is returned.
+    async fn option_detail(
+        &self,
+        flags: Vec<String>,
+        detail_system: &str,
+        cached_context: &str,
+    ) -> Result<CommandOption, QuocliError> {
+        let query = prompt::single_option_query(&flags);
+        let detail_json = self
+            .call_api_cached(detail_system, cached_context, &query, 4096, Some("claude-haiku-4-5-20251001"))
+            .await?;
+
+        serde_json::from_str(&detail_json).map_err(|e| {
+            tracing::warn!("Failed to parse option details for {:?}: {}", flags, e);
+
+            // Save failed response to debug file
+            if let Some(debug_dir) = crate::llm::debug_dir() {
+                if std::fs::create_dir_all(&debug_dir).is_ok() {
+                    let flag_name = flags.first().map(|f| f.trim_start_matches('-')).unwrap_or("unknown");
+                    let debug_file = debug_dir.join(format!("failed_{}.json", flag_name));
+                    if let Err(write_err) = std::fs::write(&debug_file, &detail_json) {
+                        tracing::warn!("Failed to save debug file: {}", write_err);
+                    } else {
+                        tracing::info!("Saved failed response to {:?}", debug_file);
+                        eprintln!("\nDebug: Failed JSON saved to {:?} (see --list-debug)", debug_file);
+                    }
+                }
+            }
+
+            QuocliError::Llm(format!("Failed to parse option detail: {}", e))
+        })
+    }
+
+    /// Ask for one positional argument's details
+    async fn positional_detail(
+        &self,
+        arg_name: String,
+        detail_system: &str,
+        cached_context: &str,
+    ) -> Result<PositionalArg, QuocliError> {
+        let query = prompt::single_positional_arg_query(&arg_name);
+        let detail_json = self
+            .call_api_cached(detail_system, cached_context, &query, 1024, Some("claude-haiku-4-5-20251001"))
+            .await?;
+
+        serde_json::from_str(&detail_json).map_err(|e| {
+            tracing::warn!("Failed to parse positional arg details for {}: {}", arg_name, e);
+            QuocliError::Llm(format!("Failed to parse positional arg detail: {}", e))
+        })
+    }
+
+    pub fn with_progress(mut self, progress: ProgressMode) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Print the one-line generation summary (quiet mode only)
+    fn report_summary(&self, spec: &CommandSpec, full_command: &str, skipped: usize, failed: usize) {
+        if self.progress == ProgressMode::Quiet {
+            eprintln!("{}", generation_summary(spec, full_command, skipped, failed));
         }
     }
 
-    /// Make an API call and return the text response with retry logic
+    /// The fallback model to try after `model` failed, unless it is the same model
+    fn fallback_for(&self, model: &str) -> Option<&str> {
+        Some(self.fallback_model.as_str()).filter(|fallback| !fallback.is_empty() && *fallback != model)
+    }
+
+    /// Make an API call and return the text response, retrying once with the fallback model
     async fn call_api(&self, system: &str, user: &str, max_tokens: u32, model_override: Option<&str>) -> Result<String, QuocliError> {
-        let model = model_override.map(|s| s.to_string()).unwrap_or_else(|| self.model.clone());
+        let model = model_override.unwrap_or(&self.model);
+        match self.send_request(system, user, max_tokens, model).await {
+            Err(e) => match self.fallback_for(model) {
+                Some(fallback) => {
+                    tracing::warn!("Request to {} failed ({}), retrying with fallback model {}", model, e, fallback);
+                    self.send_request(system, user, max_tokens, fallback).await
+                }
+                None => Err(e),
+            },
+            ok => ok,
+        }
+    }
+
+    /// Make an API call against one model and return the text response with retry logic
+    async fn send_request(&self, system: &str, user: &str, max_tokens: u32, model: &str) -> Result<String, QuocliError> {
+        let model = model.to_string();
         let request = AnthropicRequest {
             model,
             max_tokens,
@@ -41,12 +308,11 @@ impl AnthropicClient {
         };
 
         let mut last_error = None;
-        let retry_delays = [2000, 4000, 8000, 16000]; // milliseconds
 
-        for attempt in 0..=retry_delays.len() {
+        for attempt in 0..=RETRY_DELAYS_MS.len() {
             let result = self
                 .client
-                .post("https://api.anthropic.com/v1/messages")
+                .post(&self.api_url)
                 .header("x-api-key", &self.api_key)
                 .header("anthropic-version", "2023-06-01")
                 .header("content-type", "application/json")
@@ -56,8 +322,17 @@ impl AnthropicClient {
 
             match result {
                 Ok(response) => {
-                    if !response.status().is_success() {
-                        let status = response.status();
+                    let status = response.status();
+
+                    if is_retryable_status(status) && attempt < RETRY_DELAYS_MS.len() {
+                        let delay = retry_delay(response.headers(), attempt);
+                        tracing::warn!("API returned {}, retrying in {}ms (attempt {}/{})",
+                            status, delay.as_millis(), attempt + 1, RETRY_DELAYS_MS.len());
+                        tokio::time::sleep(delay).await;
+                        continue;
+                    }
+
+                    if !status.is_success() {
                         let error_text = response.text().await.unwrap_or_default();
                         return Err(QuocliError::Llm(format!(
                             "API request failed with status {}: {}",
@@ -79,11 +354,11 @@ impl AnthropicClient {
                     // Only retry on connection/network errors
                     if e.is_connect() || e.is_request() {
                         last_error = Some(e);
-                        if attempt < retry_delays.len() {
-                            let delay = retry_delays[attempt];
+                        if attempt < RETRY_DELAYS_MS.len() {
+                            let delay = backoff(attempt);
                             tracing::warn!("Connection error, retrying in {}ms (attempt {}/{})",
-                                delay, attempt + 1, retry_delays.len());
-                            tokio::time::sleep(tokio::time::Duration::from_millis(delay as u64)).await;
+                                delay.as_millis(), attempt + 1, RETRY_DELAYS_MS.len());
+                            tokio::time::sleep(delay).await;
                             continue;
                         }
                     } else {
@@ -97,7 +372,7 @@ impl AnthropicClient {
             QuocliError::Llm("Max retries exceeded".to_string())))
     }
 
-    /// Make an API call with prompt caching for the context
+    /// Make an API call with prompt caching for the context, retrying once with the fallback model
     async fn call_api_cached(
         &self,
         system: &str,
@@ -106,7 +381,29 @@ impl AnthropicClient {
         max_tokens: u32,
         model_override: Option<&str>,
     ) -> Result<String, QuocliError> {
-        let model = model_override.map(|s| s.to_string()).unwrap_or_else(|| self.model.clone());
+        let model = model_override.unwrap_or(&self.model);
+        match self.send_cached_request(system, cached_context, user_query, max_tokens, model).await {
+            Err(e) => match self.fallback_for(model) {
+                Some(fallback) => {
+                    tracing::warn!("Request to {} failed ({}), retrying with fallback model {}", model, e, fallback);
+                    self.send_cached_request(system, cached_context, user_query, max_tokens, fallback).await
+                }
+                None => Err(e),
+            },
+            ok => ok,
+        }
+    }
+
+    /// Make a prompt-cached API call against one model with retry logic
+    async fn send_cached_request(
+        &self,
+        system: &str,
+        cached_context: &str,
+        user_query: &str,
+        max_tokens: u32,
+        model: &str,
+    ) -> Result<String, QuocliError> {
+        let model = model.to_string();
         let request = CachedAnthropicRequest {
             model,
             max_tokens,
@@ -130,12 +427,10 @@ impl AnthropicClient {
             }],
         };
 
-        let retry_delays = [2000, 4000, 8000, 16000];
-
-        for attempt in 0..=retry_delays.len() {
+        for attempt in 0..=RETRY_DELAYS_MS.len() {
             let result = self
                 .client
-                .post("https://api.anthropic.com/v1/messages")
+                .post(&self.api_url)
                 .header("x-api-key", &self.api_key)
                 .header("anthropic-version", "2023-06-01")
                 .header("anthropic-beta", "prompt-caching-2024-07-31")
@@ -148,19 +443,18 @@ impl AnthropicClient {
                 Ok(response) => {
                     let status = response.status();
 
-                    // Retry on 529 (Overloaded) or 503 (Service Unavailable)
-                    if status.as_u16() == 529 || status.as_u16() == 503 {
-                        if attempt < retry_delays.len() {
-                            let delay = retry_delays[attempt];
-                            tracing::warn!("API overloaded ({}), retrying in {}ms (attempt {}/{})",
-                                status, delay, attempt + 1, retry_delays.len());
-                            tokio::time::sleep(tokio::time::Duration::from_millis(delay as u64)).await;
+                    if is_retryable_status(status) {
+                        if attempt < RETRY_DELAYS_MS.len() {
+                            let delay = retry_delay(response.headers(), attempt);
+                            tracing::warn!("API returned {}, retrying in {}ms (attempt {}/{})",
+                                status, delay.as_millis(), attempt + 1, RETRY_DELAYS_MS.len());
+                            tokio::time::sleep(delay).await;
                             continue;
                         } else {
                             let error_text = response.text().await.unwrap_or_default();
                             return Err(QuocliError::Llm(format!(
-                                "API overloaded after {} retries: {}",
-                                retry_delays.len(), error_text
+                                "API still returning {} after {} retries: {}",
+                                status, RETRY_DELAYS_MS.len(), error_text
                             )));
                         }
                     }
@@ -184,14 +478,12 @@ impl AnthropicClient {
                     return Ok(strip_markdown_code_blocks(&text));
                 }
                 Err(e) => {
-                    if e.is_connect() || e.is_request() {
-                        if attempt < retry_delays.len() {
-                            let delay = retry_delays[attempt];
-                            tracing::warn!("Connection error, retrying in {}ms (attempt {}/{})",
-                                delay, attempt + 1, retry_delays.len());
-                            tokio::time::sleep(tokio::time::Duration::from_millis(delay as u64)).await;
-                            continue;
-                        }
+                    if (e.is_connect() || e.is_request()) && attempt < RETRY_DELAYS_MS.len() {
+                        let delay = backoff(attempt);
+                        tracing::warn!("Connection error, retrying in {}ms (attempt {}/{})",
+                            delay.as_millis(), attempt + 1, RETRY_DELAYS_MS.len());
+                        tokio::time::sleep(delay).await;
+                        continue;
                     }
                     return Err(e.into());
                 }
@@ -202,9 +494,38 @@ impl AnthropicClient {
     }
 }
 
+/// Whether a response status is worth retrying: rate limited (429),
+/// service unavailable (503) or overloaded (529)
+fn is_retryable_status(status: reqwest::StatusCode) -> bool {
+    matches!(status.as_u16(), 429 | 503 | 529)
+}
+
+/// Seconds to wait from a `Retry-After` header, capped at [`MAX_RETRY_AFTER`]
+///
+/// Only the delay-seconds form is understood; HTTP dates are ignored.
+fn parse_retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let seconds: u64 = headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(Duration::from_secs(seconds).min(MAX_RETRY_AFTER))
+}
+
+/// Our own backoff before retry `attempt`
+fn backoff(attempt: usize) -> Duration {
+    Duration::from_millis(RETRY_DELAYS_MS[attempt.min(RETRY_DELAYS_MS.len() - 1)])
+}
+
+/// How long to wait before retry `attempt`: the server's `Retry-After` if given, else our backoff
+fn retry_delay(headers: &reqwest::header::HeaderMap, attempt: usize) -> Duration {
+    parse_retry_after(headers).unwrap_or_else(|| backoff(attempt))
+}
 
 /// Strip markdown code blocks from LLM response
-fn strip_markdown_code_blocks(text: &str) -> String {
+pub(crate) fn strip_markdown_code_blocks(text: &str) -> String {
     let text = text.trim();
 
     // Check for ```json or ``` at start
@@ -226,7 +547,7 @@ fn strip_markdown_code_blocks(text: &str) -> String {
 }
 
 /// Extract flags from help text using regex (local, no LLM needed)
-fn extract_flags_from_help(help_text: &str) -> Vec<Vec<String>> {
+pub(crate) fn extract_flags_from_help(help_text: &str) -> Vec<Vec<String>> {
     let mut all_flags: Vec<Vec<String>> = Vec::new();
     let mut seen: HashSet<String> = HashSet::new();
 
@@ -482,9 +803,20 @@ impl LlmClient for AnthropicClient {
         // === PASS 1: Extract flags locally using regex (instant, no token limits) ===
         tracing::info!("Pass 1: Extracting flags from help text for {}", full_command);
 
-        let extracted_flags = extract_flags_from_help(help_text);
+        if let Ok(mut timings) = self.timings.lock() {
+            *timings = GenerationTimings::default();
+        }
+
+        let started = Instant::now();
+        let mut extracted_flags = extract_flags_from_help(help_text);
+        self.record_timing("flag extraction", started, None);
         tracing::info!("Extracted {} flag groups from help text", extracted_flags.len());
 
+        // Ignored flags never reach the detail calls, which also saves their cost
+        let extracted = extracted_flags.len();
+        extracted_flags.retain(|flags| !is_ignored_flag(flags, &self.ignore_flags));
+        let skipped = extracted - extracted_flags.len();
+
         // Build cached context with full help text and manpage (used for all LLM calls)
         let manpage_opt = if has_manpage {
             Some(docs.manpage_text.as_str())
@@ -493,240 +825,150 @@ impl LlmClient for AnthropicClient {
         };
         let cached_context = prompt::build_cached_context(&full_command, help_text, manpage_opt);
 
+        // Small commands (unless llm.auto_single_pass is off) or --single-pass skip
+        // the per-option fan-out entirely
+        let small = self.auto_single_pass && extracted_flags.len() <= SINGLE_PASS_MAX_FLAGS;
+        if self.single_pass || small {
+            tracing::info!("Using single-pass generation ({} flags extracted)", extracted_flags.len());
+            let started = Instant::now();
+            let result = self.generate_single_pass(&cached_context).await;
+            self.record_timing("single-pass call", started, None);
+            match result {
+                Ok(mut generated) => {
+                    // The fan-out asks for examples separately; do the same if the reply had none
+                    if generated.examples.is_empty() {
+                        let started = Instant::now();
+                        generated.examples = self
+                            .generate_examples(&prompt::option_detail_system_prompt(), &cached_context, &full_command)
+                            .await;
+                        self.record_timing("examples call", started, None);
+                    }
+                    let spec = generated.into_spec(command, help_hash, docs, &self.ignore_flags);
+                    self.report_summary(&spec, &full_command, skipped, 0);
+                    return Ok(spec);
+                }
+                Err(e) => {
+                    tracing::warn!("Single-pass generation failed, falling back to per-option extraction: {}", e);
+                }
+            }
+        }
+
+        // Large commands fan out into one paid call per option, so check before spending anything
+        self.confirm_option_count(&full_command, extracted_flags.len(), cached_context.len())?;
+
         // Extract positional args using LLM with full context (use Sonnet for better semantic understanding)
         let positional_system = "You are a CLI command parser. Extract positional argument names from usage syntax.";
         let positional_query = prompt::extract_positional_args_query(&cached_context);
 
+        let started = Instant::now();
         let positional_json = self.call_api(positional_system, &positional_query, 512, None).await?;
+        self.record_timing("positional call", started, None);
 
-        #[derive(Deserialize)]
-        struct PositionalArgsResponse {
-            args: Vec<String>,
-            #[serde(default)]
-            positionals_first: bool,
-        }
-
-        let (positional_names, positionals_first) = serde_json::from_str::<PositionalArgsResponse>(&positional_json)
+        let (positional_names, positionals_first) = serde_json::from_str::<PositionalNames>(&positional_json)
             .map(|r| (r.args, r.positionals_first))
             .unwrap_or_else(|e| {
+                // Fall back to the local usage-line scan rather than dropping positionals
                 tracing::warn!("Failed to parse positional args JSON: {}", e);
-                (vec![], false)
+                let names = extract_positional_args_from_help(help_text).into_iter().map(|arg| arg.name).collect();
+                (names, false)
             });
         tracing::info!("Extracted {} positional arg names from help text (positionals_first: {})",
             positional_names.len(), positionals_first);
 
         // Get command metadata (description, danger level) with a small LLM call
         let metadata_system = "You are a CLI analyzer. Return only valid JSON.";
-        let metadata_user = format!(
-            r#"Analyze this command and return JSON with description and danger_level.
-
-COMMAND: {full_command}
-
-HELP TEXT (first 500 chars):
-{}
-
-Return: {{"description": "brief description", "danger_level": "low"}}
-danger_level: low/medium/high/critical based on potential for data loss.
-
-JSON only, no other text."#,
-            help_text.chars().take(500).collect::<String>()
-        );
+        let metadata_user = prompt::command_metadata_query(&full_command, help_text);
 
+        let started = Instant::now();
         let metadata_json = self.call_api(metadata_system, &metadata_user, 256, None).await?;
+        self.record_timing("metadata call", started, None);
 
-        #[derive(Deserialize)]
-        struct Metadata {
-            description: String,
-            danger_level: DangerLevel,
-        }
-
-        let metadata: Metadata = serde_json::from_str(&metadata_json).unwrap_or(Metadata {
-            description: format!("Command: {}", full_command),
-            danger_level: DangerLevel::Low,
-        });
+        let metadata: CommandMetadata = serde_json::from_str(&metadata_json)
+            .unwrap_or_else(|_| CommandMetadata::fallback(&full_command));
 
         tracing::info!("Got metadata: {} options to process", extracted_flags.len());
 
         // === PASS 2: Get details for each option ===
         let detail_system = prompt::option_detail_system_prompt();
         let total = extracted_flags.len();
-        let mut detailed_options: Vec<CommandOption> = Vec::with_capacity(total);
 
-        tracing::info!("Using prompt caching for {} options ({} concurrent)", total, MAX_CONCURRENT_REQUESTS);
+        tracing::info!("Using prompt caching for {} options ({} concurrent)", total, self.concurrency);
 
         // Show initial progress (after metadata call and context setup)
-        eprint!("\rProcessing options: 0/{}    ", total);
-        io::stderr().flush().ok();
-
-        // Process first option alone to warm the cache
-        if let Some(first_flags) = extracted_flags.first() {
-            let query = prompt::single_option_query(first_flags);
-            let detail_json = self.call_api_cached(
-                &detail_system,
-                &cached_context,
-                &query,
-                4096,
-                Some("claude-haiku-4-5-20251001"),
-            ).await?;
-
-            let detailed: CommandOption = serde_json::from_str(&detail_json).map_err(|e| {
-                tracing::warn!("Failed to parse option details for {:?}: {}", first_flags, e);
-                QuocliError::Llm(format!("Failed to parse option detail: {}", e))
-            })?;
-
-            detailed_options.push(detailed);
-            eprint!("\rProcessing options: 1/{}    ", total);
-            io::stderr().flush().ok();
-
-            // Small delay to ensure cache is ready
-            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-        }
-
-        // Helper to create option extraction future
-        let make_option_future = |flags: Vec<String>, detail_system: String, cached_context: String| -> BoxFuture<'_, Result<CommandOption, QuocliError>> {
-            Box::pin(async move {
-                let query = prompt::single_option_query(&flags);
-                let detail_json = self.call_api_cached(
-                    &detail_system,
-                    &cached_context,
-                    &query,
-                    4096,
-                    Some("claude-haiku-4-5-20251001"),
-                ).await?;
-
-                let detailed: CommandOption = serde_json::from_str(&detail_json).map_err(|e| {
-                    tracing::warn!("Failed to parse option details for {:?}: {}", flags, e);
-
-                    // Save failed response to debug file
-                    if let Some(proj_dirs) = directories::ProjectDirs::from("", "", "quocli") {
-                        let debug_dir = proj_dirs.data_dir().join("debug");
-                        if std::fs::create_dir_all(&debug_dir).is_ok() {
-                            let flag_name = flags.first().map(|f| f.trim_start_matches('-')).unwrap_or("unknown");
-                            let debug_file = debug_dir.join(format!("failed_{}.json", flag_name));
-                            if let Err(write_err) = std::fs::write(&debug_file, &detail_json) {
-                                tracing::warn!("Failed to save debug file: {}", write_err);
-                            } else {
-                                tracing::info!("Saved failed response to {:?}", debug_file);
-                                eprintln!("\nDebug: Failed JSON saved to {:?}", debug_file);
-                            }
-                        }
-                    }
-
-                    QuocliError::Llm(format!("Failed to parse option detail: {}", e))
-                })?;
-
-                Ok(detailed)
-            })
-        };
-
-        // Process remaining options with streaming concurrency (start new request as each completes)
-        let remaining_flags: Vec<_> = extracted_flags.iter().skip(1).cloned().collect();
-        let mut flag_iter = remaining_flags.into_iter();
-        let mut in_flight: FuturesUnordered<BoxFuture<'_, Result<CommandOption, QuocliError>>> = FuturesUnordered::new();
-
-        // Start initial batch of concurrent requests
-        for _ in 0..MAX_CONCURRENT_REQUESTS {
-            if let Some(flags) = flag_iter.next() {
-                in_flight.push(make_option_future(flags, detail_system.clone(), cached_context.clone()));
-            }
-        }
-
-        // Process results as they complete, starting new requests immediately
-        while let Some(result) = in_flight.next().await {
-            let detailed = result?;
-            detailed_options.push(detailed);
-
-            // Show progress
-            eprint!("\rProcessing options: {}/{}    ", detailed_options.len(), total);
-            io::stderr().flush().ok();
-
-            // Start next request if there are more flags
-            if let Some(flags) = flag_iter.next() {
-                in_flight.push(make_option_future(flags, detail_system.clone(), cached_context.clone()));
-            }
-        }
-
-        // Clear the progress line
-        eprintln!("\rProcessing options: {}/{}    ", total, total);
-        tracing::info!("Successfully processed {} options", detailed_options.len());
+        let mut progress = BatchProgress::start(self.progress, "Processing options", total);
+        let options_started = Instant::now();
+
+        // The first option runs alone to warm the cache, with a small delay to ensure it is ready
+        let options = fan_out(
+            extracted_flags,
+            self.concurrency,
+            Some(Duration::from_millis(500)),
+            &mut progress,
+            |flags| flags.join(", "),
+            |flags| Box::pin(self.option_detail(flags, &detail_system, &cached_context)),
+        )
+        .await?;
+
+        progress.finish();
+        self.record_timing("option fan-out", options_started, Some(total));
+        tracing::info!("Successfully processed {} options", options.items.len());
 
         // === PASS 3: Get details for each positional argument ===
         let pos_total = positional_names.len();
         let mut detailed_positional: Vec<PositionalArg> = Vec::with_capacity(pos_total);
+        let mut failed = options.failed;
 
         if pos_total > 0 {
             tracing::info!("Processing {} positional arguments", pos_total);
-            eprint!("\rProcessing positional args: 0/{}    ", pos_total);
-            io::stderr().flush().ok();
-
-            // Helper to create positional arg extraction future
-            let make_positional_future = |arg_name: String, detail_system: String, cached_context: String| -> BoxFuture<'_, Result<PositionalArg, QuocliError>> {
-                Box::pin(async move {
-                    let query = prompt::single_positional_arg_query(&arg_name);
-                    let detail_json = self.call_api_cached(
-                        &detail_system,
-                        &cached_context,
-                        &query,
-                        1024,
-                        Some("claude-haiku-4-5-20251001"),
-                    ).await?;
-
-                    let detailed: PositionalArg = serde_json::from_str(&detail_json).map_err(|e| {
-                        tracing::warn!("Failed to parse positional arg details for {}: {}", arg_name, e);
-                        QuocliError::Llm(format!("Failed to parse positional arg detail: {}", e))
-                    })?;
-
-                    Ok(detailed)
-                })
-            };
-
-            // Process positional args with streaming concurrency
-            let mut arg_iter = positional_names.into_iter();
-            let mut pos_in_flight: FuturesUnordered<BoxFuture<'_, Result<PositionalArg, QuocliError>>> = FuturesUnordered::new();
-
-            // Start initial batch of concurrent requests
-            for _ in 0..MAX_CONCURRENT_REQUESTS {
-                if let Some(arg_name) = arg_iter.next() {
-                    pos_in_flight.push(make_positional_future(arg_name, detail_system.clone(), cached_context.clone()));
-                }
-            }
-
-            // Process results as they complete, starting new requests immediately
-            while let Some(result) = pos_in_flight.next().await {
-                let detailed = result?;
-                detailed_positional.push(detailed);
-
-                // Show progress
-                eprint!("\rProcessing positional args: {}/{}    ", detailed_positional.len(), pos_total);
-                io::stderr().flush().ok();
-
-                // Start next request if there are more args
-                if let Some(arg_name) = arg_iter.next() {
-                    pos_in_flight.push(make_positional_future(arg_name, detail_system.clone(), cached_context.clone()));
-                }
-            }
+            let mut progress = BatchProgress::start(self.progress, "Processing positional args", pos_total);
+            let positionals_started = Instant::now();
+
+            let positionals = fan_out(
+                positional_names,
+                self.concurrency,
+                None,
+                &mut progress,
+                |name| name.clone(),
+                |name| Box::pin(self.positional_detail(name, &detail_system, &cached_context)),
+            )
+            .await?;
+            detailed_positional = positionals.items;
+            failed += positionals.failed;
 
-            // Clear the progress line
-            eprintln!("\rProcessing positional args: {}/{}    ", pos_total, pos_total);
+            progress.finish();
+            self.record_timing("positional details", positionals_started, Some(pos_total));
             tracing::info!("Successfully processed {} positional arguments", detailed_positional.len());
         }
 
+        // Same system prompt and context as the option calls, so this reads from the cache too
+        let started = Instant::now();
+        let examples = self.generate_examples(&detail_system, &cached_context, &full_command).await;
+        self.record_timing("examples call", started, None);
+
         // === Assemble final spec ===
-        let spec = CommandSpec {
-            command: command.to_string(),
-            version_hash: help_hash.to_string(),
+        let generated = GeneratedSpec {
             description: metadata.description,
-            options: detailed_options,
-            positional_args: detailed_positional,
-            subcommands: vec![],
             danger_level: metadata.danger_level,
-            examples: vec![],
             positionals_first,
+            options: options.items,
+            positional_args: detailed_positional,
+            examples,
+            failed,
         };
+        let spec = generated.into_spec(command, help_hash, docs, &self.ignore_flags);
+
+        self.report_summary(&spec, &full_command, skipped, failed);
 
         Ok(spec)
     }
 
+    fn timings(&self) -> GenerationTimings {
+        self.timings
+            .lock()
+            .map(|timings| timings.clone())
+            .unwrap_or_default()
+    }
+
     async fn chat(
         &self,
         context: &str,
@@ -744,7 +986,7 @@ JSON only, no other text."#,
 
         let response = self
             .client
-            .post("https://api.anthropic.com/v1/messages")
+            .post(&self.api_url)
             .header("x-api-key", &self.api_key)
             .header("anthropic-version", "2023-06-01")
             .header("content-type", "application/json")
@@ -776,6 +1018,156 @@ JSON only, no other text."#,
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::DangerLevel;
+
+    /// Serve Messages API responses locally: 404 for `missing-model`, a reply for anything else.
+    /// Returns the endpoint URL and the models requested, in order.
+    async fn mock_messages_api(requests: usize) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v1/messages", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let mut models = Vec::new();
+            for _ in 0..requests {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut data = Vec::new();
+                let mut buf = [0u8; 4096];
+                // Read until the whole body named by Content-Length has arrived
+                let body = loop {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    data.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&data).to_string();
+                    if let Some(split) = text.find("\r\n\r\n") {
+                        let length: usize = text[..split]
+                            .lines()
+                            .find_map(|l| l.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse().unwrap()))
+                            .unwrap_or(0);
+                        if data.len() >= split + 4 + length {
+                            break text[split + 4..].to_string();
+                        }
+                    }
+                };
+                let request: serde_json::Value = serde_json::from_str(&body).unwrap();
+                let model = request["model"].as_str().unwrap().to_string();
+
+                let (status, reply) = if model == "missing-model" {
+                    ("404 Not Found", r#"{"type":"error","error":{"type":"not_found_error","message":"model: missing-model"}}"#.to_string())
+                } else {
+                    ("200 OK", format!(r#"{{"content":[{{"type":"text","text":"answer from {}"}}]}}"#, model))
+                };
+                let response = format!(
+                    "HTTP/1.1 {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    status, reply.len(), reply
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+                models.push(model);
+            }
+            models
+        });
+        (url, handle)
+    }
+
+    #[tokio::test]
+    async fn test_call_api_retries_with_fallback_model() {
+        let (url, server) = mock_messages_api(2).await;
+        let mut client = AnthropicClient::new("key".to_string(), "missing-model".to_string(), "backup-model".to_string());
+        client.api_url = url;
+
+        let text = client.call_api("system", "user", 16, None).await.unwrap();
+        assert_eq!(text, "answer from backup-model");
+        assert_eq!(server.await.unwrap(), vec!["missing-model", "backup-model"]);
+    }
+
+    #[tokio::test]
+    async fn test_call_api_without_distinct_fallback_surfaces_error() {
+        let (url, server) = mock_messages_api(1).await;
+        let mut client = AnthropicClient::new("key".to_string(), "missing-model".to_string(), "missing-model".to_string());
+        client.api_url = url;
+
+        let err = client.call_api_cached("system", "context", "query", 16, None).await.unwrap_err();
+        assert!(matches!(err, QuocliError::Llm(ref msg) if msg.contains("404")));
+        assert_eq!(server.await.unwrap(), vec!["missing-model"]);
+    }
+
+    #[test]
+    fn test_parse_examples() {
+        let examples = parse_examples(r#"{"examples": ["tar -czf out.tgz dir", "  ", "tar -xf out.tgz "]}"#).unwrap();
+        assert_eq!(examples, vec!["tar -czf out.tgz dir", "tar -xf out.tgz"]);
+        assert_eq!(parse_examples(r#"["tar -tf a.tar"]"#).unwrap(), vec!["tar -tf a.tar"]);
+        assert!(parse_examples("Here are some examples:").is_err());
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(parse_retry_after(&headers), None);
+        assert_eq!(retry_delay(&headers, 1), Duration::from_millis(4000));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("7"));
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(7)));
+        assert_eq!(retry_delay(&headers, 1), Duration::from_secs(7));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("3600"));
+        assert_eq!(parse_retry_after(&headers), Some(MAX_RETRY_AFTER));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"));
+        assert_eq!(parse_retry_after(&headers), None);
+
+        assert!(is_retryable_status(reqwest::StatusCode::TOO_MANY_REQUESTS));
+        assert!(!is_retryable_status(reqwest::StatusCode::NOT_FOUND));
+    }
+
+    #[test]
+    fn test_estimate_fan_out_cost_grows_with_options() {
+        let small = estimate_fan_out_cost(40_000, 20);
+        let large = estimate_fan_out_cost(40_000, 400);
+        assert!(small > 0.0);
+        assert!(large > small * 5.0);
+        // A few hundred options over a large help text stays in the dollar range
+        assert!(large < 10.0);
+    }
+
+    #[test]
+    fn test_option_guard_allows_small_or_confirmed_commands() {
+        let client = AnthropicClient::new("key".to_string(), "model".to_string(), String::new())
+            .with_option_guard(100, false);
+        assert!(client.confirm_option_count("tool", 100, 1000).is_ok());
+
+        let client = client.with_option_guard(100, true);
+        assert!(client.confirm_option_count("tool", 500, 1000).is_ok());
+
+        let client = client.with_option_guard(0, false);
+        assert!(client.confirm_option_count("tool", 500, 1000).is_ok());
+    }
+
+    #[test]
+    fn test_parse_single_pass_spec() {
+        let json = r#"{
+            "description": "Print lines matching a pattern",
+            "danger_level": "low",
+            "options": [
+                {"flags": ["-i", "--ignore-case"], "description": "Ignore case", "argument_type": "bool"}
+            ],
+            "positional_args": [
+                {"name": "pattern", "description": "Pattern to match", "argument_type": "string", "required": true}
+            ]
+        }"#;
+
+        let spec: GeneratedSpec = serde_json::from_str(json).unwrap();
+        assert_eq!(spec.danger_level, DangerLevel::Low);
+        assert!(!spec.positionals_first);
+        assert_eq!(spec.options.len(), 1);
+        assert_eq!(spec.options[0].flags, vec!["-i", "--ignore-case"]);
+        assert_eq!(spec.positional_args[0].name, "pattern");
+
+        let empty: GeneratedSpec =
+            serde_json::from_str(r#"{"description": "x", "danger_level": "high"}"#).unwrap();
+        assert!(empty.options.is_empty());
+        assert!(empty.positional_args.is_empty());
+    }
 
     #[test]
     fn test_extract_required_positional_args() {
@@ -0,0 +1,275 @@
+//! Spec provider backed by a user-supplied external program
+//!
+//! With `provider = "command"`, quocli runs `llm.provider_command` through
+//! `sh -c` for every spec it needs to generate. The contract is:
+//!
+//! - **stdin**: the command's help text, followed by its manpage (if any)
+//!   after a `--- MANPAGE ---` separator line.
+//! - **environment**: `QUOCLI_COMMAND` (e.g. `git`), `QUOCLI_SUBCOMMANDS`
+//!   (space-separated, empty for none) and `QUOCLI_HELP_HASH`.
+//! - **stdout**: one JSON object in the `CommandSpec` shape. Only `options`
+//!   is really needed; `description` (default `""`), `danger_level` (`low`,
+//!   `medium`, `high` or `critical`; default `low`), `positional_args`,
+//!   `positionals_first` and `examples` are optional. `command`,
+//!   `version_hash` and any other fields are ignored - quocli fills them in.
+//! - **exit status**: non-zero fails generation, with stderr in the error.
+//!
+//! The program's options go through the same `parser.ignore_flags` filtering
+//! and description enrichment as the LLM providers' output.
+
+use crate::llm::client::{
+    async_trait, drop_ignored_options, enrich_descriptions, generation_summary,
+    normalize_enum_defaults, GenerationTimings, LlmClient, ProgressMode,
+};
+use crate::parser::{CommandOption, CommandSpec, DangerLevel, HelpDocumentation, PositionalArg};
+use crate::QuocliError;
+use serde::Deserialize;
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::Instant;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// What the external program prints on stdout
+#[derive(Deserialize)]
+struct ProvidedSpec {
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    danger_level: DangerLevel,
+    #[serde(default)]
+    positionals_first: bool,
+    #[serde(default)]
+    options: Vec<CommandOption>,
+    #[serde(default)]
+    positional_args: Vec<PositionalArg>,
+    #[serde(default)]
+    examples: Vec<String>,
+}
+
+/// Generates specs by shelling out to an external program
+pub struct CommandProvider {
+    program: String,
+    progress: ProgressMode,
+    ignore_flags: Vec<String>,
+    timings: Mutex<GenerationTimings>,
+}
+
+impl CommandProvider {
+    /// `program` is a shell snippet, run via `sh -c`
+    pub fn new(program: String) -> Self {
+        Self {
+            program,
+            progress: ProgressMode::default(),
+            ignore_flags: Vec::new(),
+            timings: Mutex::new(GenerationTimings::default()),
+        }
+    }
+
+    pub fn with_progress(mut self, progress: ProgressMode) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    pub fn with_ignore_flags(mut self, ignore_flags: Vec<String>) -> Self {
+        self.ignore_flags = ignore_flags;
+        self
+    }
+
+    /// Run the program with `input` on stdin and return its stdout
+    async fn run(
+        &self,
+        command: &str,
+        subcommands: &[String],
+        help_hash: &str,
+        input: &str,
+    ) -> Result<String, QuocliError> {
+        let mut child = Command::new("sh")
+            .args(["-c", &self.program])
+            .env("QUOCLI_COMMAND", command)
+            .env("QUOCLI_SUBCOMMANDS", subcommands.join(" "))
+            .env("QUOCLI_HELP_HASH", help_hash)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|_| QuocliError::CommandNotFound("sh".to_string()))?;
+
+        // Feed stdin while reading the output: a program that prints before it has
+        // read everything would otherwise fill its stdout pipe and block us both
+        let stdin = child.stdin.take();
+        let write_input = async move {
+            if let Some(mut stdin) = stdin {
+                // A program that exits without reading its input is fine
+                if let Err(e) = stdin.write_all(input.as_bytes()).await {
+                    tracing::debug!("Spec provider closed stdin early: {}", e);
+                }
+                // Dropping stdin closes it, so the program sees end of input
+            }
+        };
+        let ((), output) = tokio::join!(write_input, child.wait_with_output());
+        let output = output?;
+        if !output.status.success() {
+            return Err(QuocliError::Llm(format!(
+                "Spec provider `{}` failed ({}): {}",
+                self.program,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+}
+
+#[async_trait]
+impl LlmClient for CommandProvider {
+    async fn generate_spec(
+        &self,
+        command: &str,
+        subcommands: &[String],
+        docs: &HelpDocumentation,
+        help_hash: &str,
+    ) -> Result<CommandSpec, QuocliError> {
+        let full_command = if subcommands.is_empty() {
+            command.to_string()
+        } else {
+            format!("{} {}", command, subcommands.join(" "))
+        };
+
+        let started = Instant::now();
+        let stdout = self
+            .run(command, subcommands, help_hash, &docs.combined_text())
+            .await?;
+        if let Ok(mut timings) = self.timings.lock() {
+            *timings = GenerationTimings::default();
+            timings.record("provider command", started.elapsed());
+        }
+
+        let provided: ProvidedSpec = serde_json::from_str(stdout.trim()).map_err(|e| {
+            QuocliError::Llm(format!(
+                "Spec provider `{}` did not print a valid spec: {}",
+                self.program, e
+            ))
+        })?;
+
+        let provided_options = provided.options.len();
+        let mut options = drop_ignored_options(provided.options, &self.ignore_flags);
+        let skipped = provided_options - options.len();
+        enrich_descriptions(&mut options, docs);
+        normalize_enum_defaults(&mut options);
+
+        let spec = CommandSpec {
+            command: command.to_string(),
+            version_hash: help_hash.to_string(),
+            description: provided.description,
+            options,
+            positional_args: provided.positional_args,
+            subcommands: vec![],
+            danger_level: provided.danger_level,
+            examples: provided.examples,
+            positionals_first: provided.positionals_first,
+            tool_version: None,
+            basic_only: false,
+        };
+
+        if self.progress == ProgressMode::Quiet {
+            eprintln!("{}", generation_summary(&spec, &full_command, skipped, 0));
+        }
+
+        Ok(spec)
+    }
+
+    async fn chat(
+        &self,
+        _context: &str,
+        _message: &str,
+    ) -> Result<String, QuocliError> {
+        Err(QuocliError::Llm(
+            "The command spec provider does not support chat".to_string(),
+        ))
+    }
+
+    fn timings(&self) -> GenerationTimings {
+        self.timings
+            .lock()
+            .map(|timings| timings.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn docs(help_text: &str) -> HelpDocumentation {
+        HelpDocumentation {
+            help_text: help_text.to_string(),
+            manpage_text: String::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generate_spec_from_program_output() {
+        let program = r#"grep -q -- '--verbose' && [ "$QUOCLI_COMMAND" = tool ] && echo '{
+            "description": "A tool",
+            "danger_level": "medium",
+            "options": [
+                {"flags": ["-v", "--verbose"], "description": "Be loud", "argument_type": "bool"},
+                {"flags": ["-h", "--help"], "description": "Help", "argument_type": "bool"}
+            ]
+        }'"#;
+        let provider = CommandProvider::new(program.to_string())
+            .with_ignore_flags(vec!["--help".to_string()]);
+
+        let spec = provider
+            .generate_spec("tool", &[], &docs("Usage: tool [--verbose]"), "abc123")
+            .await
+            .unwrap();
+
+        assert_eq!(spec.command, "tool");
+        assert_eq!(spec.version_hash, "abc123");
+        assert_eq!(spec.description, "A tool");
+        assert_eq!(spec.danger_level, DangerLevel::Medium);
+        assert_eq!(spec.options.len(), 1);
+        assert_eq!(spec.options[0].flags, vec!["-v", "--verbose"]);
+        assert!(spec.positional_args.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_generate_spec_with_output_before_input_is_read() {
+        // More than a pipe buffer each way, with the output written first
+        let program = r#"head -c 200000 /dev/zero | tr '\0' ' '; cat > /dev/null; echo '{"description": "x", "danger_level": "low"}'"#;
+        let provider = CommandProvider::new(program.to_string());
+        let help = "-v, --verbose  be loud\n".repeat(20_000);
+
+        let spec = tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            provider.generate_spec("tool", &[], &docs(&help), "abc123"),
+        )
+        .await
+        .expect("provider deadlocked")
+        .unwrap();
+        assert_eq!(spec.description, "x");
+    }
+
+    #[tokio::test]
+    async fn test_generate_spec_reports_program_failure() {
+        let provider = CommandProvider::new("echo 'no model loaded' >&2; exit 3".to_string());
+        let err = provider
+            .generate_spec("tool", &[], &docs("help"), "abc123")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("no model loaded"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_generate_spec_rejects_invalid_json() {
+        let provider = CommandProvider::new("echo not json".to_string());
+        let err = provider
+            .generate_spec("tool", &[], &docs("help"), "abc123")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("did not print a valid spec"), "{}", err);
+    }
+}
@@ -1 +1,3 @@
 pub mod anthropic;
+pub mod command;
+pub mod ollama;
@@ -0,0 +1,361 @@
+use crate::llm::client::{
+    async_trait, generation_summary, is_ignored_flag, CommandMetadata, GeneratedSpec, GenerationTimings,
+    LlmClient, PositionalNames, ProgressMode,
+};
+use crate::llm::fan_out::fan_out;
+use crate::llm::progress::BatchProgress;
+use crate::llm::prompt;
+use crate::llm::providers::anthropic::{
+    extract_flags_from_help, strip_markdown_code_blocks, SINGLE_PASS_MAX_FLAGS,
+};
+use crate::parser::{CommandSpec, HelpDocumentation};
+use crate::QuocliError;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Default Ollama chat endpoint
+const DEFAULT_BASE_URL: &str = "http://localhost:11434/api/chat";
+
+/// Local models serve few requests at once, so fan out gently by default
+const DEFAULT_CONCURRENT_REQUESTS: usize = 2;
+
+/// Model used when `llm.model` is left at its (Anthropic) default
+pub const DEFAULT_MODEL: &str = "llama3.1";
+
+/// Client for a local (or self-hosted) Ollama server
+pub struct OllamaClient {
+    base_url: String,
+    model: String,
+    client: reqwest::Client,
+    progress: ProgressMode,
+    single_pass: bool,
+    auto_single_pass: bool,
+    ignore_flags: Vec<String>,
+    concurrency: usize,
+    timings: Mutex<GenerationTimings>,
+}
+
+impl OllamaClient {
+    pub fn new(model: String) -> Self {
+        Self {
+            base_url: DEFAULT_BASE_URL.to_string(),
+            model,
+            client: reqwest::Client::new(),
+            progress: ProgressMode::default(),
+            single_pass: false,
+            auto_single_pass: true,
+            ignore_flags: Vec::new(),
+            concurrency: DEFAULT_CONCURRENT_REQUESTS,
+            timings: Mutex::new(GenerationTimings::default()),
+        }
+    }
+
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.to_string();
+        self
+    }
+
+    pub fn with_progress(mut self, progress: ProgressMode) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Leave options using any of these flags out of generated specs
+    pub fn with_ignore_flags(mut self, ignore_flags: Vec<String>) -> Self {
+        self.ignore_flags = ignore_flags;
+        self
+    }
+
+    /// Generate specs with one call instead of per-option fan-out: always with
+    /// `single_pass`, or with `auto` for commands with few flags
+    pub fn with_single_pass(mut self, single_pass: bool, auto: bool) -> Self {
+        self.single_pass = single_pass;
+        self.auto_single_pass = auto;
+        self
+    }
+
+    /// Limit how many per-option requests run at once
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Record how long a generation phase took
+    fn record_timing(&self, phase: &str, started: Instant, items: Option<usize>) {
+        if let Ok(mut timings) = self.timings.lock() {
+            match items {
+                Some(count) => timings.record_items(phase, started.elapsed(), count),
+                None => timings.record(phase, started.elapsed()),
+            }
+        }
+    }
+
+    /// Send one chat request and return the reply text
+    async fn call_api(&self, system: &str, user: &str, json: bool) -> Result<String, QuocliError> {
+        let request = OllamaRequest {
+            model: self.model.clone(),
+            messages: vec![
+                Message {
+                    role: "system".to_string(),
+                    content: system.to_string(),
+                },
+                Message {
+                    role: "user".to_string(),
+                    content: user.to_string(),
+                },
+            ],
+            // A streamed reply arrives as NDJSON chunks; ask for a single object
+            stream: false,
+            format: json.then(|| "json".to_string()),
+        };
+
+        let response = self.client.post(&self.base_url).json(&request).send().await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(QuocliError::Llm(format!(
+                "Ollama request failed with status {}: {}",
+                status, error_text
+            )));
+        }
+
+        let api_response: OllamaResponse = response.json().await?;
+        Ok(api_response.message.content)
+    }
+
+    /// Send a chat request and parse the reply as JSON
+    async fn call_json<T: DeserializeOwned>(&self, system: &str, user: &str) -> Result<T, QuocliError> {
+        let text = self.call_api(system, user, true).await?;
+        serde_json::from_str(&strip_markdown_code_blocks(&text))
+            .map_err(|e| QuocliError::Llm(format!("Failed to parse model response: {}", e)))
+    }
+
+    /// Generate the spec option by option, for commands too large for one reply
+    async fn generate_per_option(
+        &self,
+        full_command: &str,
+        help_text: &str,
+        cached_context: &str,
+        extracted_flags: &[Vec<String>],
+    ) -> Result<GeneratedSpec, QuocliError> {
+        let started = Instant::now();
+        let positional: PositionalNames = self
+            .call_json(
+                "You are a CLI command parser. Extract positional argument names from usage syntax.",
+                &prompt::extract_positional_args_query(cached_context),
+            )
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!("Failed to extract positional args: {}", e);
+                PositionalNames::default()
+            });
+        self.record_timing("positional call", started, None);
+
+        let started = Instant::now();
+        let metadata: CommandMetadata = self
+            .call_json(
+                "You are a CLI analyzer. Return only valid JSON.",
+                &prompt::command_metadata_query(full_command, help_text),
+            )
+            .await
+            .unwrap_or_else(|_| CommandMetadata::fallback(full_command));
+        self.record_timing("metadata call", started, None);
+
+        // Each request carries the full documentation; there is no prompt cache to lean on
+        let detail_system = prompt::option_detail_system_prompt();
+        let total = extracted_flags.len();
+        let started = Instant::now();
+        let mut progress = BatchProgress::start(self.progress, "Processing options", total);
+        let options = fan_out(
+            extracted_flags.to_vec(),
+            self.concurrency,
+            None,
+            &mut progress,
+            |flags| flags.join(", "),
+            |flags| {
+                let user = format!("{}\n\n{}", cached_context, prompt::single_option_query(&flags));
+                let detail_system = &detail_system;
+                Box::pin(async move { self.call_json(detail_system, &user).await })
+            },
+        )
+        .await?;
+        progress.finish();
+        self.record_timing("option fan-out", started, Some(total));
+
+        let started = Instant::now();
+        let pos_total = positional.args.len();
+        let mut progress = BatchProgress::start(self.progress, "Processing positional args", pos_total);
+        let positional_args = fan_out(
+            positional.args,
+            self.concurrency,
+            None,
+            &mut progress,
+            |name| name.clone(),
+            |name| {
+                let user = format!("{}\n\n{}", cached_context, prompt::single_positional_arg_query(&name));
+                let detail_system = &detail_system;
+                Box::pin(async move { self.call_json(detail_system, &user).await })
+            },
+        )
+        .await?;
+        progress.finish();
+        self.record_timing("positional details", started, Some(pos_total));
+
+        Ok(GeneratedSpec {
+            description: metadata.description,
+            danger_level: metadata.danger_level,
+            positionals_first: positional.positionals_first,
+            options: options.items,
+            positional_args: positional_args.items,
+            examples: vec![],
+            failed: options.failed + positional_args.failed,
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct OllamaRequest {
+    model: String,
+    messages: Vec<Message>,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Message {
+    role: String,
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct OllamaResponse {
+    message: Message,
+}
+
+#[async_trait]
+impl LlmClient for OllamaClient {
+    async fn generate_spec(
+        &self,
+        command: &str,
+        subcommands: &[String],
+        docs: &HelpDocumentation,
+        help_hash: &str,
+    ) -> Result<CommandSpec, QuocliError> {
+        let full_command = if subcommands.is_empty() {
+            command.to_string()
+        } else {
+            format!("{} {}", command, subcommands.join(" "))
+        };
+
+        if let Ok(mut timings) = self.timings.lock() {
+            *timings = GenerationTimings::default();
+        }
+
+        let started = Instant::now();
+        let mut extracted_flags = extract_flags_from_help(&docs.help_text);
+        self.record_timing("flag extraction", started, None);
+        tracing::info!("Extracted {} flag groups from help text", extracted_flags.len());
+        let extracted = extracted_flags.len();
+        extracted_flags.retain(|flags| !is_ignored_flag(flags, &self.ignore_flags));
+        let skipped = extracted - extracted_flags.len();
+
+        let manpage = (!docs.manpage_text.is_empty()).then_some(docs.manpage_text.as_str());
+        let cached_context = prompt::build_cached_context(&full_command, &docs.help_text, manpage);
+
+        let mut generated = None;
+        let small = self.auto_single_pass && extracted_flags.len() <= SINGLE_PASS_MAX_FLAGS;
+        if self.single_pass || small {
+            tracing::info!("Using single-pass generation with {} ({} flags extracted)", self.model, extracted_flags.len());
+            let started = Instant::now();
+            let result = self
+                .call_json::<GeneratedSpec>(
+                    &prompt::spec_generation_system_prompt(),
+                    &prompt::spec_generation_user_prompt(&cached_context),
+                )
+                .await;
+            self.record_timing("single-pass call", started, None);
+            match result {
+                Ok(spec) => generated = Some(spec),
+                Err(e) => tracing::warn!("Single-pass generation failed, falling back to per-option extraction: {}", e),
+            }
+        }
+
+        let generated = match generated {
+            Some(spec) => spec,
+            None => {
+                self.generate_per_option(&full_command, &docs.help_text, &cached_context, &extracted_flags)
+                    .await?
+            }
+        };
+
+        let failed = generated.failed;
+        let spec = generated.into_spec(command, help_hash, docs, &self.ignore_flags);
+
+        if self.progress == ProgressMode::Quiet {
+            eprintln!("{}", generation_summary(&spec, &full_command, skipped, failed));
+        }
+
+        Ok(spec)
+    }
+
+    fn timings(&self) -> GenerationTimings {
+        self.timings
+            .lock()
+            .map(|timings| timings.clone())
+            .unwrap_or_default()
+    }
+
+    async fn chat(
+        &self,
+        context: &str,
+        message: &str,
+    ) -> Result<String, QuocliError> {
+        self.call_api(context, message, false).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_disables_streaming() {
+        let request = OllamaRequest {
+            model: "llama3.1".to_string(),
+            messages: vec![Message {
+                role: "user".to_string(),
+                content: "hi".to_string(),
+            }],
+            stream: false,
+            format: Some("json".to_string()),
+        };
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["stream"], false);
+        assert_eq!(json["format"], "json");
+        assert_eq!(json["model"], "llama3.1");
+    }
+
+    #[test]
+    fn test_parse_chat_response() {
+        let body = r#"{"model":"llama3.1","message":{"role":"assistant","content":"{\"args\":[]}"},"done":true}"#;
+        let response: OllamaResponse = serde_json::from_str(body).unwrap();
+        assert_eq!(response.message.content, r#"{"args":[]}"#);
+    }
+
+    #[test]
+    fn test_client_defaults() {
+        let client = OllamaClient::new("llama3.1".to_string());
+        assert_eq!(client.base_url, DEFAULT_BASE_URL);
+        assert_eq!(client.concurrency, DEFAULT_CONCURRENT_REQUESTS);
+
+        let client = client
+            .with_base_url("http://gpu-box:11434/api/chat")
+            .with_concurrency(0);
+        assert_eq!(client.base_url, "http://gpu-box:11434/api/chat");
+        assert_eq!(client.concurrency, 1);
+    }
+}
@@ -1,7 +1,13 @@
 use anyhow::Result;
-use clap::Parser;
-use quocli::{cache, config, executor, llm, parser, shell, tui};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use clap::{Parser, ValueEnum};
+use quocli::{audit, cache, config, executor, invocation, llm, parser, shell, tui};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+/// Rotated log files kept besides the current one
+const LOG_FILES_KEPT: usize = 5;
 
 #[derive(Parser, Debug)]
 #[command(name = "quocli")]
@@ -9,125 +15,623 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 #[command(version)]
 struct Args {
     /// Command to wrap with interactive form
-    #[arg(required = true)]
+    #[arg(required_unless_present_any = ["workflow", "list_cached", "list_debug", "clear_debug", "paths", "migrate", "refresh_all", "import_invocation"])]
     command: Vec<String>,
 
+    /// List cached command specs with danger level and usage, most used first
+    #[arg(long, conflicts_with = "workflow")]
+    list_cached: bool,
+
+    /// List the failed LLM responses saved for debugging, and where they are kept
+    #[arg(long, conflicts_with_all = ["workflow", "list_cached"])]
+    list_debug: bool,
+
+    /// Delete the failed LLM responses saved for debugging
+    #[arg(long, conflicts_with_all = ["workflow", "list_cached", "list_debug"])]
+    clear_debug: bool,
+
+    /// Print where the config, cache database, audit log and data directory are
+    #[arg(long, conflicts_with_all = ["workflow", "list_cached", "list_debug", "clear_debug"])]
+    paths: bool,
+
+    /// Apply pending cache database migrations, listing each, then exit
+    #[arg(long, conflicts_with_all = ["workflow", "list_cached", "list_debug", "clear_debug", "paths", "refresh_all"])]
+    migrate: bool,
+
+    /// Run a named workflow from the config: fill and run each step's form in turn
+    #[arg(long, value_name = "NAME", conflicts_with_all = ["direct", "show_spec", "export_doc", "export_history_script"])]
+    workflow: Option<String>,
+
     /// Refresh cache for this command
     #[arg(long)]
     refresh_cache: bool,
 
+    /// Regenerate every cached spec whose help text changed (all of them with --refresh-cache)
+    #[arg(long, conflicts_with_all = ["command", "workflow", "list_cached", "list_debug", "clear_debug", "paths"])]
+    refresh_all: bool,
+
     /// Clear cached values for this command
     #[arg(long)]
     clear_values: bool,
 
+    /// Add a line to your note about this command, shown in the form header (n in the form edits it)
+    #[arg(long, value_name = "TEXT", conflicts_with_all = ["workflow", "import_invocation"])]
+    add_note: Option<String>,
+
+    /// Tag this command (e.g. devops); with --list-cached, list only commands with this tag
+    #[arg(long, value_name = "TAG", conflicts_with_all = ["workflow", "import_invocation", "add_note"])]
+    tag: Option<String>,
+
+    /// Clear only the cached values of sensitive fields, keeping the rest
+    #[arg(long, conflicts_with = "clear_values")]
+    clear_sensitive: bool,
+
+    /// Print the cached values --direct would use, as JSON or flag=value lines
+    #[arg(long, value_name = "FORMAT", num_args = 0..=1, require_equals = true, default_missing_value = "json")]
+    print_values: Option<ValuesFormat>,
+
     /// Execute directly without TUI (use cached/default values)
     #[arg(long)]
     direct: bool,
 
+    /// With --direct, run with the values of this saved preset instead of the cached ones
+    #[arg(long, value_name = "NAME", requires = "direct")]
+    preset: Option<String>,
+
+    /// Open the form in search mode with this query typed (e.g. a flag name)
+    #[arg(long, value_name = "QUERY", conflicts_with_all = ["direct", "workflow"])]
+    search: Option<String>,
+
     /// Show the generated spec without executing
     #[arg(long)]
     show_spec: bool,
+
+    /// Export the cached spec as a man-page-style markdown document
+    #[arg(long)]
+    export_doc: bool,
+
+    /// Write the exported document to a file instead of stdout
+    #[arg(long, value_name = "FILE", requires = "export_doc")]
+    doc_file: Option<std::path::PathBuf>,
+
+    /// Write the cached values (what --direct would run) to FILE as a shareable
+    /// invocation descriptor; sensitive values are left out
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["workflow", "import_invocation"])]
+    export_invocation: Option<std::path::PathBuf>,
+
+    /// Load the values of an invocation descriptor into the form (or run them with --direct)
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["command", "workflow", "preset"])]
+    import_invocation: Option<std::path::PathBuf>,
+
+    /// Export recorded invocations of this command as a runnable shell script
+    #[arg(long, value_name = "FILE")]
+    export_history_script: Option<std::path::PathBuf>,
+
+    /// Only export history newer than this age (e.g. 30m, 12h, 7d)
+    #[arg(long, value_name = "AGE", requires = "export_history_script", value_parser = shell::parse_age)]
+    since: Option<i64>,
+
+    /// Only export invocations that exited successfully
+    #[arg(long, requires = "export_history_script")]
+    successful_only: bool,
+
+    /// Suppress progress output; print a one-line summary after spec generation
+    #[arg(long, short)]
+    quiet: bool,
+
+    /// Generate the spec from plain --help only, skipping the manpage and extended help
+    #[arg(long)]
+    basic_only: bool,
+
+    /// Generate the spec with a single LLM call instead of one call per option
+    #[arg(long)]
+    single_pass: bool,
+
+    /// Launch the command detached, logging its output to a file, and return immediately
+    #[arg(long, conflicts_with = "workflow")]
+    background: bool,
+
+    /// Skip confirmations for cached-value overwrites (cache.confirm_value_overwrite),
+    /// for generating specs with more than llm.max_options options and for --via-shell
+    #[arg(long, short)]
+    yes: bool,
+
+    /// Explain the command built by the form instead of executing it
+    #[arg(long, conflicts_with_all = ["direct", "workflow"])]
+    learn: bool,
+
+    /// Print the fully resolved command line instead of executing it
+    #[arg(long, conflicts_with_all = ["learn", "background", "workflow"])]
+    dry_run: bool,
+
+    /// Report the command's wall time, CPU time and peak memory after it finishes
+    #[arg(long, conflicts_with_all = ["background", "dry_run", "learn"])]
+    measure: bool,
+
+    /// Kill the command if it runs longer than this many seconds (overrides execution.timeout_secs)
+    #[arg(long, value_name = "SECS", conflicts_with_all = ["background", "dry_run", "learn"])]
+    timeout: Option<u64>,
+
+    /// Run the command through an interactive $SHELL (-i -c), so aliases and functions resolve
+    #[arg(long, conflicts_with_all = ["background", "learn"])]
+    via_shell: bool,
+
+    /// Print the exact argv right before running, one argument per line, secrets masked
+    #[arg(long, conflicts_with_all = ["background", "dry_run", "learn"])]
+    trace_command: bool,
+
+    /// Capture the command's output and print it with the exit code as a JSON object
+    #[arg(long, conflicts_with_all = ["background", "dry_run", "learn", "measure"])]
+    json: bool,
+
+    /// Print how long each spec generation phase took
+    #[arg(long)]
+    timings: bool,
+
+    /// Merge the config's [profiles.NAME] table over the base settings
+    #[arg(long, value_name = "NAME")]
+    config_profile: Option<String>,
+
+    /// Print a timing breakdown of the whole run (config, cache, help, spec, TUI, execution)
+    #[arg(long)]
+    profile: bool,
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    // Initialize logging
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ValuesFormat {
+    Json,
+    Lines,
+}
+
+/// Whole-run timing breakdown for `--profile`, printed when dropped
+struct Profiler {
+    enabled: bool,
+    started: Instant,
+    timings: llm::GenerationTimings,
+}
+
+impl Profiler {
+    fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            started: Instant::now(),
+            timings: llm::GenerationTimings::default(),
+        }
+    }
+
+    fn record(&mut self, phase: &str, started: Instant) {
+        self.timings.record(phase, started.elapsed());
+    }
+}
+
+impl Drop for Profiler {
+    fn drop(&mut self) {
+        if self.enabled {
+            eprint!("{}", self.timings.report("Run profile", self.started.elapsed()));
+        }
+    }
+}
+
+/// Log to stderr (filtered by RUST_LOG, else by --quiet) and, with `logging.file`,
+/// also to a size-rotated file. The returned guard flushes the file when dropped.
+fn init_logging(quiet: bool, logging: &config::LoggingConfig) -> Result<Option<WorkerGuard>> {
+    let default_filter = if quiet { "quocli=warn" } else { "quocli=info" };
+    let stderr_layer = tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| default_filter.into()));
+
+    let (file_layer, guard) = match &logging.file {
+        Some(path) => {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let appender = rolling_file::BasicRollingFileAppender::new(
+                path,
+                rolling_file::RollingConditionBasic::new().max_size(logging.max_size),
+                LOG_FILES_KEPT,
+            )?;
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let layer = tracing_subscriber::fmt::layer()
+                .with_writer(writer)
+                .with_ansi(false)
+                .with_filter(EnvFilter::try_new(format!("quocli={}", logging.level))?);
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+
     tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "quocli=info".into()),
-        )
-        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .with(stderr_layer)
+        .with(file_layer)
         .init();
+    Ok(guard)
+}
 
+#[tokio::main]
+async fn main() -> Result<()> {
     let args = Args::parse();
+    // Dropped on every return path; drop it explicitly before process::exit
+    let mut profiler = Profiler::new(args.profile);
 
     // Load configuration
-    let config = config::load_config()?;
+    let started = Instant::now();
+    let config = config::load_config(args.config_profile.as_deref())?;
+    profiler.record("config load", started);
+
+    // Initialize logging; like the profiler, drop the guard before process::exit
+    let log_guard = init_logging(args.quiet, &config.logging)?;
+
+    if args.list_debug || args.clear_debug {
+        return debug_files(args.clear_debug);
+    }
+    if args.paths {
+        print_paths(&config);
+        return Ok(());
+    }
+    if args.migrate {
+        return migrate_cache(&config).await;
+    }
 
     // Initialize cache
-    let cache = cache::Cache::new(&config.cache.path).await?;
+    let started = Instant::now();
+    let mut cache = cache::Cache::new(&config.cache.path).await?;
+    if let Some(dir) = &config.cache.spec_dir {
+        cache = cache.with_spec_dir(dir);
+    }
+    if config.security.encrypt_cache {
+        let passphrase = tokio::task::spawn_blocking(cache::keyring_passphrase).await??;
+        cache = cache.with_encryption(&passphrase);
+    }
+    if config.security.keyring_integration {
+        cache = cache.with_keyring();
+    }
+    profiler.record("cache open", started);
+
+    if args.list_cached {
+        list_cached(&cache, args.tag.as_deref()).await?;
+        return Ok(());
+    }
 
-    // Get command name and any subcommands
-    let command_parts = &args.command;
+    if args.refresh_all {
+        let failed = refresh_all(&cache, &config, &args, &mut profiler).await?;
+        if failed > 0 {
+            anyhow::bail!("{} spec(s) failed to refresh", failed);
+        }
+        return Ok(());
+    }
+
+    if let Some(name) = &args.workflow {
+        let code = run_workflow(&cache, &config, &args, name, &mut profiler).await?;
+        drop(profiler);
+        drop(log_guard);
+        std::process::exit(code);
+    }
+
+    // Get command name and any subcommands, from the invocation file when importing one
+    let imported = args
+        .import_invocation
+        .as_deref()
+        .map(invocation::InvocationDescriptor::read)
+        .transpose()?;
+    let command_parts = &match &imported {
+        Some(descriptor) => descriptor.command_parts(),
+        None => args.command.clone(),
+    };
     if command_parts.is_empty() {
         anyhow::bail!("No command specified");
     }
 
     let command_name = &command_parts[0];
     let subcommands = &command_parts[1..];
+    let history_key = command_parts.join(":");
 
     // Handle cache operations
+    if let Some(tag) = &args.tag {
+        let tag = tag.trim();
+        if tag.is_empty() || tag.contains(char::is_whitespace) {
+            anyhow::bail!("Tags can't be empty or contain spaces: '{}'", tag);
+        }
+        let name = command_parts.join(" ");
+        if !cache.add_tag(&history_key, tag).await? {
+            println!("{} is already tagged '{}'", name, tag);
+        } else if cache.get_spec(&history_key).await?.is_none() {
+            println!("Tagged {} with '{}' (listed once its spec is cached)", name, tag);
+        } else {
+            println!("Tagged {} with '{}'", name, tag);
+        }
+        return Ok(());
+    }
+
+    if let Some(text) = &args.add_note {
+        let note = cache.add_note(&history_key, text).await?;
+        println!("Note for {}:\n{}", command_parts.join(" "), note);
+        return Ok(());
+    }
+
     if args.clear_values {
         cache.clear_values(command_name).await?;
         println!("Cleared cached values for: {}", command_name);
         return Ok(());
     }
 
+    if args.clear_sensitive {
+        let field_ids = cached_sensitive_field_ids(&cache, command_name, &history_key).await?;
+        let removed = cache.clear_field_values(command_name, &field_ids).await?;
+        println!("Cleared {} sensitive value(s) for: {}", removed, command_name);
+        return Ok(());
+    }
+
+    if let Some(format) = args.print_values {
+        // Sensitive values (including secrets read back from the keyring) are masked;
+        // sorted for stable, diffable output
+        let sensitive = cached_sensitive_field_ids(&cache, command_name, &history_key).await?;
+        let values: std::collections::BTreeMap<String, String> = cache
+            .get_values(command_name)
+            .await?
+            .into_iter()
+            .map(|(id, value)| {
+                let masked = sensitive.contains(&id);
+                (id, if masked { "***".to_string() } else { value })
+            })
+            .collect();
+        match format {
+            ValuesFormat::Json => println!("{}", serde_json::to_string_pretty(&values)?),
+            ValuesFormat::Lines => {
+                // A repeatable option's values each get their own line
+                for (flag, value) in &values {
+                    for value in value.split(executor::VALUE_SEPARATOR) {
+                        println!("{}={}", flag, value);
+                    }
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    // The reference is rendered from the cached spec only, never a fresh generation
+    if args.export_doc {
+        let Some(spec) = cache.get_spec(&history_key).await? else {
+            anyhow::bail!(
+                "No cached spec for {}; run `quocli {}` first",
+                command_parts.join(" "),
+                command_parts.join(" ")
+            );
+        };
+        let doc = parser::render_doc(&spec, &command_parts.join(" "));
+        match &args.doc_file {
+            Some(path) => {
+                std::fs::write(path, doc)?;
+                println!("Wrote documentation to: {}", path.display());
+            }
+            None => print!("{}", doc),
+        }
+        return Ok(());
+    }
+
     // Get or generate command spec
-    let spec = get_or_generate_spec(
+    let started = Instant::now();
+    let generation_options = llm::GenerationOptions {
+        progress: llm::ProgressMode::detect(args.quiet),
+        single_pass: args.single_pass,
+        timings: args.timings,
+        assume_yes: args.yes,
+        basic_only: args.basic_only || config.help.basic_only,
+        ..Default::default()
+    };
+    let (mut spec, generated) = get_or_generate_spec(
         &cache,
         &config,
         command_name,
         subcommands,
         args.refresh_cache,
+        generation_options.clone(),
+        &mut profiler.timings,
     )
     .await?;
+    if !generated {
+        cache.update_usage(&history_key).await?;
+    }
+
+    // Help fetch is recorded separately inside get_or_generate_spec
+    let spec_phase = started
+        .elapsed()
+        .saturating_sub(profiler.timings.duration("help fetch"));
+    profiler.timings.record("spec get/generate", spec_phase);
+
+    // A bare `quocli git` offers git's subcommands before the form, level by
+    // level (docker, then docker image); Esc keeps the command picked so far
+    let opens_form = !args.direct
+        && !args.show_spec
+        && args.export_history_script.is_none()
+        && args.export_invocation.is_none()
+        && imported.is_none();
+    let mut picked_parts = command_parts.clone();
+    if opens_form && subcommands.is_empty() {
+        while !spec.subcommands.is_empty() {
+            let subcommand = match tui::pick_subcommand(&spec, &picked_parts.join(" "))? {
+                tui::SubcommandPick::Subcommand(subcommand) => subcommand,
+                tui::SubcommandPick::Command => break,
+                // Same as cancelling the form: nothing runs or is saved
+                tui::SubcommandPick::Cancel => return Ok(()),
+            };
+            picked_parts.push(subcommand);
+            let (picked_spec, generated) = get_or_generate_spec(
+                &cache,
+                &config,
+                command_name,
+                &picked_parts[1..],
+                args.refresh_cache,
+                generation_options.clone(),
+                &mut profiler.timings,
+            )
+            .await?;
+            if !generated {
+                cache.update_usage(&picked_parts.join(":")).await?;
+            }
+            spec = picked_spec;
+        }
+    }
+    let command_parts = &picked_parts;
+    let history_key = command_parts.join(":");
 
     if args.show_spec {
         println!("{}", serde_json::to_string_pretty(&spec)?);
+        // Keep stdout pure JSON so the spec can still be piped to jq
+        eprintln!(
+            "\nSample invocation: {}",
+            executor::build_command(&spec, &executor::sample_values(&spec))
+        );
+        if spec.basic_only {
+            eprintln!("Documentation: help text only (basic-only spec)");
+        } else if let Some(docs) = cache.get_docs(&history_key).await? {
+            eprintln!("Documentation: {}", docs.sources());
+        }
+        return Ok(());
+    }
+
+    if let Some(path) = &args.export_history_script {
+        let since = args.since.map(|age| {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0);
+            now.saturating_sub(age)
+        });
+        let entries = cache
+            .get_history(&history_key, since, args.successful_only)
+            .await?;
+        let script = shell::render_history_script(&spec, &command_parts.join(" "), &entries);
+        std::fs::write(path, script)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+        }
+        println!(
+            "Wrote {} invocation(s) to: {}",
+            entries.len(),
+            path.display()
+        );
         return Ok(());
     }
 
+    // Apply the user's sensitivity corrections before anything masks, caches or logs values
+    spec.apply_sensitivity_overrides(&cache.get_sensitivity_overrides(command_name).await?);
+
     // Load cached values
     let cached_values = cache.get_values(command_name).await?;
 
+    if let Some(path) = &args.export_invocation {
+        return write_invocation(&command_parts.join(" "), &spec, &cached_values, path);
+    }
+
+    // An imported invocation's values take the place of the cached ones
+    let initial_values = match &imported {
+        Some(descriptor) => descriptor.values_for(&spec)?,
+        None => cached_values.clone(),
+    };
+
     if args.direct {
-        // Execute with cached/default values
+        // Execute with cached/default (or imported) values, or a preset's
+        let cached_values = match &args.preset {
+            Some(preset) => match cache.load_preset(command_name, preset).await? {
+                Some(values) => values,
+                None => anyhow::bail!("No preset named '{}' for {}", preset, command_parts.join(" ")),
+            },
+            None => initial_values,
+        };
         let command_line = executor::build_command(&spec, &cached_values);
-        let result = executor::execute(&command_line).await?;
+        if args.dry_run {
+            println!("{}", command_line);
+            return Ok(());
+        }
+        if args.background {
+            return launch_background(&cache, &config, &history_key, &spec, &cached_values, &command_line)
+                .await;
+        }
+        let started = Instant::now();
+        let Some(run) = run_command(&spec, &cached_values, &args, &config).await? else {
+            println!("Execution cancelled.");
+            return Ok(());
+        };
+        profiler.record("execution", started);
+
+        record_run(&cache, &config, &history_key, &cached_values, &spec, &run).await?;
 
         // Export to shell history
         shell::export_to_history(&config.shell, &command_line)?;
 
-        std::process::exit(result.code.unwrap_or(0));
+        drop(profiler);
+        drop(log_guard);
+        std::process::exit(run.result.exit_code());
+    }
+
+    if !confirm_form_opening(&config, command_parts, &spec)? {
+        println!("Cancelled.");
+        return Ok(());
     }
 
     // Run interactive TUI
-    let form_result = tui::run_form(&config, &spec, cached_values).await?;
+    let mut changes = tui::FormChanges::default();
+    let presets = load_presets(&cache, command_name).await?;
+    let note = cache.get_note(&history_key).await?;
+    let started = Instant::now();
+    let form_result = tui::run_form(
+        &config,
+        &spec,
+        initial_values,
+        presets,
+        note,
+        args.search.as_deref(),
+        &mut changes,
+    )
+    .await?;
+    profiler.record("TUI session", started);
+    save_form_changes(&cache, command_name, &history_key, &mut spec, &changes).await?;
 
+    let background = args.background || matches!(form_result, tui::FormResult::Background(_));
     match form_result {
-        tui::FormResult::Execute(values) => {
+        tui::FormResult::Execute(values) | tui::FormResult::Background(values) => {
             // Build and execute command
             let command_line = executor::build_command(&spec, &values);
 
-            // Show danger warning for high-risk commands
-            if spec.danger_level == parser::DangerLevel::High
-                || spec.danger_level == parser::DangerLevel::Critical
-            {
-                if !tui::confirm_dangerous(&spec, &command_line)? {
-                    println!("Execution cancelled.");
-                    return Ok(());
-                }
+            if args.learn {
+                print!("{}", parser::render_explanation(&spec, &values, &command_line));
+                return Ok(());
             }
 
-            let result = executor::execute(&command_line).await?;
-
-            // Cache non-sensitive values
-            cache
-                .save_values(command_name, &values, &spec.options)
-                .await?;
-
-            // Export to shell history
-            shell::export_to_history(&config.shell, &command_line)?;
+            // Nothing runs, so skip the danger gate, the value cache and shell history
+            if args.dry_run {
+                println!("{}", command_line);
+                return Ok(());
+            }
 
-            std::process::exit(result.code.unwrap_or(0));
+            let command = FormCommand {
+                command_name,
+                history_key: &history_key,
+                spec: &spec,
+                cached_values: &cached_values,
+            };
+            match execute_form_result(&cache, &config, &args, &command, &values, background, &mut profiler).await? {
+                FormRun::Cancelled => println!("Execution cancelled."),
+                FormRun::Backgrounded => {}
+                FormRun::Ran(result) => {
+                    drop(profiler);
+                    drop(log_guard);
+                    std::process::exit(result.exit_code());
+                }
+            }
         }
         tui::FormResult::Preview(values) => {
             // Build command and print it without executing
             let command_line = executor::build_command(&spec, &values);
             println!("{}", command_line);
         }
+        tui::FormResult::ExportInvocation(values) => {
+            let command = command_parts.join(" ");
+            let path = std::path::PathBuf::from(invocation::default_file_name(&command));
+            write_invocation(&command, &spec, &values, &path)?;
+        }
         tui::FormResult::Cancel => {
             // User cancelled, do nothing
         }
@@ -136,44 +640,726 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// Gate critical commands before the form opens, so a stray Enter can't stage them;
+/// false when the warning was declined
+fn confirm_form_opening(config: &config::Config, command_parts: &[String], spec: &parser::CommandSpec) -> Result<bool> {
+    let confirm_before_form = config
+        .command_config(&command_parts[0], &command_parts[1..])
+        .and_then(|c| c.confirm_before_form)
+        .unwrap_or(
+            config.security.confirm_before_form
+                && spec.danger_level == parser::DangerLevel::Critical,
+        );
+    Ok(!confirm_before_form || tui::confirm_open_form(&config.ui, spec, &command_parts.join(" "))?)
+}
+
+/// Walk through each step of a workflow: fill its form, then run it.
+/// Stops at the first cancelled or failing step; returns the exit code to use.
+async fn run_workflow(
+    cache: &cache::Cache,
+    config: &config::Config,
+    args: &Args,
+    name: &str,
+    profiler: &mut Profiler,
+) -> Result<i32> {
+    let Some(workflow) = config.workflows.get(name) else {
+        anyhow::bail!("No workflow named '{}' in the config", name);
+    };
+    let steps = workflow.step_parts();
+    if steps.is_empty() {
+        anyhow::bail!("Workflow '{}' has no steps", name);
+    }
+
+    for (n, parts) in steps.iter().enumerate() {
+        let step = parts.join(" ");
+        eprintln!("Workflow '{}': step {}/{}: {}", name, n + 1, steps.len(), step);
+
+        let command_name = &parts[0];
+        let subcommands = &parts[1..];
+        let history_key = parts.join(":");
+
+        let (mut spec, generated) = get_or_generate_spec(
+            cache,
+            config,
+            command_name,
+            subcommands,
+            args.refresh_cache,
+            llm::GenerationOptions {
+                progress: llm::ProgressMode::detect(args.quiet),
+                single_pass: args.single_pass,
+                timings: args.timings,
+                assume_yes: args.yes,
+                basic_only: args.basic_only || config.help.basic_only,
+                ..Default::default()
+            },
+            &mut profiler.timings,
+        )
+        .await?;
+        if !generated {
+            cache.update_usage(&history_key).await?;
+        }
+        spec.apply_sensitivity_overrides(&cache.get_sensitivity_overrides(command_name).await?);
+
+        if !confirm_form_opening(config, parts, &spec)? {
+            eprintln!("Workflow '{}' cancelled at step {} ({})", name, n + 1, step);
+            return Ok(0);
+        }
+
+        let cached_values = cache.get_values(command_name).await?;
+        let mut changes = tui::FormChanges::default();
+        let presets = load_presets(cache, command_name).await?;
+        let note = cache.get_note(&history_key).await?;
+        let form_result = tui::run_form(
+            config,
+            &spec,
+            cached_values.clone(),
+            presets,
+            note,
+            None,
+            &mut changes,
+        )
+        .await?;
+        save_form_changes(cache, command_name, &history_key, &mut spec, &changes).await?;
+
+        let (values, background) = match form_result {
+            tui::FormResult::Execute(values) => (values, false),
+            tui::FormResult::Background(values) => (values, true),
+            tui::FormResult::Preview(values) => {
+                println!("{}", executor::build_command(&spec, &values));
+                continue;
+            }
+            tui::FormResult::ExportInvocation(values) => {
+                let path = std::path::PathBuf::from(invocation::default_file_name(&step));
+                write_invocation(&step, &spec, &values, &path)?;
+                continue;
+            }
+            tui::FormResult::Cancel => {
+                eprintln!("Workflow '{}' cancelled at step {} ({})", name, n + 1, step);
+                return Ok(0);
+            }
+        };
+
+        let command = FormCommand {
+            command_name,
+            history_key: &history_key,
+            spec: &spec,
+            cached_values: &cached_values,
+        };
+        // A step sent to the background (Ctrl+B) isn't waited for; the workflow moves on
+        let result = match execute_form_result(cache, config, args, &command, &values, background, profiler).await? {
+            FormRun::Cancelled => {
+                eprintln!("Workflow '{}' cancelled at step {} ({})", name, n + 1, step);
+                return Ok(0);
+            }
+            FormRun::Backgrounded => continue,
+            FormRun::Ran(result) => result,
+        };
+
+        if result.interrupted {
+            eprintln!("Workflow '{}' stopped: step {} ({}) was interrupted", name, n + 1, step);
+            return Ok(130);
+        }
+        if !result.succeeded() {
+            let code = result.code.unwrap_or(1);
+            eprintln!(
+                "Workflow '{}' stopped: step {} ({}) exited with code {}",
+                name,
+                n + 1,
+                step,
+                code
+            );
+            return Ok(code);
+        }
+    }
+
+    eprintln!("Workflow '{}' completed {} step(s)", name, steps.len());
+    Ok(0)
+}
+
+/// The command a form was filled in for
+struct FormCommand<'a> {
+    command_name: &'a str,
+    history_key: &'a str,
+    spec: &'a parser::CommandSpec,
+    /// Values cached before the form opened, to spot overwrites
+    cached_values: &'a HashMap<String, String>,
+}
+
+/// How running a form's values ended
+enum FormRun {
+    /// A confirmation was declined, so nothing ran
+    Cancelled,
+    /// Launched detached (Ctrl+B or `--background`)
+    Backgrounded,
+    Ran(executor::ExecutionResult),
+}
+
+/// Run the values of a form submitted with Execute or Background, for a single
+/// command or a workflow step alike: confirm high-risk commands, ask before
+/// overwriting cached values, then run (or launch) the command and record it in
+/// the value cache, history, audit log and shell history
+async fn execute_form_result(
+    cache: &cache::Cache,
+    config: &config::Config,
+    args: &Args,
+    command: &FormCommand<'_>,
+    values: &HashMap<String, String>,
+    background: bool,
+    profiler: &mut Profiler,
+) -> Result<FormRun> {
+    let spec = command.spec;
+    let command_line = executor::build_command(spec, values);
+
+    // Show danger warning for high-risk commands
+    if (spec.danger_level == parser::DangerLevel::High
+        || spec.danger_level == parser::DangerLevel::Critical)
+        && !tui::confirm_dangerous(&config.ui, spec, &command_line)?
+    {
+        return Ok(FormRun::Cancelled);
+    }
+
+    // Protect curated cached values from an accidental one-off run
+    let changes = cache::value_overwrites(command.cached_values, values, &spec.options);
+    let update_cache = changes.is_empty()
+        || !config.cache.confirm_value_overwrite
+        || args.yes
+        || tui::confirm_value_overwrite(&config.ui, &changes)?;
+
+    if background {
+        if update_cache {
+            cache
+                .save_values(command.command_name, values, &spec.options)
+                .await?;
+        }
+        launch_background(cache, config, command.history_key, spec, values, &command_line).await?;
+        return Ok(FormRun::Backgrounded);
+    }
+
+    let started = Instant::now();
+    let Some(run) = run_command(spec, values, args, config).await? else {
+        return Ok(FormRun::Cancelled);
+    };
+    profiler.record("execution", started);
+
+    // Cache non-sensitive values
+    if update_cache {
+        cache
+            .save_values(command.command_name, values, &spec.options)
+            .await?;
+    }
+
+    record_run(cache, config, command.history_key, values, spec, &run).await?;
+
+    // Export to shell history
+    shell::export_to_history(&config.shell, &command_line)?;
+
+    Ok(FormRun::Ran(run.result))
+}
+
+/// All of a command's saved presets, for the form's preset picker
+async fn load_presets(
+    cache: &cache::Cache,
+    command_name: &str,
+) -> Result<Vec<(String, HashMap<String, String>)>> {
+    let mut presets = Vec::new();
+    for name in cache.list_presets(command_name).await? {
+        if let Some(values) = cache.load_preset(command_name, &name).await? {
+            presets.push((name, values));
+        }
+    }
+    Ok(presets)
+}
+
+/// List (or, with `clear`, delete) the saved failed LLM responses
+fn debug_files(clear: bool) -> Result<()> {
+    let Some(dir) = llm::debug_dir() else {
+        anyhow::bail!("Could not determine the data directory");
+    };
+    println!("Debug directory: {}", dir.display());
+
+    if clear {
+        let removed = llm::clear_debug_files(&dir)?;
+        println!("Removed {} debug file(s)", removed);
+        return Ok(());
+    }
+
+    let files = llm::list_debug_files(&dir)?;
+    if files.is_empty() {
+        println!("No debug files.");
+        return Ok(());
+    }
+    for file in &files {
+        let name = file.path.file_name().unwrap_or_default().to_string_lossy();
+        println!("  {}  ({} bytes)", name, file.size);
+    }
+    Ok(())
+}
+
+/// Print the resolved storage paths, noting which come from environment overrides
+fn print_paths(config: &config::Config) {
+    let source = |name: &str| {
+        if std::env::var_os(name).is_some_and(|value| !value.is_empty()) {
+            format!("  (from {})", name)
+        } else {
+            String::new()
+        }
+    };
+    let unknown = || "(unknown)".to_string();
+
+    let config_path = config::config_path();
+    let missing = if config_path.exists() { "" } else { "  (not found)" };
+    println!("Config:    {}{}{}", config_path.display(), source(config::CONFIG_ENV), missing);
+    println!("Cache:     {}{}", config.cache.path.display(), source(config::CACHE_ENV));
+    println!(
+        "Audit log: {}",
+        audit::audit_path().map_or_else(unknown, |path| path.display().to_string())
+    );
+    println!(
+        "Data dir:  {}",
+        config::data_dir().map_or_else(unknown, |dir| dir.display().to_string())
+    );
+}
+
+/// Apply the cache migrations the database is missing, for `--migrate`
+async fn migrate_cache(config: &config::Config) -> Result<()> {
+    let cache = cache::Cache::connect(&config.cache.path).await?;
+    let applied = cache.migrate().await?;
+    for migration in &applied {
+        println!("Applied migration {}: {}", migration.version, migration.description);
+    }
+    let version = cache.schema_version().await?;
+    if applied.is_empty() {
+        println!("Cache schema is up to date (version {}): {}", version, config.cache.path.display());
+    } else {
+        println!("Cache schema is now at version {}: {}", version, config.cache.path.display());
+    }
+    Ok(())
+}
+
+/// Persist sensitivity toggles, saved presets and note edits made in the form
+async fn save_form_changes(
+    cache: &cache::Cache,
+    command_name: &str,
+    history_key: &str,
+    spec: &mut parser::CommandSpec,
+    changes: &tui::FormChanges,
+) -> Result<()> {
+    if !changes.sensitivity_overrides.is_empty() {
+        cache
+            .save_sensitivity_overrides(command_name, &changes.sensitivity_overrides)
+            .await?;
+        spec.apply_sensitivity_overrides(&changes.sensitivity_overrides);
+    }
+    for (name, values) in &changes.saved_presets {
+        cache.save_preset(command_name, name, values, spec).await?;
+    }
+    if let Some(note) = &changes.note {
+        cache.save_note(history_key, note).await?;
+    }
+    Ok(())
+}
+
+/// Save values as a shareable invocation descriptor (`--export-invocation`, Ctrl+O in the form)
+fn write_invocation(
+    command: &str,
+    spec: &parser::CommandSpec,
+    values: &HashMap<String, String>,
+    path: &std::path::Path,
+) -> Result<()> {
+    invocation::InvocationDescriptor::new(command, spec, values).write(path)?;
+    println!("Wrote invocation to: {}", path.display());
+    Ok(())
+}
+
+/// Print the cached specs as a table, most used first
+async fn list_cached(cache: &cache::Cache, tag: Option<&str>) -> Result<()> {
+    let specs = cache.list_specs(tag).await?;
+    if specs.is_empty() {
+        match tag {
+            Some(tag) => println!("No cached commands tagged '{}'.", tag),
+            None => println!("No cached commands."),
+        }
+        return Ok(());
+    }
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let width = specs
+        .iter()
+        .map(|s| s.display_name().len())
+        .max()
+        .unwrap_or(0)
+        .max("COMMAND".len());
+
+    println!("{:width$}  {:8}  {:>5}  {:9}  TAGS", "COMMAND", "DANGER", "USES", "LAST USED");
+    for spec in &specs {
+        let line = format!(
+            "{:width$}  {:8}  {:>5}  {:9}  {}",
+            spec.display_name(),
+            spec.danger_level,
+            spec.use_count,
+            spec.last_used_ago(now),
+            spec.tags.join(", ")
+        );
+        println!("{}", line.trim_end());
+    }
+    Ok(())
+}
+
+/// Run the built command; with `--measure`, also report and return its resource usage,
+/// and with `--json`, capture its output and print it as a JSON object.
+/// `--timeout` (else `execution.timeout_secs`) kills it if it runs too long, and
+/// `--via-shell` runs it through the user's interactive shell once confirmed.
+/// `--trace-command` prints the final argv, one argument per line, secrets masked.
+/// Returns None if the user declines the `--via-shell` confirmation.
+async fn run_command(
+    spec: &parser::CommandSpec,
+    values: &HashMap<String, String>,
+    args: &Args,
+    config: &config::Config,
+) -> Result<Option<CommandRun>> {
+    let mut argv = executor::build_argv(spec, values);
+    let mut masked_argv = executor::build_masked_argv(spec, values);
+    if args.via_shell {
+        let shell = executor::user_shell()?;
+        if !args.yes && !tui::confirm_via_shell(&config.ui, &shell, &shell_words::join(&argv))? {
+            return Ok(None);
+        }
+        argv = executor::shell_argv(&shell, &argv);
+        masked_argv = executor::shell_argv(&shell, &masked_argv);
+    }
+    if args.trace_command {
+        eprint!("{}", executor::format_trace(&masked_argv));
+    }
+    let argv = &argv;
+    let timeout = args
+        .timeout
+        .or(config.execution.timeout_secs)
+        .map(Duration::from_secs);
+    let (result, usage) = if args.json {
+        let result = executor::execute_captured(argv, timeout).await?;
+        println!("{}", result.to_json(&shell_words::join(argv)));
+        if result.timed_out {
+            return Err(executor::timed_out_error(timeout).into());
+        }
+        (result, None)
+    } else if args.measure {
+        let (result, usage) = executor::execute_measured(argv, timeout).await?;
+        eprintln!("Measured: {}", usage.summary());
+        (result, Some(usage))
+    } else {
+        (executor::execute(argv, timeout).await?, None)
+    };
+    Ok(Some(CommandRun {
+        result,
+        usage,
+        masked_argv,
+    }))
+}
+
+/// A command that [`run_command`] ran
+struct CommandRun {
+    result: executor::ExecutionResult,
+    /// Resource usage, with `--measure`
+    usage: Option<executor::ResourceUsage>,
+    /// The argv that ran (shell-wrapped with `--via-shell`), secrets masked
+    masked_argv: Vec<String>,
+}
+
+/// Ids of a command's sensitive fields, from the cached spec, the user's
+/// sensitivity overrides and the values kept in the keyring. Uses the cached spec
+/// only, so no generation is triggered just to clear or print values.
+async fn cached_sensitive_field_ids(cache: &cache::Cache, command_name: &str, history_key: &str) -> Result<Vec<String>> {
+    let overrides = cache.get_sensitivity_overrides(command_name).await?;
+    let mut field_ids: Vec<String> = overrides
+        .iter()
+        .filter(|(_, sensitive)| **sensitive)
+        .map(|(id, _)| id.clone())
+        .collect();
+    if let Some(mut spec) = cache.get_spec(history_key).await? {
+        spec.apply_sensitivity_overrides(&overrides);
+        field_ids.extend(spec.sensitive_field_ids());
+    }
+    field_ids.extend(cache.keyring_field_ids(command_name).await?);
+    Ok(field_ids)
+}
+
+/// Record a finished (or interrupted) run in history and the audit log. The
+/// command already ran, so a failure to write the audit log is only warned about.
+async fn record_run(
+    cache: &cache::Cache,
+    config: &config::Config,
+    history_key: &str,
+    values: &HashMap<String, String>,
+    spec: &parser::CommandSpec,
+    run: &CommandRun,
+) -> Result<()> {
+    let result = &run.result;
+    if config.security.audit_log {
+        let id = if result.interrupted {
+            cache.log_interrupted(history_key, values, spec).await?
+        } else {
+            cache
+                .log_execution(history_key, values, spec, result.succeeded())
+                .await?
+        };
+        if let Some(usage) = &run.usage {
+            cache.log_measurement(id, usage).await?;
+        }
+    }
+    if let Err(e) = audit::record(config, &run.masked_argv, spec, result) {
+        tracing::warn!("Failed to write the audit log: {}", e);
+    }
+    Ok(())
+}
+
+/// Launch a command detached, record it in history, and report where its output goes
+async fn launch_background(
+    cache: &cache::Cache,
+    config: &config::Config,
+    history_key: &str,
+    spec: &parser::CommandSpec,
+    values: &HashMap<String, String>,
+    command_line: &str,
+) -> Result<()> {
+    let log_dir = config::data_dir()
+        .map(|dir| dir.join("background"))
+        .unwrap_or_else(|| std::env::temp_dir().join("quocli-background"));
+    let launch = executor::spawn_background(&executor::build_argv(spec, values), &log_dir)?;
+
+    if config.security.audit_log {
+        cache
+            .log_background(history_key, values, spec, launch.pid)
+            .await?;
+    }
+    shell::export_to_history(&config.shell, command_line)?;
+
+    println!("Started in background (pid {})", launch.pid);
+    println!("Output: {}", launch.log_path.display());
+    Ok(())
+}
+
+/// Regenerate the cached specs for `--refresh-all`, reporting each one and a
+/// summary. Commands that are no longer installed are skipped; returns how many failed.
+async fn refresh_all(
+    cache: &cache::Cache,
+    config: &config::Config,
+    args: &Args,
+    profiler: &mut Profiler,
+) -> Result<usize> {
+    let specs = cache.list_specs(None).await?;
+    if specs.is_empty() {
+        println!("No cached commands.");
+        return Ok(0);
+    }
+    // Asking for a refresh overrides a disabled auto_refresh
+    let mut config = config.clone();
+    config.cache.auto_refresh = true;
+
+    let (mut refreshed, mut unchanged, mut missing, mut failed) = (0, 0, 0, 0);
+    for (n, summary) in specs.iter().enumerate() {
+        let display_name = summary.display_name();
+        eprintln!("[{}/{}] {}", n + 1, specs.len(), display_name);
+
+        let parts: Vec<String> = summary.command_name.split(':').map(str::to_string).collect();
+        if !parser::command_exists(&parts[0]) {
+            eprintln!("  not installed, skipped");
+            missing += 1;
+            continue;
+        }
+        let result = get_or_generate_spec(
+            cache,
+            &config,
+            &parts[0],
+            &parts[1..],
+            args.refresh_cache,
+            llm::GenerationOptions {
+                progress: llm::ProgressMode::detect(args.quiet),
+                single_pass: args.single_pass,
+                timings: args.timings,
+                assume_yes: args.yes,
+                basic_only: args.basic_only || config.help.basic_only,
+                ..Default::default()
+            },
+            &mut profiler.timings,
+        )
+        .await;
+
+        match result {
+            Ok((_, true)) => refreshed += 1,
+            Ok((_, false)) => {
+                eprintln!("  unchanged, skipped");
+                unchanged += 1;
+            }
+            Err(e) => {
+                eprintln!("  failed: {:#}", e);
+                failed += 1;
+            }
+        }
+    }
+
+    println!(
+        "Refreshed {}, unchanged {}, not installed {}, failed {} (of {} cached specs)",
+        refreshed,
+        unchanged,
+        missing,
+        failed,
+        specs.len()
+    );
+    Ok(failed)
+}
+
+/// The cached spec if it is still current, else a freshly generated (and cached)
+/// one. The flag is true when the spec was generated, so callers only count usage
+/// of cached specs.
 async fn get_or_generate_spec(
     cache: &cache::Cache,
     config: &config::Config,
     command_name: &str,
     subcommands: &[String],
     force_refresh: bool,
-) -> Result<parser::CommandSpec> {
+    mut options: llm::GenerationOptions,
+    profile: &mut llm::GenerationTimings,
+) -> Result<(parser::CommandSpec, bool)> {
     let full_command = if subcommands.is_empty() {
         command_name.to_string()
     } else {
         format!("{}:{}", command_name, subcommands.join(":"))
     };
 
-    // Get help documentation (help text + manpage)
-    let docs = parser::get_help_documentation(command_name, subcommands)?;
+    let cached = if force_refresh {
+        None
+    } else {
+        cache.get_spec(&full_command).await?
+    };
+
+    // A cached spec keeps the mode it was generated in: full specs are never
+    // downgraded, and basic-only ones are only upgraded by --refresh-cache
+    let basic_only = match &cached {
+        Some(spec) => spec.basic_only,
+        None => options.basic_only,
+    };
+    if basic_only && !options.basic_only {
+        tracing::info!(
+            "Using the basic-only spec for {}; run with --refresh-cache to generate the full spec",
+            full_command
+        );
+    }
+
+    // Get help documentation (help text + manpage), honoring any custom help command
+    let help_command = config
+        .command_config(command_name, subcommands)
+        .and_then(|c| c.help_command.as_deref());
+    let started = Instant::now();
+    // A forced refresh fetches everything again, the manpage included
+    let previous_docs = if force_refresh {
+        None
+    } else {
+        cache.get_docs(&full_command).await?
+    };
+    let docs = parser::get_help_documentation(
+        command_name,
+        subcommands,
+        help_command,
+        config.help.pty_fallback,
+        Duration::from_secs(config.help.timeout_secs),
+        basic_only,
+        previous_docs.as_ref(),
+    )?;
+    // Basic-only runs skip the manpage, so saving them would hide it from later full
+    // runs; an empty manpage may be a failed fetch, so it isn't kept either
+    if !basic_only && !docs.manpage_text.is_empty() && previous_docs.as_ref() != Some(&docs) {
+        cache.save_docs(&full_command, &docs).await?;
+    }
+    let help_fetch = started.elapsed();
+    profile.record("help fetch", help_fetch);
     let help_hash = parser::hash_help_text(&docs.combined_text());
 
+    let tool_version = docs.version();
+    // Listed subcommands are read from the help text, so specs cached before
+    // they were tracked get them too
+    let listed_subcommands = parser::extract_subcommands(&docs.help_text);
+
     // Check cache
-    if !force_refresh {
-        if let Some(cached_spec) = cache.get_spec(&full_command).await? {
-            if cached_spec.version_hash == help_hash {
-                tracing::info!("Using cached spec for: {}", full_command);
-                cache.update_usage(&full_command).await?;
-                return Ok(cached_spec);
+    if let Some(mut cached_spec) = cached {
+        if cached_spec.subcommands.is_empty() {
+            cached_spec.subcommands = listed_subcommands.clone();
+        }
+        // A version bump is a clearer staleness signal than an opaque hash mismatch
+        let version_change = match (&cached_spec.tool_version, &tool_version) {
+            (Some(old), Some(new)) if old != new => Some((old.clone(), new.clone())),
+            _ => None,
+        };
+
+        // Old specs are regenerated even if the help text is unchanged (prompts improve)
+        let expired = config.cache.auto_refresh
+            && cache.is_spec_expired(&full_command, config.cache.ttl_days).await?;
+        if expired {
+            tracing::info!(
+                "Cached spec for {} is older than {} days, regenerating",
+                full_command,
+                config.cache.ttl_days
+            );
+        } else if cached_spec.version_hash == help_hash {
+            tracing::info!("Using cached spec for: {}", full_command);
+            if options.timings {
+                eprintln!("Spec loaded from cache; run with --refresh-cache to time generation");
+            }
+            return Ok((cached_spec, false));
+        }
+
+        if !config.cache.auto_refresh {
+            match version_change {
+                Some((old, new)) => eprintln!(
+                    "Warning: {} changed from version {} to {}; the cached spec may be outdated (run with --refresh-cache to regenerate)",
+                    full_command.replace(':', " "),
+                    old,
+                    new
+                ),
+                None => eprintln!(
+                    "Help text for {} changed; using the cached spec (run with --refresh-cache to regenerate)",
+                    full_command.replace(':', " ")
+                ),
             }
+            return Ok((cached_spec, false));
+        }
+        if let Some((old, new)) = version_change {
+            tracing::info!("{} changed from version {} to {}, regenerating spec", full_command, old, new);
+        } else if !expired {
             tracing::info!("Help text changed, regenerating spec for: {}", full_command);
         }
     }
 
     // Generate spec using LLM
     tracing::info!("Generating spec for: {}", full_command);
-    let llm_client = llm::create_client(config)?;
-    let spec = llm_client
+    let command_config = config.command_config(command_name, subcommands);
+    options.model = command_config.and_then(|c| c.model.clone());
+    options.ignore_flags = config.parser.ignore_flags.clone();
+    options
+        .ignore_flags
+        .extend(command_config.iter().flat_map(|c| c.ignore_flags.iter().cloned()));
+    let llm_client = llm::create_client(config, &options)?;
+    let mut spec = llm_client
         .generate_spec(command_name, subcommands, &docs, &help_hash)
         .await?;
+    spec.tool_version = tool_version;
+    spec.basic_only = basic_only;
+    if spec.subcommands.is_empty() {
+        spec.subcommands = listed_subcommands;
+    }
+
+    if options.timings {
+        let mut timings = llm::GenerationTimings::default();
+        timings.record("help fetch", help_fetch);
+        timings.extend(llm_client.timings());
+        let title = format!("Generation timings for {}", full_command.replace(':', " "));
+        eprint!("{}", timings.report(&title, started.elapsed()));
+    }
 
     // Cache the spec
     cache.save_spec(&full_command, &spec).await?;
 
-    Ok(spec)
+    Ok((spec, true))
 }
@@ -0,0 +1,435 @@
+use super::spec::{
+    ArgumentType, CommandOption, CommandSpec, DangerLevel, OptionLevel, PositionalArg,
+};
+use crate::executor::split_values;
+use std::collections::HashMap;
+
+/// Render a command spec as a shareable man-page-style markdown document
+pub fn render_doc(spec: &CommandSpec, name: &str) -> String {
+    let mut out = String::new();
+
+    out.push_str(&format!("# {}\n\n", name));
+    if !spec.description.is_empty() {
+        out.push_str(&format!("{}\n\n", spec.description.trim()));
+    }
+    out.push_str(&format!("**Danger level:** {}\n\n", spec.danger_level));
+
+    // Synopsis
+    out.push_str("## Synopsis\n\n```\n");
+    out.push_str(&synopsis(spec, name));
+    out.push_str("\n```\n\n");
+
+    // Positional arguments
+    if !spec.positional_args.is_empty() {
+        out.push_str("## Arguments\n\n");
+        for arg in &spec.positional_args {
+            out.push_str(&render_positional(arg));
+        }
+    }
+
+    // Options, basic first then advanced
+    let basic: Vec<&CommandOption> = spec
+        .options
+        .iter()
+        .filter(|o| o.level == OptionLevel::Basic)
+        .collect();
+    let advanced: Vec<&CommandOption> = spec
+        .options
+        .iter()
+        .filter(|o| o.level == OptionLevel::Advanced)
+        .collect();
+
+    if !basic.is_empty() {
+        out.push_str("## Options\n\n");
+        for opt in basic {
+            out.push_str(&render_option(opt));
+        }
+    }
+
+    if !advanced.is_empty() {
+        out.push_str("## Advanced Options\n\n");
+        for opt in advanced {
+            out.push_str(&render_option(opt));
+        }
+    }
+
+    if !spec.subcommands.is_empty() {
+        out.push_str("## Subcommands\n\n");
+        for sub in &spec.subcommands {
+            out.push_str(&format!("- `{}`\n", sub));
+        }
+        out.push('\n');
+    }
+
+    if !spec.examples.is_empty() {
+        out.push_str("## Examples\n\n```\n");
+        for example in &spec.examples {
+            out.push_str(example);
+            out.push('\n');
+        }
+        out.push_str("```\n");
+    }
+
+    out.trim_end().to_string() + "\n"
+}
+
+/// Explain a built command flag by flag, for `--learn` mode.
+/// Sensitive values are masked (each value of a repeatable option on its own);
+/// unset fields and false booleans are skipped.
+pub fn render_explanation(
+    spec: &CommandSpec,
+    values: &HashMap<String, String>,
+    command_line: &str,
+) -> String {
+    let mut out = format!("Command:\n  {}\n\n", command_line);
+    if !spec.description.is_empty() {
+        out.push_str(&format!("What it does:\n  {}\n\n", spec.description.trim()));
+    }
+
+    let mut chosen = String::new();
+    for opt in &spec.options {
+        let Some(value) = values.get(opt.primary_flag()).filter(|v| !v.is_empty()) else {
+            continue;
+        };
+        let shown: Vec<&str> = split_values(value, opt.repeatable)
+            .into_iter()
+            .map(|v| if opt.sensitive { "***" } else { v })
+            .collect();
+        let heading = match opt.argument_type {
+            ArgumentType::Bool if value == "true" => opt.flags.join(", "),
+            ArgumentType::Bool => continue,
+            // Several values of a repeatable option are listed under it
+            _ if shown.len() > 1 => format!("{} (given {} times)", opt.flags.join(", "), shown.len()),
+            _ => format!("{} {}", opt.flags.join(", "), shown.join("")),
+        };
+        chosen.push_str(&format!("  {}\n", heading));
+        if shown.len() > 1 {
+            for value in &shown {
+                chosen.push_str(&format!("      - {}\n", value));
+            }
+        }
+        if !opt.description.is_empty() {
+            chosen.push_str(&format!("      {}\n", opt.description.trim()));
+        }
+    }
+    if !chosen.is_empty() {
+        out.push_str("Options:\n");
+        out.push_str(&chosen);
+        out.push('\n');
+    }
+
+    let mut arguments = String::new();
+    for arg in &spec.positional_args {
+        let key = format!("_pos_{}", arg.name);
+        let Some(value) = values.get(&key).filter(|v| !v.is_empty()) else {
+            continue;
+        };
+        let shown = if arg.sensitive { "***" } else { value.as_str() };
+        arguments.push_str(&format!("  {} = {}\n", arg.name, shown));
+        if !arg.description.is_empty() {
+            arguments.push_str(&format!("      {}\n", arg.description.trim()));
+        }
+    }
+    if !arguments.is_empty() {
+        out.push_str("Arguments:\n");
+        out.push_str(&arguments);
+        out.push('\n');
+    }
+
+    let assessment = match spec.danger_level {
+        DangerLevel::Low => "read-only or easily undone",
+        DangerLevel::Medium => "modifies files or state, but usually recoverable",
+        DangerLevel::High => "can destroy data or make hard-to-undo changes",
+        DangerLevel::Critical => "can cause irreversible, system-wide damage",
+    };
+    out.push_str(&format!(
+        "Danger level: {} ({})\n\nLearn mode: the command was not executed.\n",
+        spec.danger_level, assessment
+    ));
+    out
+}
+
+/// Build a one-line synopsis like `cp [OPTIONS] <source> <dest>`
+fn synopsis(spec: &CommandSpec, name: &str) -> String {
+    let mut parts = vec![name.to_string()];
+    let options = if spec.options.is_empty() {
+        None
+    } else {
+        Some("[OPTIONS]".to_string())
+    };
+    let positionals: Vec<String> = spec
+        .positional_args
+        .iter()
+        .map(|a| {
+            if a.required {
+                format!("<{}>", a.name)
+            } else {
+                format!("[{}]", a.name)
+            }
+        })
+        .collect();
+
+    if spec.positionals_first {
+        parts.extend(positionals);
+        parts.extend(options);
+    } else {
+        parts.extend(options);
+        parts.extend(positionals);
+    }
+
+    parts.join(" ")
+}
+
+fn render_positional(arg: &PositionalArg) -> String {
+    let mut attrs = vec![type_name(&arg.argument_type).to_string()];
+    attrs.push(if arg.required { "required" } else { "optional" }.to_string());
+    if arg.sensitive {
+        attrs.push("sensitive".to_string());
+    }
+    if let Some(default) = &arg.default {
+        attrs.push(format!("default: `{}`", default));
+    }
+
+    let mut out = format!("- `{}` ({})", arg.name, attrs.join(", "));
+    if !arg.description.is_empty() {
+        out.push_str(&format!(" — {}", arg.description.trim()));
+    }
+    out.push('\n');
+    out
+}
+
+fn render_option(opt: &CommandOption) -> String {
+    let mut heading = opt.flags.join(", ");
+    if opt.argument_type != ArgumentType::Bool {
+        if let Some(arg_name) = &opt.argument_name {
+            heading.push_str(&format!(" <{}>", arg_name));
+        }
+    }
+
+    let mut attrs = vec![format!("type: {}", type_name(&opt.argument_type))];
+    if opt.required {
+        attrs.push("required".to_string());
+    }
+    if opt.sensitive {
+        attrs.push("sensitive".to_string());
+    }
+    if opt.repeatable {
+        attrs.push("repeatable".to_string());
+    }
+    if opt.deprecated {
+        attrs.push("deprecated".to_string());
+    }
+    if let Some(default) = &opt.default {
+        attrs.push(format!("default: `{}`", default));
+    }
+    if !opt.enum_values.is_empty() {
+        attrs.push(format!("values: {}", opt.enum_values.join(", ")));
+    }
+
+    let mut out = format!("### `{}`\n\n*{}*\n\n", heading, attrs.join(" · "));
+    if !opt.description.is_empty() {
+        out.push_str(&format!("{}\n\n", opt.description.trim()));
+    }
+    if !opt.conflicts_with.is_empty() {
+        out.push_str(&format!("Conflicts with: {}\n\n", opt.conflicts_with.join(", ")));
+    }
+    if !opt.requires.is_empty() {
+        out.push_str(&format!("Requires: {}\n\n", opt.requires.join(", ")));
+    }
+    out
+}
+
+fn type_name(arg_type: &ArgumentType) -> &'static str {
+    match arg_type {
+        ArgumentType::Bool => "flag",
+        ArgumentType::String => "string",
+        ArgumentType::Int => "integer",
+        ArgumentType::Float => "number",
+        ArgumentType::Path => "path",
+        ArgumentType::Enum => "choice",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_spec() -> CommandSpec {
+        CommandSpec {
+            command: "cp".to_string(),
+            version_hash: "hash".to_string(),
+            description: "Copy files".to_string(),
+            options: vec![
+                CommandOption {
+                    flags: vec!["-r".to_string(), "--recursive".to_string()],
+                    description: "Copy directories recursively".to_string(),
+                    argument_type: ArgumentType::Bool,
+                    argument_name: None,
+                    required: false,
+                    sensitive: false,
+                    repeatable: false,
+                    conflicts_with: vec![],
+                    requires: vec![],
+                    default: None,
+                    enum_values: vec![],
+                    level: OptionLevel::Basic,
+                    deprecated: false,
+                    example: None,
+                    min: None,
+                    max: None,
+                    pattern: None,
+                },
+                CommandOption {
+                    flags: vec!["--backup".to_string()],
+                    description: "Make a backup of each existing destination file".to_string(),
+                    argument_type: ArgumentType::Enum,
+                    argument_name: Some("CONTROL".to_string()),
+                    required: false,
+                    sensitive: false,
+                    repeatable: false,
+                    conflicts_with: vec![],
+                    requires: vec![],
+                    default: Some("existing".to_string()),
+                    enum_values: vec!["none".to_string(), "numbered".to_string(), "existing".to_string()],
+                    level: OptionLevel::Advanced,
+                    deprecated: false,
+                    example: None,
+                    min: None,
+                    max: None,
+                    pattern: None,
+                },
+            ],
+            positional_args: vec![
+                PositionalArg {
+                    name: "source".to_string(),
+                    description: "File to copy".to_string(),
+                    required: true,
+                    sensitive: false,
+                    argument_type: ArgumentType::Path,
+                    default: None,
+                },
+                PositionalArg {
+                    name: "dest".to_string(),
+                    description: "Destination".to_string(),
+                    required: true,
+                    sensitive: false,
+                    argument_type: ArgumentType::Path,
+                    default: None,
+                },
+            ],
+            subcommands: vec![],
+            danger_level: DangerLevel::Medium,
+            examples: vec!["cp -r src/ dst/".to_string()],
+            positionals_first: false,
+            tool_version: None,
+            basic_only: false,
+        }
+    }
+
+    #[test]
+    fn test_render_doc_sections() {
+        let doc = render_doc(&create_test_spec(), "cp");
+
+        assert!(doc.starts_with("# cp\n"));
+        assert!(doc.contains("Copy files"));
+        assert!(doc.contains("**Danger level:** medium"));
+        assert!(doc.contains("cp [OPTIONS] <source> <dest>"));
+        assert!(doc.contains("## Arguments"));
+        assert!(doc.contains("- `source` (path, required) — File to copy"));
+        assert!(doc.contains("### `-r, --recursive`"));
+        assert!(doc.contains("## Advanced Options"));
+        assert!(doc.contains("### `--backup <CONTROL>`"));
+        assert!(doc.contains("values: none, numbered, existing"));
+        assert!(doc.contains("default: `existing`"));
+        assert!(doc.contains("## Examples"));
+        assert!(doc.contains("cp -r src/ dst/"));
+    }
+
+    #[test]
+    fn test_render_doc_positionals_first_synopsis() {
+        let mut spec = create_test_spec();
+        spec.positionals_first = true;
+        spec.positional_args.truncate(1);
+        spec.positional_args[0].required = false;
+
+        let doc = render_doc(&spec, "find");
+        assert!(doc.contains("find [source] [OPTIONS]"));
+    }
+
+    #[test]
+    fn test_render_explanation() {
+        let mut spec = create_test_spec();
+        spec.options[1].sensitive = true;
+
+        let mut values = HashMap::new();
+        values.insert("--recursive".to_string(), "true".to_string());
+        values.insert("--backup".to_string(), "numbered".to_string());
+        values.insert("_pos_source".to_string(), "a.txt".to_string());
+        values.insert("_pos_dest".to_string(), String::new());
+
+        let text = render_explanation(&spec, &values, "cp --recursive --backup numbered a.txt");
+        assert!(text.starts_with("Command:\n  cp --recursive --backup numbered a.txt\n"));
+        assert!(text.contains("What it does:\n  Copy files\n"));
+        assert!(text.contains("  -r, --recursive\n      Copy directories recursively\n"));
+        assert!(text.contains("  --backup ***\n"));
+        assert!(text.contains("  source = a.txt\n      File to copy\n"));
+        assert!(!text.contains("dest ="));
+        assert!(text.contains("Danger level: medium (modifies files"));
+        assert!(text.ends_with("the command was not executed.\n"));
+    }
+
+    #[test]
+    fn test_render_explanation_groups_repeated_values() {
+        let mut spec = create_test_spec();
+        let mut header = spec.options[1].clone();
+        header.flags = vec!["-H".to_string(), "--header".to_string()];
+        header.argument_type = ArgumentType::String;
+        header.repeatable = true;
+        header.description = "Extra header to include".to_string();
+        spec.options.push(header.clone());
+        header.flags = vec!["--cookie".to_string()];
+        header.sensitive = true;
+        spec.options.push(header);
+
+        let mut values = HashMap::new();
+        values.insert("--header".to_string(), "A: 1\nB: 2\nC: 3".to_string());
+        values.insert("--cookie".to_string(), "session=abc\ntheme=dark".to_string());
+
+        let text = render_explanation(&spec, &values, "cp");
+        assert!(text.contains(
+            "  -H, --header (given 3 times)\n      - A: 1\n      - B: 2\n      - C: 3\n      Extra header to include\n"
+        ));
+        assert!(text.contains("  --cookie (given 2 times)\n      - ***\n      - ***\n"));
+        assert!(!text.contains("abc"));
+
+        // A single value stays on the heading line
+        values.insert("--header".to_string(), "A: 1\n".to_string());
+        let text = render_explanation(&spec, &values, "cp");
+        assert!(text.contains("  -H, --header A: 1\n      Extra header to include\n"));
+    }
+
+    #[test]
+    fn test_render_explanation_skips_false_flags() {
+        let spec = create_test_spec();
+        let mut values = HashMap::new();
+        values.insert("--recursive".to_string(), "false".to_string());
+
+        let text = render_explanation(&spec, &values, "cp");
+        assert!(!text.contains("Options:"));
+        assert!(!text.contains("Arguments:"));
+    }
+
+    #[test]
+    fn test_render_doc_omits_empty_sections() {
+        let mut spec = create_test_spec();
+        spec.options.clear();
+        spec.positional_args.clear();
+        spec.examples.clear();
+
+        let doc = render_doc(&spec, "cp");
+        assert!(!doc.contains("## Options"));
+        assert!(!doc.contains("## Arguments"));
+        assert!(!doc.contains("## Examples"));
+        assert!(doc.contains("```\ncp\n```"));
+    }
+}
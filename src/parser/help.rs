@@ -1,8 +1,25 @@
 use crate::QuocliError;
+use portable_pty::{native_pty_system, CommandBuilder, PtySize};
+use regex::Regex;
 use sha2::{Digest, Sha256};
-use std::process::Command;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::sync::{mpsc, LazyLock};
+use std::time::Duration;
+
+/// The wide gap separating a help line's flags from their description
+static FLAG_GAP: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\S\s{2,}").unwrap());
+
+/// An explicit `version 2.43.0` or `Version: v1.2.3`
+static EXPLICIT_VERSION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\bversion:?\s+v?(\d+(?:\.\d+)+[\w.+-]*)").unwrap());
+
+/// A bare version number such as `8.4.0` or `(v1.2)`
+static BARE_VERSION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:^|[\s(])v?(\d+\.\d+(?:\.\d+)*[\w+-]*)").unwrap());
 
 /// Combined help documentation for a command
+#[derive(Debug, Clone, PartialEq)]
 pub struct HelpDocumentation {
     /// Help text from --help or similar
     pub help_text: String,
@@ -11,6 +28,21 @@ pub struct HelpDocumentation {
 }
 
 impl HelpDocumentation {
+    /// Detect the tool's version from the help text, falling back to the manpage
+    pub fn version(&self) -> Option<String> {
+        detect_version(&self.help_text).or_else(|| detect_version(&self.manpage_text))
+    }
+
+    /// The most detailed description of an option found in the help text or manpage
+    pub fn option_description(&self, flags: &[String]) -> Option<String> {
+        let from_help = option_description(&self.help_text, flags);
+        let from_manpage = option_description(&self.manpage_text, flags);
+        match (from_help, from_manpage) {
+            (Some(help), Some(man)) => Some(if man.len() > help.len() { man } else { help }),
+            (help, man) => help.or(man),
+        }
+    }
+
     /// Get the combined text for hashing (to detect changes)
     pub fn combined_text(&self) -> String {
         if self.manpage_text.is_empty() {
@@ -19,12 +51,56 @@ impl HelpDocumentation {
             format!("{}\n\n--- MANPAGE ---\n\n{}", self.help_text, self.manpage_text)
         }
     }
+
+    /// Which sources this documentation came from, e.g. "help text (40 lines), no manpage"
+    pub fn sources(&self) -> String {
+        let manpage = if self.manpage_text.is_empty() {
+            "no manpage".to_string()
+        } else {
+            format!("manpage ({} lines)", self.manpage_text.lines().count())
+        };
+        format!("help text ({} lines), {}", self.help_text.lines().count(), manpage)
+    }
 }
 
 /// Get help text and manpage for a command
-pub fn get_help_documentation(command: &str, subcommands: &[String]) -> Result<HelpDocumentation, QuocliError> {
-    let help_text = get_help_text_only(command, subcommands)?;
-    let manpage_text = get_manpage_text(command, subcommands).unwrap_or_default();
+///
+/// When `help_command` is set, it is run via `sh -c` in place of the usual
+/// `--help` probing. The result is hashed like any other help text, so changes
+/// to the custom help output are still detected.
+///
+/// With `pty_fallback`, thin or missing help is retried under a pseudo-terminal
+/// for tools that only print full help when attached to a TTY.
+///
+/// A manpage fetch that runs longer than `manpage_timeout` is abandoned and
+/// treated as no manpage, so a misbehaving `man` can't stall generation.
+///
+/// `previous` is the documentation fetched last time, if any. While the help
+/// text is unchanged the tool hasn't been upgraded, so its manpage is reused
+/// instead of running `man` again. An empty previous manpage is never reused,
+/// since it may only mean that fetch failed or timed out.
+pub fn get_help_documentation(
+    command: &str,
+    subcommands: &[String],
+    help_command: Option<&str>,
+    pty_fallback: bool,
+    manpage_timeout: Duration,
+    basic_only: bool,
+    previous: Option<&HelpDocumentation>,
+) -> Result<HelpDocumentation, QuocliError> {
+    let help_text = match help_command {
+        Some(snippet) => get_custom_help_text(command, snippet)?,
+        None => get_help_text_only(command, subcommands, pty_fallback, !basic_only)?,
+    };
+    // Windows has no man (or col), so don't spend the timeout looking
+    let manpage_text = match previous {
+        _ if cfg!(windows) || basic_only => String::new(),
+        Some(previous) if previous.help_text == help_text && !previous.manpage_text.is_empty() => {
+            tracing::debug!("Help text for {} is unchanged, reusing the cached manpage", command);
+            previous.manpage_text.clone()
+        }
+        _ => get_manpage_text(command, subcommands, manpage_timeout).unwrap_or_default(),
+    };
 
     Ok(HelpDocumentation {
         help_text,
@@ -32,40 +108,203 @@ pub fn get_help_documentation(command: &str, subcommands: &[String]) -> Result<H
     })
 }
 
+/// Find the paragraph documenting one of `flags` in help or manpage text.
+///
+/// Handles both the help layout (description after the flags on the same line)
+/// and the manpage layout (description indented on the following lines).
+pub fn option_description(text: &str, flags: &[String]) -> Option<String> {
+    let lines: Vec<&str> = text.lines().collect();
+
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        if !trimmed.starts_with('-') {
+            continue;
+        }
+        let indent = line.len() - trimmed.len();
+
+        // The flag spec runs up to the first wide gap; anything after it is description
+        let (head, rest) = match FLAG_GAP.find(trimmed) {
+            Some(m) => (&trimmed[..m.start() + 1], trimmed[m.end()..].trim()),
+            None => (trimmed.trim_end(), ""),
+        };
+        let documents_flag = head
+            .split(|c: char| c.is_whitespace() || matches!(c, ',' | '=' | '[' | '<'))
+            .any(|token| flags.iter().any(|flag| flag == token));
+        if !documents_flag {
+            continue;
+        }
+
+        let mut parts: Vec<&str> = Vec::new();
+        if !rest.is_empty() {
+            parts.push(rest);
+        }
+        for next in &lines[i + 1..] {
+            let next_trimmed = next.trim_start();
+            if next_trimmed.is_empty() || next.len() - next_trimmed.len() <= indent {
+                break;
+            }
+            parts.push(next_trimmed.trim_end());
+        }
+
+        if !parts.is_empty() {
+            return Some(parts.join(" "));
+        }
+    }
+
+    None
+}
+
+/// Number of lines at the start and end of help text scanned for a bare version number
+const VERSION_SCAN_LINES: usize = 5;
+
+/// Find a version string like `curl 8.4.0` or `version 2.43.0` in help or manpage text
+pub fn detect_version(text: &str) -> Option<String> {
+    if let Some(caps) = EXPLICIT_VERSION.captures(text) {
+        return Some(caps[1].trim_end_matches('.').to_string());
+    }
+
+    // Bare x.y.z numbers only count near the top (banner) or bottom (manpage footer)
+    let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+    let head = lines.iter().take(VERSION_SCAN_LINES);
+    let tail = lines.iter().rev().take(VERSION_SCAN_LINES);
+    head.chain(tail)
+        .find_map(|line| BARE_VERSION.captures(line))
+        .map(|caps| caps[1].trim_end_matches('.').to_string())
+}
+
+/// Find the subcommands listed under a commands heading, such as cargo's
+/// `Commands:` or git's `These are common Git commands ...:`. Entries are indented
+/// names followed by a description (`    build, b    Compile a package`); an alias
+/// after a comma is skipped.
+pub fn extract_subcommands(help_text: &str) -> Vec<String> {
+    let entry_pattern = Regex::new(r"^\s{2,}([a-z][a-z0-9_-]*)(?:,\s*[\w-]+)*(?:\s{2,}|\t)\S").unwrap();
+
+    let mut in_commands = false;
+    let mut subcommands: Vec<String> = Vec::new();
+    for line in help_text.lines() {
+        let heading = line.trim_end();
+        if !heading.is_empty() && !line.starts_with(char::is_whitespace) {
+            // Unindented lines without a colon (git's "start a working area") are
+            // groupings within the same list
+            let is_heading = heading.ends_with(':') || heading == heading.to_uppercase();
+            if is_heading {
+                let lower = heading.to_lowercase();
+                in_commands = lower.contains("command") && !lower.contains("option");
+            }
+            continue;
+        }
+        if let Some(caps) = entry_pattern.captures(line).filter(|_| in_commands) {
+            let name = caps[1].to_string();
+            if !subcommands.contains(&name) {
+                subcommands.push(name);
+            }
+        }
+    }
+    subcommands
+}
+
 /// Get help text for a command, trying various methods
 pub fn get_help_text(command: &str, subcommands: &[String]) -> Result<String, QuocliError> {
-    get_help_text_only(command, subcommands)
+    get_help_text_only(command, subcommands, false, true)
+}
+
+/// Extended help variants, in the order they are tried
+const EXTENDED_HELP_VARIANTS: &[&[&str]] = &[&["--help", "all"], &["--help=all"], &["--help-all"]];
+
+/// Check whether help text says it is abridged by pointing at this particular
+/// extended help variant (e.g. curl's `For all options use ... "--help all"`)
+fn is_truncated_help(text: &str, variant: &[&str]) -> bool {
+    text.to_lowercase().contains(&variant.join(" "))
 }
 
-/// Get help text only (no manpage fallback)
-fn get_help_text_only(command: &str, subcommands: &[String]) -> Result<String, QuocliError> {
+/// Help output shorter than this is considered thin enough to retry under a PTY
+const THIN_HELP_LEN: usize = 500;
+
+/// How long a PTY help fetch may run before the command is killed
+const PTY_HELP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Get help text only (no manpage fallback); `extended` also tries `--help all` and friends
+fn get_help_text_only(
+    command: &str,
+    subcommands: &[String],
+    pty_fallback: bool,
+    extended: bool,
+) -> Result<String, QuocliError> {
+    let result = fetch_help_text(command, subcommands, extended);
+    if !pty_fallback {
+        return result;
+    }
+
+    let current_len = result.as_ref().map(|text| text.len()).unwrap_or(0);
+    if current_len >= THIN_HELP_LEN {
+        return result;
+    }
+
+    // Last resort: some tools only print full help when stdout is a terminal
     let mut args: Vec<&str> = subcommands.iter().map(|s| s.as_str()).collect();
+    args.push("--help");
+    match try_command_pty(command, &args) {
+        Ok(output) if output.len() > 50 && output.len() > current_len => {
+            tracing::info!("Using help text fetched under a PTY for {}", command);
+            Ok(output)
+        }
+        Ok(_) => result,
+        Err(e) => {
+            tracing::warn!("PTY help fetch for {} failed: {}", command, e);
+            result
+        }
+    }
+}
+
+/// Probe the usual help flags and subcommands for help text
+fn fetch_help_text(command: &str, subcommands: &[String], extended: bool) -> Result<String, QuocliError> {
+    // Native Windows tools answer `/?`, and may print a long "invalid switch"
+    // error for --help that would otherwise pass for help text
+    if cfg!(windows) {
+        if let Some(output) = fetch_windows_switch_help(command, subcommands) {
+            return Ok(output);
+        }
+    }
+
+    let mut args: Vec<&str> = subcommands.iter().map(|s| s.as_str()).collect();
+
+    // Fetch basic help up front so we can tell whether it admits to being truncated
+    let mut basic_args = args.clone();
+    basic_args.push("--help");
+    let basic_help = try_command(command, &basic_args).ok();
 
     // Try extended help variants first (for commands like curl that have truncated default help)
-    for extended in &["--help", "all", "--help=all", "--help-all"] {
+    let variants = if extended { EXTENDED_HELP_VARIANTS } else { &[] };
+    for variant in variants {
         let mut extended_args = args.clone();
-        if *extended == "--help" {
-            extended_args.push("--help");
-            extended_args.push("all");
-        } else {
-            extended_args.push(extended);
+        extended_args.extend_from_slice(variant);
+        // A tool without the variant usually fails with an "unknown option" error,
+        // which must not stand in for its real help
+        let Some(output) = try_command_succeeded(command, &extended_args) else {
+            continue;
+        };
+        // Extended help should be substantial, unless the basic help pointed at this
+        // variant - then any distinct extended output is an improvement
+        let truncated = basic_help
+            .as_deref()
+            .is_some_and(|help| is_truncated_help(help, variant));
+        if truncated {
+            tracing::info!("Basic help for {} points at {}, preferring it", command, variant.join(" "));
         }
-        if let Ok(output) = try_command(command, &extended_args) {
-            // Extended help should be substantial
-            if !output.is_empty() && output.len() > 500 {
-                return Ok(output);
-            }
+        let substantial = output.len() > 500;
+        let replaces_truncated =
+            truncated && output.len() > 50 && basic_help.as_deref() != Some(output.as_str());
+        if substantial || replaces_truncated {
+            return Ok(output);
         }
     }
 
-    // Try --help
-    args.push("--help");
-    if let Ok(output) = try_command(command, &args) {
-        if !output.is_empty() && output.len() > 50 {
+    // Fall back to the basic --help output
+    if let Some(output) = basic_help {
+        if output.len() > 50 {
             return Ok(output);
         }
     }
-    args.pop();
 
     // Try -h
     args.push("-h");
@@ -85,11 +324,84 @@ fn get_help_text_only(command: &str, subcommands: &[String]) -> Result<String, Q
         }
     }
 
+    if cfg!(windows) {
+        if let Some(output) = fetch_powershell_help(command) {
+            return Ok(output);
+        }
+    }
+
     Err(QuocliError::NoHelpText(command.to_string()))
 }
 
+/// Windows help switches, `/?` then `-?`, for native tools. Only a successful
+/// run counts, so tools that reject the switch fall through to `--help`.
+fn fetch_windows_switch_help(command: &str, subcommands: &[String]) -> Option<String> {
+    windows_help_args(subcommands).into_iter().find_map(|args| {
+        let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+        try_command_succeeded(command, &args).filter(|output| output.len() > 50)
+    })
+}
+
+/// `Get-Help` for PowerShell cmdlets, the last resort on Windows
+fn fetch_powershell_help(command: &str) -> Option<String> {
+    let args = powershell_help_args(command);
+    let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    match try_command("powershell", &args) {
+        Ok(output) if output.len() > 50 => Some(output),
+        _ => None,
+    }
+}
+
+/// Argument lists for Windows-style help, in the order they are tried;
+/// the help switch goes after any subcommands (`net use /?`)
+fn windows_help_args(subcommands: &[String]) -> Vec<Vec<String>> {
+    ["/?", "-?"]
+        .iter()
+        .map(|switch| {
+            let mut args = subcommands.to_vec();
+            args.push(switch.to_string());
+            args
+        })
+        .collect()
+}
+
+/// `powershell` arguments that print a cmdlet's full help
+fn powershell_help_args(command: &str) -> Vec<String> {
+    vec![
+        "-NoProfile".to_string(),
+        "-NonInteractive".to_string(),
+        "-Command".to_string(),
+        format!("Get-Help {} -Full", command),
+    ]
+}
+
+/// Get help text by running a user-configured shell snippet
+fn get_custom_help_text(command: &str, snippet: &str) -> Result<String, QuocliError> {
+    let output = Command::new("sh")
+        .args(["-c", snippet])
+        .output()
+        .map_err(|_| QuocliError::CommandNotFound("sh".to_string()))?;
+
+    // Some commands output help to stderr
+    let text = if output.stdout.is_empty() {
+        String::from_utf8_lossy(&output.stderr).to_string()
+    } else {
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    if text.trim().is_empty() {
+        Err(QuocliError::NoHelpText(format!("{} (help_command: {})", command, snippet)))
+    } else {
+        Ok(text)
+    }
+}
+
 /// Get manpage text for a command
-fn get_manpage_text(command: &str, subcommands: &[String]) -> Result<String, QuocliError> {
+fn get_manpage_text(
+    command: &str,
+    subcommands: &[String],
+    timeout: Duration,
+) -> Result<String, QuocliError> {
     let man_command = if subcommands.is_empty() {
         command.to_string()
     } else {
@@ -97,12 +409,23 @@ fn get_manpage_text(command: &str, subcommands: &[String]) -> Result<String, Quo
     };
 
     // Use col -b to strip formatting control characters from man output
-    let output = Command::new("sh")
-        .args(["-c", &format!("man {} 2>/dev/null | col -b", man_command)])
-        .output()
-        .map_err(|_| QuocliError::CommandNotFound("man".to_string()))?;
+    let mut man = Command::new("sh");
+    man.args(["-c", &format!("man {} 2>/dev/null | col -b", man_command)]);
+    let stdout = match stdout_with_timeout(man, timeout)
+        .map_err(|_| QuocliError::CommandNotFound("man".to_string()))?
+    {
+        Some(stdout) => stdout,
+        None => {
+            tracing::warn!(
+                "man {} did not finish within {}s, continuing without the manpage",
+                man_command,
+                timeout.as_secs()
+            );
+            return Err(QuocliError::NoHelpText(format!("man {}", man_command)));
+        }
+    };
 
-    let text = String::from_utf8_lossy(&output.stdout).to_string();
+    let text = String::from_utf8_lossy(&stdout).to_string();
 
     if text.len() > 100 {
         Ok(text)
@@ -111,8 +434,61 @@ fn get_manpage_text(command: &str, subcommands: &[String]) -> Result<String, Quo
     }
 }
 
+/// Run a command and collect its stdout, or `None` if it outlives `timeout`
+///
+/// The command gets its own process group so that a timeout kills the whole
+/// pipeline (e.g. `man | col`), not just the shell in front of it.
+fn stdout_with_timeout(mut command: Command, timeout: Duration) -> std::io::Result<Option<Vec<u8>>> {
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = stdout.read_to_end(&mut buf);
+        let _ = tx.send(buf);
+    });
+
+    match rx.recv_timeout(timeout) {
+        Ok(buf) => {
+            let _ = child.wait();
+            Ok(Some(buf))
+        }
+        Err(_) => {
+            #[cfg(unix)]
+            // SAFETY: signalling the process group we created above
+            unsafe {
+                libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+            }
+            let _ = child.kill();
+            let _ = child.wait();
+            Ok(None)
+        }
+    }
+}
+
 /// Try to run a command and get its output
 fn try_command(command: &str, args: &[&str]) -> Result<String, QuocliError> {
+    run_command(command, args).map(|(text, _)| text)
+}
+
+/// Like [`try_command`], but only output from a run that exited with status 0
+fn try_command_succeeded(command: &str, args: &[&str]) -> Option<String> {
+    match run_command(command, args) {
+        Ok((text, true)) => Some(text),
+        _ => None,
+    }
+}
+
+/// Run a command, returning its output and whether it exited successfully
+fn run_command(command: &str, args: &[&str]) -> Result<(String, bool), QuocliError> {
     let output = Command::new(command)
         .args(args)
         .output()
@@ -125,7 +501,80 @@ fn try_command(command: &str, args: &[&str]) -> Result<String, QuocliError> {
         String::from_utf8_lossy(&output.stdout).to_string()
     };
 
-    Ok(text)
+    Ok((text, output.status.success()))
+}
+
+/// Run a command attached to a pseudo-terminal and capture what it prints
+fn try_command_pty(command: &str, args: &[&str]) -> Result<String, QuocliError> {
+    let pty_error = |e: anyhow::Error| QuocliError::Execution(format!("PTY error: {}", e));
+
+    // Wide enough that tools don't wrap their help to a narrow default
+    let pair = native_pty_system()
+        .openpty(PtySize {
+            rows: 60,
+            cols: 200,
+            pixel_width: 0,
+            pixel_height: 0,
+        })
+        .map_err(pty_error)?;
+
+    let mut builder = CommandBuilder::new(command);
+    builder.args(args);
+    // A TTY invites pagers, which would wait for input
+    builder.env("PAGER", "cat");
+    builder.env("GIT_PAGER", "cat");
+
+    let mut child = pair
+        .slave
+        .spawn_command(builder)
+        .map_err(|_| QuocliError::CommandNotFound(command.to_string()))?;
+    drop(pair.slave);
+
+    let mut reader = pair.master.try_clone_reader().map_err(pty_error)?;
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        // The read ends with EIO once the child exits; keep what was read
+        let _ = reader.read_to_end(&mut buf);
+        let _ = tx.send(buf);
+    });
+
+    let output = match rx.recv_timeout(PTY_HELP_TIMEOUT) {
+        Ok(buf) => buf,
+        Err(_) => {
+            tracing::warn!("PTY help fetch for {} timed out", command);
+            let _ = child.kill();
+            rx.recv_timeout(Duration::from_secs(1)).unwrap_or_default()
+        }
+    };
+    let _ = child.wait();
+
+    Ok(clean_terminal_output(&String::from_utf8_lossy(&output)))
+}
+
+/// Strip ANSI escapes, overstrike sequences and carriage returns from terminal output
+fn clean_terminal_output(text: &str) -> String {
+    let escapes = Regex::new(r"\x1b\[[0-?]*[ -/]*[@-~]|\x1b\][^\x07\x1b]*(?:\x07|\x1b\\)|\x1b[@-Z\\-_]").unwrap();
+    let overstrike = Regex::new(r".\x08").unwrap();
+
+    let text = escapes.replace_all(text, "");
+    let text = overstrike.replace_all(&text, "");
+    text.replace("\r\n", "\n").replace('\r', "")
+}
+
+/// Whether `command` resolves to a file, either as a path or via `PATH`
+pub fn command_exists(command: &str) -> bool {
+    let path = std::path::Path::new(command);
+    if path.components().count() > 1 {
+        return path.is_file();
+    }
+    let Some(search_path) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&search_path).any(|dir| {
+        let candidate = dir.join(command);
+        candidate.is_file() || (cfg!(windows) && candidate.with_extension("exe").is_file())
+    })
 }
 
 /// Hash help text using SHA-256
@@ -139,6 +588,86 @@ pub fn hash_help_text(help_text: &str) -> String {
 mod tests {
     use super::*;
 
+    #[cfg(unix)]
+    #[test]
+    fn test_command_exists() {
+        assert!(command_exists("sh"));
+        assert!(command_exists("/bin/sh"));
+        assert!(!command_exists("quocli-no-such-command"));
+        assert!(!command_exists("/nonexistent/sh"));
+    }
+
+    #[test]
+    fn test_windows_help_args_order() {
+        assert_eq!(windows_help_args(&[]), vec![vec!["/?"], vec!["-?"]]);
+
+        let subcommands = vec!["use".to_string()];
+        assert_eq!(
+            windows_help_args(&subcommands),
+            vec![vec!["use", "/?"], vec!["use", "-?"]]
+        );
+    }
+
+    #[test]
+    fn test_powershell_help_args() {
+        let args = powershell_help_args("Get-ChildItem");
+        assert_eq!(args.last().map(String::as_str), Some("Get-Help Get-ChildItem -Full"));
+        assert!(args.contains(&"-NoProfile".to_string()));
+    }
+
+    #[test]
+    fn test_stdout_with_timeout_collects_output() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo manpage"]);
+        let stdout = stdout_with_timeout(command, Duration::from_secs(5)).unwrap();
+        assert_eq!(stdout.as_deref(), Some(&b"manpage\n"[..]));
+    }
+
+    #[test]
+    fn test_stdout_with_timeout_gives_up_on_hang() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "sleep 30 | cat"]);
+        let started = std::time::Instant::now();
+        let stdout = stdout_with_timeout(command, Duration::from_millis(200)).unwrap();
+        assert!(stdout.is_none());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_option_description_prefers_richer_manpage() {
+        let docs = HelpDocumentation {
+            help_text: "Usage: ls [OPTION]...\n  -a, --all                  do not ignore entries starting with .\n  -l                         use a long listing format\n".to_string(),
+            manpage_text: "OPTIONS\n       -a, --all\n              do not ignore entries starting with .,\n              including the . and .. directory entries\n\n       -l     use a long listing format\n".to_string(),
+        };
+        let flags = |f: &[&str]| f.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            docs.option_description(&flags(&["-a", "--all"])).as_deref(),
+            Some("do not ignore entries starting with ., including the . and .. directory entries")
+        );
+        assert_eq!(
+            docs.option_description(&flags(&["-l"])).as_deref(),
+            Some("use a long listing format")
+        );
+        assert_eq!(docs.option_description(&flags(&["--missing"])), None);
+        assert_eq!(
+            option_description("  --color[=WHEN]   colorize the output\n", &flags(&["--color"])).as_deref(),
+            Some("colorize the output")
+        );
+    }
+
+    #[test]
+    fn test_clean_terminal_output() {
+        let raw = "\x1b[1mUsage:\x1b[0m tool [opts]\r\n  -\x08-v\x08v  verbose\r\n\x1b]0;title\x07done\r";
+        assert_eq!(clean_terminal_output(raw), "Usage: tool [opts]\n  -v  verbose\ndone");
+    }
+
+    #[test]
+    fn test_try_command_pty_sees_a_terminal() {
+        let output = try_command_pty("sh", &["-c", "if [ -t 1 ]; then echo tty; else echo pipe; fi"]).unwrap();
+        assert_eq!(output.trim(), "tty");
+    }
+
     #[test]
     fn test_hash_help_text() {
         let hash1 = hash_help_text("hello world");
@@ -149,4 +678,202 @@ mod tests {
         assert_ne!(hash1, hash3);
         assert_eq!(hash1.len(), 64); // SHA-256 produces 64 hex chars
     }
+
+    #[test]
+    fn test_extract_subcommands_git_and_cargo() {
+        let git = "usage: git [-v | --version] [-h | --help] <command> [<args>]
+
+These are common Git commands used in various situations:
+
+start a working area (see also: git help tutorial)
+   clone     Clone a repository into a new directory
+   init      Create an empty Git repository or reinitialize an existing one
+
+work on the current change (see also: git help everyday)
+   add       Add file contents to the index
+   commit    Record changes to the repository
+
+'git help -a' and 'git help -g' list available subcommands and some
+concept guides.
+";
+        assert_eq!(extract_subcommands(git), vec!["clone", "init", "add", "commit"]);
+
+        let cargo = "Rust's package manager
+
+Usage: cargo [+toolchain] [OPTIONS] [COMMAND]
+
+Options:
+  -V, --version            Print version info and exit
+  -v, --verbose...         Use verbose output
+
+Commands:
+    build, b    Compile the current package
+    check, c    Analyze the current package and report errors
+    ...         See all commands with --list
+";
+        assert_eq!(extract_subcommands(cargo), vec!["build", "check"]);
+    }
+
+    #[test]
+    fn test_extract_subcommands_ignores_option_lists() {
+        let ls = "Usage: ls [OPTION]... [FILE]...
+
+Mandatory arguments to long options are mandatory for short options too:
+  -a, --all                  do not ignore entries starting with .
+
+Command options:
+  color    colorize the output
+";
+        assert!(extract_subcommands(ls).is_empty());
+    }
+
+    #[test]
+    fn test_detect_version_explicit() {
+        assert_eq!(
+            detect_version("Usage: tool [opts]\n\nTool version 2.43.0 (built 2024)").as_deref(),
+            Some("2.43.0")
+        );
+        assert_eq!(detect_version("Version: v1.2.3-beta").as_deref(), Some("1.2.3-beta"));
+    }
+
+    #[test]
+    fn test_detect_version_banner_and_footer() {
+        let banner = "curl 8.4.0 (x86_64-pc-linux-gnu) libcurl/8.4.0\nUsage: curl [options...] <url>";
+        assert_eq!(detect_version(banner).as_deref(), Some("8.4.0"));
+
+        let mut manpage = String::from("LS(1)   User Commands   LS(1)\n\nNAME\n    ls - list\n");
+        for i in 0..20 {
+            manpage.push_str(&format!("    option line {}\n", i));
+        }
+        manpage.push_str("GNU coreutils 9.4    April 2024    LS(1)\n");
+        assert_eq!(detect_version(&manpage).as_deref(), Some("9.4"));
+    }
+
+    #[test]
+    fn test_detect_version_absent() {
+        assert_eq!(detect_version("Usage: ls [OPTION]... [FILE]...\n  -a  show all"), None);
+        // Bare numbers deep inside the text are ignored
+        let mut text = String::from("Usage: tool\n");
+        for i in 0..20 {
+            text.push_str(&format!("  --opt{} desc\n", i));
+        }
+        text.insert_str(text.len() / 2, "  --ip  e.g. 10.0.0.1\n");
+        for i in 0..10 {
+            text.push_str(&format!("  --more{} desc\n", i));
+        }
+        assert_eq!(detect_version(&text), None);
+    }
+
+    #[test]
+    fn test_is_truncated_help_curl_style() {
+        let help = "Usage: curl [options...] <url>\n \
+            -d, --data <data>   HTTP POST data\n\n\
+            This is not the full help, this menu is stripped into categories.\n\
+            Use \"--help category\" to get an overview of all categories.\n\
+            For all options use the manual or \"--help all\".";
+        assert!(is_truncated_help(help, &["--help", "all"]));
+        assert!(!is_truncated_help(help, &["--help-all"]));
+    }
+
+    #[test]
+    fn test_is_truncated_help_hint_variants() {
+        assert!(is_truncated_help("Run 'tool --help-all' to see every flag", &["--help-all"]));
+        assert!(is_truncated_help("See --HELP=ALL for more", &["--help=all"]));
+        assert!(!is_truncated_help("See --help=all for more", &["--help", "all"]));
+    }
+
+    #[test]
+    fn test_is_truncated_help_ignores_generic_phrases() {
+        let help = "Usage: ls [OPTION]... [FILE]...\n  -a, --all   do not ignore entries starting with .\n\
+            For more options and the full list of options, see the manual.";
+        for variant in EXTENDED_HELP_VARIANTS {
+            assert!(!is_truncated_help(help, variant));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_try_command_succeeded_rejects_failures() {
+        assert_eq!(try_command_succeeded("sh", &["-c", "echo help"]).as_deref(), Some("help\n"));
+        assert!(try_command_succeeded("sh", &["-c", "echo 'unknown option --help=all' >&2; exit 2"]).is_none());
+        assert!(try_command("sh", &["-c", "echo usage >&2; exit 1"]).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fetch_help_text_basic_only_skips_extended_help() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let script = dir.path().join("tool");
+        std::fs::write(
+            &script,
+            "#!/bin/sh\nif [ \"$2\" = all ]; then printf '%0600d' 0; else echo 'Usage: tool [--verbose] FILE, prints the short help text only'; fi\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let tool = script.to_str().unwrap();
+
+        assert_eq!(fetch_help_text(tool, &[], true).unwrap().len(), 600);
+        assert!(fetch_help_text(tool, &[], false).unwrap().starts_with("Usage: tool"));
+    }
+
+    #[test]
+    fn test_custom_help_text_runs_snippet() {
+        let text = get_custom_help_text("mytool", "echo 'Usage: mytool [options]' | tr a-z A-Z").unwrap();
+        assert_eq!(text.trim(), "USAGE: MYTOOL [OPTIONS]");
+    }
+
+    #[test]
+    fn test_custom_help_text_falls_back_to_stderr() {
+        let text = get_custom_help_text("mytool", "echo 'help on stderr' >&2").unwrap();
+        assert_eq!(text.trim(), "help on stderr");
+    }
+
+    #[test]
+    fn test_documentation_sources() {
+        let mut docs = HelpDocumentation {
+            help_text: "Usage: mytool\n  -v  verbose".to_string(),
+            manpage_text: String::new(),
+        };
+        assert_eq!(docs.sources(), "help text (2 lines), no manpage");
+        docs.manpage_text = "MYTOOL(1)\n\nNAME\n  mytool".to_string();
+        assert_eq!(docs.sources(), "help text (2 lines), manpage (4 lines)");
+    }
+
+    #[test]
+    fn test_unchanged_help_text_reuses_previous_manpage() {
+        let previous = HelpDocumentation {
+            help_text: "Usage: mytool\n".to_string(),
+            manpage_text: "MYTOOL(1)".to_string(),
+        };
+        let fetch = |previous| {
+            get_help_documentation(
+                "mytool",
+                &[],
+                Some("echo 'Usage: mytool'"),
+                false,
+                Duration::from_secs(1),
+                false,
+                Some(&previous),
+            )
+            .unwrap()
+        };
+
+        assert_eq!(fetch(previous.clone()), previous);
+
+        // New help text means a new release, so the stale manpage is dropped
+        // (and mytool has no real manpage to fetch)
+        let outdated = HelpDocumentation {
+            help_text: "Usage: mytool [old]".to_string(),
+            ..previous
+        };
+        assert_eq!(fetch(outdated).manpage_text, "");
+    }
+
+    #[test]
+    fn test_custom_help_text_empty_is_error() {
+        let result = get_custom_help_text("mytool", "true");
+        assert!(matches!(result, Err(QuocliError::NoHelpText(_))));
+    }
 }
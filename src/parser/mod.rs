@@ -1,7 +1,12 @@
+mod doc;
 mod help;
 mod spec;
 
-pub use help::{get_help_documentation, get_help_text, hash_help_text, HelpDocumentation};
+pub use doc::{render_doc, render_explanation};
+pub use help::{
+    command_exists, detect_version, extract_subcommands, get_help_documentation, get_help_text, hash_help_text,
+    HelpDocumentation,
+};
 pub use spec::{
     ArgumentType, CommandOption, CommandSpec, DangerLevel, OptionLevel, PositionalArg,
 };
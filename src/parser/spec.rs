@@ -1,4 +1,5 @@
 use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
 
 /// Custom deserializer for Option<String> that handles LLM returning boolean/number instead of null
 fn deserialize_optional_string<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
@@ -97,6 +98,15 @@ where
     deserializer.deserialize_any(OptionalStringVisitor)
 }
 
+/// Custom deserializer for optional numbers: accepts a number, a numeric string
+/// or null; anything unparsable is dropped rather than failing the whole spec
+fn deserialize_optional_number<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(deserialize_optional_string(deserializer)?.and_then(|s| s.trim().parse().ok()))
+}
+
 /// Custom deserializer for String that handles LLM returning boolean/number instead of string
 fn deserialize_flexible_string<'de, D>(deserializer: D) -> Result<String, D::Error>
 where
@@ -173,6 +183,44 @@ pub struct CommandSpec {
     /// Default is false (standard: `command [flags] <positionals>`)
     #[serde(default)]
     pub positionals_first: bool,
+    /// Tool version found in the help text when the spec was generated
+    #[serde(default)]
+    pub tool_version: Option<String>,
+    /// Generated from plain `--help` only, without the manpage or extended help
+    #[serde(default)]
+    pub basic_only: bool,
+}
+
+impl CommandSpec {
+    /// Apply user sensitivity overrides, keyed by field id
+    /// (primary flag for options, `_pos_<name>` for positionals)
+    pub fn apply_sensitivity_overrides(&mut self, overrides: &HashMap<String, bool>) {
+        for opt in &mut self.options {
+            if let Some(&sensitive) = overrides.get(opt.primary_flag()) {
+                opt.sensitive = sensitive;
+            }
+        }
+        for arg in &mut self.positional_args {
+            if let Some(&sensitive) = overrides.get(&format!("_pos_{}", arg.name)) {
+                arg.sensitive = sensitive;
+            }
+        }
+    }
+
+    /// Field ids (as used for cached values) of every sensitive option and positional
+    pub fn sensitive_field_ids(&self) -> Vec<String> {
+        let options = self
+            .options
+            .iter()
+            .filter(|opt| opt.sensitive)
+            .map(|opt| opt.primary_flag().to_string());
+        let positionals = self
+            .positional_args
+            .iter()
+            .filter(|arg| arg.sensitive)
+            .map(|arg| format!("_pos_{}", arg.name));
+        options.chain(positionals).collect()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -199,6 +247,21 @@ pub struct CommandOption {
     pub enum_values: Vec<String>,
     #[serde(default)]
     pub level: OptionLevel,
+    /// Marked deprecated in the help text; hidden from the Basic tab
+    #[serde(default)]
+    pub deprecated: bool,
+    /// Representative value (e.g. `POST` for `--request`), shown as a placeholder
+    #[serde(default, deserialize_with = "deserialize_optional_string")]
+    pub example: Option<String>,
+    /// Smallest accepted value of an int/float option, when documented
+    #[serde(default, deserialize_with = "deserialize_optional_number")]
+    pub min: Option<f64>,
+    /// Largest accepted value of an int/float option, when documented
+    #[serde(default, deserialize_with = "deserialize_optional_number")]
+    pub max: Option<f64>,
+    /// Regex the whole value of a string option must match, when documented
+    #[serde(default, deserialize_with = "deserialize_optional_string")]
+    pub pattern: Option<String>,
 }
 
 impl CommandOption {
@@ -234,9 +297,10 @@ pub struct PositionalArg {
     pub default: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub enum ArgumentType {
     Bool,
+    #[default]
     String,
     Int,
     Float,
@@ -268,27 +332,33 @@ impl serde::Serialize for ArgumentType {
     where
         S: serde::Serializer,
     {
-        let s = match self {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl ArgumentType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
             ArgumentType::Bool => "bool",
             ArgumentType::String => "string",
             ArgumentType::Int => "int",
             ArgumentType::Float => "float",
             ArgumentType::Path => "path",
             ArgumentType::Enum => "enum",
-        };
-        serializer.serialize_str(s)
+        }
     }
 }
 
-impl Default for ArgumentType {
-    fn default() -> Self {
-        ArgumentType::String
+impl std::fmt::Display for ArgumentType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum DangerLevel {
+    #[default]
     Low,
     Medium,
     High,
@@ -296,27 +366,16 @@ pub enum DangerLevel {
 }
 
 /// Level indicating how commonly used an option is
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum OptionLevel {
     /// Common options shown in basic --help
+    #[default]
     Basic,
     /// Advanced options from --help all or manpage
     Advanced,
 }
 
-impl Default for OptionLevel {
-    fn default() -> Self {
-        OptionLevel::Basic
-    }
-}
-
-impl Default for DangerLevel {
-    fn default() -> Self {
-        DangerLevel::Low
-    }
-}
-
 impl std::fmt::Display for DangerLevel {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -332,6 +391,33 @@ impl std::fmt::Display for DangerLevel {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_apply_sensitivity_overrides() {
+        let json = r#"{
+            "command": "curl", "version_hash": "h", "description": "",
+            "options": [
+                {"flags": ["-o", "--output"], "description": "", "argument_type": "path", "sensitive": true},
+                {"flags": ["--token"], "description": "", "argument_type": "string"}
+            ],
+            "positional_args": [{"name": "url", "description": ""}],
+            "subcommands": [], "danger_level": "low", "examples": []
+        }"#;
+        let mut spec: CommandSpec = serde_json::from_str(json).unwrap();
+
+        let overrides = HashMap::from([
+            ("--output".to_string(), false),
+            ("--token".to_string(), true),
+            ("_pos_url".to_string(), true),
+            ("--missing".to_string(), true),
+        ]);
+        spec.apply_sensitivity_overrides(&overrides);
+
+        assert!(!spec.options[0].sensitive);
+        assert!(spec.options[1].sensitive);
+        assert!(spec.positional_args[0].sensitive);
+        assert_eq!(spec.sensitive_field_ids(), vec!["--token", "_pos_url"]);
+    }
+
     #[test]
     fn test_argument_type_deserialize_bool() {
         let cases = ["\"bool\"", "\"boolean\"", "\"flag\""];
@@ -434,10 +520,9 @@ mod tests {
         assert_eq!(level, OptionLevel::Basic);
     }
 
-    #[test]
-    fn test_command_option_primary_flag() {
-        let opt = CommandOption {
-            flags: vec!["--verbose".to_string(), "-v".to_string()],
+    fn create_test_option(flags: &[&str]) -> CommandOption {
+        CommandOption {
+            flags: flags.iter().map(|f| f.to_string()).collect(),
             description: "test".to_string(),
             argument_type: ArgumentType::Bool,
             argument_name: None,
@@ -449,7 +534,17 @@ mod tests {
             default: None,
             enum_values: vec![],
             level: OptionLevel::Basic,
-        };
+            deprecated: false,
+            example: None,
+            min: None,
+            max: None,
+            pattern: None,
+        }
+    }
+
+    #[test]
+    fn test_command_option_primary_flag() {
+        let opt = create_test_option(&["--verbose", "-v"]);
 
         // Primary flag should be the longest
         assert_eq!(opt.primary_flag(), "--verbose");
@@ -457,40 +552,14 @@ mod tests {
 
     #[test]
     fn test_command_option_short_flag() {
-        let opt = CommandOption {
-            flags: vec!["--verbose".to_string(), "-v".to_string()],
-            description: "test".to_string(),
-            argument_type: ArgumentType::Bool,
-            argument_name: None,
-            required: false,
-            sensitive: false,
-            repeatable: false,
-            conflicts_with: vec![],
-            requires: vec![],
-            default: None,
-            enum_values: vec![],
-            level: OptionLevel::Basic,
-        };
+        let opt = create_test_option(&["--verbose", "-v"]);
 
         assert_eq!(opt.short_flag(), Some("-v"));
     }
 
     #[test]
     fn test_command_option_no_short_flag() {
-        let opt = CommandOption {
-            flags: vec!["--verbose".to_string()],
-            description: "test".to_string(),
-            argument_type: ArgumentType::Bool,
-            argument_name: None,
-            required: false,
-            sensitive: false,
-            repeatable: false,
-            conflicts_with: vec![],
-            requires: vec![],
-            default: None,
-            enum_values: vec![],
-            level: OptionLevel::Basic,
-        };
+        let opt = create_test_option(&["--verbose"]);
 
         assert_eq!(opt.short_flag(), None);
     }
@@ -714,6 +783,28 @@ mod tests {
         assert_eq!(opt.requires, vec!["--output"]);
     }
 
+    #[test]
+    fn test_command_option_with_range_and_pattern() {
+        let json = r#"{
+            "flags": ["-j", "--jobs"],
+            "description": "Parallel jobs",
+            "argument_type": "int",
+            "min": "1",
+            "max": 64,
+            "pattern": null
+        }"#;
+
+        let opt: CommandOption = serde_json::from_str(json).unwrap();
+        assert_eq!(opt.min, Some(1.0));
+        assert_eq!(opt.max, Some(64.0));
+        assert_eq!(opt.pattern, None);
+
+        // Specs cached before these fields existed have no constraints
+        let opt: CommandOption =
+            serde_json::from_str(r#"{"flags": ["--jobs"], "description": "", "argument_type": "int"}"#).unwrap();
+        assert!(opt.min.is_none() && opt.max.is_none() && opt.pattern.is_none());
+    }
+
     #[test]
     fn test_positional_arg_with_default() {
         let json = r#"{
@@ -735,24 +826,17 @@ mod tests {
             version_hash: "hash".to_string(),
             description: "Test command".to_string(),
             options: vec![CommandOption {
-                flags: vec!["--flag".to_string()],
-                description: "A flag".to_string(),
-                argument_type: ArgumentType::Bool,
-                argument_name: None,
-                required: false,
-                sensitive: false,
-                repeatable: false,
-                conflicts_with: vec![],
-                requires: vec![],
-                default: None,
-                enum_values: vec![],
-                level: OptionLevel::Basic,
+                argument_type: ArgumentType::String,
+                example: Some("POST".to_string()),
+                ..create_test_option(&["--flag"])
             }],
             positional_args: vec![],
             subcommands: vec!["sub1".to_string()],
             danger_level: DangerLevel::Medium,
             examples: vec!["test --flag".to_string()],
             positionals_first: true,
+            tool_version: None,
+            basic_only: false,
         };
 
         let serialized = serde_json::to_string(&spec).unwrap();
@@ -762,5 +846,6 @@ mod tests {
         assert_eq!(spec.options.len(), deserialized.options.len());
         assert_eq!(spec.danger_level, deserialized.danger_level);
         assert_eq!(spec.positionals_first, deserialized.positionals_first);
+        assert_eq!(deserialized.options[0].example.as_deref(), Some("POST"));
     }
 }
@@ -3,6 +3,16 @@ use regex::Regex;
 use std::collections::HashMap;
 use std::env;
 
+/// How many of `patterns` are whole `_`-separated tokens of an env var name,
+/// so `key` counts for `API_KEY` but not for `MONKEY`
+fn token_hits(name: &str, patterns: &[&str]) -> usize {
+    let tokens: Vec<String> = name.split('_').map(|t| t.to_lowercase()).collect();
+    patterns
+        .iter()
+        .filter(|p| tokens.iter().any(|t| t.eq_ignore_ascii_case(p)))
+        .count()
+}
+
 /// Scan environment for variables that might match command arguments
 pub fn scan_matching_env_vars(patterns: &[&str]) -> HashMap<String, String> {
     let mut matches = HashMap::new();
@@ -18,6 +28,78 @@ pub fn scan_matching_env_vars(patterns: &[&str]) -> HashMap<String, String> {
     matches
 }
 
+/// Scan an environment snapshot for variables with one of `patterns` as a whole name token
+pub fn scan_env_snapshot(vars: &HashMap<String, String>, patterns: &[&str]) -> HashMap<String, String> {
+    vars.iter()
+        .filter(|(key, _)| token_hits(key, patterns) > 0)
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
+
+/// Words too generic to be useful when matching env var names against a flag
+const FLAG_STOP_WORDS: &[&str] = &[
+    "with", "without", "enable", "disable", "use", "set", "show", "all", "max", "min", "file",
+];
+
+/// Words that indicate a credential-style flag, which commonly maps to `*_TOKEN`/`*_KEY` vars
+const CREDENTIAL_WORDS: &[&str] = &["key", "token", "secret", "password", "pass", "auth", "apikey"];
+
+/// Name words of variables that hold secrets, offered for fields marked sensitive
+const SECRET_ENV_WORDS: &[&str] = &["token", "key", "password", "secret"];
+
+/// Derive env var name patterns from a flag or positional name
+/// (e.g. `--api-key` -> ["api", "key"])
+pub fn flag_env_patterns(flag: &str) -> Vec<String> {
+    let name = flag.trim_start_matches("_pos_").trim_start_matches('-');
+    name.split(['-', '_'])
+        .map(|w| w.to_lowercase())
+        .filter(|w| w.len() >= 3 && !FLAG_STOP_WORDS.contains(&w.as_str()))
+        .collect()
+}
+
+/// Credential words a variable may match on instead of the flag's own words:
+/// `*_KEY`/`*_TOKEN` for credential flags (`--api-key` takes `GITHUB_TOKEN`),
+/// and any secret-holding name for a field marked sensitive
+pub fn credential_env_patterns(patterns: &[String], sensitive: bool) -> Vec<String> {
+    let words: &[&str] = if sensitive {
+        SECRET_ENV_WORDS
+    } else if patterns.iter().any(|p| CREDENTIAL_WORDS.contains(&p.as_str())) {
+        &["key", "token"]
+    } else {
+        return Vec::new();
+    };
+    words
+        .iter()
+        .filter(|word| !patterns.iter().any(|p| p == *word))
+        .map(|word| word.to_string())
+        .collect()
+}
+
+/// Find the variable in an environment snapshot that best matches the given patterns.
+/// A variable matches on `patterns` or `credentials`, but any hit on `patterns`
+/// ranks above credential hits alone. More hits win; ties go to the shorter,
+/// then alphabetically first name.
+pub fn best_matching_env_var(
+    vars: &HashMap<String, String>,
+    patterns: &[&str],
+    credentials: &[&str],
+) -> Option<String> {
+    let all: Vec<&str> = patterns.iter().chain(credentials).copied().collect();
+    scan_env_snapshot(vars, &all)
+        .into_keys()
+        .map(|key| {
+            let hits = (token_hits(&key, patterns), token_hits(&key, credentials));
+            (key, hits)
+        })
+        .min_by(|(a, a_hits), (b, b_hits)| {
+            b_hits
+                .cmp(a_hits)
+                .then(a.len().cmp(&b.len()))
+                .then(a.cmp(b))
+        })
+        .map(|(key, _)| key)
+}
+
 /// Get all environment variables
 pub fn get_all_env_vars() -> HashMap<String, String> {
     env::vars().collect()
@@ -86,11 +168,13 @@ pub fn resolve_and_convert(value: &str, target_type: &ArgumentType) -> String {
     convert_env_value(&resolved, target_type)
 }
 
-/// Get environment variable suggestions based on a prefix
-pub fn get_env_suggestions(prefix: &str) -> Vec<(String, String)> {
+/// Get environment variable suggestions from a snapshot based on a prefix
+pub fn get_env_suggestions(vars: &HashMap<String, String>, prefix: &str) -> Vec<(String, String)> {
     let prefix_lower = prefix.to_lowercase();
-    let mut suggestions: Vec<(String, String)> = env::vars()
+    let mut suggestions: Vec<(String, String)> = vars
+        .iter()
         .filter(|(key, _)| key.to_lowercase().starts_with(&prefix_lower))
+        .map(|(key, value)| (key.clone(), value.clone()))
         .collect();
 
     // Sort by key name
@@ -104,11 +188,12 @@ pub fn get_env_suggestions(prefix: &str) -> Vec<(String, String)> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_env::EnvGuard;
 
     #[test]
     fn test_resolve_env_vars() {
-        // Set test env var
-        env::set_var("TEST_VAR", "test_value");
+        let mut env = EnvGuard::lock();
+        env.set("TEST_VAR", "test_value");
 
         assert_eq!(resolve_env_vars("$TEST_VAR"), "test_value");
         assert_eq!(resolve_env_vars("${TEST_VAR}"), "test_value");
@@ -120,8 +205,6 @@ mod tests {
 
         // Unknown var should be preserved
         assert_eq!(resolve_env_vars("$UNKNOWN_VAR_123"), "$UNKNOWN_VAR_123");
-
-        env::remove_var("TEST_VAR");
     }
 
     #[test]
@@ -150,6 +233,82 @@ mod tests {
         assert_eq!(convert_env_value("not a number", &ArgumentType::Int), "not a number");
     }
 
+    #[test]
+    fn test_flag_env_patterns() {
+        assert_eq!(flag_env_patterns("--api-key"), vec!["api", "key"]);
+        assert_eq!(flag_env_patterns("--output"), vec!["output"]);
+        assert_eq!(flag_env_patterns("_pos_source_dir"), vec!["source", "dir"]);
+        // Short and stop-word-only flags yield nothing useful
+        assert!(flag_env_patterns("-v").is_empty());
+        assert!(flag_env_patterns("--with-file").is_empty());
+    }
+
+    #[test]
+    fn test_credential_env_patterns() {
+        assert_eq!(credential_env_patterns(&flag_env_patterns("--api-key"), false), vec!["token"]);
+        assert_eq!(credential_env_patterns(&flag_env_patterns("--db-password"), false), vec!["key", "token"]);
+        assert!(credential_env_patterns(&flag_env_patterns("--output"), false).is_empty());
+        // A sensitive field takes any secret-holding name, whatever its flag says
+        assert_eq!(
+            credential_env_patterns(&flag_env_patterns("--login"), true),
+            vec!["token", "key", "password", "secret"]
+        );
+    }
+
+    fn vars(names: &[&str]) -> HashMap<String, String> {
+        names.iter().map(|name| (name.to_string(), "value".to_string())).collect()
+    }
+
+    #[test]
+    fn test_best_matching_env_var_prefers_more_hits() {
+        let vars = vars(&["ZORPLE_TOKEN", "ZORPLE", "PATH"]);
+        let best = best_matching_env_var(&vars, &["zorple", "token"], &[]);
+        assert_eq!(best.as_deref(), Some("ZORPLE_TOKEN"));
+    }
+
+    #[test]
+    fn test_best_matching_env_var_matches_whole_tokens() {
+        let vars = vars(&["MONKEY", "RAPID_MODE", "TOKENIZER_PATH"]);
+        assert!(best_matching_env_var(&vars, &["api", "key", "token"], &[]).is_none());
+        let vars = self::vars(&["MONKEY", "OPENAI_API_KEY"]);
+        assert_eq!(best_matching_env_var(&vars, &["api", "key"], &[]).as_deref(), Some("OPENAI_API_KEY"));
+    }
+
+    #[test]
+    fn test_best_matching_env_var_credential_words() {
+        // A credential flag is offered a token named after something else
+        let vars = vars(&["GITHUB_TOKEN", "HOME"]);
+        assert_eq!(best_matching_env_var(&vars, &["api", "key"], &["token"]).as_deref(), Some("GITHUB_TOKEN"));
+
+        // ...but a hit on the flag's own words ranks above credential words alone
+        let vars = self::vars(&["GITHUB_TOKEN", "AWS_SECRET_KEY_TOKEN", "SERVICE_URL"]);
+        let best = best_matching_env_var(&vars, &["service", "password"], &["key", "token"]);
+        assert_eq!(best.as_deref(), Some("SERVICE_URL"));
+
+        let vars = self::vars(&["SERVICE_API_KEY", "SERVICE_URL", "GITHUB_TOKEN"]);
+        let best = best_matching_env_var(&vars, &["service", "password"], &["key", "token"]);
+        assert_eq!(best.as_deref(), Some("SERVICE_API_KEY"));
+    }
+
+    #[test]
+    fn test_best_matching_env_var_no_patterns() {
+        assert!(best_matching_env_var(&vars(&["HOME"]), &[], &[]).is_none());
+    }
+
+    #[test]
+    fn test_scan_env_snapshot() {
+        let vars = vars(&["MONKEY", "API_KEY", "PATH"]);
+        let found = scan_env_snapshot(&vars, &["key"]);
+        assert_eq!(found.keys().collect::<Vec<_>>(), vec!["API_KEY"]);
+    }
+
+    #[test]
+    fn test_get_env_suggestions() {
+        let vars = vars(&["HOME", "HOSTNAME", "PATH"]);
+        let names: Vec<String> = get_env_suggestions(&vars, "ho").into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["HOME", "HOSTNAME"]);
+    }
+
     #[test]
     fn test_convert_env_value_float() {
         assert_eq!(convert_env_value("3.14", &ArgumentType::Float), "3.14");
@@ -3,13 +3,24 @@ use anyhow::Result;
 use std::env;
 use std::fs::OpenOptions;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Export executed command to shell history
 pub fn export_to_history(config: &ShellConfig, command_line: &str) -> Result<()> {
     let shell_type = detect_shell(&config.shell_type);
     let history_path = get_history_path(&config.history_file, &shell_type)?;
 
+    // Nushell's SQLite history can't be appended to like a text file
+    if shell_type == "nu"
+        && !history_path.exists()
+        && history_path.with_file_name("history.sqlite3").exists()
+    {
+        tracing::warn!(
+            "Nushell uses SQLite history, which quocli can't write to; set shell.history_file to a history.txt to export"
+        );
+        return Ok(());
+    }
+
     tracing::info!("Exporting to history: {:?}", history_path);
 
     // Open history file in append mode
@@ -18,37 +29,40 @@ pub fn export_to_history(config: &ShellConfig, command_line: &str) -> Result<()>
         .append(true)
         .open(&history_path)?;
 
-    // Format based on shell type
-    let entry = match shell_type.as_str() {
-        "zsh" => {
-            // Zsh uses extended history format
-            let timestamp = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)?
-                .as_secs();
-            format!(": {}:0;{}\n", timestamp, command_line)
-        }
-        "fish" => {
-            // Fish uses a different format
-            let timestamp = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)?
-                .as_secs();
-            format!("- cmd: {}\n  when: {}\n", command_line, timestamp)
-        }
-        _ => {
-            // Bash and others use simple format
-            format!("{}\n", command_line)
-        }
-    };
-
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
+    let marker = config.history_marker && (shell_type != "zsh" || config.zsh_interactive_comments);
+    let entry = format_history_entry(&shell_type, command_line, timestamp, marker);
     file.write_all(entry.as_bytes())?;
 
-    // Add comment marker for traceability
-    let marker = format!("# via quocli\n");
-    file.write_all(marker.as_bytes())?;
-
     Ok(())
 }
 
+/// Trailing comment tagging entries written by quocli
+const HISTORY_MARKER: &str = " # via quocli";
+
+/// Format one history record for the shell. The marker is a trailing comment
+/// inside the record itself, since a separate `# via quocli` line would be
+/// read as a command (or a malformed entry) by history tooling.
+fn format_history_entry(shell_type: &str, command_line: &str, timestamp: u64, marker: bool) -> String {
+    let command_line = if marker {
+        format!("{}{}", command_line, HISTORY_MARKER)
+    } else {
+        command_line.to_string()
+    };
+
+    match shell_type {
+        // Zsh uses extended history format
+        "zsh" => format!(": {}:0;{}\n", timestamp, command_line),
+        // Fish uses a YAML-like block per entry
+        "fish" => format!("- cmd: {}\n  when: {}\n", command_line, timestamp),
+        // Bash, PowerShell (PSReadLine), nushell's history.txt and others use
+        // one command per line
+        _ => format!("{}\n", command_line),
+    }
+}
+
 /// Detect the current shell type
 fn detect_shell(configured: &str) -> String {
     if configured != "auto" {
@@ -56,26 +70,55 @@ fn detect_shell(configured: &str) -> String {
     }
 
     // Try to detect from SHELL environment variable
-    if let Ok(shell) = env::var("SHELL") {
-        if shell.contains("zsh") {
-            return "zsh".to_string();
-        } else if shell.contains("fish") {
-            return "fish".to_string();
-        } else if shell.contains("bash") {
-            return "bash".to_string();
-        }
+    if let Some(shell) = env::var("SHELL").ok().and_then(|path| shell_from_path(&path)) {
+        return shell.to_string();
+    }
+
+    // Windows has no SHELL; PowerShell is the shell that keeps a history file
+    if cfg!(windows) {
+        return "powershell".to_string();
     }
 
     // Default to bash
     "bash".to_string()
 }
 
+/// Shell type for a shell executable path like `/usr/bin/zsh` or `pwsh.exe`
+fn shell_from_path(path: &str) -> Option<&'static str> {
+    let name = Path::new(path).file_name()?.to_str()?;
+    let name = name.strip_suffix(".exe").unwrap_or(name);
+    if name.contains("zsh") {
+        Some("zsh")
+    } else if name.contains("fish") {
+        Some("fish")
+    } else if name.contains("bash") {
+        Some("bash")
+    } else if name == "pwsh" || name == "powershell" {
+        Some("powershell")
+    } else if name == "nu" {
+        Some("nu")
+    } else {
+        None
+    }
+}
+
 /// Get the history file path
 fn get_history_path(configured: &str, shell_type: &str) -> Result<PathBuf> {
     if configured != "auto" {
         return Ok(PathBuf::from(shellexpand::tilde(configured).to_string()));
     }
 
+    match shell_type {
+        "powershell" => return powershell_history_path(),
+        "nu" => {
+            let config_dir = directories::BaseDirs::new()
+                .map(|dirs| dirs.config_dir().to_path_buf())
+                .ok_or_else(|| anyhow::anyhow!("Could not determine the config directory"))?;
+            return Ok(config_dir.join("nushell").join("history.txt"));
+        }
+        _ => {}
+    }
+
     let home = env::var("HOME")?;
 
     let path = match shell_type {
@@ -90,9 +133,22 @@ fn get_history_path(configured: &str, shell_type: &str) -> Result<PathBuf> {
     Ok(PathBuf::from(path))
 }
 
+/// PSReadLine's history file: under %APPDATA% on Windows, the XDG data dir elsewhere
+fn powershell_history_path() -> Result<PathBuf> {
+    let dir = if cfg!(windows) {
+        PathBuf::from(env::var("APPDATA")?).join("Microsoft").join("Windows").join("PowerShell")
+    } else {
+        directories::BaseDirs::new()
+            .map(|dirs| dirs.data_dir().join("powershell"))
+            .ok_or_else(|| anyhow::anyhow!("Could not determine the data directory"))?
+    };
+    Ok(dir.join("PSReadLine").join("ConsoleHost_history.txt"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use regex::Regex;
     use tempfile::TempDir;
 
     #[test]
@@ -128,6 +184,34 @@ mod tests {
         assert_eq!(detect_shell("auto"), "fish");
     }
 
+    #[test]
+    fn test_shell_from_path() {
+        assert_eq!(shell_from_path("/usr/bin/zsh"), Some("zsh"));
+        assert_eq!(shell_from_path("/usr/local/bin/pwsh"), Some("powershell"));
+        assert_eq!(shell_from_path("powershell.exe"), Some("powershell"));
+        assert_eq!(shell_from_path("/home/me/.cargo/bin/nu"), Some("nu"));
+        assert_eq!(shell_from_path("/usr/bin/nushell-helper"), None);
+        assert_eq!(shell_from_path("/bin/sh"), None);
+    }
+
+    #[test]
+    fn test_detect_shell_powershell_and_nu() {
+        assert_eq!(detect_shell("powershell"), "powershell");
+        assert_eq!(detect_shell("nu"), "nu");
+    }
+
+    #[test]
+    fn test_get_history_path_auto_powershell() {
+        let result = get_history_path("auto", "powershell").unwrap();
+        assert!(result.ends_with("PSReadLine/ConsoleHost_history.txt"), "{:?}", result);
+    }
+
+    #[test]
+    fn test_get_history_path_auto_nu() {
+        let result = get_history_path("auto", "nu").unwrap();
+        assert!(result.ends_with("nushell/history.txt"), "{:?}", result);
+    }
+
     #[test]
     fn test_get_history_path_custom() {
         let result = get_history_path("~/.custom_history", "bash").unwrap();
@@ -174,6 +258,8 @@ mod tests {
             shell_type: "bash".to_string(),
             history_file: history_path.to_string_lossy().to_string(),
             export_envvars: true,
+            history_marker: true,
+            zsh_interactive_comments: false,
         };
 
         export_to_history(&config, "ls -la").unwrap();
@@ -192,14 +278,51 @@ mod tests {
             shell_type: "zsh".to_string(),
             history_file: history_path.to_string_lossy().to_string(),
             export_envvars: true,
+            history_marker: true,
+            zsh_interactive_comments: false,
         };
 
         export_to_history(&config, "echo test").unwrap();
 
         let content = std::fs::read_to_string(&history_path).unwrap();
-        // Zsh format: ": timestamp:0;command"
-        assert!(content.contains(":0;echo test"));
-        assert!(content.contains("# via quocli"));
+        // Zsh format: ": timestamp:0;command", without the marker by default
+        assert!(content.ends_with(":0;echo test\n"), "{:?}", content);
+
+        let config = ShellConfig {
+            zsh_interactive_comments: true,
+            ..config
+        };
+        export_to_history(&config, "echo again").unwrap();
+        let content = std::fs::read_to_string(&history_path).unwrap();
+        assert!(content.ends_with(":0;echo again # via quocli\n"), "{:?}", content);
+    }
+
+    #[test]
+    fn test_recalled_zsh_entry_runs_unchanged() {
+        let temp_dir = TempDir::new().unwrap();
+        let history_path = temp_dir.path().join(".zsh_history");
+        let config = ShellConfig {
+            shell_type: "zsh".to_string(),
+            history_file: history_path.to_string_lossy().to_string(),
+            export_envvars: true,
+            history_marker: true,
+            zsh_interactive_comments: false,
+        };
+        export_to_history(&config, "echo test").unwrap();
+
+        let content = std::fs::read_to_string(&history_path).unwrap();
+        let (_, recalled) = content.trim_end().split_once(';').unwrap();
+
+        // An interactive zsh with default options, as when the entry is recalled
+        let Ok(output) = std::process::Command::new("zsh")
+            .args(["-f", "-i", "-c", recalled])
+            .stdin(std::process::Stdio::null())
+            .output()
+        else {
+            eprintln!("zsh not installed, skipping");
+            return;
+        };
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "test\n");
     }
 
     #[test]
@@ -211,6 +334,8 @@ mod tests {
             shell_type: "fish".to_string(),
             history_file: history_path.to_string_lossy().to_string(),
             export_envvars: true,
+            history_marker: true,
+            zsh_interactive_comments: false,
         };
 
         export_to_history(&config, "git status").unwrap();
@@ -221,6 +346,92 @@ mod tests {
         assert!(content.contains("when:"));
     }
 
+    #[test]
+    fn test_history_entry_is_single_bash_record() {
+        let entry = format_history_entry("bash", "ls -la", 1700000000, true);
+        assert_eq!(entry, "ls -la # via quocli\n");
+        assert_eq!(entry.lines().count(), 1);
+
+        assert_eq!(format_history_entry("bash", "ls -la", 1700000000, false), "ls -la\n");
+    }
+
+    #[test]
+    fn test_history_entry_is_single_zsh_record() {
+        let entry = format_history_entry("zsh", "echo test", 1700000000, true);
+        assert_eq!(entry.lines().count(), 1);
+        let zsh_record = Regex::new(r"^: \d+:\d+;.+$").unwrap();
+        assert!(zsh_record.is_match(entry.trim_end()), "{:?}", entry);
+        assert!(entry.ends_with(";echo test # via quocli\n"));
+    }
+
+    #[test]
+    fn test_history_entry_is_single_fish_record() {
+        let entry = format_history_entry("fish", "git status", 1700000000, true);
+        let lines: Vec<&str> = entry.lines().collect();
+        // One "- cmd:" line starts the record; every other line is an indented key
+        assert_eq!(lines.iter().filter(|l| l.starts_with("- ")).count(), 1);
+        assert_eq!(lines[0], "- cmd: git status # via quocli");
+        assert!(lines[1..].iter().all(|l| l.starts_with("  ") && l.contains(": ")));
+        assert_eq!(lines[1], "  when: 1700000000");
+    }
+
+    #[test]
+    fn test_export_to_history_powershell_format() {
+        let temp_dir = TempDir::new().unwrap();
+        let history_path = temp_dir.path().join("ConsoleHost_history.txt");
+
+        let config = ShellConfig {
+            shell_type: "powershell".to_string(),
+            history_file: history_path.to_string_lossy().to_string(),
+            export_envvars: true,
+            history_marker: true,
+            zsh_interactive_comments: false,
+        };
+
+        export_to_history(&config, "Get-ChildItem -Recurse").unwrap();
+
+        let content = std::fs::read_to_string(&history_path).unwrap();
+        // PSReadLine format: one command per line
+        assert_eq!(content, "Get-ChildItem -Recurse # via quocli\n");
+    }
+
+    #[test]
+    fn test_export_to_history_nu_format() {
+        let temp_dir = TempDir::new().unwrap();
+        let history_path = temp_dir.path().join("history.txt");
+
+        let config = ShellConfig {
+            shell_type: "nu".to_string(),
+            history_file: history_path.to_string_lossy().to_string(),
+            export_envvars: true,
+            history_marker: false,
+            zsh_interactive_comments: false,
+        };
+
+        export_to_history(&config, "ls | where size > 1kb").unwrap();
+
+        let content = std::fs::read_to_string(&history_path).unwrap();
+        assert_eq!(content, "ls | where size > 1kb\n");
+    }
+
+    #[test]
+    fn test_export_to_history_skips_nu_sqlite_history() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("history.sqlite3"), "").unwrap();
+        let history_path = temp_dir.path().join("history.txt");
+
+        let config = ShellConfig {
+            shell_type: "nu".to_string(),
+            history_file: history_path.to_string_lossy().to_string(),
+            export_envvars: true,
+            history_marker: true,
+            zsh_interactive_comments: false,
+        };
+
+        export_to_history(&config, "ls").unwrap();
+        assert!(!history_path.exists());
+    }
+
     #[test]
     fn test_export_to_history_appends() {
         let temp_dir = TempDir::new().unwrap();
@@ -233,6 +444,8 @@ mod tests {
             shell_type: "bash".to_string(),
             history_file: history_path.to_string_lossy().to_string(),
             export_envvars: true,
+            history_marker: true,
+            zsh_interactive_comments: false,
         };
 
         export_to_history(&config, "new command").unwrap();
@@ -251,6 +464,8 @@ mod tests {
             shell_type: "bash".to_string(),
             history_file: history_path.to_string_lossy().to_string(),
             export_envvars: true,
+            history_marker: true,
+            zsh_interactive_comments: false,
         };
 
         assert!(!history_path.exists());
@@ -267,6 +482,8 @@ mod tests {
             shell_type: "bash".to_string(),
             history_file: history_path.to_string_lossy().to_string(),
             export_envvars: true,
+            history_marker: true,
+            zsh_interactive_comments: false,
         };
 
         let command = r#"echo "hello world" && grep 'pattern' file.txt"#;
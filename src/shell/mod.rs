@@ -1,8 +1,11 @@
 mod envvars;
 mod history;
+mod script;
 
 pub use envvars::{
-    contains_env_var, convert_env_value, get_all_env_vars, get_env_suggestions,
-    resolve_and_convert, resolve_env_vars, scan_matching_env_vars,
+    best_matching_env_var, contains_env_var, convert_env_value, credential_env_patterns,
+    flag_env_patterns, get_all_env_vars, get_env_suggestions, resolve_and_convert,
+    resolve_env_vars, scan_env_snapshot, scan_matching_env_vars,
 };
 pub use history::export_to_history;
+pub use script::{parse_age, render_history_script};
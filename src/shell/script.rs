@@ -0,0 +1,191 @@
+use crate::cache::{HistoryEntry, BACKGROUND_PID_KEY, INTERRUPTED_KEY};
+use crate::executor::build_command;
+use crate::parser::CommandSpec;
+
+/// Render recorded invocations as a runnable POSIX shell script
+pub fn render_history_script(spec: &CommandSpec, name: &str, entries: &[HistoryEntry]) -> String {
+    let mut out = String::from("#!/bin/sh\n");
+    out.push_str(&format!(
+        "# Replay of {} recorded `{}` invocation(s), exported by quocli\n",
+        entries.len(),
+        name
+    ));
+
+    let redacted = entries
+        .iter()
+        .any(|e| e.args.values().any(|v| v == "***"));
+    if redacted {
+        out.push_str("# NOTE: sensitive values were redacted as '***'; fill them in before running\n");
+    }
+
+    for entry in entries {
+        let status = match (entry.success, entry.args.get(BACKGROUND_PID_KEY)) {
+            _ if entry.args.contains_key(INTERRUPTED_KEY) => "interrupted".to_string(),
+            (Some(true), _) => "succeeded".to_string(),
+            (Some(false), _) => "failed".to_string(),
+            (None, Some(pid)) => format!("launched in background, pid {}", pid),
+            (None, None) => "status unknown".to_string(),
+        };
+        out.push_str(&format!(
+            "\n# {} ({})\n",
+            format_timestamp(entry.timestamp),
+            status
+        ));
+        out.push_str(&build_command(spec, &entry.args));
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Parse an age like `90s`, `30m`, `12h`, `7d` or `2w` into seconds
+pub fn parse_age(s: &str) -> Result<i64, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (digits, unit) = s.split_at(split);
+
+    let invalid = || format!("invalid age '{}', expected e.g. 12h or 7d", s);
+    let count: i64 = digits.parse().map_err(|_| invalid())?;
+    let multiplier = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(format!("unknown age unit '{}', expected s, m, h, d or w", unit)),
+    };
+
+    count.checked_mul(multiplier).ok_or_else(invalid)
+}
+
+/// Format a unix timestamp as `YYYY-MM-DD HH:MM:SS UTC`
+fn format_timestamp(timestamp: i64) -> String {
+    let days = timestamp.div_euclid(86_400);
+    let secs = timestamp.rem_euclid(86_400);
+
+    // Civil-from-days (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{ArgumentType, CommandOption, DangerLevel, OptionLevel};
+    use std::collections::HashMap;
+
+    fn create_test_spec() -> CommandSpec {
+        CommandSpec {
+            command: "curl".to_string(),
+            version_hash: "hash".to_string(),
+            description: "Transfer a URL".to_string(),
+            options: vec![CommandOption {
+                flags: vec!["--user".to_string()],
+                description: "Server user and password".to_string(),
+                argument_type: ArgumentType::String,
+                argument_name: Some("USER:PASS".to_string()),
+                required: false,
+                sensitive: true,
+                repeatable: false,
+                conflicts_with: vec![],
+                requires: vec![],
+                default: None,
+                enum_values: vec![],
+                level: OptionLevel::Basic,
+                deprecated: false,
+                example: None,
+                min: None,
+                max: None,
+                pattern: None,
+            }],
+            positional_args: vec![],
+            subcommands: vec![],
+            danger_level: DangerLevel::Low,
+            examples: vec![],
+            positionals_first: false,
+            tool_version: None,
+            basic_only: false,
+        }
+    }
+
+    #[test]
+    fn test_render_history_script() {
+        let mut args = HashMap::new();
+        args.insert("--user".to_string(), "***".to_string());
+        let entries = vec![
+            HistoryEntry {
+                args: HashMap::new(),
+                timestamp: 0,
+                success: Some(true),
+            },
+            HistoryEntry {
+                args,
+                timestamp: 1_700_000_000,
+                success: Some(false),
+            },
+        ];
+
+        let script = render_history_script(&create_test_spec(), "curl", &entries);
+        assert!(script.starts_with("#!/bin/sh\n"));
+        assert!(script.contains("# Replay of 2 recorded `curl` invocation(s)"));
+        assert!(script.contains("redacted as '***'"));
+        assert!(script.contains("\n# 1970-01-01 00:00:00 UTC (succeeded)\ncurl\n"));
+        assert!(script.contains("# 2023-11-14 22:13:20 UTC (failed)\ncurl --user"));
+    }
+
+    #[test]
+    fn test_render_history_script_background_entry() {
+        let mut args = HashMap::new();
+        args.insert(BACKGROUND_PID_KEY.to_string(), "4242".to_string());
+        let entries = vec![HistoryEntry {
+            args,
+            timestamp: 0,
+            success: None,
+        }];
+
+        let script = render_history_script(&create_test_spec(), "curl", &entries);
+        assert!(script.contains("(launched in background, pid 4242)\ncurl\n"));
+    }
+
+    #[test]
+    fn test_render_history_script_without_redactions() {
+        let script = render_history_script(&create_test_spec(), "curl", &[]);
+        assert!(!script.contains("redacted"));
+    }
+
+    #[test]
+    fn test_parse_age() {
+        assert_eq!(parse_age("90"), Ok(90));
+        assert_eq!(parse_age("30m"), Ok(1800));
+        assert_eq!(parse_age("12h"), Ok(43_200));
+        assert_eq!(parse_age("7d"), Ok(604_800));
+        assert_eq!(parse_age("2w"), Ok(1_209_600));
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("5y").is_err());
+        // Too large to fit in seconds
+        assert!(parse_age("9223372036854775807w").is_err());
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29 00:00:00 UTC");
+        assert_eq!(format_timestamp(1_735_689_599), "2024-12-31 23:59:59 UTC");
+    }
+}
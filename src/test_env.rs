@@ -0,0 +1,42 @@
+//! Serialized changes to the process environment for tests, which otherwise
+//! run in parallel threads sharing it
+
+use std::ffi::{OsStr, OsString};
+use std::sync::{Mutex, MutexGuard};
+
+static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+/// Holds the environment lock for a test, restoring every variable it set or
+/// removed when dropped
+pub(crate) struct EnvGuard {
+    saved: Vec<(String, Option<OsString>)>,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl EnvGuard {
+    /// Wait for other environment-changing tests to finish, then take the lock
+    pub(crate) fn lock() -> Self {
+        EnvGuard {
+            saved: Vec::new(),
+            // A test that panicked while holding the lock has still restored its variables
+            _lock: ENV_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner()),
+        }
+    }
+
+    pub(crate) fn set(&mut self, name: &str, value: impl AsRef<OsStr>) -> &mut Self {
+        self.saved.push((name.to_string(), std::env::var_os(name)));
+        std::env::set_var(name, value);
+        self
+    }
+}
+
+impl Drop for EnvGuard {
+    fn drop(&mut self) {
+        for (name, value) in self.saved.drain(..).rev() {
+            match value {
+                Some(value) => std::env::set_var(&name, value),
+                None => std::env::remove_var(&name),
+            }
+        }
+    }
+}
@@ -1,7 +1,14 @@
-use crate::config::Config;
+use crate::cache::ValueOverwrite;
+use crate::config::{Action, Config, KeyBindings, UiConfig};
+use crate::executor::{build_command, parse_command};
+use crate::llm::{chat_context, create_client, GenerationOptions, LlmClient, PendingChat};
 use crate::parser::{ArgumentType, CommandSpec, DangerLevel};
+use crate::tui::markdown::markdown_lines;
 use crate::tui::theme::Theme;
-use crate::tui::widgets::{FormField, FormState, OptionTab};
+use crate::tui::widgets::{
+    ChatPanel, ConstraintKind, ExecuteConfirm, FieldOrder, FormField, FormState, LabelStyle, OptionTab,
+};
+use crate::tui::wrap::{popup_width, wrap_lines, wrap_text};
 use anyhow::Result;
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers, MouseEventKind},
@@ -13,28 +20,63 @@ use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
     Frame, Terminal,
 };
 use std::collections::HashMap;
 use std::io;
+use std::sync::Arc;
+use std::time::Duration;
 
 /// Result of running the form
 #[derive(Debug)]
 pub enum FormResult {
     /// User wants to execute the command
     Execute(HashMap<String, String>),
+    /// User wants to launch the command detached and return immediately
+    Background(HashMap<String, String>),
     /// User wants to preview the command without executing
     Preview(HashMap<String, String>),
+    /// User wants the values saved as a shareable invocation file
+    ExportInvocation(HashMap<String, String>),
     /// User cancelled the form
     Cancel,
 }
 
-/// Run the interactive form
+/// Result of the subcommand picker
+#[derive(Debug)]
+pub enum SubcommandPick {
+    /// User picked this subcommand
+    Subcommand(String),
+    /// User wants the form for the command picked so far
+    Command,
+    /// User cancelled
+    Cancel,
+}
+
+/// What the user changed in the form besides its values, for the caller to persist
+#[derive(Debug, Default)]
+pub struct FormChanges {
+    /// Sensitivity toggled by the user (field id -> sensitive)
+    pub sensitivity_overrides: HashMap<String, bool>,
+    /// Presets saved in the form
+    pub saved_presets: Vec<(String, HashMap<String, String>)>,
+    /// The command's note, if it was edited (blank once cleared)
+    pub note: Option<String>,
+}
+
+/// Run the interactive form. `presets` are offered in the preset picker and
+/// `note` is shown in the header; edits to either, and sensitivity toggles,
+/// are added to `changes`. With `initial_search`, the form opens in search
+/// mode with that query typed.
 pub async fn run_form(
     config: &Config,
     spec: &CommandSpec,
     cached_values: HashMap<String, String>,
+    presets: Vec<(String, HashMap<String, String>)>,
+    note: Option<String>,
+    initial_search: Option<&str>,
+    changes: &mut FormChanges,
 ) -> Result<FormResult> {
     // Build form fields
     let mut fields: Vec<FormField> = Vec::new();
@@ -55,15 +97,22 @@ pub async fn run_form(
     }
 
     // Create form state
-    let mut state = FormState::new(fields);
+    let order = FieldOrder::from_config(&config.ui.field_order);
+    let mut state = FormState::with_field_order(fields, order);
     state.load_cached_values(&cached_values);
+    state.set_presets(presets);
+    state.note = note.unwrap_or_default();
+    state.strict_numeric = config.ui.strict_numeric_input;
+    if config.ui.show_examples {
+        state.set_examples(spec.examples.clone());
+    }
+    if let Some(query) = initial_search {
+        state.start_search_with(query);
+    }
 
-    // Get theme
-    let theme = if config.ui.theme == "light" {
-        Theme::light()
-    } else {
-        Theme::dark()
-    };
+    // With theme = "auto" this asks the terminal for its background, so it
+    // comes before raw mode and the form's event loop take over stdin
+    let theme = Theme::from_config(&config.ui);
 
     // Setup terminal
     enable_raw_mode()?;
@@ -78,6 +127,12 @@ pub async fn run_form(
     disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
 
+    changes.sensitivity_overrides.extend(state.sensitivity_overrides);
+    changes.saved_presets.extend(state.saved_presets);
+    if state.note_changed {
+        changes.note = Some(state.note);
+    }
+
     result
 }
 
@@ -88,11 +143,30 @@ fn run_form_loop(
     theme: &Theme,
     config: &Config,
 ) -> Result<FormResult> {
+    // Opened on first copy and kept for the life of the form, since on X11
+    // the copied text is only served while the clipboard handle is alive
+    let mut clipboard: Option<arboard::Clipboard> = None;
+    // Created on the first assist request, since the form works without an LLM
+    let mut chat_client: Option<Arc<dyn LlmClient>> = None;
+    let mut pending_chat: Option<PendingChat> = None;
+    // Validated when the config was loaded
+    let bindings = KeyBindings::from_config(&config.keybindings).unwrap_or_default();
+
     loop {
+        if let Some(result) = pending_chat.as_mut().and_then(|pending| pending.try_result()) {
+            state.receive_chat_reply(result.map_err(|e| e.to_string()));
+            pending_chat = None;
+        }
+        state.update_env_hint();
+        state.update_constraint_warning(spec);
+
         // Draw UI
-        terminal.draw(|f| draw_form(f, state, spec, theme, config))?;
+        terminal.draw(|f| draw_form(f, state, spec, theme, config, &bindings))?;
 
-        // Handle input
+        // Handle input, waking up regularly while an assist reply is on its way
+        if pending_chat.is_some() && !event::poll(Duration::from_millis(100))? {
+            continue;
+        }
         let event = event::read()?;
 
         // Handle mouse events for description scrolling
@@ -101,8 +175,12 @@ fn run_form_loop(
             if !state.editing && !state.showing_suggestions {
                 if let Some(field) = state.current_field() {
                     if !field.description.is_empty() {
-                        // Estimate max scroll based on description length
-                        let max_scroll = estimate_max_scroll(&field.description, terminal.size()?.height);
+                        let size = terminal.size()?;
+                        let max_scroll = max_description_scroll(
+                            &field.description,
+                            Rect::new(0, 0, size.width, size.height),
+                            config.ui.description_wrap_width,
+                        );
                         match mouse.kind {
                             // Natural scrolling: scroll wheel up shows content above
                             MouseEventKind::ScrollUp => state.scroll_description_up(),
@@ -117,6 +195,33 @@ fn run_form_loop(
         }
 
         if let Event::Key(key) = event {
+            // Validation feedback lasts until the next keypress
+            state.clear_status();
+
+            // Ctrl+E or Ctrl+B is waiting for its confirming keystroke (ui.confirm_execute)
+            if state.execute_armed {
+                let confirm = ExecuteConfirm::from_config(&config.ui.confirm_execute);
+                let background = state.armed_background;
+                let run_action = if background { Action::Background } else { Action::Execute };
+                let confirmed = match confirm {
+                    ExecuteConfirm::Twice => bindings.action(&key) == Some(run_action),
+                    ExecuteConfirm::Enter => key.code == KeyCode::Enter,
+                    ExecuteConfirm::Off => false,
+                };
+                if confirmed {
+                    if state.request_execute(confirm, "", background) && ready_to_run(terminal, state, spec, theme, config)? {
+                        return Ok(run_result(background, state.get_values()));
+                    }
+                    continue;
+                }
+                state.execute_armed = false;
+                if confirm == ExecuteConfirm::Enter {
+                    // The cancelling key is swallowed so it can't do anything unexpected
+                    state.status_message = Some("Run cancelled".to_string());
+                    continue;
+                }
+            }
+
             if state.editing {
                 if state.showing_suggestions {
                     // Handle suggestion navigation
@@ -140,8 +245,11 @@ fn run_form_loop(
                     }
                 } else {
                     match key.code {
-                        KeyCode::Esc => state.stop_editing(),
-                        KeyCode::Enter => state.stop_editing(),
+                        KeyCode::Esc | KeyCode::Enter => {
+                            let was_set = state.editing_was_set;
+                            state.stop_editing();
+                            resolve_conflicts(terminal, state, spec, theme, was_set, config.ui.description_wrap_width)?;
+                        }
                         KeyCode::Backspace => {
                             state.delete_char();
                             state.update_env_suggestions();
@@ -153,6 +261,17 @@ fn run_form_loop(
                         _ => {}
                     }
                 }
+            } else if state.jump_mode {
+                // Jump mode: type a flag name, Enter moves selection to the best match
+                match key.code {
+                    KeyCode::Esc => state.cancel_jump(),
+                    KeyCode::Enter => {
+                        state.finish_jump();
+                    }
+                    KeyCode::Backspace => state.jump_delete_char(),
+                    KeyCode::Char(c) => state.jump_insert_char(c),
+                    _ => {}
+                }
             } else if state.search_mode {
                 // Search mode key handling
                 match key.code {
@@ -164,6 +283,62 @@ fn run_form_loop(
                     KeyCode::Char(c) => state.search_insert_char(c),
                     _ => {}
                 }
+            } else if state.preset_name_input.is_some() {
+                // Naming a preset: Enter saves, Esc cancels
+                match key.code {
+                    KeyCode::Esc => state.cancel_preset_save(),
+                    KeyCode::Enter => state.finish_preset_save(),
+                    KeyCode::Backspace => state.preset_name_delete_char(),
+                    KeyCode::Char(c) => state.preset_name_insert_char(c),
+                    _ => {}
+                }
+            } else if state.chat.is_some() {
+                // Assistant panel: Enter sends the request, Tab applies the suggestion
+                match key.code {
+                    KeyCode::Esc => {
+                        // Dropping the request aborts it
+                        pending_chat = None;
+                        state.close_chat();
+                    }
+                    KeyCode::Enter => {
+                        if let Some(message) = state.take_chat_message() {
+                            match chat_client_for(&mut chat_client, config) {
+                                Ok(client) => {
+                                    let context = chat_context(spec, &state.get_values());
+                                    let timeout = Duration::from_secs(config.llm.chat_timeout_secs);
+                                    pending_chat = Some(PendingChat::spawn(client, context, message, timeout));
+                                }
+                                Err(e) => state.receive_chat_reply(Err(e.to_string())),
+                            }
+                        }
+                    }
+                    KeyCode::Tab => {
+                        let suggestion = state.chat.as_ref().and_then(|chat| chat.suggestion.clone());
+                        if let Some(parsed) = suggestion.and_then(|line| parse_command(spec, &line)) {
+                            state.apply_chat_suggestion(&parsed);
+                        }
+                    }
+                    KeyCode::Backspace => state.chat_delete_char(),
+                    KeyCode::Char(c) => state.chat_insert_char(c),
+                    _ => {}
+                }
+            } else if state.showing_note {
+                // Note popup: typing edits the note, Enter starts a new line
+                match key.code {
+                    KeyCode::Esc => state.close_note(),
+                    KeyCode::Enter => state.note_insert_char('\n'),
+                    KeyCode::Backspace => state.note_delete_char(),
+                    KeyCode::Char(c) => state.note_insert_char(c),
+                    _ => {}
+                }
+            } else if state.showing_presets {
+                match key.code {
+                    KeyCode::Esc | KeyCode::Char('q') => state.close_preset_picker(),
+                    KeyCode::Enter => state.apply_selected_preset(),
+                    KeyCode::Up | KeyCode::Char('k') => state.prev_preset(),
+                    KeyCode::Down | KeyCode::Char('j') => state.next_preset(),
+                    _ => {}
+                }
             } else if state.showing_help {
                 // Close help sheet on any key
                 match key.code {
@@ -172,69 +347,110 @@ fn run_form_loop(
                     }
                     _ => state.showing_help = false,
                 }
-            } else {
-                match key.code {
-                    KeyCode::Char('q') | KeyCode::Esc => {
+            } else if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                // Always available, whatever the keybindings
+                return Ok(FormResult::Cancel);
+            } else if let Some(action) = bindings.action(&key) {
+                match action {
+                    Action::Quit => {
                         if !state.search_query.is_empty() {
                             state.clear_search();
                         } else {
                             return Ok(FormResult::Cancel);
                         }
                     }
-                    KeyCode::Char('?') => state.toggle_help(),
-                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        return Ok(FormResult::Cancel)
-                    }
-                    KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        return Ok(FormResult::Execute(state.get_values()))
-                    }
-                    KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        return Ok(FormResult::Preview(state.get_values()))
+                    Action::Help => state.toggle_help(),
+                    Action::AcceptEnvHint => state.accept_env_hint(),
+                    Action::Jump => state.start_jump(),
+                    Action::ToggleSensitive => state.toggle_sensitive(),
+                    Action::TypeFilter => state.cycle_type_filter(),
+                    Action::Assistant => state.open_chat(),
+                    Action::ToggleSection => state.toggle_section(),
+                    Action::AddValue => state.add_repeated_value(),
+                    Action::RemoveValue => state.remove_repeated_value(),
+                    // Execution is blocked (and the missing fields flagged) until required
+                    // fields and required options are filled; conflicts need confirming
+                    Action::Execute | Action::Background => {
+                        let background = action == Action::Background;
+                        let confirm = ExecuteConfirm::from_config(&config.ui.confirm_execute);
+                        if state.request_execute(confirm, &bindings.label(action), background)
+                            && ready_to_run(terminal, state, spec, theme, config)?
+                        {
+                            return Ok(run_result(background, state.get_values()));
+                        }
                     }
-                    KeyCode::Char('x') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        state.clear_all_values()
+                    Action::Preview => return Ok(FormResult::Preview(state.get_values())),
+                    Action::ExportInvocation => {
+                        return Ok(FormResult::ExportInvocation(state.get_values()))
                     }
-                    // Description scrolling with Ctrl+Up/Down
-                    KeyCode::Up if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        state.scroll_description_up();
+                    Action::Clear => state.clear_all_values(),
+                    Action::Copy => {
+                        // The real, shell-quoted command - sensitive values included
+                        let command_line = build_command(spec, &state.get_values());
+                        state.status_message = Some(match copy_to_clipboard(&mut clipboard, command_line) {
+                            Ok(()) => "Copied command to clipboard".to_string(),
+                            Err(e) => format!("Could not copy to clipboard: {}", e),
+                        });
                     }
-                    KeyCode::Down if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    Action::ClearSensitive => state.clear_sensitive_values(),
+                    Action::LoadPreset => state.open_preset_picker(),
+                    Action::SavePreset => state.start_preset_save(),
+                    Action::Note => state.open_note(),
+                    Action::ScrollUp => state.scroll_description_up(),
+                    Action::ScrollDown => {
                         if let Some(field) = state.current_field() {
                             if !field.description.is_empty() {
-                                let term_height = terminal.size().map(|s| s.height).unwrap_or(24);
-                                let max_scroll = estimate_max_scroll(&field.description, term_height);
+                                let size = terminal.size()?;
+                                let max_scroll = max_description_scroll(
+                                    &field.description,
+                                    Rect::new(0, 0, size.width, size.height),
+                                    config.ui.description_wrap_width,
+                                );
                                 state.scroll_description_down(max_scroll);
                             }
                         }
                     }
-                    // Search: / for flag-only search, Ctrl+/ for including description
-                    KeyCode::Char('/') => {
-                        let include_desc = key.modifiers.contains(KeyModifiers::CONTROL);
-                        state.start_search(include_desc);
+                    // Flag-only search, or search including descriptions
+                    Action::Search => state.start_search(false),
+                    Action::SearchDescriptions => state.start_search(true),
+                    Action::NextTab => state.next_tab(),
+                    Action::PrevTab => state.prev_tab(),
+                    Action::TabBasic => state.set_tab(OptionTab::Basic),
+                    Action::TabAdvanced => state.set_tab(OptionTab::Advanced),
+                    Action::TabFrequent => state.set_tab(OptionTab::Frequent),
+                    Action::TabExamples => state.set_tab(OptionTab::Examples),
+                    Action::Up | Action::PrevField => state.move_up(),
+                    Action::Down | Action::NextField => state.move_down(),
+                    Action::PageUp => state.page_up(10),
+                    Action::PageDown => state.page_down(10),
+                    Action::Top => state.move_to_top(),
+                    Action::Bottom => state.move_to_bottom(),
+                    Action::Edit if state.current_tab == OptionTab::Examples => {
+                        if let Some(example) = state.current_example() {
+                            match parse_command(spec, example) {
+                                Some(parsed) => state.apply_example(&parsed),
+                                None => {
+                                    state.status_message = Some("Could not parse this example".to_string())
+                                }
+                            }
+                        }
                     }
-                    // Tab switching
-                    KeyCode::Char('`') => state.next_tab(),
-                    KeyCode::Char('1') => state.set_tab(OptionTab::Basic),
-                    KeyCode::Char('2') => state.set_tab(OptionTab::Advanced),
-                    KeyCode::Char('3') => state.set_tab(OptionTab::Frequent),
-                    KeyCode::Up | KeyCode::Char('k') => state.move_up(),
-                    KeyCode::Down | KeyCode::Char('j') => state.move_down(),
-                    KeyCode::PageUp => state.page_up(10),
-                    KeyCode::PageDown => state.page_down(10),
-                    KeyCode::Home => state.move_to_top(),
-                    KeyCode::End => state.move_to_bottom(),
-                    KeyCode::Enter => {
+                    Action::Edit => {
                         if let Some(field) = state.current_field() {
+                            let was_set = field.is_set();
                             match field.field_type {
-                                ArgumentType::Bool => state.toggle_bool(),
-                                ArgumentType::Enum => state.cycle_enum(),
+                                ArgumentType::Bool => {
+                                    state.toggle_bool();
+                                    resolve_conflicts(terminal, state, spec, theme, was_set, config.ui.description_wrap_width)?;
+                                }
+                                ArgumentType::Enum => {
+                                    state.cycle_enum();
+                                    resolve_conflicts(terminal, state, spec, theme, was_set, config.ui.description_wrap_width)?;
+                                }
                                 _ => state.start_editing(),
                             }
                         }
                     }
-                    KeyCode::Tab => state.move_down(),
-                    KeyCode::BackTab => state.move_up(),
-                    _ => {}
                 }
             }
         }
@@ -242,38 +458,49 @@ fn run_form_loop(
 }
 
 /// Build help text lines with proper wrapping
-fn build_help_lines(state: &FormState, width: usize) -> Vec<Line<'static>> {
-    let commands: Vec<(&str, &str)> = if state.editing {
+fn build_help_lines(state: &FormState, bindings: &KeyBindings, width: usize) -> Vec<Line<'static>> {
+    // The first key bound to an action, with Ctrl abbreviated to ^
+    let hint = |action| {
+        let label = bindings.label(action);
+        label.split('/').next().unwrap_or_default().replace("Ctrl+", "^")
+    };
+    let commands: Vec<(String, &str)> = if state.editing {
         if state.showing_suggestions {
             vec![
-                ("↑/↓", "select"),
-                ("Tab/Enter", "accept"),
-                ("Esc", "cancel"),
+                ("↑/↓".to_string(), "select"),
+                ("Tab/Enter".to_string(), "accept"),
+                ("Esc".to_string(), "cancel"),
             ]
         } else {
             vec![
-                ("Esc/Enter", "finish"),
-                ("$VAR", "env vars"),
+                ("Esc/Enter".to_string(), "finish"),
+                ("$VAR".to_string(), "env vars"),
             ]
         }
+    } else if state.jump_mode {
+        vec![
+            ("Type".to_string(), "flag name"),
+            ("Enter".to_string(), "jump"),
+            ("Esc".to_string(), "cancel"),
+        ]
     } else if state.search_mode {
         vec![
-            ("Type", "search"),
-            ("↑/↓", "nav"),
-            ("Enter", "select"),
-            ("Esc", "clear"),
+            ("Type".to_string(), "search"),
+            ("↑/↓".to_string(), "nav"),
+            ("Enter".to_string(), "select"),
+            ("Esc".to_string(), "clear"),
         ]
     } else {
         vec![
-            ("↑/↓", "nav"),
-            ("PgUp/Dn", "page"),
-            ("Enter", "edit"),
-            ("/", "search"),
-            ("1/2/3", "tabs"),
-            ("^X", "clear"),
-            ("^E", "exec"),
-            ("^P", "preview"),
-            ("q", "cancel"),
+            ("↑/↓".to_string(), "nav"),
+            ("PgUp/Dn".to_string(), "page"),
+            (hint(Action::Edit), "edit"),
+            (hint(Action::Search), "search"),
+            ("1-4".to_string(), "tabs"),
+            (hint(Action::Clear), "clear"),
+            (hint(Action::Execute), "exec"),
+            (hint(Action::Preview), "preview"),
+            (hint(Action::Quit), "cancel"),
         ]
     };
 
@@ -288,7 +515,8 @@ fn build_help_lines(state: &FormState, width: usize) -> Vec<Line<'static>> {
     let one_line = formatted.join(separator);
 
     // For non-editing mode, we may need to show "?: help"
-    let help_suffix = if !state.editing && !state.search_mode { " | ?: help" } else { "" };
+    let normal_mode = !state.editing && !state.search_mode && !state.jump_mode;
+    let help_suffix = if normal_mode { format!(" | {}: help", hint(Action::Help)) } else { String::new() };
 
     if one_line.len() + help_suffix.len() <= width {
         return vec![Line::from(format!("{}{}", one_line, help_suffix))];
@@ -325,7 +553,7 @@ fn build_help_lines(state: &FormState, width: usize) -> Vec<Line<'static>> {
                 current_len += sep_len + cmd_len;
             } else {
                 // Would need a third line - truncate and add ?: help
-                if !state.editing && !state.search_mode {
+                if normal_mode {
                     // Add ?: help at the end of line 1
                     let line1_text = line1.join(separator);
                     return vec![Line::from(format!("{} | ?: help", line1_text))];
@@ -342,8 +570,8 @@ fn build_help_lines(state: &FormState, width: usize) -> Vec<Line<'static>> {
     let mut line2_text = line2.join(separator);
 
     // Add help suffix to line 2 if room and in normal mode
-    if !state.editing && !state.search_mode && line2_text.len() + help_suffix.len() <= target_len {
-        line2_text.push_str(help_suffix);
+    if normal_mode && line2_text.len() + help_suffix.len() <= target_len {
+        line2_text.push_str(&help_suffix);
     }
 
     if line2_text.is_empty() {
@@ -353,130 +581,272 @@ fn build_help_lines(state: &FormState, width: usize) -> Vec<Line<'static>> {
     }
 }
 
+/// Terminals shorter than this get the compact layout: one-line header,
+/// preview, search and help, leaving every other row to the field list
+const COMPACT_HEIGHT: u16 = 24;
+
 fn draw_form(
     f: &mut Frame,
     state: &FormState,
     spec: &CommandSpec,
     theme: &Theme,
     config: &Config,
+    bindings: &KeyBindings,
 ) {
-    // Add search bar height when in search mode
-    let search_height = if state.search_mode || !state.search_query.is_empty() { 3 } else { 0 };
-
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(1)
-        .constraints([
-            Constraint::Length(3),              // Header
-            Constraint::Min(10),                // Form fields
-            Constraint::Length(5),              // Command preview
-            Constraint::Length(search_height),  // Search bar
-            Constraint::Length(2),              // Help
-        ])
-        .split(f.area());
+    let compact = f.area().height < COMPACT_HEIGHT;
+    let searching = state.search_mode
+        || state.jump_mode
+        || state.preset_name_input.is_some()
+        || !state.search_query.is_empty();
+
+    let chunks = if compact {
+        Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),                                      // Header
+                Constraint::Min(3),                                         // Form fields
+                Constraint::Length(if config.ui.preview_command { 1 } else { 0 }), // Command preview
+                Constraint::Length(if searching { 1 } else { 0 }),          // Search bar
+                Constraint::Length(1),                                      // Help
+            ])
+            .split(f.area())
+    } else {
+        // Add search bar height when in search mode
+        let search_height = if searching { 3 } else { 0 };
+        Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([
+                Constraint::Length(3),              // Header
+                Constraint::Min(10),                // Form fields
+                Constraint::Length(5),              // Command preview
+                Constraint::Length(search_height),  // Search bar
+                Constraint::Length(2),              // Help
+            ])
+            .split(f.area())
+    };
 
     // Header
-    let header = Paragraph::new(vec![
-        Line::from(vec![
+    let danger_style =
+        if spec.danger_level == DangerLevel::High || spec.danger_level == DangerLevel::Critical {
+            theme.danger
+        } else {
+            theme.normal
+        };
+    let header = if compact {
+        Paragraph::new(Line::from(vec![
             Span::styled(&spec.command, theme.header),
+            Span::styled(format!(" [{}]", spec.danger_level), danger_style),
             Span::raw(" - "),
             Span::raw(&spec.description),
-        ]),
-        Line::from(Span::styled(
-            format!("Danger level: {}", spec.danger_level),
-            if spec.danger_level == DangerLevel::High || spec.danger_level == DangerLevel::Critical {
-                theme.danger
-            } else {
-                theme.normal
-            },
-        )),
-    ])
-    .block(Block::default().borders(Borders::BOTTOM));
+        ]))
+    } else {
+        Paragraph::new(vec![
+            Line::from(vec![
+                Span::styled(&spec.command, theme.header),
+                Span::raw(" - "),
+                Span::raw(&spec.description),
+            ]),
+            Line::from(
+                [Span::styled(format!("Danger level: {}", spec.danger_level), danger_style)]
+                    .into_iter()
+                    .chain(state.note_summary().map(|note| Span::styled(format!("  Note: {}", note), theme.help)))
+                    .collect::<Vec<_>>(),
+            ),
+        ])
+        .block(Block::default().borders(Borders::BOTTOM))
+    };
     f.render_widget(header, chunks[0]);
 
     // Form fields - show only filtered results
     let visible = state.visible_fields();
-    let items: Vec<ListItem> = visible
-        .iter()
-        .map(|(i, field)| {
-            let is_selected = *i == state.selected;
-            let style = if is_selected {
-                theme.selected
-            } else if field.required {
-                theme.required
-            } else if field.sensitive {
-                theme.sensitive
-            } else {
-                theme.normal
-            };
-
-            let marker = if field.required { "*" } else { " " };
-            let value_display = field.display_value();
-            let cursor = if is_selected && state.editing { "_" } else { "" };
+    let label_style = LabelStyle::from_config(&config.ui.label_style);
+
+    // Section headers, when both kinds are shown and search isn't reordering them
+    let show_sections = state.search_query.is_empty()
+        && visible.iter().any(|(_, f)| f.is_positional())
+        && visible.iter().any(|(_, f)| !f.is_positional());
+    let mut items: Vec<ListItem> = Vec::with_capacity(visible.len() + 2);
+    let mut current_section = None;
+
+    for (i, field) in &visible {
+        if show_sections && current_section != Some(field.is_positional()) {
+            current_section = Some(field.is_positional());
+            let title = if field.is_positional() { "Arguments" } else { "Options" };
+            items.push(ListItem::new(Line::from(Span::styled(
+                format!("── {} ──", title),
+                theme.header,
+            ))));
+        }
+        let is_selected = *i == state.selected;
+        let invalid = field.validate().err();
+        let style = if is_selected {
+            theme.selected
+        } else if state.missing_fields.contains(i) || invalid.is_some() {
+            theme.danger
+        } else if field.required {
+            theme.required
+        } else if field.sensitive {
+            theme.sensitive
+        } else if field.deprecated {
+            theme.deprecated
+        } else {
+            theme.normal
+        };
 
-            let content = format!(
-                "{} {}: {}{}",
-                marker, field.label, value_display, cursor
-            );
+        let marker = if field.required { "*" } else { " " };
+        // ● marks values pre-filled from the cache, as opposed to defaults or empty
+        let cached_marker = if state.has_cached_value(*i) { "●" } else { " " };
+        let value_display = field.display_value();
+        let cursor = if is_selected && state.editing { "_" } else { "" };
+
+        let deprecated = if field.deprecated { " (deprecated)" } else { "" };
+        let content = format!(
+            " {}{}: {}{}",
+            field.display_label(label_style),
+            deprecated,
+            value_display,
+            cursor
+        );
+
+        let mut spans = vec![
+            Span::styled(marker, style),
+            Span::styled(cached_marker, if is_selected { style } else { theme.help }),
+            Span::styled(content, style),
+        ];
+        // Example hint, greyed out so it can't be mistaken for a value
+        if !(is_selected && state.editing) {
+            if let Some(placeholder) = field.placeholder() {
+                spans.push(Span::styled(placeholder, theme.help));
+            }
+        }
+        if let Some(message) = invalid.filter(|_| is_selected) {
+            spans.push(Span::styled(format!("  ({})", message), theme.danger));
+        }
+        if is_selected && !state.editing && field.repeatable && field.is_set() {
+            spans.push(Span::styled("  (+: add another)", theme.help));
+        }
+        if is_selected && !state.editing {
+            if let Some(var_name) = &state.env_hint {
+                let hint = if field.sensitive {
+                    Span::styled(
+                        format!("  ($: use ${} instead of pasting the secret)", var_name),
+                        theme.sensitive,
+                    )
+                } else {
+                    Span::styled(format!("  ($: use ${}?)", var_name), theme.help)
+                };
+                spans.push(hint);
+            }
+        }
 
-            ListItem::new(Line::from(Span::styled(content, style)))
-        })
-        .collect();
+        items.push(ListItem::new(Line::from(spans)));
+    }
 
     // Build title showing tab and count
     let tab_name = match state.current_tab {
         OptionTab::Basic => "Basic",
         OptionTab::Advanced => "Advanced",
         OptionTab::Frequent => "Frequent",
+        OptionTab::Examples => "Examples",
     };
-    let title = if state.search_query.is_empty() {
+    let mut title = if state.search_query.is_empty() {
         format!("[{}] Options ({})", tab_name, visible.len())
     } else {
         format!("[{}] Options ({}/{})", tab_name, visible.len(), state.fields.len())
     };
+    if let Some(field_type) = &state.type_filter {
+        title.push_str(&format!(" · type: {}", field_type));
+    }
 
-    let list = List::new(items)
-        .block(Block::default().title(title).borders(Borders::ALL))
-        .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+    let list = if state.current_tab == OptionTab::Examples {
+        // Examples stand in for the field list; Enter fills the form from one
+        let items: Vec<ListItem> = state
+            .examples
+            .iter()
+            .enumerate()
+            .map(|(i, example)| {
+                let style = if i == state.selected_example { theme.selected } else { theme.normal };
+                ListItem::new(Line::from(Span::styled(format!(" $ {}", example), style)))
+            })
+            .collect();
+        let title = format!("[Examples] ({}) · Enter fills the form", state.examples.len());
+        List::new(items).block(Block::default().title(title).borders(Borders::ALL))
+    } else {
+        List::new(items)
+            .block(Block::default().title(title).borders(Borders::ALL))
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+    };
 
     f.render_widget(list, chunks[1]);
 
-    // Command preview
+    // Command preview (a single unboxed line in the compact layout)
     if config.ui.preview_command {
         let command_line = build_preview(spec, state);
-        let preview = Paragraph::new(command_line)
-            .style(theme.preview)
-            .block(Block::default().title("Command Preview").borders(Borders::ALL))
-            .wrap(Wrap { trim: false });
+        let preview = if compact {
+            Paragraph::new(format!("$ {}", command_line)).style(theme.preview)
+        } else {
+            Paragraph::new(command_line)
+                .style(theme.preview)
+                .block(Block::default().title("Command Preview").borders(Borders::ALL))
+                .wrap(Wrap { trim: false })
+        };
         f.render_widget(preview, chunks[2]);
     }
 
-    // Search bar
-    if state.search_mode || !state.search_query.is_empty() {
+    // Search bar (doubles as the jump prompt)
+    let search_block = if compact { Block::default() } else { Block::default().borders(Borders::ALL) };
+    if let Some(name) = &state.preset_name_input {
+        let prompt = Paragraph::new(format!("Save preset as: {}_", name))
+            .style(theme.selected)
+            .block(search_block);
+        f.render_widget(prompt, chunks[3]);
+    } else if state.jump_mode {
+        let jump = Paragraph::new(format!("Jump to: {}_", state.jump_query))
+            .style(theme.selected)
+            .block(search_block);
+        f.render_widget(jump, chunks[3]);
+    } else if state.search_mode || !state.search_query.is_empty() {
         let search_indicator = if state.include_description { "Search (+ desc): " } else { "Search: " };
         let cursor = if state.search_mode { "_" } else { "" };
         let search_text = format!("{}{}{}", search_indicator, state.search_query, cursor);
         let search = Paragraph::new(search_text)
             .style(if state.search_mode { theme.selected } else { theme.normal })
-            .block(Block::default().borders(Borders::ALL));
+            .block(search_block);
         f.render_widget(search, chunks[3]);
     }
 
-    // Help text - render with dynamic wrapping
-    let help_lines = build_help_lines(state, chunks[4].width as usize);
-    let help = Paragraph::new(help_lines).style(theme.help);
-    f.render_widget(help, chunks[4]);
+    // Help text - render with dynamic wrapping, replaced by validation feedback when present
+    if let Some(message) = state.status_message.as_ref().or(state.constraint_warning.as_ref()) {
+        let status = Paragraph::new(message.as_str()).style(theme.danger);
+        f.render_widget(status, chunks[4]);
+    } else {
+        let mut help_lines = build_help_lines(state, bindings, chunks[4].width as usize);
+        help_lines.truncate(chunks[4].height as usize);
+        let help = Paragraph::new(help_lines).style(theme.help);
+        f.render_widget(help, chunks[4]);
+    }
 
     // Show description popup when field is selected (but not when showing suggestions or help)
-    if !state.showing_suggestions && !state.showing_help {
+    if !state.showing_suggestions
+        && !state.showing_help
+        && !state.showing_presets
+        && state.current_tab != OptionTab::Examples
+    {
         if let Some(field) = state.current_field() {
             if !field.description.is_empty() {
-                let area = centered_rect(60, 20, f.area());
+                let area = description_rect(f.area());
                 f.render_widget(Clear, area);
 
+                let wrapped = wrap_description(
+                    &field.description,
+                    area,
+                    config.ui.description_wrap_width,
+                );
+
                 // Calculate scroll info
                 let (_, can_scroll_up, can_scroll_down) =
-                    calc_scroll_info(&field.description, area, state.description_scroll);
+                    calc_scroll_info(wrapped.len(), area, state.description_scroll);
 
                 // Build scroll indicator for title
                 let scroll_indicator = match (can_scroll_up, can_scroll_down) {
@@ -487,9 +857,15 @@ fn draw_form(
                 };
                 let title = format!("Description{}", scroll_indicator);
 
-                let desc = Paragraph::new(field.description.clone())
+                let markdown = if config.ui.render_markdown {
+                    markdown_lines(&wrapped, theme.preview)
+                } else {
+                    None
+                };
+                let lines: Vec<Line> =
+                    markdown.unwrap_or_else(|| wrapped.into_iter().map(Line::from).collect());
+                let desc = Paragraph::new(lines)
                     .block(Block::default().title(title).borders(Borders::ALL))
-                    .wrap(Wrap { trim: true })
                     .scroll((state.description_scroll, 0));
                 f.render_widget(desc, area);
             }
@@ -529,33 +905,105 @@ fn draw_form(
         f.render_widget(list, area);
     }
 
+    // Preset picker
+    if state.showing_presets {
+        let items: Vec<ListItem> = state
+            .presets
+            .iter()
+            .enumerate()
+            .map(|(i, (name, values))| {
+                let style = if i == state.selected_preset { theme.selected } else { theme.normal };
+                ListItem::new(Line::from(Span::styled(
+                    format!("{} ({} value{})", name, values.len(), if values.len() == 1 { "" } else { "s" }),
+                    style,
+                )))
+            })
+            .collect();
+
+        let area = suggestion_rect(50, state.presets.len() as u16 + 2, f.area());
+        f.render_widget(Clear, area);
+        let list = List::new(items)
+            .block(Block::default().title("Load Preset (Enter to load, Esc to close)").borders(Borders::ALL));
+        f.render_widget(list, area);
+    }
+
+    if let Some(chat) = &state.chat {
+        draw_chat_panel(f, chat, theme, config.ui.description_wrap_width);
+    }
+
+    if state.showing_note {
+        let area = centered_rect(60, 40, f.area());
+        f.render_widget(Clear, area);
+        let width = popup_width(area, config.ui.description_wrap_width);
+        let lines: Vec<Line> = wrap_text(&format!("{}_", state.note), width)
+            .into_iter()
+            .map(Line::from)
+            .collect();
+        let note = Paragraph::new(lines).block(
+            Block::default()
+                .title(format!("Note for {} (Enter: new line, Esc: close)", spec.command))
+                .borders(Borders::ALL),
+        );
+        f.render_widget(note, area);
+    }
+
     // Show help sheet popup when requested (render last to be on top)
     if state.showing_help {
         let area = centered_rect(70, 60, f.area());
         f.render_widget(Clear, area);
 
+        // Keys as configured in [keybindings]; several actions share a line
+        let keys = |actions: &[Action]| {
+            actions
+                .iter()
+                .map(|action| bindings.label(*action))
+                .filter(|label| !label.is_empty())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
         let help_items = vec![
-            ("↑/↓ or j/k", "Navigate fields"),
-            ("PgUp/PgDn", "Page navigation"),
-            ("Home/End", "Jump to top/bottom"),
-            ("Enter", "Edit field / Toggle bool / Cycle enum"),
-            ("Tab/Shift+Tab", "Next/previous field"),
-            ("Ctrl+↑/↓", "Scroll description"),
-            ("/", "Search by flag name"),
-            ("Ctrl+/", "Search including descriptions"),
-            ("1/2/3", "Switch to Basic/Advanced/Frequent tab"),
-            ("`", "Cycle through tabs"),
-            ("Ctrl+X", "Clear all values"),
-            ("Ctrl+E", "Execute command"),
-            ("Ctrl+P", "Preview command"),
-            ("q/Esc", "Cancel"),
+            (keys(&[Action::Up, Action::Down]), "Navigate fields"),
+            (keys(&[Action::PageUp, Action::PageDown]), "Page navigation"),
+            (keys(&[Action::Top, Action::Bottom]), "Jump to top/bottom"),
+            (keys(&[Action::Edit]), "Edit field / Toggle bool / Cycle enum"),
+            (keys(&[Action::AcceptEnvHint]), "Use suggested env var"),
+            (keys(&[Action::NextField, Action::PrevField]), "Next/previous field"),
+            (keys(&[Action::ScrollUp, Action::ScrollDown]), "Scroll description"),
+            (keys(&[Action::Search]), "Search by flag name"),
+            (keys(&[Action::Jump]), "Jump to a flag by name"),
+            (keys(&[Action::ToggleSection]), "Jump between Arguments and Options"),
+            (keys(&[Action::TypeFilter]), "Filter by type (bool/string/int/float/path/enum/all)"),
+            (keys(&[Action::ToggleSensitive]), "Toggle sensitive (masking, caching, history)"),
+            (
+                keys(&[Action::AddValue, Action::RemoveValue]),
+                "Add another value / drop the last one (repeatable options)",
+            ),
+            (keys(&[Action::SearchDescriptions]), "Search including descriptions"),
+            (
+                keys(&[Action::TabBasic, Action::TabAdvanced, Action::TabFrequent, Action::TabExamples]),
+                "Switch to Basic/Advanced/Frequent/Examples tab",
+            ),
+            (keys(&[Action::NextTab, Action::PrevTab]), "Cycle through tabs"),
+            (keys(&[Action::Clear]), "Clear all values"),
+            (keys(&[Action::ClearSensitive]), "Clear sensitive values only"),
+            (keys(&[Action::LoadPreset]), "Load a saved preset"),
+            (keys(&[Action::SavePreset]), "Save values as a named preset (not sensitive ones)"),
+            (keys(&[Action::Note]), "View or edit your note about this command"),
+            (keys(&[Action::Execute]), "Execute command"),
+            (keys(&[Action::Background]), "Run command in the background"),
+            (keys(&[Action::Preview]), "Preview command"),
+            (keys(&[Action::ExportInvocation]), "Save values to a shareable invocation file and exit"),
+            (keys(&[Action::Copy]), "Copy command to clipboard (sensitive values unmasked)"),
+            (keys(&[Action::Assistant]), "Ask the assistant which options to use"),
+            (keys(&[Action::Quit]), "Cancel"),
         ];
+        let width = help_items.iter().map(|(key, _)| key.chars().count()).max().unwrap_or(0) + 2;
 
         let items: Vec<ListItem> = help_items
             .iter()
             .map(|(key, desc)| {
                 ListItem::new(Line::from(vec![
-                    Span::styled(format!("{:15}", key), Style::default().add_modifier(Modifier::BOLD)),
+                    Span::styled(format!("{:width$}", key), Style::default().add_modifier(Modifier::BOLD)),
                     Span::raw(*desc),
                 ]))
             })
@@ -567,6 +1015,39 @@ fn draw_form(
     }
 }
 
+/// The assistant panel: the last request and reply above the input line
+fn draw_chat_panel(f: &mut Frame, chat: &ChatPanel, theme: &Theme, wrap_width: Option<usize>) {
+    let area = centered_rect(70, 60, f.area());
+    f.render_widget(Clear, area);
+
+    let mut lines = Vec::new();
+    if let Some(asked) = &chat.asked {
+        lines.push(Line::from(Span::styled(format!("> {}", asked), theme.selected)));
+        lines.push(Line::from(""));
+    }
+    if chat.waiting {
+        lines.push(Line::from(Span::styled("Thinking...", theme.help)));
+    } else if let Some(reply) = &chat.reply {
+        lines.extend(reply.lines().map(|line| Line::from(line.to_string())));
+    } else {
+        lines.push(Line::from(Span::styled(
+            "Describe what you want to do, e.g. \"download the file and follow redirects\"",
+            theme.help,
+        )));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(format!("Ask: {}_", chat.input), theme.selected)));
+
+    let hint = if chat.suggestion.is_some() {
+        "Assistant (Enter: ask, Tab: apply suggestion, Esc: close)"
+    } else {
+        "Assistant (Enter: ask, Esc: close)"
+    };
+    let panel = Paragraph::new(wrap_lines(&lines, popup_width(area, wrap_width)))
+        .block(Block::default().title(hint).borders(Borders::ALL));
+    f.render_widget(panel, area);
+}
+
 /// Helper function to create a rect for suggestions popup
 fn suggestion_rect(width: u16, height: u16, r: Rect) -> Rect {
     let height = height.min(15); // Max height of 15
@@ -599,7 +1080,7 @@ fn build_preview(spec: &CommandSpec, state: &FormState) -> String {
 
     // First pass: flags (non-positional)
     for field in &state.fields {
-        if field.value.is_empty() || field.id.starts_with("_pos_") {
+        if !field.is_set() || field.id.starts_with("_pos_") {
             continue;
         }
 
@@ -610,15 +1091,18 @@ fn build_preview(spec: &CommandSpec, state: &FormState) -> String {
                 }
             }
             _ => {
-                flag_parts.push(field.id.clone());
-                let display_value = if field.sensitive {
-                    "***".to_string()
-                } else if field.value.contains(' ') {
-                    format!("\"{}\"", field.value)
-                } else {
-                    field.value.clone()
-                };
-                flag_parts.push(display_value);
+                // The flag is repeated for each value of a repeatable option
+                for value in field.all_values() {
+                    flag_parts.push(field.id.clone());
+                    let display_value = if field.sensitive {
+                        "***".to_string()
+                    } else if value.contains(' ') {
+                        format!("\"{}\"", value)
+                    } else {
+                        value.to_string()
+                    };
+                    flag_parts.push(display_value);
+                }
             }
         }
     }
@@ -651,15 +1135,192 @@ fn build_preview(spec: &CommandSpec, state: &FormState) -> String {
     parts.join(" ")
 }
 
+/// The LLM client for assist requests, created on first use
+fn chat_client_for(
+    client: &mut Option<Arc<dyn LlmClient>>,
+    config: &Config,
+) -> crate::Result<Arc<dyn LlmClient>> {
+    if let Some(client) = client {
+        return Ok(client.clone());
+    }
+    let created: Arc<dyn LlmClient> = Arc::from(create_client(config, &GenerationOptions::default())?);
+    *client = Some(created.clone());
+    Ok(created)
+}
+
+/// The form's result for a confirmed run, in the foreground or the background
+fn run_result(background: bool, values: HashMap<String, String>) -> FormResult {
+    if background {
+        FormResult::Background(values)
+    } else {
+        FormResult::Execute(values)
+    }
+}
+
+/// Validate the form for running, asking before running with conflicting
+/// options and, with `ui.review_before_exec`, showing the final review
+fn ready_to_run(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    state: &mut FormState,
+    spec: &CommandSpec,
+    theme: &Theme,
+    config: &Config,
+) -> Result<bool> {
+    if !state.validate_for_execution() || !state.validate_requirements(spec) || !state.validate_values() {
+        return Ok(false);
+    }
+    let wrap_width = config.ui.description_wrap_width;
+    if !confirm_conflicts(terminal, state, spec, theme, wrap_width)? {
+        return Ok(false);
+    }
+    if config.ui.review_before_exec {
+        return review_before_exec(terminal, state, spec, theme, wrap_width);
+    }
+    Ok(true)
+}
+
+/// Summarize the values about to be run; Esc goes back to the form
+fn review_before_exec(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    state: &FormState,
+    spec: &CommandSpec,
+    theme: &Theme,
+    wrap_width: Option<usize>,
+) -> Result<bool> {
+    let summary = state.review_summary();
+    let width = summary.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+
+    let mut content = vec![
+        Line::from(vec![
+            Span::styled(spec.command.clone(), theme.header),
+            Span::raw(" - danger level: "),
+            Span::styled(
+                spec.danger_level.to_string(),
+                if spec.danger_level == DangerLevel::High || spec.danger_level == DangerLevel::Critical {
+                    theme.danger
+                } else {
+                    theme.normal
+                },
+            ),
+        ]),
+        Line::from(""),
+    ];
+    if summary.is_empty() {
+        content.push(Line::from(Span::styled("(no values set)", theme.help)));
+    }
+    content.extend(summary.into_iter().map(|(name, value)| {
+        Line::from(vec![
+            Span::styled(format!("  {:width$}  ", name), Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(value),
+        ])
+    }));
+    content.extend([
+        Line::from(""),
+        Line::from(Span::styled("Press 'y' to run, Esc to go back and edit", theme.help)),
+    ]);
+
+    run_yes_no_dialog(terminal, "Review", content, wrap_width)
+}
+
+/// Ask before running with conflicting options set; true when there are none
+fn confirm_conflicts(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    state: &FormState,
+    spec: &CommandSpec,
+    theme: &Theme,
+    wrap_width: Option<usize>,
+) -> Result<bool> {
+    let conflicts: Vec<String> = state
+        .validate_constraints(spec)
+        .into_iter()
+        .filter(|v| v.kind == ConstraintKind::Conflict)
+        .map(|v| v.message)
+        .collect();
+    if conflicts.is_empty() {
+        return Ok(true);
+    }
+
+    let mut content = vec![
+        Line::from(Span::styled("⚠️  CONFLICTING OPTIONS", theme.danger)),
+        Line::from(""),
+    ];
+    content.extend(conflicts.into_iter().map(|message| Line::from(format!("  {}", message))));
+    content.extend([
+        Line::from(""),
+        Line::from("The command will likely fail or ignore one of them. Run it anyway?"),
+        Line::from(""),
+        Line::from(Span::styled("Press 'y' to execute, 'n' to go back", theme.help)),
+    ]);
+
+    run_yes_no_dialog(terminal, "Confirmation Required", content, wrap_width)
+}
+
+/// When the selected field has just been given a value that conflicts with
+/// other set fields, offer to disable them; declining leaves every value as typed,
+/// and running then asks about the conflict again (see [`confirm_conflicts`])
+fn resolve_conflicts(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    state: &mut FormState,
+    spec: &CommandSpec,
+    theme: &Theme,
+    was_set: bool,
+    wrap_width: Option<usize>,
+) -> Result<()> {
+    let selected = state.selected;
+    let Some(field) = state.current_field() else {
+        return Ok(());
+    };
+    if was_set || !field.is_set() {
+        return Ok(());
+    }
+    let conflicting = state.conflicting_fields(spec, selected);
+    if conflicting.is_empty() {
+        return Ok(());
+    }
+
+    let enabling = field.id.clone();
+    let others: Vec<String> = conflicting
+        .iter()
+        .filter_map(|&i| state.fields.get(i))
+        .map(|f| f.id.clone())
+        .collect();
+    let content = vec![
+        Line::from(Span::styled("⚠️  CONFLICTING OPTIONS", theme.danger)),
+        Line::from(""),
+        Line::from(format!("Disable {} to enable {}?", others.join(", "), enabling)),
+        Line::from(""),
+        Line::from(Span::styled("Press 'y' to disable, 'n' to keep both", theme.help)),
+    ];
+
+    if run_yes_no_dialog(terminal, "Conflict", content, wrap_width)? {
+        state.disable_fields(&conflicting);
+    } else {
+        state.status_message = Some(format!("{} conflicts with {}", enabling, others.join(", ")));
+    }
+    Ok(())
+}
+
+/// Put text on the system clipboard, opening the clipboard on first use
+fn copy_to_clipboard(
+    clipboard: &mut Option<arboard::Clipboard>,
+    text: String,
+) -> Result<(), arboard::Error> {
+    let clipboard = match clipboard {
+        Some(clipboard) => clipboard,
+        None => clipboard.insert(arboard::Clipboard::new()?),
+    };
+    clipboard.set_text(text)
+}
+
 /// Show danger confirmation dialog
-pub fn confirm_dangerous(spec: &CommandSpec, command_line: &str) -> Result<bool> {
+pub fn confirm_dangerous(ui: &UiConfig, spec: &CommandSpec, command_line: &str) -> Result<bool> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let result = run_confirm_dialog(&mut terminal, spec, command_line);
+    let result = run_confirm_dialog(&mut terminal, spec, command_line, ui.description_wrap_width);
 
     disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
@@ -671,32 +1332,237 @@ fn run_confirm_dialog(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     spec: &CommandSpec,
     command_line: &str,
+    wrap_width: Option<usize>,
+) -> Result<bool> {
+    let theme = Theme::dark();
+    let content = vec![
+        Line::from(Span::styled(
+            "⚠️  DANGEROUS COMMAND",
+            theme.danger,
+        )),
+        Line::from(""),
+        Line::from(format!("This command has a {} danger level.", spec.danger_level)),
+        Line::from(""),
+        Line::from("Command to execute:"),
+        Line::from(Span::styled(command_line.to_string(), theme.preview)),
+        Line::from(""),
+        Line::from("Are you sure you want to proceed?"),
+        Line::from(""),
+        Line::from(Span::styled("Press 'y' to execute, 'n' to cancel", theme.help)),
+    ];
+
+    run_yes_no_dialog(terminal, "Confirmation Required", content, wrap_width)
+}
+
+/// Show a warning gate before the form opens for a critical command
+pub fn confirm_open_form(ui: &UiConfig, spec: &CommandSpec, command: &str) -> Result<bool> {
+    // May query the terminal (theme = "auto"), so before raw mode
+    let theme = Theme::from_config(ui);
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    let mut content = vec![
+        Line::from(Span::styled(
+            "⚠️  CRITICAL COMMAND",
+            theme.danger,
+        )),
+        Line::from(""),
+        Line::from(format!(
+            "'{}' has a {} danger level and can cause irreversible damage.",
+            command, spec.danger_level
+        )),
+    ];
+    if !spec.description.is_empty() {
+        content.push(Line::from(""));
+        content.push(Line::from(Span::styled(spec.description.clone(), theme.normal)));
+    }
+    content.extend([
+        Line::from(""),
+        Line::from("Open the form anyway?"),
+        Line::from(""),
+        Line::from(Span::styled("Press 'y' to continue, 'n' to cancel", theme.help)),
+    ]);
+
+    let result = run_yes_no_dialog(&mut terminal, "Critical Command", content, ui.description_wrap_width);
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+
+    result
+}
+
+/// Let the user pick one of the command's subcommands, typing to filter the list.
+/// Esc fills in the command itself instead; Ctrl+C cancels.
+pub fn pick_subcommand(spec: &CommandSpec, command: &str) -> Result<SubcommandPick> {
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    let result = run_subcommand_picker(&mut terminal, spec, command);
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+
+    result
+}
+
+fn run_subcommand_picker(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    spec: &CommandSpec,
+    command: &str,
+) -> Result<SubcommandPick> {
+    let theme = Theme::dark();
+    let mut filter = String::new();
+    let mut selected = 0;
+    loop {
+        let query = filter.to_lowercase();
+        let matches: Vec<&String> = spec
+            .subcommands
+            .iter()
+            .filter(|name| name.to_lowercase().contains(&query))
+            .collect();
+        selected = selected.min(matches.len().saturating_sub(1));
+
+        terminal.draw(|f| {
+            let area = centered_rect(60, 70, f.area());
+            f.render_widget(Clear, area);
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(3), Constraint::Min(1), Constraint::Length(1)])
+                .split(area);
+
+            let search = Paragraph::new(format!("{}_", filter))
+                .block(Block::default().title("Filter").borders(Borders::ALL));
+            f.render_widget(search, chunks[0]);
+
+            let items: Vec<ListItem> = matches
+                .iter()
+                .map(|name| ListItem::new(format!(" {} {}", command, name)))
+                .collect();
+            let title = format!("Subcommands of {} ({}/{})", command, matches.len(), spec.subcommands.len());
+            let list = List::new(items)
+                .block(Block::default().title(title).borders(Borders::ALL))
+                .highlight_style(theme.selected);
+            let mut list_state = ListState::default().with_selected(Some(selected));
+            f.render_stateful_widget(list, chunks[1], &mut list_state);
+
+            let help = Paragraph::new(format!("Enter: open the form · Esc: fill in {} itself", command))
+                .style(theme.help);
+            f.render_widget(help, chunks[2]);
+        })?;
+
+        if let Event::Key(key) = event::read()? {
+            match key.code {
+                KeyCode::Esc => return Ok(SubcommandPick::Command),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(SubcommandPick::Cancel)
+                }
+                KeyCode::Enter => {
+                    if let Some(name) = matches.get(selected) {
+                        return Ok(SubcommandPick::Subcommand(name.to_string()));
+                    }
+                }
+                KeyCode::Up => selected = selected.saturating_sub(1),
+                KeyCode::Down => selected += 1,
+                KeyCode::Backspace => {
+                    filter.pop();
+                    selected = 0;
+                }
+                KeyCode::Char(c) => {
+                    filter.push(c);
+                    selected = 0;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Ask before running a command through an interactive shell, which sources its rc files
+pub fn confirm_via_shell(ui: &UiConfig, shell: &str, command_line: &str) -> Result<bool> {
+    // May query the terminal (theme = "auto"), so before raw mode
+    let theme = Theme::from_config(ui);
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    let content = vec![
+        Line::from(format!("The command will run through an interactive {}:", shell)),
+        Line::from(""),
+        Line::from(Span::styled(command_line.to_string(), theme.preview)),
+        Line::from(""),
+        Line::from("Its rc files are sourced first, and aliases or functions may"),
+        Line::from("replace what the command line appears to run."),
+        Line::from(""),
+        Line::from(Span::styled("Press 'y' to run, 'n' to cancel", theme.help)),
+    ];
+
+    let result = run_yes_no_dialog(&mut terminal, "Run Via Shell", content, ui.description_wrap_width);
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+
+    result
+}
+
+/// Ask whether a run may replace previously cached values
+pub fn confirm_value_overwrite(ui: &UiConfig, changes: &[ValueOverwrite]) -> Result<bool> {
+    // May query the terminal (theme = "auto"), so before raw mode
+    let theme = Theme::from_config(ui);
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    let mut content = vec![
+        Line::from("This run would replace these cached values:"),
+        Line::from(""),
+    ];
+    for change in changes {
+        content.push(Line::from(vec![
+            Span::styled(format!("{}: ", change.flag), theme.header),
+            Span::styled(change.old.clone(), theme.help),
+            Span::raw(" → "),
+            Span::styled(change.new.clone(), theme.normal),
+        ]));
+    }
+    content.extend([
+        Line::from(""),
+        Line::from("Update the cache? The command runs either way."),
+        Line::from(""),
+        Line::from(Span::styled("Press 'y' to update, 'n' to keep the cached values", theme.help)),
+    ]);
+
+    let result = run_yes_no_dialog(&mut terminal, "Cached Values", content, ui.description_wrap_width);
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+
+    result
+}
+
+/// Draw a bordered dialog and wait for a y/n answer
+fn run_yes_no_dialog(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    title: &str,
+    content: Vec<Line<'static>>,
+    wrap_width: Option<usize>,
 ) -> Result<bool> {
     loop {
         terminal.draw(|f| {
             let area = centered_rect(70, 50, f.area());
             f.render_widget(Clear, area);
 
-            let theme = Theme::dark();
-            let content = vec![
-                Line::from(Span::styled(
-                    "⚠️  DANGEROUS COMMAND",
-                    theme.danger,
-                )),
-                Line::from(""),
-                Line::from(format!("This command has a {} danger level.", spec.danger_level)),
-                Line::from(""),
-                Line::from("Command to execute:"),
-                Line::from(Span::styled(command_line, theme.preview)),
-                Line::from(""),
-                Line::from("Are you sure you want to proceed?"),
-                Line::from(""),
-                Line::from(Span::styled("Press 'y' to execute, 'n' to cancel", theme.help)),
-            ];
-
-            let paragraph = Paragraph::new(content)
-                .block(Block::default().title("Confirmation Required").borders(Borders::ALL))
-                .wrap(Wrap { trim: true });
+            let paragraph = Paragraph::new(wrap_lines(&content, popup_width(area, wrap_width)))
+                .block(Block::default().title(title).borders(Borders::ALL));
 
             f.render_widget(paragraph, area);
         })?;
@@ -732,46 +1598,33 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         .split(popup_layout[1])[1]
 }
 
-/// Estimate the maximum scroll offset for a description
-fn estimate_max_scroll(description: &str, terminal_height: u16) -> u16 {
-    // Popup is 20% of terminal height, minus 2 for borders
-    let popup_height = (terminal_height as f32 * 0.20) as u16;
-    let content_height = popup_height.saturating_sub(2);
-
-    if content_height == 0 {
-        return 0;
-    }
+/// Area of the description popup within the terminal area
+fn description_rect(r: Rect) -> Rect {
+    centered_rect(60, 20, r)
+}
 
-    // Estimate wrapped lines: assume ~60 chars per line (60% of terminal width)
-    let chars_per_line = 50;
-    let estimated_lines = (description.len() as u16 / chars_per_line) + 1;
+/// Wrap a description to fit inside the popup borders
+fn wrap_description(description: &str, area: Rect, wrap_width: Option<usize>) -> Vec<String> {
+    wrap_text(description, popup_width(area, wrap_width))
+}
 
-    // Max scroll is the number of lines that don't fit
-    estimated_lines.saturating_sub(content_height)
+/// Max scroll offset for a description shown in a terminal of the given size
+fn max_description_scroll(description: &str, terminal_area: Rect, wrap_width: Option<usize>) -> u16 {
+    let area = description_rect(terminal_area);
+    let total_lines = wrap_description(description, area, wrap_width).len();
+    calc_scroll_info(total_lines, area, 0).0
 }
 
-/// Calculate scroll information for a description in the given area
-fn calc_scroll_info(description: &str, area: Rect, scroll_offset: u16) -> (u16, bool, bool) {
+/// Calculate scroll information for wrapped content in the given area
+fn calc_scroll_info(total_lines: usize, area: Rect, scroll_offset: u16) -> (u16, bool, bool) {
     // Content area is area minus borders
     let content_height = area.height.saturating_sub(2);
-    let content_width = area.width.saturating_sub(2);
 
-    if content_height == 0 || content_width == 0 {
+    if content_height == 0 {
         return (0, false, false);
     }
 
-    // Estimate wrapped lines
-    let mut total_lines = 0u16;
-    for line in description.lines() {
-        let line_len = line.len() as u16;
-        let wrapped = if line_len == 0 {
-            1
-        } else {
-            (line_len + content_width - 1) / content_width
-        };
-        total_lines += wrapped;
-    }
-
+    let total_lines = u16::try_from(total_lines).unwrap_or(u16::MAX);
     let max_scroll = total_lines.saturating_sub(content_height);
     let can_scroll_up = scroll_offset > 0;
     let can_scroll_down = scroll_offset < max_scroll;
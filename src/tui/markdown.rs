@@ -0,0 +1,115 @@
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+
+/// Render wrapped description lines with lightweight markdown: `**bold**`,
+/// `` `code` `` spans and `-`/`*`/`+` bullets (shown as `•`).
+///
+/// Bold and code spans may continue across wrapped lines. Returns None when a
+/// marker is left unclosed, so the caller can fall back to plain text rather
+/// than styling the rest of the description by accident.
+pub fn markdown_lines(lines: &[String], code_style: Style) -> Option<Vec<Line<'static>>> {
+    let mut bold = false;
+    let mut code = false;
+    let mut rendered = Vec::with_capacity(lines.len());
+
+    for line in lines {
+        let line = bullet(line);
+        let mut spans = Vec::new();
+        let mut current = String::new();
+        let mut chars = line.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            let toggle_code = c == '`';
+            let toggle_bold = !code && c == '*' && chars.peek() == Some(&'*');
+            if !toggle_code && !toggle_bold {
+                current.push(c);
+                continue;
+            }
+
+            if !current.is_empty() {
+                spans.push(Span::styled(std::mem::take(&mut current), style(bold, code, code_style)));
+            }
+            if toggle_code {
+                code = !code;
+            } else {
+                chars.next();
+                bold = !bold;
+            }
+        }
+        if !current.is_empty() {
+            spans.push(Span::styled(current, style(bold, code, code_style)));
+        }
+        rendered.push(Line::from(spans));
+    }
+
+    (!bold && !code).then_some(rendered)
+}
+
+/// Replace a leading `- `, `* ` or `+ ` list marker with a bullet, keeping indentation
+fn bullet(line: &str) -> String {
+    let content = line.trim_start();
+    let indent = &line[..line.len() - content.len()];
+    for marker in ["- ", "* ", "+ "] {
+        if let Some(rest) = content.strip_prefix(marker) {
+            return format!("{}• {}", indent, rest);
+        }
+    }
+    line.to_string()
+}
+
+fn style(bold: bool, code: bool, code_style: Style) -> Style {
+    let style = if code { code_style } else { Style::default() };
+    if bold {
+        style.add_modifier(Modifier::BOLD)
+    } else {
+        style
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Color;
+
+    fn code_style() -> Style {
+        Style::default().fg(Color::Green)
+    }
+
+    fn lines(text: &[&str]) -> Vec<String> {
+        text.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_markdown_bold_and_code() {
+        let rendered = markdown_lines(&lines(&["Use **with care**, see `man ls`"]), code_style()).unwrap();
+        let spans = &rendered[0].spans;
+        assert_eq!(spans.len(), 4);
+        assert_eq!(spans[0].content, "Use ");
+        assert_eq!(spans[1].content, "with care");
+        assert!(spans[1].style.add_modifier.contains(Modifier::BOLD));
+        assert_eq!(spans[3].content, "man ls");
+        assert_eq!(spans[3].style.fg, Some(Color::Green));
+    }
+
+    #[test]
+    fn test_markdown_bullets_and_literal_stars_in_code() {
+        let rendered = markdown_lines(&lines(&["  - first", "* `**/*.rs`"]), code_style()).unwrap();
+        assert_eq!(rendered[0].spans[0].content, "  • first");
+        assert_eq!(rendered[1].spans[0].content, "• ");
+        assert_eq!(rendered[1].spans[1].content, "**/*.rs");
+    }
+
+    #[test]
+    fn test_markdown_bold_continues_across_wrapped_lines() {
+        let rendered = markdown_lines(&lines(&["a **long", "phrase** end"]), code_style()).unwrap();
+        assert!(rendered[1].spans[0].style.add_modifier.contains(Modifier::BOLD));
+        assert_eq!(rendered[1].spans[1].content, " end");
+        assert!(!rendered[1].spans[1].style.add_modifier.contains(Modifier::BOLD));
+    }
+
+    #[test]
+    fn test_markdown_unclosed_marker_falls_back() {
+        assert!(markdown_lines(&lines(&["glob like `*.txt"]), code_style()).is_none());
+        assert!(markdown_lines(&lines(&["a ** b"]), code_style()).is_none());
+    }
+}
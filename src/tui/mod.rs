@@ -1,5 +1,10 @@
 mod form;
+mod markdown;
 mod theme;
 mod widgets;
+mod wrap;
 
-pub use form::{confirm_dangerous, run_form, FormResult};
+pub use form::{
+    confirm_dangerous, confirm_open_form, confirm_value_overwrite, confirm_via_shell, pick_subcommand, run_form,
+    FormChanges, FormResult, SubcommandPick,
+};
@@ -1,4 +1,10 @@
+use crate::config::UiConfig;
 use ratatui::style::{Color, Modifier, Style};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// How long to wait for the terminal to report its background color
+const BACKGROUND_QUERY_TIMEOUT: Duration = Duration::from_millis(150);
 
 pub struct Theme {
     pub normal: Style,
@@ -9,6 +15,7 @@ pub struct Theme {
     pub preview: Style,
     pub danger: Style,
     pub help: Style,
+    pub deprecated: Style,
 }
 
 impl Theme {
@@ -30,10 +37,12 @@ impl Theme {
                 .fg(Color::Red)
                 .add_modifier(Modifier::BOLD),
             help: Style::default().fg(Color::DarkGray),
+            deprecated: Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::ITALIC),
         }
     }
 
-    #[allow(dead_code)]
     pub fn light() -> Self {
         Self {
             normal: Style::default().fg(Color::Black),
@@ -52,6 +61,234 @@ impl Theme {
                 .fg(Color::Red)
                 .add_modifier(Modifier::BOLD),
             help: Style::default().fg(Color::Gray),
+            deprecated: Style::default()
+                .fg(Color::Gray)
+                .add_modifier(Modifier::ITALIC),
+        }
+    }
+
+    /// The theme named by `ui.theme`; anything unrecognized or unparsable falls back to dark.
+    /// `auto` may query the terminal, so call this before the form starts reading input.
+    pub fn from_config(ui: &UiConfig) -> Self {
+        match ui.theme.as_str() {
+            "light" => Self::light(),
+            "auto" => match detect_light_background() {
+                Some(true) => Self::light(),
+                _ => Self::dark(),
+            },
+            "custom" => Self::custom(&ui.colors).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring ui.colors ({}); using the dark theme", e);
+                Self::dark()
+            }),
+            _ => Self::dark(),
+        }
+    }
+
+    /// The dark theme with the foreground of each element in `colors` replaced
+    fn custom(colors: &std::collections::HashMap<String, String>) -> Result<Self, String> {
+        let mut theme = Self::dark();
+        for (element, value) in colors {
+            let color = Color::from_str(value.trim())
+                .map_err(|_| format!("{} = \"{}\" is not a color", element, value))?;
+            let style = match element.as_str() {
+                "normal" => &mut theme.normal,
+                "selected" => &mut theme.selected,
+                "required" => &mut theme.required,
+                "sensitive" => &mut theme.sensitive,
+                "header" => &mut theme.header,
+                "preview" => &mut theme.preview,
+                "danger" => &mut theme.danger,
+                "help" => &mut theme.help,
+                _ => return Err(format!("unknown element \"{}\"", element)),
+            };
+            *style = style.fg(color);
+        }
+        Ok(theme)
+    }
+}
+
+/// Whether the terminal background is light: from `COLORFGBG` when the terminal
+/// sets it, otherwise by asking the terminal (OSC 11). None if neither answers.
+fn detect_light_background() -> Option<bool> {
+    if let Some(light) = std::env::var("COLORFGBG").ok().as_deref().and_then(background_is_light) {
+        return Some(light);
+    }
+    let reply = query_background_color(BACKGROUND_QUERY_TIMEOUT)?;
+    let light = osc11_background_is_light(&reply);
+    tracing::debug!("Terminal background reply {:?}: light = {:?}", reply, light);
+    light
+}
+
+/// Send the OSC 11 background color query and collect the reply, giving up
+/// after `timeout` (many terminals and multiplexers never answer).
+///
+/// A DA1 (device attributes) query follows it, which virtually every terminal
+/// answers, and in order: once its reply is in, any OSC 11 reply is too, so
+/// nothing arrives late to be read as keystrokes by the form.
+#[cfg(unix)]
+fn query_background_color(timeout: Duration) -> Option<String> {
+    use std::io::{IsTerminal, Read, Write};
+    use std::time::Instant;
+
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        return None;
+    }
+    // Raw mode so the reply is neither echoed nor held back until Enter
+    let was_raw = crossterm::terminal::is_raw_mode_enabled().unwrap_or(false);
+    if !was_raw {
+        crossterm::terminal::enable_raw_mode().ok()?;
+    }
+
+    let mut reply = Vec::new();
+    let mut stdout = std::io::stdout();
+    if stdout.write_all(b"\x1b]11;?\x1b\\\x1b[c").and_then(|_| stdout.flush()).is_ok() {
+        let deadline = Instant::now() + timeout;
+        let mut stdin = std::io::stdin();
+        while !has_da1_reply(&reply) {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let mut fds = libc::pollfd { fd: libc::STDIN_FILENO, events: libc::POLLIN, revents: 0 };
+            // SAFETY: fds is a single valid pollfd that outlives the call
+            let ready = unsafe { libc::poll(&mut fds, 1, remaining.as_millis() as libc::c_int) };
+            if ready <= 0 {
+                break;
+            }
+            let mut buf = [0u8; 64];
+            match stdin.read(&mut buf) {
+                Ok(n) if n > 0 => reply.extend_from_slice(&buf[..n]),
+                _ => break,
+            }
+        }
+        if !has_da1_reply(&reply) {
+            // Timed out: drop whatever part of a reply has arrived
+            // SAFETY: tcflush only discards pending input on stdin
+            unsafe {
+                libc::tcflush(libc::STDIN_FILENO, libc::TCIFLUSH);
+            }
+        }
+    }
+
+    if !was_raw {
+        crossterm::terminal::disable_raw_mode().ok();
+    }
+    osc11_reply(&reply)
+}
+
+/// Whether `buf` holds a complete DA1 reply, `ESC [ ? <attributes> c`
+fn has_da1_reply(buf: &[u8]) -> bool {
+    buf.windows(3)
+        .position(|w| w == b"\x1b[?")
+        .is_some_and(|start| buf[start + 3..].contains(&b'c'))
+}
+
+/// The OSC 11 reply within everything read back, up to its BEL or ST (ESC \\)
+fn osc11_reply(buf: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(buf);
+    let start = text.find("\x1b]11;")?;
+    let reply = &text[start..];
+    let end = reply
+        .find('\x07')
+        .map(|i| i + 1)
+        .or_else(|| reply.find("\x1b\\").map(|i| i + 2))?;
+    Some(reply[..end].to_string())
+}
+
+#[cfg(not(unix))]
+fn query_background_color(_timeout: Duration) -> Option<String> {
+    None
+}
+
+/// Whether an OSC 11 reply like `ESC ]11;rgb:ffff/ffff/dddd BEL` reports a light
+/// color; each channel has 1-4 hex digits
+fn osc11_background_is_light(reply: &str) -> Option<bool> {
+    let rgb = reply.split("rgb:").nth(1)?;
+    let rgb = rgb.trim_end_matches(['\x07', '\\']).trim_end_matches('\x1b');
+    let channels: Vec<f64> = rgb
+        .split('/')
+        .map(|channel| {
+            let max = 16f64.powi(channel.len() as i32) - 1.0;
+            match channel.len() {
+                1..=4 => u32::from_str_radix(channel, 16).ok().map(|v| f64::from(v) / max),
+                _ => None,
+            }
+        })
+        .collect::<Option<_>>()?;
+    let [r, g, b] = channels[..] else {
+        return None;
+    };
+    // Relative luminance, close enough to tell light from dark
+    Some(0.2126 * r + 0.7152 * g + 0.0722 * b > 0.5)
+}
+
+/// Whether `COLORFGBG` ("fg;bg", sometimes "fg;default;bg") describes a light
+/// background: by rxvt convention, colors 7 and 9-15 are light
+fn background_is_light(colorfgbg: &str) -> Option<bool> {
+    let bg: u8 = colorfgbg.rsplit(';').next()?.trim().parse().ok()?;
+    Some(bg == 7 || (9..=15).contains(&bg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_background_is_light() {
+        assert_eq!(background_is_light("15;0"), Some(false));
+        assert_eq!(background_is_light("0;15"), Some(true));
+        assert_eq!(background_is_light("0;default;7"), Some(true));
+        assert_eq!(background_is_light("15;8"), Some(false));
+        assert_eq!(background_is_light("default"), None);
+    }
+
+    #[test]
+    fn test_osc11_background_is_light() {
+        assert_eq!(osc11_background_is_light("\x1b]11;rgb:ffff/ffff/ffff\x07"), Some(true));
+        assert_eq!(osc11_background_is_light("\x1b]11;rgb:1e1e/1e1e/2e2e\x1b\\"), Some(false));
+        assert_eq!(osc11_background_is_light("\x1b]11;rgb:fd/f6/e3\x07"), Some(true));
+        assert_eq!(osc11_background_is_light("\x1b]11;rgb:0/0/0\x07"), Some(false));
+        assert_eq!(osc11_background_is_light("\x1b]11;rgb:ffff/ffff\x07"), None);
+        assert_eq!(osc11_background_is_light("garbage"), None);
+    }
+
+    #[test]
+    fn test_osc11_reply_is_split_from_da1_reply() {
+        let read = b"\x1b]11;rgb:ffff/ffff/ffff\x1b\\\x1b[?62;22c";
+        assert!(has_da1_reply(read));
+        assert_eq!(osc11_reply(read).as_deref(), Some("\x1b]11;rgb:ffff/ffff/ffff\x1b\\"));
+
+        // A terminal without OSC 11 answers only DA1
+        assert!(has_da1_reply(b"\x1b[?1;2c"));
+        assert_eq!(osc11_reply(b"\x1b[?1;2c"), None);
+        assert!(!has_da1_reply(b"\x1b]11;rgb:0/0/0\x07\x1b[?6"));
+    }
+
+    #[test]
+    fn test_custom_theme_overrides_colors() {
+        let ui = UiConfig {
+            theme: "custom".to_string(),
+            colors: HashMap::from([
+                ("selected".to_string(), "#00afff".to_string()),
+                ("help".to_string(), "lightblue".to_string()),
+            ]),
+            ..UiConfig::default()
+        };
+        let theme = Theme::from_config(&ui);
+        assert_eq!(theme.selected.fg, Some(Color::Rgb(0x00, 0xaf, 0xff)));
+        // Modifiers come from the dark theme
+        assert!(theme.selected.add_modifier.contains(Modifier::BOLD));
+        assert_eq!(theme.help.fg, Some(Color::LightBlue));
+        assert_eq!(theme.normal, Theme::dark().normal);
+    }
+
+    #[test]
+    fn test_custom_theme_falls_back_to_dark() {
+        for (element, value) in [("selected", "not-a-color"), ("border", "red")] {
+            let ui = UiConfig {
+                theme: "custom".to_string(),
+                colors: HashMap::from([(element.to_string(), value.to_string())]),
+                ..UiConfig::default()
+            };
+            assert_eq!(Theme::from_config(&ui).selected, Theme::dark().selected);
         }
     }
 }
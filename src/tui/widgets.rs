@@ -1,13 +1,121 @@
-use crate::parser::{ArgumentType, CommandOption, OptionLevel, PositionalArg};
-use crate::shell::get_env_suggestions;
+use crate::executor::{split_values, ParsedCommand, VALUE_SEPARATOR};
+use crate::parser::{ArgumentType, CommandOption, CommandSpec, OptionLevel, PositionalArg};
+use crate::shell::{
+    best_matching_env_var, contains_env_var, credential_env_patterns, flag_env_patterns, get_all_env_vars,
+    get_env_suggestions,
+};
 use std::collections::HashMap;
 
+/// Which `conflicts_with` / `requires` rule a set of values breaks
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstraintKind {
+    /// Both options are set but the spec says they conflict
+    Conflict,
+    /// An option is set but one it requires is empty
+    MissingRequirement,
+}
+
+/// A broken option constraint, between `field` and `other` (field indices)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConstraintViolation {
+    pub field: usize,
+    pub other: usize,
+    pub kind: ConstraintKind,
+    pub message: String,
+}
+
 /// Tab categories for organizing options
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum OptionTab {
     Basic,
     Advanced,
     Frequent,
+    /// The spec's example invocations, listed instead of fields
+    Examples,
+}
+
+/// Order in which fields are laid out in the form
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FieldOrder {
+    /// As generated from the help text
+    Documented,
+    /// Positionals first, then options sorted by flag name
+    Alphabetical,
+    /// Required fields first, otherwise as documented
+    RequiredFirst,
+    /// Positionals first, then options grouped by argument type
+    TypeGrouped,
+}
+
+impl FieldOrder {
+    /// Parse a `ui.field_order` setting, falling back to documented order
+    pub fn from_config(value: &str) -> Self {
+        match value {
+            "alphabetical" => FieldOrder::Alphabetical,
+            "required_first" => FieldOrder::RequiredFirst,
+            "type_grouped" => FieldOrder::TypeGrouped,
+            _ => FieldOrder::Documented,
+        }
+    }
+}
+
+/// How field labels are rendered in the form list
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LabelStyle {
+    /// Short and long flag, e.g. `-v, --verbose`
+    Friendly,
+    /// Only the flag that will be emitted
+    Primary,
+    /// Every flag the option accepts
+    AllFlags,
+}
+
+impl LabelStyle {
+    /// Parse a `ui.label_style` setting, falling back to friendly labels
+    pub fn from_config(value: &str) -> Self {
+        match value {
+            "primary" => LabelStyle::Primary,
+            "all_flags" => LabelStyle::AllFlags,
+            _ => LabelStyle::Friendly,
+        }
+    }
+}
+
+/// Confirmation required after Ctrl+E (or Ctrl+B) before the command runs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExecuteConfirm {
+    /// Run at once
+    Off,
+    /// Press Ctrl+E a second time
+    Twice,
+    /// Press Enter; any other key cancels
+    Enter,
+}
+
+impl ExecuteConfirm {
+    /// Parse a `ui.confirm_execute` setting, falling back to no confirmation
+    pub fn from_config(value: &str) -> Self {
+        match value {
+            "twice" => ExecuteConfirm::Twice,
+            "enter" => ExecuteConfirm::Enter,
+            _ => ExecuteConfirm::Off,
+        }
+    }
+}
+
+/// The assistant panel opened with Ctrl+A
+#[derive(Debug, Clone, Default)]
+pub struct ChatPanel {
+    /// Request being typed
+    pub input: String,
+    /// Last request sent
+    pub asked: Option<String>,
+    /// Reply (or error) for the last request
+    pub reply: Option<String>,
+    /// Command line the reply suggests, applied with Tab
+    pub suggestion: Option<String>,
+    /// A request is in flight
+    pub waiting: bool,
 }
 
 /// Form field representing a single input
@@ -15,14 +123,58 @@ pub enum OptionTab {
 pub struct FormField {
     pub id: String,
     pub label: String,
+    /// All flags of the option (empty for positionals)
+    pub flags: Vec<String>,
+    /// Value placeholder like `FILE`, shown after the flag for non-bool options
+    pub argument_name: Option<String>,
     pub description: String,
     pub field_type: ArgumentType,
     pub required: bool,
     pub sensitive: bool,
+    /// The value being edited; for a repeatable option, the latest of its values
     pub value: String,
+    /// Whether the option may be given several times (`-e A=1 -e B=2`)
+    pub repeatable: bool,
+    /// Earlier values of a repeatable option, added with `+`
+    pub repeated: Vec<String>,
     pub enum_values: Vec<String>,
     pub default: Option<String>,
     pub level: OptionLevel,
+    pub deprecated: bool,
+    /// Example value shown greyed out while the field is empty
+    pub example: Option<String>,
+    /// Accepted range of an int/float field
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    /// Regex a string field's whole value should match
+    pub pattern: Option<ValuePattern>,
+}
+
+/// A documented value pattern, compiled once when the field is built
+#[derive(Debug, Clone)]
+pub struct ValuePattern {
+    /// The pattern as documented, for messages
+    pub source: String,
+    /// The pattern anchored to the whole value; None if it doesn't compile
+    regex: Option<regex::Regex>,
+}
+
+impl ValuePattern {
+    pub fn new(source: &str) -> Self {
+        // A malformed pattern from the spec shouldn't get in the way of the command
+        let regex = regex::Regex::new(&format!("^(?:{})$", source))
+            .map_err(|e| tracing::debug!("Ignoring value pattern {:?}: {}", source, e))
+            .ok();
+        ValuePattern {
+            source: source.to_string(),
+            regex,
+        }
+    }
+
+    /// Whether `value` matches; a pattern that didn't compile accepts anything
+    pub fn matches(&self, value: &str) -> bool {
+        self.regex.as_ref().is_none_or(|regex| regex.is_match(value))
+    }
 }
 
 impl FormField {
@@ -42,14 +194,27 @@ impl FormField {
         Self {
             id,
             label,
+            flags: opt.flags.clone(),
+            argument_name: opt
+                .argument_name
+                .clone()
+                .filter(|name| !name.is_empty() && opt.argument_type != ArgumentType::Bool),
             description: opt.description.clone(),
             field_type: opt.argument_type.clone(),
             required: opt.required,
             sensitive: opt.sensitive,
             value: String::new(),
+            // A repeated boolean is still one on/off switch in the form
+            repeatable: opt.repeatable && opt.argument_type != ArgumentType::Bool,
+            repeated: Vec::new(),
             enum_values: opt.enum_values.clone(),
             default: opt.default.clone(),
             level: opt.level.clone(),
+            deprecated: opt.deprecated,
+            example: opt.example.clone().filter(|e| !e.is_empty()),
+            min: opt.min,
+            max: opt.max,
+            pattern: opt.pattern.as_deref().map(ValuePattern::new),
         }
     }
 
@@ -57,19 +222,138 @@ impl FormField {
         Self {
             id: format!("_pos_{}", arg.name),
             label: arg.name.clone(),
+            flags: vec![],
+            argument_name: None,
             description: arg.description.clone(),
             field_type: arg.argument_type.clone(),
             required: arg.required,
             sensitive: arg.sensitive,
             value: String::new(),
+            repeatable: false,
+            repeated: Vec::new(),
             enum_values: vec![],
             default: arg.default.clone(),
             level: OptionLevel::Basic, // Positional args are always basic
+            deprecated: false,
+            example: None,
+            min: None,
+            max: None,
+            pattern: None,
+        }
+    }
+
+    /// Label to show in the form list; positionals always show their name
+    pub fn display_label(&self, style: LabelStyle) -> String {
+        if self.is_positional() {
+            return self.label.clone();
+        }
+        let flags = match style {
+            LabelStyle::Friendly => self.label.clone(),
+            LabelStyle::Primary => self.id.clone(),
+            LabelStyle::AllFlags if !self.flags.is_empty() => self.flags.join(", "),
+            LabelStyle::AllFlags => self.label.clone(),
+        };
+        match &self.argument_name {
+            Some(name) => format!("{} <{}>", flags, name),
+            None => flags,
+        }
+    }
+
+    pub fn is_positional(&self) -> bool {
+        self.id.starts_with("_pos_")
+    }
+
+    /// Whether the field contributes to the command (a bool must be on)
+    pub fn is_set(&self) -> bool {
+        match self.field_type {
+            ArgumentType::Bool => self.value == "true",
+            _ => !self.value.trim().is_empty() || !self.repeated.is_empty(),
+        }
+    }
+
+    /// Every value of the field, earlier repeated ones first
+    pub fn all_values(&self) -> Vec<&str> {
+        self.repeated
+            .iter()
+            .map(String::as_str)
+            .chain((!self.value.is_empty()).then_some(self.value.as_str()))
+            .collect()
+    }
+
+    /// The field's value as passed to the command builder: a repeatable
+    /// option's values are joined with [`VALUE_SEPARATOR`]
+    pub fn full_value(&self) -> String {
+        self.all_values().join(&VALUE_SEPARATOR.to_string())
+    }
+
+    /// Set the value from a stored one, splitting a repeatable option's values
+    pub fn set_value(&mut self, value: &str) {
+        let mut values: Vec<String> = split_values(value, self.repeatable)
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        self.value = values.pop().unwrap_or_default();
+        self.repeated = values;
+    }
+
+    /// Check each value against the field's type and documented range or
+    /// pattern; env var references like `$PORT` are left for the shell
+    pub fn validate(&self) -> Result<(), String> {
+        let name = if self.is_positional() { &self.label } else { &self.id };
+        for value in self.all_values() {
+            let value = value.trim();
+            if value.is_empty() || value.starts_with('$') {
+                continue;
+            }
+            match self.field_type {
+                ArgumentType::Int | ArgumentType::Float => {
+                    let number = if self.field_type == ArgumentType::Int {
+                        value.parse::<i64>().map(|n| n as f64).ok()
+                    } else {
+                        value.parse::<f64>().ok()
+                    };
+                    let Some(number) = number else {
+                        let expected = if self.field_type == ArgumentType::Int { "a whole number" } else { "a number" };
+                        return Err(format!("{} expects {}, not {:?}", name, expected, value));
+                    };
+                    if let Some(min) = self.min.filter(|&min| number < min) {
+                        return Err(format!("{} must be at least {}", name, min));
+                    }
+                    if let Some(max) = self.max.filter(|&max| number > max) {
+                        return Err(format!("{} must be at most {}", name, max));
+                    }
+                }
+                ArgumentType::String => {
+                    if let Some(pattern) = self.pattern.as_ref().filter(|p| !p.matches(value)) {
+                        return Err(format!("{} must match {}", name, pattern.source));
+                    }
+                }
+                _ => {}
+            }
         }
+        Ok(())
+    }
+
+    pub fn clear_value(&mut self) {
+        self.value.clear();
+        self.repeated.clear();
+    }
+
+    /// Flag name without dashes, for alphabetical sorting
+    fn sort_name(&self) -> String {
+        self.id.trim_start_matches('-').to_lowercase()
     }
 
     /// Get display value (masked for sensitive)
     pub fn display_value(&self) -> String {
+        if !self.repeated.is_empty() {
+            let values = self.all_values();
+            return if self.sensitive {
+                values.iter().map(|v| "*".repeat(v.len().min(20))).collect::<Vec<_>>().join(", ")
+            } else {
+                values.join(", ")
+            };
+        }
         if self.sensitive && !self.value.is_empty() {
             "*".repeat(self.value.len().min(20))
         } else if self.value.is_empty() {
@@ -82,6 +366,14 @@ impl FormField {
             self.value.clone()
         }
     }
+
+    /// Example hint for an empty field, unless a default is already shown
+    pub fn placeholder(&self) -> Option<String> {
+        if self.is_set() || self.default.is_some() {
+            return None;
+        }
+        self.example.as_ref().map(|example| format!("e.g. {}", example))
+    }
 }
 
 /// Form state
@@ -95,6 +387,11 @@ pub struct FormState {
     pub search_query: String,
     pub filtered_indices: Vec<usize>,
     pub include_description: bool,
+    // Only show fields of this argument type (composes with tabs and search)
+    pub type_filter: Option<ArgumentType>,
+    // Jump-to-flag state (moves selection without filtering)
+    pub jump_mode: bool,
+    pub jump_query: String,
     // Tab state
     pub current_tab: OptionTab,
     pub basic_indices: Vec<usize>,    // indices of basic-level fields
@@ -104,26 +401,66 @@ pub struct FormState {
     pub showing_suggestions: bool,
     pub env_suggestions: Vec<(String, String)>, // (name, value)
     pub selected_suggestion: usize,
+    // Proactive env var hint for the selected field (var name)
+    pub env_hint: Option<String>,
+    // The environment as it was when the form opened, for suggestions and hints
+    pub env_vars: HashMap<String, String>,
+    // Conflict between the selected field and another set option, if any
+    pub constraint_warning: Option<String>,
     // Description scroll state
     pub description_scroll: u16,
     // Help sheet state
     pub showing_help: bool,
+    // Validation state: fields blocking execution and the message explaining why
+    pub missing_fields: Vec<usize>,
+    pub status_message: Option<String>,
+    // Sensitivity toggled by the user this session, keyed by field id
+    pub sensitivity_overrides: HashMap<String, bool>,
+    // Named value presets for this command, sorted by name
+    pub presets: Vec<(String, HashMap<String, String>)>,
+    // Presets saved this session, for the caller to persist
+    pub saved_presets: Vec<(String, HashMap<String, String>)>,
+    // Name being typed for a new preset (Some while prompting)
+    pub preset_name_input: Option<String>,
+    // Assistant panel (Some while open)
+    pub chat: Option<ChatPanel>,
+    // The user's note about the command, shown in the header
+    pub note: String,
+    // Note popup open, for reading and editing the note
+    pub showing_note: bool,
+    // The note was edited, so the caller should save it
+    pub note_changed: bool,
+    // Preset picker state
+    pub showing_presets: bool,
+    pub selected_preset: usize,
+    // Example invocations for the Examples tab (empty when ui.show_examples is off)
+    pub examples: Vec<String>,
+    pub selected_example: usize,
+    // Ctrl+E (or Ctrl+B) was pressed and awaits its confirming keystroke
+    pub execute_armed: bool,
+    // The armed run goes to the background (Ctrl+B)
+    pub armed_background: bool,
+    // Whether the field being edited had a value when editing started
+    pub editing_was_set: bool,
+    // Ignore keystrokes that can't be part of a number in int/float fields
+    pub strict_numeric: bool,
 }
 
 impl FormState {
     pub fn new(fields: Vec<FormField>) -> Self {
-        // Compute basic and advanced indices based on level
+        // Compute basic and advanced indices based on level; deprecated options
+        // are only shown under Advanced to keep the Basic tab uncluttered
         let basic_indices: Vec<usize> = fields
             .iter()
             .enumerate()
-            .filter(|(_, f)| f.level == OptionLevel::Basic)
+            .filter(|(_, f)| f.level == OptionLevel::Basic && !f.deprecated)
             .map(|(i, _)| i)
             .collect();
 
         let advanced_indices: Vec<usize> = fields
             .iter()
             .enumerate()
-            .filter(|(_, f)| f.level == OptionLevel::Advanced)
+            .filter(|(_, f)| f.level == OptionLevel::Advanced || f.deprecated)
             .map(|(i, _)| i)
             .collect();
 
@@ -143,6 +480,9 @@ impl FormState {
             search_query: String::new(),
             filtered_indices,
             include_description: false,
+            type_filter: None,
+            jump_mode: false,
+            jump_query: String::new(),
             current_tab: OptionTab::Basic,
             basic_indices,
             advanced_indices,
@@ -150,9 +490,52 @@ impl FormState {
             showing_suggestions: false,
             env_suggestions: Vec::new(),
             selected_suggestion: 0,
+            env_hint: None,
+            env_vars: get_all_env_vars(),
+            constraint_warning: None,
             description_scroll: 0,
             showing_help: false,
+            missing_fields: Vec::new(),
+            status_message: None,
+            sensitivity_overrides: HashMap::new(),
+            presets: Vec::new(),
+            saved_presets: Vec::new(),
+            preset_name_input: None,
+            chat: None,
+            note: String::new(),
+            showing_note: false,
+            note_changed: false,
+            showing_presets: false,
+            selected_preset: 0,
+            examples: Vec::new(),
+            selected_example: 0,
+            execute_armed: false,
+            armed_background: false,
+            editing_was_set: false,
+            strict_numeric: true,
+        }
+    }
+
+    /// Create form state with fields reordered according to `order`
+    pub fn with_field_order(mut fields: Vec<FormField>, order: FieldOrder) -> Self {
+        // Stable sorts keep documented order among equal keys
+        match order {
+            FieldOrder::Documented => {}
+            FieldOrder::Alphabetical => {
+                // Positionals keep their documented order since it is significant
+                fields.sort_by_key(|f| {
+                    let name = if f.is_positional() { String::new() } else { f.sort_name() };
+                    (!f.is_positional(), name)
+                });
+            }
+            FieldOrder::RequiredFirst => {
+                fields.sort_by_key(|f| !f.required);
+            }
+            FieldOrder::TypeGrouped => {
+                fields.sort_by_key(|f| (!f.is_positional(), type_rank(&f.field_type)));
+            }
         }
+        Self::new(fields)
     }
 
     /// Cycle to next tab
@@ -160,13 +543,30 @@ impl FormState {
         self.current_tab = match self.current_tab {
             OptionTab::Basic => OptionTab::Advanced,
             OptionTab::Advanced => OptionTab::Frequent,
-            OptionTab::Frequent => OptionTab::Basic,
+            OptionTab::Frequent if !self.examples.is_empty() => OptionTab::Examples,
+            OptionTab::Frequent | OptionTab::Examples => OptionTab::Basic,
+        };
+        self.apply_tab_filter();
+    }
+
+    /// Switch to the previous tab, the reverse of [`FormState::next_tab`]
+    pub fn prev_tab(&mut self) {
+        self.current_tab = match self.current_tab {
+            OptionTab::Basic if !self.examples.is_empty() => OptionTab::Examples,
+            OptionTab::Basic => OptionTab::Frequent,
+            OptionTab::Advanced => OptionTab::Basic,
+            OptionTab::Frequent => OptionTab::Advanced,
+            OptionTab::Examples => OptionTab::Frequent,
         };
         self.apply_tab_filter();
     }
 
     /// Set specific tab
     pub fn set_tab(&mut self, tab: OptionTab) {
+        if tab == OptionTab::Examples && self.examples.is_empty() {
+            self.status_message = Some("No examples for this command".to_string());
+            return;
+        }
         self.current_tab = tab;
         self.apply_tab_filter();
     }
@@ -195,7 +595,10 @@ impl FormState {
                 // Don't fall back to all - empty is correct when nothing has been used
                 self.filtered_indices = self.frequent_indices.clone();
             }
+            // Examples replace the field list; the fields keep the previous filter
+            OptionTab::Examples => return,
         }
+        self.retain_type_filter();
 
         // Re-apply search filter if there's an active search
         if !self.search_query.is_empty() {
@@ -216,6 +619,16 @@ impl FormState {
         self.update_filter();
     }
 
+    /// Open search mode with `query` already typed and its best match selected
+    pub fn start_search_with(&mut self, query: &str) {
+        self.start_search(false);
+        self.search_query = query.to_string();
+        self.update_filter();
+        if let Some(&best) = self.filtered_indices.first() {
+            self.selected = best;
+        }
+    }
+
     /// Stop search mode
     pub fn stop_search(&mut self) {
         self.search_mode = false;
@@ -225,8 +638,38 @@ impl FormState {
     pub fn clear_search(&mut self) {
         self.search_query.clear();
         self.filtered_indices = (0..self.fields.len()).collect();
+        self.retain_type_filter();
         self.search_mode = false;
-        self.selected = 0;
+        self.selected = self.filtered_indices.first().copied().unwrap_or(0);
+    }
+
+    /// Show only fields of the given type (None shows all types)
+    pub fn filter_by_type(&mut self, field_type: Option<ArgumentType>) {
+        self.type_filter = field_type;
+        self.apply_tab_filter();
+    }
+
+    /// Cycle the type filter: all → bool → string → int → float → path → enum → all
+    pub fn cycle_type_filter(&mut self) {
+        let next = match self.type_filter {
+            None => Some(ArgumentType::Bool),
+            Some(ArgumentType::Bool) => Some(ArgumentType::String),
+            Some(ArgumentType::String) => Some(ArgumentType::Int),
+            Some(ArgumentType::Int) => Some(ArgumentType::Float),
+            Some(ArgumentType::Float) => Some(ArgumentType::Path),
+            Some(ArgumentType::Path) => Some(ArgumentType::Enum),
+            Some(ArgumentType::Enum) => None,
+        };
+        self.filter_by_type(next);
+    }
+
+    /// Drop filtered fields that don't match the type filter
+    fn retain_type_filter(&mut self) {
+        if let Some(field_type) = &self.type_filter {
+            let fields = &self.fields;
+            self.filtered_indices
+                .retain(|&i| fields[i].field_type == *field_type);
+        }
     }
 
     /// Add character to search query
@@ -253,38 +696,15 @@ impl FormState {
                 .iter()
                 .enumerate()
                 .filter_map(|(i, field)| {
-                    let label_lower = field.label.to_lowercase();
-                    let id_lower = field.id.to_lowercase();
-                    let desc_lower = field.description.to_lowercase();
-
-                    // Exact flag match gets highest priority
-                    if id_lower == query || label_lower.contains(&format!("{},", &query)) {
-                        return Some((i, 100));
-                    }
-
-                    // Flag starts with query
-                    if id_lower.starts_with(&query) || label_lower.starts_with(&query) {
-                        return Some((i, 50));
-                    }
-
-                    // Flag contains query
-                    if id_lower.contains(&query) || label_lower.contains(&query) {
-                        return Some((i, 25));
-                    }
-
-                    // Description contains query (if enabled)
-                    if self.include_description && desc_lower.contains(&query) {
-                        return Some((i, 10));
-                    }
-
-                    None
+                    match_score(field, &query, self.include_description).map(|score| (i, score))
                 })
                 .collect();
 
             // Sort by score descending
-            scored.sort_by(|a, b| b.1.cmp(&a.1));
+            scored.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
             self.filtered_indices = scored.into_iter().map(|(i, _)| i).collect();
         }
+        self.retain_type_filter();
 
         // Adjust selected to stay within filtered results
         if !self.filtered_indices.is_empty() {
@@ -301,6 +721,62 @@ impl FormState {
         }
     }
 
+    /// Start jump-to-flag mode
+    pub fn start_jump(&mut self) {
+        self.jump_mode = true;
+        self.jump_query.clear();
+    }
+
+    /// Leave jump mode without moving
+    pub fn cancel_jump(&mut self) {
+        self.jump_mode = false;
+        self.jump_query.clear();
+    }
+
+    pub fn jump_insert_char(&mut self, c: char) {
+        self.jump_query.push(c);
+    }
+
+    pub fn jump_delete_char(&mut self) {
+        self.jump_query.pop();
+    }
+
+    /// Select the best match for the jump query and leave jump mode.
+    /// Returns false if nothing matched.
+    pub fn finish_jump(&mut self) -> bool {
+        let query = self.jump_query.to_lowercase();
+        self.cancel_jump();
+        if query.is_empty() {
+            return false;
+        }
+
+        // Highest score wins; ties go to the earliest field
+        let best = self
+            .fields
+            .iter()
+            .enumerate()
+            .filter_map(|(i, field)| match_score(field, &query, false).map(|score| (i, score)))
+            .min_by_key(|&(i, score)| (std::cmp::Reverse(score), i));
+        let Some((target, _)) = best else {
+            return false;
+        };
+
+        // Bring the target into view if the current tab or search hides it
+        if !self.filtered_indices.contains(&target) {
+            self.search_query.clear();
+            let tab = match self.fields[target].level {
+                _ if self.fields[target].deprecated => OptionTab::Advanced,
+                OptionLevel::Basic => OptionTab::Basic,
+                OptionLevel::Advanced => OptionTab::Advanced,
+            };
+            self.set_tab(tab);
+        }
+
+        self.selected = target;
+        self.description_scroll = 0;
+        true
+    }
+
     /// Get visible fields (filtered)
     pub fn visible_fields(&self) -> Vec<(usize, &FormField)> {
         self.filtered_indices
@@ -318,6 +794,10 @@ impl FormState {
     }
 
     pub fn move_up(&mut self) {
+        if self.current_tab == OptionTab::Examples {
+            self.selected_example = self.selected_example.saturating_sub(1);
+            return;
+        }
         if self.filtered_indices.is_empty() {
             return;
         }
@@ -335,6 +815,12 @@ impl FormState {
     }
 
     pub fn move_down(&mut self) {
+        if self.current_tab == OptionTab::Examples {
+            if self.selected_example + 1 < self.examples.len() {
+                self.selected_example += 1;
+            }
+            return;
+        }
         if self.filtered_indices.is_empty() {
             return;
         }
@@ -351,6 +837,24 @@ impl FormState {
         }
     }
 
+    /// Jump between the Arguments (positional) and Options (flag) sections,
+    /// selecting the first visible field of the other section
+    pub fn toggle_section(&mut self) {
+        let in_arguments = self
+            .current_field()
+            .map(|f| f.is_positional())
+            .unwrap_or(false);
+        let target = self
+            .filtered_indices
+            .iter()
+            .copied()
+            .find(|&i| self.fields[i].is_positional() != in_arguments);
+        if let Some(target) = target {
+            self.selected = target;
+            self.description_scroll = 0;
+        }
+    }
+
     /// Move to first field (Home)
     pub fn move_to_top(&mut self) {
         if !self.filtered_indices.is_empty() {
@@ -415,8 +919,9 @@ impl FormState {
 
     pub fn start_editing(&mut self) {
         self.editing = true;
-        if let Some(field) = self.current_field() {
-            self.cursor_pos = field.value.len();
+        if let Some((len, set)) = self.current_field().map(|f| (f.value.len(), f.is_set())) {
+            self.cursor_pos = len;
+            self.editing_was_set = set;
         }
     }
 
@@ -424,9 +929,65 @@ impl FormState {
         self.editing = false;
     }
 
+    /// Handle the execute (or, with `background`, the background) key (`key`, as
+    /// shown to the user): whether to run now, or (when a confirmation is
+    /// configured) wait for the confirming keystroke with a prompt in the status line
+    pub fn request_execute(&mut self, confirm: ExecuteConfirm, key: &str, background: bool) -> bool {
+        if confirm == ExecuteConfirm::Off || self.execute_armed {
+            self.execute_armed = false;
+            return true;
+        }
+        self.execute_armed = true;
+        self.armed_background = background;
+        self.status_message = Some(match confirm {
+            ExecuteConfirm::Enter => "Press Enter to run, any other key to cancel".to_string(),
+            _ => format!("Press {} again to run", key),
+        });
+        false
+    }
+
+    /// Keep the selected repeatable option's current value and start typing another
+    pub fn add_repeated_value(&mut self) {
+        let Some(field) = self.current_field_mut() else {
+            return;
+        };
+        if !field.repeatable {
+            self.status_message = Some("This option takes a single value".to_string());
+            return;
+        }
+        if !field.value.trim().is_empty() {
+            let value = std::mem::take(&mut field.value);
+            field.repeated.push(value);
+        }
+        self.start_editing();
+    }
+
+    /// Drop the selected repeatable option's latest value, bringing back the one before
+    pub fn remove_repeated_value(&mut self) {
+        if let Some(field) = self.current_field_mut() {
+            if field.repeatable {
+                field.value = field.repeated.pop().unwrap_or_default();
+            }
+        }
+    }
+
     pub fn insert_char(&mut self, c: char) {
         let pos = self.cursor_pos;
+        let strict = self.strict_numeric;
         if let Some(field) = self.current_field_mut() {
+            let float = match field.field_type {
+                ArgumentType::Int => Some(false),
+                ArgumentType::Float => Some(true),
+                _ => None,
+            };
+            if let Some(float) = float.filter(|_| strict) {
+                let mut typed = field.value.clone();
+                typed.insert(pos, c);
+                // An env var reference ($JOBS) is resolved later, so anything goes
+                if !typed.starts_with('$') && !is_numeric_prefix(&typed, float) {
+                    return;
+                }
+            }
             field.value.insert(pos, c);
         }
         self.cursor_pos += 1;
@@ -459,12 +1020,13 @@ impl FormState {
             if field.field_type == ArgumentType::Enum && !field.enum_values.is_empty() {
                 if field.required {
                     // Required enums: cycle through values only
-                    let current_idx = field
-                        .enum_values
-                        .iter()
-                        .position(|v| v == &field.value)
-                        .unwrap_or(0);
-                    let next_idx = (current_idx + 1) % field.enum_values.len();
+                    let current_idx = field.enum_values.iter().position(|v| v == &field.value);
+                    let next_idx = match current_idx {
+                        Some(idx) => (idx + 1) % field.enum_values.len(),
+                        // A value outside the list (e.g. an unlisted default) starts over
+                        None if !field.value.is_empty() => 0,
+                        None => 1 % field.enum_values.len(),
+                    };
                     field.value = field.enum_values[next_idx].clone();
                 } else {
                     // Optional enums: include empty state in cycle
@@ -481,238 +1043,1371 @@ impl FormState {
                             field.value = String::new();
                         }
                     } else {
-                        // Value not in enum_values -> reset to empty
-                        field.value = String::new();
+                        // Value not in enum_values (e.g. an unlisted default) -> first value,
+                        // so the cycle continues instead of dropping back to empty
+                        field.value = field.enum_values[0].clone();
                     }
                 }
             }
         }
     }
 
-    /// Get all values as a HashMap
-    pub fn get_values(&self) -> HashMap<String, String> {
+    /// Indices of required fields that have neither a value nor a default
+    ///
+    /// Booleans are skipped: an unset flag is a valid answer, not a missing one.
+    pub fn missing_required(&self) -> Vec<usize> {
         self.fields
             .iter()
-            .filter(|f| !f.value.is_empty())
-            .map(|f| (f.id.clone(), f.value.clone()))
+            .enumerate()
+            .filter(|(_, f)| {
+                f.required
+                    && f.field_type != ArgumentType::Bool
+                    && !f.is_set()
+                    && f.default.is_none()
+            })
+            .map(|(i, _)| i)
             .collect()
     }
 
-    /// Clear all field values
-    pub fn clear_all_values(&mut self) {
-        for field in &mut self.fields {
-            field.value = String::new();
-        }
-    }
-
-    /// Load cached values and track frequent fields
-    pub fn load_cached_values(&mut self, cached: &HashMap<String, String>) {
-        self.frequent_indices.clear();
-        for (i, field) in self.fields.iter_mut().enumerate() {
-            if let Some(value) = cached.get(&field.id) {
-                field.value = value.clone();
-                self.frequent_indices.push(i);
-            }
-        }
-    }
+    /// Check the form can be executed; otherwise flag the missing fields by name
+    /// and select the first one
+    pub fn validate_for_execution(&mut self) -> bool {
+        self.missing_fields = self.missing_required();
+        let Some(&first) = self.missing_fields.first() else {
+            self.status_message = None;
+            return true;
+        };
 
-    /// Update env var suggestions based on current field value
-    pub fn update_env_suggestions(&mut self) {
-        if let Some(field) = self.current_field() {
-            // Find the last $ in the value and get the text after it
-            if let Some(dollar_pos) = field.value.rfind('$') {
-                let after_dollar = &field.value[dollar_pos + 1..];
-                // Check if we're past the $ in cursor position and it's a valid var name start
-                if self.cursor_pos > dollar_pos {
-                    // Get prefix (text after $, could be empty)
-                    let prefix = if after_dollar.contains(|c: char| !c.is_alphanumeric() && c != '_') {
-                        // There's a non-var char after the $, not in env var mode
-                        self.showing_suggestions = false;
-                        self.env_suggestions.clear();
-                        return;
-                    } else {
-                        after_dollar
-                    };
+        let names: Vec<&str> = self
+            .missing_fields
+            .iter()
+            .map(|&i| self.fields[i].label.as_str())
+            .collect();
+        self.status_message = Some(format!(
+            "Missing required argument{}: {}",
+            if names.len() == 1 { "" } else { "s" },
+            names.join(", ")
+        ));
 
-                    // Get suggestions
-                    let suggestions = get_env_suggestions(prefix);
-                    if !suggestions.is_empty() {
-                        self.env_suggestions = suggestions;
-                        self.showing_suggestions = true;
-                        self.selected_suggestion = 0;
-                    } else {
-                        self.showing_suggestions = false;
-                        self.env_suggestions.clear();
-                    }
-                    return;
-                }
-            }
-        }
+        self.reveal_field(first);
+        false
+    }
 
-        // Not in suggestion mode
-        self.showing_suggestions = false;
-        self.env_suggestions.clear();
+    /// Values outside their field's documented type, range or pattern, as messages
+    pub fn invalid_values(&self) -> Vec<String> {
+        self.fields.iter().filter_map(|field| field.validate().err()).collect()
     }
 
-    /// Move to next suggestion
-    pub fn next_suggestion(&mut self) {
-        if !self.env_suggestions.is_empty() {
-            self.selected_suggestion = (self.selected_suggestion + 1) % self.env_suggestions.len();
-        }
+    /// Check every value fits its field; otherwise show why in the status line
+    /// and select the first invalid field
+    pub fn validate_values(&mut self) -> bool {
+        let invalid = self.invalid_values();
+        let Some(first) = self.fields.iter().position(|field| field.validate().is_err()) else {
+            return true;
+        };
+
+        self.status_message = Some(invalid.join("; "));
+        self.reveal_field(first);
+        false
     }
 
-    /// Move to previous suggestion
-    pub fn prev_suggestion(&mut self) {
-        if !self.env_suggestions.is_empty() {
-            if self.selected_suggestion == 0 {
-                self.selected_suggestion = self.env_suggestions.len() - 1;
+    /// Select a field, first dropping any search or type filter and switching
+    /// to the tab that lists it if it is hidden
+    fn reveal_field(&mut self, index: usize) {
+        if !self.filtered_indices.contains(&index) {
+            self.search_query.clear();
+            self.type_filter = None;
+            if self.basic_indices.is_empty() || self.basic_indices.contains(&index) {
+                self.set_tab(OptionTab::Basic);
             } else {
-                self.selected_suggestion -= 1;
+                self.set_tab(OptionTab::Advanced);
             }
         }
+        self.selected = index;
+        self.description_scroll = 0;
     }
 
-    /// Accept the currently selected suggestion
-    pub fn accept_suggestion(&mut self) {
-        if self.showing_suggestions && !self.env_suggestions.is_empty() {
-            let var_name = self.env_suggestions[self.selected_suggestion].0.clone();
+    /// Check the spec's `conflicts_with` and `requires` rules against the
+    /// current values; each conflicting pair is reported once
+    pub fn validate_constraints(&self, spec: &CommandSpec) -> Vec<ConstraintViolation> {
+        let mut violations = Vec::new();
+        for option in &spec.options {
+            let Some(field) = self.fields.iter().position(|f| f.id == option.primary_flag()) else {
+                continue;
+            };
+            if !self.fields[field].is_set() {
+                continue;
+            }
 
-            if let Some(field) = self.current_field_mut() {
-                // Find the last $ and replace everything after it with the var name
-                if let Some(dollar_pos) = field.value.rfind('$') {
-                    field.value.truncate(dollar_pos + 1);
-                    field.value.push_str(&var_name);
-                    self.cursor_pos = field.value.len();
+            for flag in &option.conflicts_with {
+                let Some(other) = self.field_for_flag(flag) else {
+                    continue;
+                };
+                let reported = violations.iter().any(|v: &ConstraintViolation| {
+                    v.kind == ConstraintKind::Conflict && v.field == other && v.other == field
+                });
+                if other != field && self.fields[other].is_set() && !reported {
+                    violations.push(ConstraintViolation {
+                        field,
+                        other,
+                        kind: ConstraintKind::Conflict,
+                        message: format!(
+                            "{} conflicts with {}",
+                            self.fields[field].id, self.fields[other].id
+                        ),
+                    });
                 }
             }
 
-            self.showing_suggestions = false;
-            self.env_suggestions.clear();
+            for flag in &option.requires {
+                let Some(other) = self.field_for_flag(flag) else {
+                    continue;
+                };
+                if !self.fields[other].is_set() {
+                    violations.push(ConstraintViolation {
+                        field,
+                        other,
+                        kind: ConstraintKind::MissingRequirement,
+                        message: format!("{} requires {}", self.fields[field].id, self.fields[other].id),
+                    });
+                }
+            }
         }
+        violations
     }
 
-    /// Cancel showing suggestions
-    pub fn cancel_suggestions(&mut self) {
-        self.showing_suggestions = false;
-        self.env_suggestions.clear();
+    /// Field for a flag named in a constraint, tolerating missing dashes
+    /// (`verbose` for `--verbose`)
+    fn field_for_flag(&self, flag: &str) -> Option<usize> {
+        self.fields
+            .iter()
+            .position(|f| f.flags.iter().any(|f| f == flag))
+            .or_else(|| {
+                let bare = flag.trim_start_matches('-');
+                self.fields
+                    .iter()
+                    .position(|f| !f.is_positional() && f.flags.iter().any(|f| f.trim_start_matches('-') == bare))
+            })
+    }
+
+    /// Set fields that conflict with the field at `index`, whichever side of
+    /// the pair declares the `conflicts_with` rule
+    pub fn conflicting_fields(&self, spec: &CommandSpec, index: usize) -> Vec<usize> {
+        self.validate_constraints(spec)
+            .into_iter()
+            .filter(|v| v.kind == ConstraintKind::Conflict)
+            .filter_map(|v| match (v.field == index, v.other == index) {
+                (true, _) => Some(v.other),
+                (_, true) => Some(v.field),
+                _ => None,
+            })
+            .collect()
     }
 
-    /// Toggle help sheet visibility
-    pub fn toggle_help(&mut self) {
+    /// Clear the given fields to make way for a conflicting one
+    pub fn disable_fields(&mut self, indices: &[usize]) {
+        let mut cleared = Vec::new();
+        for &index in indices {
+            if let Some(field) = self.fields.get_mut(index) {
+                field.clear_value();
+                cleared.push(field.id.clone());
+            }
+        }
+        self.status_message = Some(format!("Disabled {}", cleared.join(", ")));
+    }
+
+    /// Refresh the conflict warning shown while the selected field clashes with another
+    pub fn update_constraint_warning(&mut self, spec: &CommandSpec) {
+        let selected = self.selected;
+        self.constraint_warning = self
+            .validate_constraints(spec)
+            .into_iter()
+            .find(|v| v.kind == ConstraintKind::Conflict && (v.field == selected || v.other == selected))
+            .map(|v| format!("Warning: {}", v.message));
+    }
+
+    /// Block execution while a set option is missing one it requires;
+    /// flags the offending fields and selects the first
+    pub fn validate_requirements(&mut self, spec: &CommandSpec) -> bool {
+        let missing: Vec<ConstraintViolation> = self
+            .validate_constraints(spec)
+            .into_iter()
+            .filter(|v| v.kind == ConstraintKind::MissingRequirement)
+            .collect();
+        let Some(first) = missing.first() else {
+            return true;
+        };
+
+        let first = first.other;
+        self.missing_fields = missing.iter().map(|v| v.other).collect();
+        let messages: Vec<&str> = missing.iter().map(|v| v.message.as_str()).collect();
+        self.status_message = Some(messages.join("; "));
+        self.reveal_field(first);
+        false
+    }
+
+    /// Dismiss validation feedback
+    pub fn clear_status(&mut self) {
+        self.missing_fields.clear();
+        self.status_message = None;
+    }
+
+    /// Flip the selected field's sensitive flag and remember the override
+    pub fn toggle_sensitive(&mut self) {
+        let Some(field) = self.fields.get_mut(self.selected) else {
+            return;
+        };
+        field.sensitive = !field.sensitive;
+        self.sensitivity_overrides
+            .insert(field.id.clone(), field.sensitive);
+        self.status_message = Some(format!(
+            "{} marked {} (remembered for future runs)",
+            field.label,
+            if field.sensitive { "sensitive" } else { "not sensitive" }
+        ));
+    }
+
+    /// Get all values as a HashMap
+    pub fn get_values(&self) -> HashMap<String, String> {
+        self.fields
+            .iter()
+            .filter(|f| !f.value.is_empty() || !f.repeated.is_empty())
+            .map(|f| (f.id.clone(), f.full_value()))
+            .collect()
+    }
+
+    /// Fields that will be passed to the command, as (name, shown value)
+    /// pairs with sensitive values masked; options first, then positionals
+    pub fn review_summary(&self) -> Vec<(String, String)> {
+        let (positionals, options): (Vec<&FormField>, Vec<&FormField>) = self
+            .fields
+            .iter()
+            .filter(|f| f.is_set())
+            .partition(|f| f.is_positional());
+        options
+            .into_iter()
+            .chain(positionals)
+            .map(|f| {
+                let name = if f.is_positional() { f.label.clone() } else { f.id.clone() };
+                let value = if f.field_type == ArgumentType::Bool {
+                    "on".to_string()
+                } else {
+                    f.display_value()
+                };
+                (name, value)
+            })
+            .collect()
+    }
+
+    /// Clear all field values
+    pub fn clear_all_values(&mut self) {
+        for field in &mut self.fields {
+            field.clear_value();
+        }
+    }
+
+    /// Clear only sensitive field values (e.g. to rotate a token), keeping the rest
+    pub fn clear_sensitive_values(&mut self) {
+        let mut cleared = 0;
+        for field in self.fields.iter_mut().filter(|f| f.sensitive && f.is_set()) {
+            field.clear_value();
+            cleared += 1;
+        }
+        self.status_message = Some(format!("Cleared {} sensitive value(s)", cleared));
+    }
+
+    /// Load cached values and track frequent fields
+    pub fn load_cached_values(&mut self, cached: &HashMap<String, String>) {
+        self.frequent_indices.clear();
+        for (i, field) in self.fields.iter_mut().enumerate() {
+            if let Some(value) = cached.get(&field.id) {
+                field.set_value(value);
+                self.frequent_indices.push(i);
+            }
+        }
+    }
+
+    /// Make saved presets available to the picker
+    pub fn set_presets(&mut self, mut presets: Vec<(String, HashMap<String, String>)>) {
+        presets.sort_by(|a, b| a.0.cmp(&b.0));
+        self.presets = presets;
+    }
+
+    /// Start prompting for a name to save the current values under
+    pub fn start_preset_save(&mut self) {
+        self.preset_name_input = Some(String::new());
+    }
+
+    pub fn cancel_preset_save(&mut self) {
+        self.preset_name_input = None;
+    }
+
+    pub fn preset_name_insert_char(&mut self, c: char) {
+        if let Some(name) = &mut self.preset_name_input {
+            name.push(c);
+        }
+    }
+
+    pub fn preset_name_delete_char(&mut self) {
+        if let Some(name) = &mut self.preset_name_input {
+            name.pop();
+        }
+    }
+
+    /// Save the current values, minus sensitive ones, under the typed name
+    /// (replacing a preset of the same name)
+    pub fn finish_preset_save(&mut self) {
+        let Some(name) = self.preset_name_input.take() else {
+            return;
+        };
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return;
+        }
+
+        let values: HashMap<String, String> = self
+            .fields
+            .iter()
+            .filter(|f| !f.sensitive && (!f.value.is_empty() || !f.repeated.is_empty()))
+            .map(|f| (f.id.clone(), f.full_value()))
+            .collect();
+        self.presets.retain(|(existing, _)| *existing != name);
+        self.presets.push((name.clone(), values.clone()));
+        self.presets.sort_by(|a, b| a.0.cmp(&b.0));
+        self.saved_presets.retain(|(existing, _)| *existing != name);
+        self.saved_presets.push((name.clone(), values));
+        self.status_message = Some(format!("Saved preset '{}'", name));
+    }
+
+    /// Open the preset picker, or explain that there is nothing to pick
+    pub fn open_preset_picker(&mut self) {
+        if self.presets.is_empty() {
+            self.status_message = Some("No presets saved for this command (P saves one)".to_string());
+            return;
+        }
+        self.showing_presets = true;
+        self.selected_preset = 0;
+    }
+
+    pub fn close_preset_picker(&mut self) {
+        self.showing_presets = false;
+    }
+
+    pub fn next_preset(&mut self) {
+        if !self.presets.is_empty() {
+            self.selected_preset = (self.selected_preset + 1) % self.presets.len();
+        }
+    }
+
+    pub fn prev_preset(&mut self) {
+        if !self.presets.is_empty() {
+            self.selected_preset = self
+                .selected_preset
+                .checked_sub(1)
+                .unwrap_or(self.presets.len() - 1);
+        }
+    }
+
+    /// Replace the form's values with the selected preset's. Sensitive fields
+    /// keep what they hold, since presets never store them.
+    pub fn apply_selected_preset(&mut self) {
+        self.showing_presets = false;
+        let Some((name, values)) = self.presets.get(self.selected_preset) else {
+            return;
+        };
+        for field in self.fields.iter_mut().filter(|f| !f.sensitive) {
+            field.set_value(values.get(&field.id).map_or("", String::as_str));
+        }
+        self.status_message = Some(format!("Loaded preset '{}'", name));
+    }
+
+    /// Make the spec's examples available in the Examples tab
+    pub fn set_examples(&mut self, examples: Vec<String>) {
+        self.examples = examples;
+        self.selected_example = 0;
+    }
+
+    /// The example highlighted in the Examples tab
+    pub fn current_example(&self) -> Option<&str> {
+        self.examples.get(self.selected_example).map(String::as_str)
+    }
+
+    /// Fill the form from an example parsed with [`crate::executor::parse_command`]
+    /// and go back to the fields. Like presets, sensitive values are kept.
+    pub fn apply_example(&mut self, parsed: &ParsedCommand) {
+        self.fill_from_command(parsed, "the example");
+    }
+
+    fn fill_from_command(&mut self, parsed: &ParsedCommand, source: &str) {
+        for field in self.fields.iter_mut().filter(|f| !f.sensitive) {
+            field.set_value(parsed.values.get(&field.id).map_or("", String::as_str));
+        }
+        self.set_tab(OptionTab::Basic);
+        self.status_message = Some(if parsed.unrecognized.is_empty() {
+            format!("Filled {} field(s) from {}", parsed.values.len(), source)
+        } else {
+            format!(
+                "Filled {} field(s) from {}; skipped {}",
+                parsed.values.len(),
+                source,
+                parsed.unrecognized.join(" ")
+            )
+        });
+    }
+
+    /// First non-blank line of the note, for the header
+    pub fn note_summary(&self) -> Option<&str> {
+        self.note.lines().map(str::trim).find(|line| !line.is_empty())
+    }
+
+    pub fn open_note(&mut self) {
+        self.showing_note = true;
+    }
+
+    pub fn close_note(&mut self) {
+        self.showing_note = false;
+    }
+
+    pub fn note_insert_char(&mut self, c: char) {
+        self.note.push(c);
+        self.note_changed = true;
+    }
+
+    pub fn note_delete_char(&mut self) {
+        if self.note.pop().is_some() {
+            self.note_changed = true;
+        }
+    }
+
+    pub fn open_chat(&mut self) {
+        self.chat.get_or_insert_with(ChatPanel::default);
+    }
+
+    pub fn close_chat(&mut self) {
+        self.chat = None;
+    }
+
+    pub fn chat_insert_char(&mut self, c: char) {
+        if let Some(chat) = &mut self.chat {
+            chat.input.push(c);
+        }
+    }
+
+    pub fn chat_delete_char(&mut self) {
+        if let Some(chat) = &mut self.chat {
+            chat.input.pop();
+        }
+    }
+
+    /// Take the typed request to send, clearing the previous reply; `None`
+    /// when nothing is typed or a request is already in flight
+    pub fn take_chat_message(&mut self) -> Option<String> {
+        let chat = self.chat.as_mut().filter(|chat| !chat.waiting)?;
+        let message = chat.input.trim().to_string();
+        if message.is_empty() {
+            return None;
+        }
+        chat.input.clear();
+        chat.asked = Some(message.clone());
+        chat.reply = None;
+        chat.suggestion = None;
+        chat.waiting = true;
+        Some(message)
+    }
+
+    pub fn receive_chat_reply(&mut self, result: std::result::Result<String, String>) {
+        if let Some(chat) = &mut self.chat {
+            chat.waiting = false;
+            match result {
+                Ok(reply) => {
+                    chat.suggestion = crate::llm::suggested_command(&reply);
+                    chat.reply = Some(reply);
+                }
+                Err(e) => chat.reply = Some(format!("Assist request failed: {}", e)),
+            }
+        }
+    }
+
+    /// Fill the form from the assistant's suggested command (parsed with
+    /// [`crate::executor::parse_command`]) and close the panel
+    pub fn apply_chat_suggestion(&mut self, parsed: &ParsedCommand) {
+        self.close_chat();
+        self.fill_from_command(parsed, "the suggestion");
+    }
+
+    /// Whether a field was pre-filled from cached values
+    pub fn has_cached_value(&self, index: usize) -> bool {
+        self.frequent_indices.contains(&index)
+    }
+
+    /// Update env var suggestions based on current field value
+    pub fn update_env_suggestions(&mut self) {
+        if let Some(field) = self.current_field() {
+            // Find the last $ in the value and get the text after it
+            if let Some(dollar_pos) = field.value.rfind('$') {
+                let after_dollar = &field.value[dollar_pos + 1..];
+                // Check if we're past the $ in cursor position and it's a valid var name start
+                if self.cursor_pos > dollar_pos {
+                    // Get prefix (text after $, could be empty)
+                    let prefix = if after_dollar.contains(|c: char| !c.is_alphanumeric() && c != '_') {
+                        // There's a non-var char after the $, not in env var mode
+                        self.showing_suggestions = false;
+                        self.env_suggestions.clear();
+                        return;
+                    } else {
+                        after_dollar
+                    };
+
+                    // Get suggestions
+                    let suggestions = get_env_suggestions(&self.env_vars, prefix);
+                    if !suggestions.is_empty() {
+                        self.env_suggestions = suggestions;
+                        self.showing_suggestions = true;
+                        self.selected_suggestion = 0;
+                    } else {
+                        self.showing_suggestions = false;
+                        self.env_suggestions.clear();
+                    }
+                    return;
+                }
+            }
+        }
+
+        // Not in suggestion mode
+        self.showing_suggestions = false;
+        self.env_suggestions.clear();
+    }
+
+    /// Look for an env var whose name matches the selected field's flag name.
+    /// Sensitive fields also take any `*_TOKEN`/`*_KEY`/`*_PASSWORD`/`*_SECRET`
+    /// variable, and keep offering the hint while they hold a plaintext value,
+    /// so secrets needn't be pasted into the form.
+    pub fn update_env_hint(&mut self) {
+        self.env_hint = self.current_field().and_then(|field| {
+            if matches!(field.field_type, ArgumentType::Bool | ArgumentType::Enum) {
+                return None;
+            }
+            let wants_hint = field.value.is_empty()
+                || (field.sensitive && !contains_env_var(&field.value));
+            if !wants_hint {
+                return None;
+            }
+            let patterns = flag_env_patterns(&field.id);
+            let credentials = credential_env_patterns(&patterns, field.sensitive);
+            let patterns: Vec<&str> = patterns.iter().map(|p| p.as_str()).collect();
+            let credentials: Vec<&str> = credentials.iter().map(|p| p.as_str()).collect();
+            best_matching_env_var(&self.env_vars, &patterns, &credentials)
+        });
+    }
+
+    /// Fill the selected field with a reference to the hinted env var
+    pub fn accept_env_hint(&mut self) {
+        if let Some(var_name) = self.env_hint.take() {
+            if let Some(field) = self.current_field_mut() {
+                field.value = format!("${}", var_name);
+            }
+        }
+    }
+
+    /// Move to next suggestion
+    pub fn next_suggestion(&mut self) {
+        if !self.env_suggestions.is_empty() {
+            self.selected_suggestion = (self.selected_suggestion + 1) % self.env_suggestions.len();
+        }
+    }
+
+    /// Move to previous suggestion
+    pub fn prev_suggestion(&mut self) {
+        if !self.env_suggestions.is_empty() {
+            if self.selected_suggestion == 0 {
+                self.selected_suggestion = self.env_suggestions.len() - 1;
+            } else {
+                self.selected_suggestion -= 1;
+            }
+        }
+    }
+
+    /// Accept the currently selected suggestion
+    pub fn accept_suggestion(&mut self) {
+        if self.showing_suggestions && !self.env_suggestions.is_empty() {
+            let var_name = self.env_suggestions[self.selected_suggestion].0.clone();
+
+            if let Some(field) = self.current_field_mut() {
+                // Find the last $ and replace everything after it with the var name
+                if let Some(dollar_pos) = field.value.rfind('$') {
+                    field.value.truncate(dollar_pos + 1);
+                    field.value.push_str(&var_name);
+                    self.cursor_pos = field.value.len();
+                }
+            }
+
+            self.showing_suggestions = false;
+            self.env_suggestions.clear();
+        }
+    }
+
+    /// Cancel showing suggestions
+    pub fn cancel_suggestions(&mut self) {
+        self.showing_suggestions = false;
+        self.env_suggestions.clear();
+    }
+
+    /// Toggle help sheet visibility
+    pub fn toggle_help(&mut self) {
         self.showing_help = !self.showing_help;
     }
-}
+}
+
+/// Score how well a field matches a lowercase query, preferring exact flag matches
+fn match_score(field: &FormField, query: &str, include_description: bool) -> Option<i32> {
+    let label_lower = field.label.to_lowercase();
+    let id_lower = field.id.to_lowercase();
+
+    // Exact flag match gets highest priority
+    if id_lower == query || label_lower.contains(&format!("{},", query)) {
+        return Some(100);
+    }
+
+    // Flag starts with query
+    if id_lower.starts_with(query) || label_lower.starts_with(query) {
+        return Some(50);
+    }
+
+    // Flag contains query
+    if id_lower.contains(query) || label_lower.contains(query) {
+        return Some(25);
+    }
+
+    // Description contains query (if enabled)
+    if include_description && field.description.to_lowercase().contains(query) {
+        return Some(10);
+    }
+
+    None
+}
+
+/// Grouping order for `FieldOrder::TypeGrouped`
+fn type_rank(field_type: &ArgumentType) -> u8 {
+    match field_type {
+        ArgumentType::Path => 0,
+        ArgumentType::String => 1,
+        ArgumentType::Int => 2,
+        ArgumentType::Float => 3,
+        ArgumentType::Enum => 4,
+        ArgumentType::Bool => 5,
+    }
+}
+
+/// Whether `text` can still become a number as it is typed: digits after an
+/// optional leading minus and, for floats, one `.` and an exponent (`1.5e-3`)
+fn is_numeric_prefix(text: &str, float: bool) -> bool {
+    let digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    let unsigned = text.strip_prefix('-').unwrap_or(text);
+    if !float {
+        return digits(unsigned);
+    }
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (unsigned, None),
+    };
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    digits(whole)
+        && digits(fraction)
+        && exponent.is_none_or(|exponent| {
+            mantissa.chars().any(|c| c.is_ascii_digit())
+                && digits(exponent.strip_prefix(['-', '+']).unwrap_or(exponent))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::CommandOption;
+    use crate::test_env::EnvGuard;
+
+    // Helper to create a test FormField
+    fn create_test_field(id: &str, field_type: ArgumentType, level: OptionLevel) -> FormField {
+        FormField {
+            id: id.to_string(),
+            label: id.to_string(),
+            flags: vec![id.to_string()],
+            argument_name: None,
+            description: format!("Description for {}", id),
+            field_type,
+            required: false,
+            sensitive: false,
+            value: String::new(),
+            repeatable: false,
+            repeated: Vec::new(),
+            enum_values: vec![],
+            default: None,
+            level,
+            deprecated: false,
+            example: None,
+            min: None,
+            max: None,
+            pattern: None,
+        }
+    }
+
+    #[test]
+    fn test_form_field_from_option() {
+        let opt = CommandOption {
+            flags: vec!["--verbose".to_string(), "-v".to_string()],
+            description: "Enable verbose output".to_string(),
+            argument_type: ArgumentType::Bool,
+            argument_name: None,
+            required: false,
+            sensitive: false,
+            repeatable: false,
+            conflicts_with: vec![],
+            requires: vec![],
+            default: Some("false".to_string()),
+            enum_values: vec![],
+            level: OptionLevel::Basic,
+            deprecated: false,
+            example: None,
+            min: None,
+            max: None,
+            pattern: None,
+        };
+
+        let field = FormField::from_option(&opt);
+        assert_eq!(field.id, "--verbose");
+        assert_eq!(field.label, "-v, --verbose");
+        assert!(field.argument_name.is_none());
+        assert_eq!(field.field_type, ArgumentType::Bool);
+        assert_eq!(field.default, Some("false".to_string()));
+
+        // Placeholders are only kept for options that take a value
+        let mut output = opt.clone();
+        output.flags = vec!["-o".to_string(), "--output".to_string()];
+        output.argument_name = Some("FILE".to_string());
+        assert!(FormField::from_option(&output).argument_name.is_none());
+        output.argument_type = ArgumentType::Path;
+        let field = FormField::from_option(&output);
+        assert_eq!(field.display_label(LabelStyle::Friendly), "-o, --output <FILE>");
+
+        // Example values carry over, except empty ones
+        output.default = None;
+        output.example = Some("out.txt".to_string());
+        assert_eq!(FormField::from_option(&output).placeholder().as_deref(), Some("e.g. out.txt"));
+        output.example = Some(String::new());
+        assert!(FormField::from_option(&output).example.is_none());
+    }
+
+    #[test]
+    fn test_form_field_display_label_styles() {
+        let mut field = create_test_field("--verbose", ArgumentType::Bool, OptionLevel::Basic);
+        field.label = "-v, --verbose".to_string();
+        field.flags = vec!["--verbose".to_string(), "-v".to_string(), "--debug".to_string()];
+
+        assert_eq!(field.display_label(LabelStyle::Friendly), "-v, --verbose");
+        assert_eq!(field.display_label(LabelStyle::Primary), "--verbose");
+        assert_eq!(field.display_label(LabelStyle::AllFlags), "--verbose, -v, --debug");
+
+        let positional = create_positional_field("file", true);
+        assert_eq!(positional.display_label(LabelStyle::Primary), "file");
+        assert_eq!(positional.display_label(LabelStyle::AllFlags), "file");
+
+        field.argument_name = Some("LEVEL".to_string());
+        assert_eq!(field.display_label(LabelStyle::Friendly), "-v, --verbose <LEVEL>");
+        assert_eq!(field.display_label(LabelStyle::Primary), "--verbose <LEVEL>");
+
+        assert_eq!(LabelStyle::from_config("all_flags"), LabelStyle::AllFlags);
+        assert_eq!(LabelStyle::from_config("bogus"), LabelStyle::Friendly);
+    }
+
+    #[test]
+    fn test_form_field_from_positional() {
+        let arg = PositionalArg {
+            name: "file".to_string(),
+            description: "Input file".to_string(),
+            required: true,
+            sensitive: false,
+            argument_type: ArgumentType::Path,
+            default: None,
+        };
+
+        let field = FormField::from_positional(&arg);
+        assert_eq!(field.id, "_pos_file");
+        assert_eq!(field.label, "file");
+        assert!(field.required);
+        assert_eq!(field.level, OptionLevel::Basic);
+    }
+
+    #[test]
+    fn test_form_field_display_value_normal() {
+        let mut field = create_test_field("test", ArgumentType::String, OptionLevel::Basic);
+        field.value = "hello".to_string();
+
+        assert_eq!(field.display_value(), "hello");
+    }
+
+    #[test]
+    fn test_form_field_display_value_sensitive() {
+        let mut field = create_test_field("test", ArgumentType::String, OptionLevel::Basic);
+        field.sensitive = true;
+        field.value = "secret123".to_string();
+
+        let display = field.display_value();
+        assert!(display.contains("*"));
+        assert!(!display.contains("secret"));
+    }
+
+    #[test]
+    fn test_form_field_display_value_empty_with_default() {
+        let mut field = create_test_field("test", ArgumentType::String, OptionLevel::Basic);
+        field.default = Some("default_value".to_string());
+
+        assert_eq!(field.display_value(), "(default: default_value)");
+    }
+
+    #[test]
+    fn test_form_field_display_value_empty_no_default() {
+        let field = create_test_field("test", ArgumentType::String, OptionLevel::Basic);
+        assert_eq!(field.display_value(), "");
+    }
+
+    #[test]
+    fn test_form_state_new_basic_fields() {
+        let fields = vec![
+            create_test_field("a", ArgumentType::String, OptionLevel::Basic),
+            create_test_field("b", ArgumentType::String, OptionLevel::Advanced),
+            create_test_field("c", ArgumentType::String, OptionLevel::Basic),
+        ];
+
+        let state = FormState::new(fields);
+        assert_eq!(state.basic_indices, vec![0, 2]);
+        assert_eq!(state.advanced_indices, vec![1]);
+        assert_eq!(state.current_tab, OptionTab::Basic);
+        // Initially filtered to basic fields
+        assert_eq!(state.filtered_indices, vec![0, 2]);
+    }
+
+    fn create_order_fields() -> Vec<FormField> {
+        let mut src = create_test_field("_pos_src", ArgumentType::Path, OptionLevel::Basic);
+        src.required = true;
+        let mut output = create_test_field("--output", ArgumentType::Path, OptionLevel::Advanced);
+        output.required = true;
+        vec![
+            create_test_field("--verbose", ArgumentType::Bool, OptionLevel::Basic),
+            src,
+            create_test_field("--count", ArgumentType::Int, OptionLevel::Basic),
+            output,
+            create_test_field("--Archive", ArgumentType::Bool, OptionLevel::Basic),
+        ]
+    }
+
+    fn field_ids(state: &FormState) -> Vec<&str> {
+        state.fields.iter().map(|f| f.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_field_order_documented() {
+        let state = FormState::with_field_order(create_order_fields(), FieldOrder::Documented);
+        assert_eq!(
+            field_ids(&state),
+            vec!["--verbose", "_pos_src", "--count", "--output", "--Archive"]
+        );
+    }
+
+    #[test]
+    fn test_field_order_alphabetical() {
+        let state = FormState::with_field_order(create_order_fields(), FieldOrder::Alphabetical);
+        assert_eq!(
+            field_ids(&state),
+            vec!["_pos_src", "--Archive", "--count", "--output", "--verbose"]
+        );
+        // Index sets are recomputed against the new order
+        assert_eq!(state.advanced_indices, vec![3]);
+        assert_eq!(state.basic_indices, vec![0, 1, 2, 4]);
+    }
+
+    #[test]
+    fn test_field_order_required_first() {
+        let state = FormState::with_field_order(create_order_fields(), FieldOrder::RequiredFirst);
+        assert_eq!(
+            field_ids(&state),
+            vec!["_pos_src", "--output", "--verbose", "--count", "--Archive"]
+        );
+        assert_eq!(state.advanced_indices, vec![1]);
+    }
+
+    #[test]
+    fn test_field_order_type_grouped() {
+        let state = FormState::with_field_order(create_order_fields(), FieldOrder::TypeGrouped);
+        assert_eq!(
+            field_ids(&state),
+            vec!["_pos_src", "--output", "--count", "--verbose", "--Archive"]
+        );
+    }
+
+    #[test]
+    fn test_field_order_from_config() {
+        assert_eq!(FieldOrder::from_config("alphabetical"), FieldOrder::Alphabetical);
+        assert_eq!(FieldOrder::from_config("required_first"), FieldOrder::RequiredFirst);
+        assert_eq!(FieldOrder::from_config("type_grouped"), FieldOrder::TypeGrouped);
+        assert_eq!(FieldOrder::from_config("documented"), FieldOrder::Documented);
+        assert_eq!(FieldOrder::from_config("bogus"), FieldOrder::Documented);
+    }
+
+    #[test]
+    fn test_form_state_jump_to_flag() {
+        let fields = vec![
+            create_test_field("--verbose", ArgumentType::Bool, OptionLevel::Basic),
+            create_test_field("--output", ArgumentType::Path, OptionLevel::Basic),
+            create_test_field("--output-dir", ArgumentType::Path, OptionLevel::Basic),
+        ];
+        let mut state = FormState::new(fields);
+
+        state.start_jump();
+        for c in "--outp".chars() {
+            state.jump_insert_char(c);
+        }
+        state.jump_delete_char();
+        state.jump_insert_char('p');
+        assert!(state.finish_jump());
+
+        // Prefix tie goes to the earliest field, and the list stays unfiltered
+        assert_eq!(state.selected, 1);
+        assert!(!state.jump_mode);
+        assert!(state.jump_query.is_empty());
+        assert_eq!(state.filtered_indices, vec![0, 1, 2]);
+
+        state.start_jump();
+        for c in "--output-dir".chars() {
+            state.jump_insert_char(c);
+        }
+        assert!(state.finish_jump());
+        assert_eq!(state.selected, 2);
+    }
+
+    #[test]
+    fn test_form_state_jump_switches_tab() {
+        let fields = vec![
+            create_test_field("--verbose", ArgumentType::Bool, OptionLevel::Basic),
+            create_test_field("--color", ArgumentType::String, OptionLevel::Advanced),
+        ];
+        let mut state = FormState::new(fields);
+
+        state.start_jump();
+        state.jump_insert_char('c');
+        state.jump_insert_char('o');
+        assert!(state.finish_jump());
+        assert_eq!(state.selected, 1);
+        assert_eq!(state.current_tab, OptionTab::Advanced);
+        assert!(state.filtered_indices.contains(&1));
+    }
+
+    #[test]
+    fn test_form_state_jump_no_match() {
+        let fields = vec![create_test_field("--verbose", ArgumentType::Bool, OptionLevel::Basic)];
+        let mut state = FormState::new(fields);
+
+        state.start_jump();
+        state.jump_insert_char('z');
+        assert!(!state.finish_jump());
+        assert_eq!(state.selected, 0);
+        assert!(!state.jump_mode);
+
+        state.start_jump();
+        state.cancel_jump();
+        assert!(!state.jump_mode);
+    }
+
+    fn create_positional_field(name: &str, required: bool) -> FormField {
+        let mut field = create_test_field(&format!("_pos_{}", name), ArgumentType::Path, OptionLevel::Basic);
+        field.label = name.to_string();
+        field.required = required;
+        field
+    }
+
+    #[test]
+    fn test_validate_blocks_missing_required_positional() {
+        let fields = vec![
+            create_positional_field("source", true),
+            create_positional_field("dest", true),
+            create_test_field("--verbose", ArgumentType::Bool, OptionLevel::Basic),
+        ];
+        let mut state = FormState::new(fields);
+        state.fields[0].value = "a.txt".to_string();
+        state.selected = 2;
+
+        assert!(!state.validate_for_execution());
+        assert_eq!(state.missing_fields, vec![1]);
+        assert_eq!(state.selected, 1);
+        assert_eq!(
+            state.status_message.as_deref(),
+            Some("Missing required argument: dest")
+        );
+
+        state.fields[1].value = "b.txt".to_string();
+        assert!(state.validate_for_execution());
+        assert!(state.missing_fields.is_empty());
+        assert!(state.status_message.is_none());
+    }
+
+    #[test]
+    fn test_field_validate_range_and_pattern() {
+        let mut jobs = create_test_field("--jobs", ArgumentType::Int, OptionLevel::Basic);
+        jobs.min = Some(1.0);
+        jobs.max = Some(64.0);
+        assert!(jobs.validate().is_ok());
+        jobs.value = "8".to_string();
+        assert!(jobs.validate().is_ok());
+        jobs.value = "0".to_string();
+        assert_eq!(jobs.validate().unwrap_err(), "--jobs must be at least 1");
+        jobs.value = "100".to_string();
+        assert_eq!(jobs.validate().unwrap_err(), "--jobs must be at most 64");
+        jobs.value = "2.5".to_string();
+        assert_eq!(jobs.validate().unwrap_err(), "--jobs expects a whole number, not \"2.5\"");
+        // Resolved by the shell, so not checked here
+        jobs.value = "$JOBS".to_string();
+        assert!(jobs.validate().is_ok());
+
+        let mut timeout = create_test_field("--timeout", ArgumentType::String, OptionLevel::Basic);
+        timeout.pattern = Some(ValuePattern::new("[0-9]+[smh]"));
+        timeout.repeatable = true;
+        timeout.set_value(&format!("5s{}10x", VALUE_SEPARATOR));
+        assert_eq!(timeout.validate().unwrap_err(), "--timeout must match [0-9]+[smh]");
+        timeout.value = "10m".to_string();
+        assert!(timeout.validate().is_ok());
+        // A malformed pattern from the spec is ignored
+        timeout.pattern = Some(ValuePattern::new("[0-9"));
+        timeout.value = "anything".to_string();
+        assert!(timeout.validate().is_ok());
+    }
+
+    #[test]
+    fn test_out_of_range_value_blocks_execution() {
+        let mut port = create_test_field("--port", ArgumentType::Int, OptionLevel::Advanced);
+        port.max = Some(65535.0);
+        let mut state = FormState::new(vec![
+            create_test_field("--verbose", ArgumentType::Bool, OptionLevel::Basic),
+            port,
+        ]);
+        state.fields[1].value = "70000".to_string();
+
+        assert!(state.validate_for_execution());
+        assert_eq!(state.invalid_values(), vec!["--port must be at most 65535"]);
+        assert!(!state.validate_values());
+        assert_eq!(state.selected, 1);
+        assert_eq!(state.status_message.as_deref(), Some("--port must be at most 65535"));
+
+        state.fields[1].value = "8080".to_string();
+        assert!(state.invalid_values().is_empty());
+        assert!(state.validate_values());
+    }
+
+    #[test]
+    fn test_validate_lists_all_missing_positionals() {
+        let fields = vec![
+            create_positional_field("source", true),
+            create_positional_field("dest", true),
+            create_positional_field("extra", false),
+        ];
+        let mut state = FormState::new(fields);
+
+        assert!(!state.validate_for_execution());
+        assert_eq!(state.missing_fields, vec![0, 1]);
+        assert_eq!(
+            state.status_message.as_deref(),
+            Some("Missing required arguments: source, dest")
+        );
+
+        state.clear_status();
+        assert!(state.missing_fields.is_empty());
+        assert!(state.status_message.is_none());
+    }
+
+    fn constraint_spec() -> CommandSpec {
+        serde_json::from_value(serde_json::json!({
+            "command": "curl",
+            "version_hash": "abc",
+            "description": "transfer a URL",
+            "options": [
+                {"flags": ["--silent", "-s"], "description": "", "argument_type": "bool",
+                 "conflicts_with": ["--verbose"]},
+                {"flags": ["--verbose", "-v"], "description": "", "argument_type": "bool",
+                 "conflicts_with": ["silent"]},
+                {"flags": ["--cert-type"], "description": "", "argument_type": "string",
+                 "requires": ["--cert"]},
+                {"flags": ["--cert"], "description": "", "argument_type": "path"}
+            ],
+            "positional_args": [],
+            "subcommands": [],
+            "danger_level": "low",
+            "examples": []
+        }))
+        .unwrap()
+    }
+
+    fn constraint_state() -> FormState {
+        let mut silent = create_test_field("--silent", ArgumentType::Bool, OptionLevel::Basic);
+        silent.flags = vec!["--silent".to_string(), "-s".to_string()];
+        let mut verbose = create_test_field("--verbose", ArgumentType::Bool, OptionLevel::Basic);
+        verbose.flags = vec!["--verbose".to_string(), "-v".to_string()];
+        FormState::new(vec![
+            silent,
+            verbose,
+            create_test_field("--cert-type", ArgumentType::String, OptionLevel::Basic),
+            create_test_field("--cert", ArgumentType::Path, OptionLevel::Advanced),
+        ])
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::parser::CommandOption;
+    #[test]
+    fn test_validate_constraints_reports_conflict_once() {
+        let spec = constraint_spec();
+        let mut state = constraint_state();
+        state.fields[0].value = "true".to_string();
+        assert!(state.validate_constraints(&spec).is_empty());
+
+        // Bools only count when on
+        state.fields[1].value = "false".to_string();
+        assert!(state.validate_constraints(&spec).is_empty());
+
+        state.fields[1].value = "true".to_string();
+        let violations = state.validate_constraints(&spec);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].kind, ConstraintKind::Conflict);
+        assert_eq!(violations[0].message, "--silent conflicts with --verbose");
+
+        state.selected = 1;
+        state.update_constraint_warning(&spec);
+        assert_eq!(
+            state.constraint_warning.as_deref(),
+            Some("Warning: --silent conflicts with --verbose")
+        );
+        state.selected = 2;
+        state.update_constraint_warning(&spec);
+        assert!(state.constraint_warning.is_none());
+    }
 
-    // Helper to create a test FormField
-    fn create_test_field(id: &str, field_type: ArgumentType, level: OptionLevel) -> FormField {
-        FormField {
-            id: id.to_string(),
-            label: id.to_string(),
-            description: format!("Description for {}", id),
-            field_type,
-            required: false,
-            sensitive: false,
-            value: String::new(),
-            enum_values: vec![],
-            default: None,
-            level,
-        }
+    #[test]
+    fn test_conflicting_fields_from_either_side() {
+        let spec = constraint_spec();
+        let mut state = constraint_state();
+        state.fields[0].value = "true".to_string();
+        assert!(state.conflicting_fields(&spec, 1).is_empty());
+
+        state.fields[1].value = "true".to_string();
+        assert_eq!(state.conflicting_fields(&spec, 0), vec![1]);
+        assert_eq!(state.conflicting_fields(&spec, 1), vec![0]);
+        assert!(state.conflicting_fields(&spec, 2).is_empty());
+
+        state.disable_fields(&[0]);
+        assert!(!state.fields[0].is_set());
+        assert_eq!(state.status_message.as_deref(), Some("Disabled --silent"));
+        assert!(state.validate_constraints(&spec).is_empty());
     }
 
     #[test]
-    fn test_form_field_from_option() {
-        let opt = CommandOption {
-            flags: vec!["--verbose".to_string(), "-v".to_string()],
-            description: "Enable verbose output".to_string(),
-            argument_type: ArgumentType::Bool,
-            argument_name: None,
-            required: false,
-            sensitive: false,
-            repeatable: false,
-            conflicts_with: vec![],
-            requires: vec![],
-            default: Some("false".to_string()),
-            enum_values: vec![],
-            level: OptionLevel::Basic,
-        };
+    fn test_validate_requirements_flags_missing_option() {
+        let spec = constraint_spec();
+        let mut state = constraint_state();
+        state.fields[2].value = "PEM".to_string();
+
+        assert!(!state.validate_requirements(&spec));
+        assert_eq!(state.status_message.as_deref(), Some("--cert-type requires --cert"));
+        assert_eq!(state.missing_fields, vec![3]);
+        assert_eq!(state.selected, 3);
+        assert_eq!(state.current_tab, OptionTab::Advanced);
 
-        let field = FormField::from_option(&opt);
-        assert_eq!(field.id, "--verbose");
-        assert_eq!(field.label, "-v, --verbose");
-        assert_eq!(field.field_type, ArgumentType::Bool);
-        assert_eq!(field.default, Some("false".to_string()));
+        state.fields[3].value = "client.pem".to_string();
+        assert!(state.validate_requirements(&spec));
     }
 
     #[test]
-    fn test_form_field_from_positional() {
-        let arg = PositionalArg {
-            name: "file".to_string(),
-            description: "Input file".to_string(),
-            required: true,
-            sensitive: false,
-            argument_type: ArgumentType::Path,
-            default: None,
-        };
+    fn test_placeholder_shows_example_only_when_empty() {
+        let mut field = create_test_field("--request", ArgumentType::String, OptionLevel::Basic);
+        assert!(field.placeholder().is_none());
 
-        let field = FormField::from_positional(&arg);
-        assert_eq!(field.id, "_pos_file");
-        assert_eq!(field.label, "file");
-        assert!(field.required);
-        assert_eq!(field.level, OptionLevel::Basic);
+        field.example = Some("POST".to_string());
+        assert_eq!(field.placeholder().as_deref(), Some("e.g. POST"));
+        assert_eq!(field.display_value(), "");
+
+        field.value = "GET".to_string();
+        assert!(field.placeholder().is_none());
+
+        // A default is shown instead of the example
+        field.value.clear();
+        field.default = Some("GET".to_string());
+        assert!(field.placeholder().is_none());
+        assert_eq!(field.display_value(), "(default: GET)");
     }
 
     #[test]
-    fn test_form_field_display_value_normal() {
-        let mut field = create_test_field("test", ArgumentType::String, OptionLevel::Basic);
-        field.value = "hello".to_string();
+    fn test_review_summary_lists_set_fields_masked() {
+        let mut token = create_test_field("--token", ArgumentType::String, OptionLevel::Basic);
+        token.sensitive = true;
+        token.value = "secret".to_string();
+        let mut verbose = create_test_field("--verbose", ArgumentType::Bool, OptionLevel::Basic);
+        verbose.value = "true".to_string();
+        let mut quiet = create_test_field("--quiet", ArgumentType::Bool, OptionLevel::Basic);
+        quiet.value = "false".to_string();
+        let mut url = create_positional_field("url", true);
+        url.value = "https://example.com".to_string();
+        let empty = create_test_field("--output", ArgumentType::Path, OptionLevel::Basic);
+
+        let state = FormState::new(vec![url, token, verbose, quiet, empty]);
+        assert_eq!(
+            state.review_summary(),
+            vec![
+                ("--token".to_string(), "******".to_string()),
+                ("--verbose".to_string(), "on".to_string()),
+                ("url".to_string(), "https://example.com".to_string()),
+            ]
+        );
+    }
 
-        assert_eq!(field.display_value(), "hello");
+    #[test]
+    fn test_save_and_apply_presets() {
+        let mut token = create_test_field("--token", ArgumentType::String, OptionLevel::Basic);
+        token.sensitive = true;
+        let mut state = FormState::new(vec![
+            create_test_field("--env", ArgumentType::String, OptionLevel::Basic),
+            create_test_field("--port", ArgumentType::Int, OptionLevel::Basic),
+            token,
+        ]);
+        state.fields[0].value = "prod".to_string();
+        state.fields[2].value = "secret".to_string();
+
+        state.start_preset_save();
+        for c in " prod ".chars() {
+            state.preset_name_insert_char(c);
+        }
+        state.finish_preset_save();
+        assert!(state.preset_name_input.is_none());
+        assert_eq!(state.status_message.as_deref(), Some("Saved preset 'prod'"));
+        assert_eq!(state.saved_presets.len(), 1);
+        let (name, values) = &state.saved_presets[0];
+        assert_eq!(name, "prod");
+        assert_eq!(values.get("--env").map(String::as_str), Some("prod"));
+        assert!(!values.contains_key("--token"));
+
+        state.set_presets(vec![
+            ("prod".to_string(), values.clone()),
+            (
+                "dev".to_string(),
+                HashMap::from([("--port".to_string(), "8080".to_string())]),
+            ),
+        ]);
+        state.open_preset_picker();
+        assert!(state.showing_presets);
+        // Sorted by name: dev, prod
+        state.apply_selected_preset();
+        assert!(!state.showing_presets);
+        assert_eq!(state.fields[0].value, "");
+        assert_eq!(state.fields[1].value, "8080");
+        assert_eq!(state.fields[2].value, "secret");
+        assert_eq!(state.status_message.as_deref(), Some("Loaded preset 'dev'"));
     }
 
     #[test]
-    fn test_form_field_display_value_sensitive() {
-        let mut field = create_test_field("test", ArgumentType::String, OptionLevel::Basic);
-        field.sensitive = true;
-        field.value = "secret123".to_string();
+    fn test_preset_picker_needs_presets() {
+        let mut state = FormState::new(vec![create_test_field("--env", ArgumentType::String, OptionLevel::Basic)]);
+        state.open_preset_picker();
+        assert!(!state.showing_presets);
+        assert!(state.status_message.is_some());
+    }
 
-        let display = field.display_value();
-        assert!(display.contains("*"));
-        assert!(!display.contains("secret"));
+    #[test]
+    fn test_examples_tab_fills_form() {
+        let mut token = create_test_field("--token", ArgumentType::String, OptionLevel::Basic);
+        token.sensitive = true;
+        token.value = "secret".to_string();
+        let mut state = FormState::new(vec![
+            create_test_field("--env", ArgumentType::String, OptionLevel::Basic),
+            create_test_field("--port", ArgumentType::Int, OptionLevel::Basic),
+            token,
+        ]);
+        state.fields[1].value = "8080".to_string();
+
+        // Without examples the tab is skipped
+        state.set_tab(OptionTab::Frequent);
+        state.next_tab();
+        assert_eq!(state.current_tab, OptionTab::Basic);
+        state.set_tab(OptionTab::Examples);
+        assert_eq!(state.current_tab, OptionTab::Basic);
+
+        state.set_examples(vec!["deploy --env dev".to_string(), "deploy --env prod".to_string()]);
+        state.set_tab(OptionTab::Frequent);
+        state.next_tab();
+        assert_eq!(state.current_tab, OptionTab::Examples);
+        state.move_down();
+        state.move_down();
+        assert_eq!(state.current_example(), Some("deploy --env prod"));
+
+        let parsed = ParsedCommand {
+            values: HashMap::from([("--env".to_string(), "prod".to_string())]),
+            unrecognized: vec!["--verbose".to_string()],
+        };
+        state.apply_example(&parsed);
+        assert_eq!(state.current_tab, OptionTab::Basic);
+        assert_eq!(state.fields[0].value, "prod");
+        assert_eq!(state.fields[1].value, "");
+        assert_eq!(state.fields[2].value, "secret");
+        assert!(state.status_message.as_deref().unwrap().contains("skipped --verbose"));
     }
 
     #[test]
-    fn test_form_field_display_value_empty_with_default() {
-        let mut field = create_test_field("test", ArgumentType::String, OptionLevel::Basic);
-        field.default = Some("default_value".to_string());
+    fn test_start_search_with_selects_best_match() {
+        let fields = vec![
+            create_test_field("--output-dir", ArgumentType::Path, OptionLevel::Basic),
+            create_test_field("--verbose", ArgumentType::Bool, OptionLevel::Basic),
+            create_test_field("--output", ArgumentType::Path, OptionLevel::Basic),
+        ];
+        let mut state = FormState::new(fields);
 
-        assert_eq!(field.display_value(), "(default: default_value)");
+        state.start_search_with("--output");
+        assert!(state.search_mode);
+        assert_eq!(state.search_query, "--output");
+        assert!(!state.filtered_indices.contains(&1));
+        assert_eq!(state.selected, state.filtered_indices[0]);
+        assert_eq!(state.fields[state.selected].id, "--output");
+
+        // Typing continues the seeded query
+        state.search_delete_char();
+        assert_eq!(state.search_query, "--outpu");
     }
 
     #[test]
-    fn test_form_field_display_value_empty_no_default() {
-        let field = create_test_field("test", ArgumentType::String, OptionLevel::Basic);
-        assert_eq!(field.display_value(), "");
+    fn test_validate_accepts_defaults_and_unset_bools() {
+        let mut required_bool = create_test_field("--force", ArgumentType::Bool, OptionLevel::Basic);
+        required_bool.required = true;
+        let mut defaulted = create_positional_field("dir", true);
+        defaulted.default = Some(".".to_string());
+
+        let mut state = FormState::new(vec![required_bool, defaulted]);
+        assert!(state.missing_required().is_empty());
+        assert!(state.validate_for_execution());
     }
 
     #[test]
-    fn test_form_state_new_basic_fields() {
+    fn test_validate_reveals_missing_required_option() {
+        let mut required_opt = create_test_field("--name", ArgumentType::String, OptionLevel::Advanced);
+        required_opt.required = true;
         let fields = vec![
-            create_test_field("a", ArgumentType::String, OptionLevel::Basic),
-            create_test_field("b", ArgumentType::String, OptionLevel::Advanced),
-            create_test_field("c", ArgumentType::String, OptionLevel::Basic),
+            create_test_field("--verbose", ArgumentType::Bool, OptionLevel::Basic),
+            required_opt,
         ];
+        let mut state = FormState::new(fields);
+        state.filter_by_type(Some(ArgumentType::Bool));
 
-        let state = FormState::new(fields);
-        assert_eq!(state.basic_indices, vec![0, 2]);
-        assert_eq!(state.advanced_indices, vec![1]);
-        assert_eq!(state.current_tab, OptionTab::Basic);
-        // Initially filtered to basic fields
-        assert_eq!(state.filtered_indices, vec![0, 2]);
+        assert_eq!(state.missing_required(), vec![1]);
+        assert!(!state.validate_for_execution());
+        assert_eq!(state.current_tab, OptionTab::Advanced);
+        assert!(state.type_filter.is_none());
+        assert!(state.filtered_indices.contains(&1));
+        assert_eq!(state.selected, 1);
+        assert_eq!(
+            state.status_message.as_deref(),
+            Some("Missing required argument: --name")
+        );
+
+        state.fields[1].value = "quocli".to_string();
+        assert!(state.validate_for_execution());
     }
 
     #[test]
@@ -809,6 +2504,12 @@ mod tests {
 
         state.next_tab();
         assert_eq!(state.current_tab, OptionTab::Basic);
+
+        state.prev_tab();
+        assert_eq!(state.current_tab, OptionTab::Frequent);
+        state.prev_tab();
+        assert_eq!(state.current_tab, OptionTab::Advanced);
+        assert_eq!(state.filtered_indices, vec![1]);
     }
 
     #[test]
@@ -847,6 +2548,32 @@ mod tests {
         assert!(!state.editing);
     }
 
+    fn type_into(state: &mut FormState, text: &str) -> String {
+        state.fields[0].value.clear();
+        state.start_editing();
+        text.chars().for_each(|c| state.insert_char(c));
+        state.stop_editing();
+        state.fields[0].value.clone()
+    }
+
+    #[test]
+    fn test_numeric_fields_ignore_other_keystrokes() {
+        let mut state = FormState::new(vec![create_test_field("--jobs", ArgumentType::Int, OptionLevel::Basic)]);
+        assert_eq!(type_into(&mut state, "1a2"), "12");
+        assert_eq!(state.cursor_pos, 2);
+        assert_eq!(type_into(&mut state, "-3-4.5"), "-345");
+        assert_eq!(type_into(&mut state, "$JOBS"), "$JOBS");
+
+        state.fields[0].field_type = ArgumentType::Float;
+        assert_eq!(type_into(&mut state, "-1.5.2e-3e"), "-1.52e-3");
+        assert_eq!(type_into(&mut state, "e5.5"), "5.5");
+        assert_eq!(type_into(&mut state, ".5E+2x"), ".5E+2");
+
+        // Free text when strict input is turned off
+        state.strict_numeric = false;
+        assert_eq!(type_into(&mut state, "1a2"), "1a2");
+    }
+
     #[test]
     fn test_form_state_toggle_bool() {
         let fields = vec![create_test_field("flag", ArgumentType::Bool, OptionLevel::Basic)];
@@ -901,6 +2628,60 @@ mod tests {
         assert_eq!(state.fields[0].value, "red"); // Wraps around to 0
     }
 
+    #[test]
+    fn test_cycle_enum_from_unlisted_value() {
+        let mut optional = create_test_field("color", ArgumentType::Enum, OptionLevel::Basic);
+        optional.enum_values = vec!["red".to_string(), "green".to_string()];
+        optional.value = "purple".to_string();
+        let mut required = optional.clone();
+        required.required = true;
+
+        let mut state = FormState::new(vec![optional, required]);
+        state.cycle_enum();
+        assert_eq!(state.fields[0].value, "red");
+        state.cycle_enum();
+        assert_eq!(state.fields[0].value, "green");
+
+        state.selected = 1;
+        state.cycle_enum();
+        assert_eq!(state.fields[1].value, "red");
+    }
+
+    #[test]
+    fn test_repeatable_field_collects_values() {
+        let mut env = create_test_field("--env", ArgumentType::String, OptionLevel::Basic);
+        env.repeatable = true;
+        let mut state = FormState::new(vec![
+            env,
+            create_test_field("--name", ArgumentType::String, OptionLevel::Basic),
+        ]);
+
+        state.fields[0].value = "A=1".to_string();
+        state.add_repeated_value();
+        assert!(state.editing);
+        for c in "B=2".chars() {
+            state.insert_char(c);
+        }
+        state.stop_editing();
+        assert_eq!(state.fields[0].display_value(), "A=1, B=2");
+        assert_eq!(state.get_values().get("--env").unwrap(), "A=1\nB=2");
+
+        // Stored values split back out, and single-value fields are refused
+        state.load_cached_values(&HashMap::from([("--env".to_string(), "X\nY\nZ".to_string())]));
+        assert_eq!(state.fields[0].repeated, vec!["X", "Y"]);
+        assert_eq!(state.fields[0].value, "Z");
+        state.remove_repeated_value();
+        assert_eq!(state.get_values().get("--env").unwrap(), "X\nY");
+
+        state.selected = 1;
+        state.add_repeated_value();
+        assert!(!state.editing);
+        assert!(state.status_message.is_some());
+
+        state.clear_all_values();
+        assert!(state.get_values().is_empty());
+    }
+
     #[test]
     fn test_form_state_get_values() {
         let fields = vec![
@@ -918,7 +2699,7 @@ mod tests {
         assert_eq!(values.len(), 2);
         assert_eq!(values.get("a"), Some(&"value_a".to_string()));
         assert_eq!(values.get("c"), Some(&"value_c".to_string()));
-        assert!(values.get("b").is_none());
+        assert!(!values.contains_key("b"));
     }
 
     #[test]
@@ -958,6 +2739,9 @@ mod tests {
         assert!(state.fields[1].value.is_empty());
         assert_eq!(state.fields[2].value, "cached_c");
         assert_eq!(state.frequent_indices, vec![0, 2]);
+        assert!(state.has_cached_value(0));
+        assert!(!state.has_cached_value(1));
+        assert!(state.has_cached_value(2));
     }
 
     #[test]
@@ -1127,6 +2911,293 @@ mod tests {
         assert!(!state.showing_suggestions);
     }
 
+    #[test]
+    fn test_form_state_env_hint() {
+        let mut env = EnvGuard::lock();
+        env.set("QUOCLI_TEST_FLURB_TOKEN", "secret");
+
+        let fields = vec![
+            create_test_field("--flurb-token", ArgumentType::String, OptionLevel::Basic),
+            create_test_field("--flurb", ArgumentType::Bool, OptionLevel::Basic),
+        ];
+        let mut state = FormState::new(fields);
+        // The form works from the snapshot taken when it opened; this shorter
+        // name would otherwise win the tie
+        env.set("QUOCLI_FLURB_TOKEN", "newer");
+
+        state.update_env_hint();
+        assert_eq!(state.env_hint.as_deref(), Some("QUOCLI_TEST_FLURB_TOKEN"));
+
+        state.accept_env_hint();
+        assert_eq!(state.fields[0].value, "$QUOCLI_TEST_FLURB_TOKEN");
+        assert!(state.env_hint.is_none());
+
+        // No hint once the field has a value
+        state.update_env_hint();
+        assert!(state.env_hint.is_none());
+
+        // No hint for bool fields
+        state.move_down();
+        state.update_env_hint();
+        assert!(state.env_hint.is_none());
+    }
+
+    #[test]
+    fn test_form_state_env_suggestions_from_snapshot() {
+        let fields = vec![create_test_field("test", ArgumentType::String, OptionLevel::Basic)];
+        let mut state = FormState::new(fields);
+        state.env_vars = HashMap::from([
+            ("QUOCLI_TEST_HOME".to_string(), "/home/user".to_string()),
+            ("QUOCLI_TEST_PATH".to_string(), "/bin".to_string()),
+        ]);
+        state.fields[0].value = "$QUOCLI_TEST_H".to_string();
+        state.cursor_pos = 14;
+
+        state.update_env_suggestions();
+        assert!(state.showing_suggestions);
+        assert_eq!(state.env_suggestions, vec![("QUOCLI_TEST_HOME".to_string(), "/home/user".to_string())]);
+    }
+
+    #[test]
+    fn test_deprecated_fields_only_in_advanced_tab() {
+        let mut old = create_test_field("--old", ArgumentType::Bool, OptionLevel::Basic);
+        old.deprecated = true;
+        let fields = vec![
+            create_test_field("--new", ArgumentType::Bool, OptionLevel::Basic),
+            old,
+            create_test_field("--rare", ArgumentType::Bool, OptionLevel::Advanced),
+        ];
+        let state = FormState::new(fields);
+
+        assert_eq!(state.basic_indices, vec![0]);
+        assert_eq!(state.advanced_indices, vec![1, 2]);
+        assert_eq!(state.filtered_indices, vec![0]);
+    }
+
+    #[test]
+    fn test_filter_by_type_composes_with_tabs_and_search() {
+        let fields = vec![
+            create_test_field("--output", ArgumentType::Path, OptionLevel::Basic),
+            create_test_field("--verbose", ArgumentType::Bool, OptionLevel::Basic),
+            create_test_field("--config", ArgumentType::Path, OptionLevel::Advanced),
+            create_test_field("--outdir", ArgumentType::Path, OptionLevel::Basic),
+        ];
+        let mut state = FormState::new(fields);
+        state.selected = 1;
+
+        state.filter_by_type(Some(ArgumentType::Path));
+        assert_eq!(state.filtered_indices, vec![0, 3]);
+        assert_eq!(state.selected, 0);
+
+        state.set_tab(OptionTab::Advanced);
+        assert_eq!(state.filtered_indices, vec![2]);
+
+        state.search_query = "out".to_string();
+        state.update_filter();
+        assert_eq!(state.filtered_indices, vec![0, 3]);
+
+        state.clear_search();
+        state.filter_by_type(None);
+        assert_eq!(state.filtered_indices, vec![2]);
+    }
+
+    #[test]
+    fn test_cycle_type_filter_wraps() {
+        let mut state = FormState::new(vec![create_test_field(
+            "--verbose",
+            ArgumentType::Bool,
+            OptionLevel::Basic,
+        )]);
+
+        state.cycle_type_filter();
+        assert_eq!(state.type_filter, Some(ArgumentType::Bool));
+        assert_eq!(state.filtered_indices, vec![0]);
+        state.cycle_type_filter();
+        assert!(state.filtered_indices.is_empty());
+        for _ in 0..5 {
+            state.cycle_type_filter();
+        }
+        assert_eq!(state.type_filter, None);
+        assert_eq!(state.filtered_indices, vec![0]);
+    }
+
+    #[test]
+    fn test_toggle_section() {
+        let fields = vec![
+            create_positional_field("source", true),
+            create_test_field("--verbose", ArgumentType::Bool, OptionLevel::Basic),
+            create_positional_field("dest", true),
+            create_test_field("--force", ArgumentType::Bool, OptionLevel::Basic),
+        ];
+        let mut state = FormState::new(fields);
+        state.selected = 2;
+
+        state.toggle_section();
+        assert_eq!(state.selected, 1);
+        state.toggle_section();
+        assert_eq!(state.selected, 0);
+
+        // Nothing to jump to when only one section is visible
+        state.filter_by_type(Some(ArgumentType::Bool));
+        state.toggle_section();
+        assert_eq!(state.selected, 1);
+        state.toggle_section();
+        assert_eq!(state.selected, 1);
+    }
+
+    #[test]
+    fn test_clear_sensitive_values_keeps_other_fields() {
+        let mut token = create_test_field("--token", ArgumentType::String, OptionLevel::Basic);
+        token.sensitive = true;
+        token.value = "secret".to_string();
+        let mut output = create_test_field("--output", ArgumentType::Path, OptionLevel::Basic);
+        output.value = "/tmp/out".to_string();
+        let mut state = FormState::new(vec![token, output]);
+
+        state.clear_sensitive_values();
+        assert!(state.fields[0].value.is_empty());
+        assert_eq!(state.fields[1].value, "/tmp/out");
+        assert_eq!(state.status_message.as_deref(), Some("Cleared 1 sensitive value(s)"));
+    }
+
+    #[test]
+    fn test_toggle_sensitive_records_override() {
+        let mut state = FormState::new(vec![create_test_field(
+            "--output",
+            ArgumentType::Path,
+            OptionLevel::Basic,
+        )]);
+
+        state.toggle_sensitive();
+        assert!(state.fields[0].sensitive);
+        assert_eq!(state.sensitivity_overrides.get("--output"), Some(&true));
+        assert!(state.status_message.as_deref().unwrap().contains("marked sensitive"));
+
+        state.toggle_sensitive();
+        assert!(!state.fields[0].sensitive);
+        assert_eq!(state.sensitivity_overrides.get("--output"), Some(&false));
+    }
+
+    #[test]
+    fn test_form_state_env_hint_sensitive_field() {
+        let mut env = EnvGuard::lock();
+        env.set("QUOCLI_TEST_GLORP_SECRET", "hunter2");
+
+        let mut field = create_test_field("--glorp", ArgumentType::String, OptionLevel::Basic);
+        field.sensitive = true;
+        let mut password = create_test_field("--zibble-password", ArgumentType::String, OptionLevel::Basic);
+        password.sensitive = true;
+        let mut state = FormState::new(vec![field, password]);
+
+        state.update_env_hint();
+        assert_eq!(state.env_hint.as_deref(), Some("QUOCLI_TEST_GLORP_SECRET"));
+
+        // A plaintext secret still gets the hint, and accepting replaces it
+        state.fields[0].value = "hunter2".to_string();
+        state.update_env_hint();
+        assert_eq!(state.env_hint.as_deref(), Some("QUOCLI_TEST_GLORP_SECRET"));
+        state.accept_env_hint();
+        assert_eq!(state.fields[0].value, "$QUOCLI_TEST_GLORP_SECRET");
+
+        // Already an env var reference: no hint
+        state.update_env_hint();
+        assert!(state.env_hint.is_none());
+
+        // A credential flag is offered a token named after something else
+        state.move_down();
+        state.env_vars = HashMap::from([
+            ("QUOCLI_TEST_PLONK_TOKEN".to_string(), "s3cret".to_string()),
+            ("HOME".to_string(), "/root".to_string()),
+        ]);
+        state.update_env_hint();
+        assert_eq!(state.env_hint.as_deref(), Some("QUOCLI_TEST_PLONK_TOKEN"));
+    }
+
+    #[test]
+    fn test_form_state_env_hint_sensitive_matches_secret_names() {
+        let mut auth = create_test_field("--auth", ArgumentType::String, OptionLevel::Basic);
+        auth.sensitive = true;
+        let mut login = create_test_field("--login", ArgumentType::String, OptionLevel::Basic);
+        login.sensitive = true;
+        let plain = create_test_field("--login-name", ArgumentType::String, OptionLevel::Basic);
+        let mut state = FormState::new(vec![auth, login, plain]);
+        state.env_vars = HashMap::from([
+            ("API_TOKEN".to_string(), "s3cret".to_string()),
+            ("HOME".to_string(), "/root".to_string()),
+        ]);
+
+        // A sensitive field is offered a token that shares no word with its flag
+        state.update_env_hint();
+        assert_eq!(state.env_hint.as_deref(), Some("API_TOKEN"));
+        state.move_down();
+        state.update_env_hint();
+        assert_eq!(state.env_hint.as_deref(), Some("API_TOKEN"));
+
+        // A field that isn't sensitive isn't
+        state.move_down();
+        state.update_env_hint();
+        assert!(state.env_hint.is_none());
+    }
+
+    #[test]
+    fn test_request_execute_waits_for_confirmation() {
+        let mut state = FormState::new(vec![]);
+        assert!(state.request_execute(ExecuteConfirm::Off, "Ctrl+E", false));
+        assert!(!state.execute_armed);
+
+        assert!(!state.request_execute(ExecuteConfirm::Twice, "Ctrl+E", false));
+        assert!(state.execute_armed);
+        assert_eq!(state.status_message.as_deref(), Some("Press Ctrl+E again to run"));
+        assert!(state.request_execute(ExecuteConfirm::Twice, "Ctrl+E", false));
+        assert!(!state.execute_armed);
+
+        assert!(!state.request_execute(ExecuteConfirm::Twice, "Ctrl+B", true));
+        assert!(state.armed_background);
+        assert_eq!(state.status_message.as_deref(), Some("Press Ctrl+B again to run"));
+
+        assert_eq!(ExecuteConfirm::from_config("enter"), ExecuteConfirm::Enter);
+        assert_eq!(ExecuteConfirm::from_config("bogus"), ExecuteConfirm::Off);
+    }
+
+    #[test]
+    fn test_chat_panel_request_and_reply() {
+        let mut state = FormState::new(vec![]);
+        state.open_chat();
+        assert!(state.take_chat_message().is_none());
+
+        for c in "follow redirects".chars() {
+            state.chat_insert_char(c);
+        }
+        assert_eq!(state.take_chat_message().as_deref(), Some("follow redirects"));
+        // Nothing more is sent until the reply arrives
+        state.chat_insert_char('x');
+        assert!(state.take_chat_message().is_none());
+
+        state.receive_chat_reply(Ok("Use -L.\n$ curl -L https://example.com".to_string()));
+        let chat = state.chat.as_ref().unwrap();
+        assert!(!chat.waiting);
+        assert_eq!(chat.suggestion.as_deref(), Some("curl -L https://example.com"));
+
+        state.receive_chat_reply(Err("timed out".to_string()));
+        let chat = state.chat.as_ref().unwrap();
+        assert_eq!(chat.reply.as_deref(), Some("Assist request failed: timed out"));
+    }
+
+    #[test]
+    fn test_note_editing() {
+        let mut state = FormState::new(vec![]);
+        state.note = "\n  set --region for prod\nsecond".to_string();
+        assert_eq!(state.note_summary(), Some("set --region for prod"));
+
+        state.note_delete_char();
+        state.note_insert_char('!');
+        assert!(state.note_changed);
+        assert!(state.note.ends_with("secon!"));
+
+        state.note.clear();
+        assert_eq!(state.note_summary(), None);
+    }
+
     #[test]
     fn test_form_state_empty_fields() {
         let state = FormState::new(vec![]);
@@ -0,0 +1,139 @@
+use ratatui::layout::Rect;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use textwrap::core::{break_words, Fragment, Word};
+use textwrap::wrap_algorithms::wrap_first_fit;
+use textwrap::WordSeparator;
+
+/// Wrap text to `width` columns, keeping explicit line breaks and blank lines
+pub fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        if paragraph.trim().is_empty() {
+            lines.push(String::new());
+            continue;
+        }
+        lines.extend(
+            textwrap::wrap(paragraph.trim_end(), width)
+                .into_iter()
+                .map(|line| line.into_owned()),
+        );
+    }
+    lines
+}
+
+/// Width to wrap descriptions at: the available width, capped by `ui.description_wrap_width`
+pub fn description_width(available: u16, configured: Option<usize>) -> usize {
+    let available = available as usize;
+    match configured {
+        Some(width) if width > 0 => width.min(available),
+        _ => available,
+    }
+}
+
+/// Width to wrap text at inside a bordered popup
+pub fn popup_width(area: Rect, configured: Option<usize>) -> usize {
+    description_width(area.width.saturating_sub(2), configured)
+}
+
+/// A word of a styled line, with the style of the span it came from
+#[derive(Debug)]
+struct StyledWord<'a> {
+    word: Word<'a>,
+    style: Style,
+}
+
+impl Fragment for StyledWord<'_> {
+    fn width(&self) -> f64 {
+        self.word.width()
+    }
+
+    fn whitespace_width(&self) -> f64 {
+        self.word.whitespace_width()
+    }
+
+    fn penalty_width(&self) -> f64 {
+        self.word.penalty_width()
+    }
+}
+
+/// Wrap styled lines to `width` columns like [`wrap_text`], keeping each word's style
+pub fn wrap_lines(lines: &[Line<'_>], width: usize) -> Vec<Line<'static>> {
+    let width = width.max(1);
+    let mut wrapped = Vec::new();
+    for line in lines {
+        let words: Vec<StyledWord> = line
+            .spans
+            .iter()
+            .flat_map(|span| {
+                let style = line.style.patch(span.style);
+                break_words(WordSeparator::AsciiSpace.find_words(&span.content), width)
+                    .into_iter()
+                    .map(move |word| StyledWord { word, style })
+            })
+            .collect();
+        if words.is_empty() {
+            wrapped.push(Line::default());
+            continue;
+        }
+
+        for row in wrap_first_fit(&words, &[width as f64]) {
+            let mut spans = Vec::with_capacity(row.len());
+            for (i, styled) in row.iter().enumerate() {
+                let mut text = styled.word.word.to_string();
+                // Whitespace at the end of a row is where the line broke
+                if i + 1 < row.len() {
+                    text.push_str(styled.word.whitespace);
+                }
+                spans.push(Span::styled(text, styled.style));
+            }
+            wrapped.push(Line::from(spans));
+        }
+    }
+    wrapped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_text_breaks_on_words() {
+        let lines = wrap_text("copy files and directories recursively", 16);
+        assert_eq!(lines, vec!["copy files and", "directories", "recursively"]);
+    }
+
+    #[test]
+    fn test_wrap_text_keeps_paragraphs() {
+        let lines = wrap_text("first line\n\nsecond", 40);
+        assert_eq!(lines, vec!["first line", "", "second"]);
+    }
+
+    #[test]
+    fn test_wrap_text_zero_width() {
+        assert_eq!(wrap_text("ab", 0), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_wrap_lines_keeps_styles() {
+        let bold = Style::default().add_modifier(ratatui::style::Modifier::BOLD);
+        let lines = vec![
+            Line::from(vec![Span::styled("--output: ", bold), Span::raw("write to a file")]),
+            Line::from(""),
+        ];
+        let wrapped = wrap_lines(&lines, 12);
+        let text: Vec<String> = wrapped.iter().map(|line| line.to_string()).collect();
+        assert_eq!(text, vec!["--output:", "write to a", "file", ""]);
+        assert_eq!(wrapped[0].spans[0].style, bold);
+        assert_eq!(wrapped[1].spans[0].style, Style::default());
+    }
+
+    #[test]
+    fn test_description_width() {
+        assert_eq!(description_width(80, None), 80);
+        assert_eq!(description_width(80, Some(60)), 60);
+        assert_eq!(description_width(40, Some(60)), 40);
+        assert_eq!(description_width(80, Some(0)), 80);
+    }
+}